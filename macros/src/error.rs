@@ -0,0 +1,127 @@
+use proc_macro2::{Delimiter, TokenStream as TokenStream2, TokenTree};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::{bracketed, Error, Expr, LitStr};
+
+/// The message of a spec error and the tokens at fault, written
+/// `"message" [tokens]`.
+pub(crate) struct SpecError {
+    message: LitStr,
+    tokens: TokenStream2,
+}
+
+impl Parse for SpecError {
+    fn parse(input: ParseStream) -> syn::Result<SpecError> {
+        let message = input.parse()?;
+        let content;
+        bracketed!(content in input);
+        let tokens = content.parse()?;
+        Ok(SpecError { message, tokens })
+    }
+}
+
+/// Expand `spanned_error!` into a compile error naming the tokens at fault
+/// and pointing at them.
+pub(crate) fn expand(error: SpecError) -> TokenStream2 {
+    let tokens = ungroup(error.tokens);
+    let message = format!("{} `{}`", error.message.value(), tokens);
+    Error::new_spanned(tokens, message).into_compile_error()
+}
+
+/// The message of an error following the value of an entry, the key of the
+/// entry and the tokens from its value, written
+/// `"message" [key] [tokens]`.
+pub(crate) struct ValueError {
+    message: LitStr,
+    key: TokenStream2,
+    tokens: TokenStream2,
+}
+
+impl Parse for ValueError {
+    fn parse(input: ParseStream) -> syn::Result<ValueError> {
+        let message = input.parse()?;
+        let key;
+        bracketed!(key in input);
+        let key = key.parse()?;
+        let tokens;
+        bracketed!(tokens in input);
+        let tokens = tokens.parse()?;
+        Ok(ValueError {
+            message,
+            key,
+            tokens,
+        })
+    }
+}
+
+/// Expand `spanned_value_error!` into a compile error pointing at the first
+/// token after the value, or at the tokens which are not a value.
+pub(crate) fn expand_value(error: ValueError) -> TokenStream2 {
+    let key = ungroup(error.key);
+    let message = format!("{} `{}`", error.message.value(), key);
+    match split_expr(error.tokens) {
+        Ok((_, Some(unexpected))) => {
+            let message = format!("{}, found `{}`", message, unexpected);
+            Error::new_spanned(unexpected, message).into_compile_error()
+        }
+        Ok((_, None)) => Error::new_spanned(key, message).into_compile_error(),
+        Err(err) => {
+            let message = format!("invalid value of entry name `{}`: {}", key, err);
+            Error::new(err.span(), message).into_compile_error()
+        }
+    }
+}
+
+/// Expand `spanned_key!` into the expression of a key, or into a compile
+/// error pointing at the first token after the expression, which is missing
+/// the `:` ending the key.
+pub(crate) fn expand_key(tokens: TokenStream2) -> TokenStream2 {
+    match split_expr(tokens.clone()) {
+        Ok((_, None)) => tokens,
+        Ok((key, Some(unexpected))) => missing_colon(key, unexpected),
+        Err(err) => err.into_compile_error(),
+    }
+}
+
+/// Expand `spanned_key_error!` like `spanned_error!`, unless the tokens of
+/// the key are an expression followed by other tokens, pointing then at the
+/// first of them, which is missing the `:` ending the key.
+pub(crate) fn expand_key_error(error: SpecError) -> TokenStream2 {
+    match split_expr(error.tokens.clone()) {
+        Ok((key, Some(unexpected))) => missing_colon(key, unexpected),
+        _ => expand(error),
+    }
+}
+
+fn missing_colon(key: Expr, unexpected: TokenTree) -> TokenStream2 {
+    let message = format!(
+        "expected `:` after entry name `{}`, found `{}`",
+        ungroup(key.into_token_stream()),
+        unexpected
+    );
+    Error::new_spanned(unexpected, message).into_compile_error()
+}
+
+/// The leading expression of `tokens` and the first token after it, if any.
+fn split_expr(tokens: TokenStream2) -> syn::Result<(Expr, Option<TokenTree>)> {
+    let parser = |input: ParseStream| {
+        let expr = input.parse()?;
+        let rest: TokenStream2 = input.parse()?;
+        Ok((expr, rest.into_iter().next()))
+    };
+    parser.parse2(tokens)
+}
+
+/// The `tokens` without the invisible groups wrapping the fragments passed
+/// through `macro_rules!`, whose span is not the one of their tokens.
+pub(crate) fn ungroup(tokens: TokenStream2) -> TokenStream2 {
+    tokens
+        .into_iter()
+        .flat_map(|token| match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+                ungroup(group.stream())
+            }
+            token => token.into(),
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{Error, LitStr};
+
+/// Expand `include_tree!` into the tree of the directory at `path`, relative
+/// to the manifest directory of the crate being compiled.
+pub(crate) fn expand(path: LitStr) -> syn::Result<TokenStream2> {
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let dir = manifest_dir.join(path.value());
+    let metadata = fs::metadata(&dir).map_err(|err| {
+        Error::new(
+            path.span(),
+            format!("cannot read {}: {}", dir.display(), err),
+        )
+    })?;
+    if !metadata.is_dir() {
+        return Err(Error::new(
+            path.span(),
+            format!("{} is not a directory", dir.display()),
+        ));
+    }
+    let tree = tree(&dir).map_err(|err| Error::new(path.span(), err))?;
+    Ok(quote!(#tree))
+}
+
+/// Tokens building the tree of the entries of `dir`, sorted by name.
+fn tree(dir: &Path) -> Result<TokenStream2, String> {
+    let read_error =
+        |path: &Path, err: std::io::Error| format!("cannot read {}: {}", path.display(), err);
+    let mut entries = fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| read_error(dir, err))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut pushes = Vec::new();
+    for entry in entries {
+        let path = entry.path();
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|name| format!("{:?} is not a valid UTF-8 name", name))?;
+        let file_type = entry.file_type().map_err(|err| read_error(&path, err))?;
+        let value = if file_type.is_dir() {
+            let tree = tree(&path)?;
+            quote!(::macro_files::Entry::Dir(#tree))
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(&path).map_err(|err| read_error(&path, err))?;
+            let target = target
+                .to_str()
+                .ok_or_else(|| format!("{:?} is not a valid UTF-8 target", target))?;
+            quote!(::macro_files::symlink(#target))
+        } else if file_type.is_file() {
+            file(&path)?
+        } else {
+            return Err(format!("{} is not a file nor a directory", path.display()));
+        };
+        pushes.push(quote!(tree.push(#name, #value);));
+    }
+    Ok(quote!({
+        #[allow(unused_mut)]
+        let mut tree = ::macro_files::Tree::new();
+        #(#pushes)*
+        tree
+    }))
+}
+
+/// Tokens embedding the file at `path`, executable if it is on Unix.
+fn file(path: &Path) -> Result<TokenStream2, String> {
+    let absolute = path
+        .to_str()
+        .ok_or_else(|| format!("{:?} is not a valid UTF-8 path", path))?;
+    let contents = LitStr::new(absolute, Span::call_site());
+    let file = quote!(::macro_files::File::new(include_bytes!(#contents)));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?
+            .permissions()
+            .mode();
+        if mode & 0o111 != 0 {
+            return Ok(quote!(#file.executable()));
+        }
+    }
+    Ok(file)
+}
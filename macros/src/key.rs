@@ -0,0 +1,151 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, bracketed, Error, Lit};
+
+use crate::error::ungroup;
+
+/// A string literal key of a spec, whether it is at the root of the spec,
+/// and the check failing at compile time for the keys whose validity depends
+/// on the target platform, written `root "key" { check }`.
+pub(crate) struct KeyCheck {
+    is_root: bool,
+    key: Lit,
+    fallback: TokenStream2,
+}
+
+impl Parse for KeyCheck {
+    fn parse(input: ParseStream) -> syn::Result<KeyCheck> {
+        let position: Ident = input.parse()?;
+        let is_root = match position.to_string().as_str() {
+            "root" => true,
+            "nested" => false,
+            _ => return Err(Error::new(position.span(), "expected `root` or `nested`")),
+        };
+        let key = input.parse()?;
+        let content;
+        braced!(content in input);
+        let fallback = content.parse()?;
+        Ok(KeyCheck {
+            is_root,
+            key,
+            fallback,
+        })
+    }
+}
+
+/// Expand `check_key!` into a compile error pointing at an invalid key, or
+/// into the fallback check when the key is valid or its validity depends on
+/// the target platform.
+pub(crate) fn expand(input: TokenStream2) -> TokenStream2 {
+    let check: KeyCheck = match syn::parse2(ungroup(input)) {
+        Ok(check) => check,
+        Err(err) => return err.into_compile_error(),
+    };
+    let bytes = match key_bytes(&check.key) {
+        // Keys with a Windows prefix such as `C:` only exist on Windows.
+        Some(bytes) if !has_prefix(&bytes) => bytes,
+        _ => return check.fallback,
+    };
+    match reason(&bytes, check.is_root) {
+        Some(reason) => {
+            let message = format!("invalid key {}: {}", check.key.to_token_stream(), reason);
+            Error::new(check.key.span(), message).into_compile_error()
+        }
+        None => check.fallback,
+    }
+}
+
+/// The string literal keys of a map and the check failing at compile time
+/// when one is declared twice, written `["key" ...] { check }`.
+pub(crate) struct UniqueKeys {
+    keys: Vec<Lit>,
+    fallback: TokenStream2,
+}
+
+impl Parse for UniqueKeys {
+    fn parse(input: ParseStream) -> syn::Result<UniqueKeys> {
+        let keys;
+        bracketed!(keys in input);
+        let mut lits = Vec::new();
+        while !keys.is_empty() {
+            lits.push(keys.parse()?);
+        }
+        let content;
+        braced!(content in input);
+        let fallback = content.parse()?;
+        Ok(UniqueKeys {
+            keys: lits,
+            fallback,
+        })
+    }
+}
+
+/// Expand `check_unique_keys!` into a compile error pointing at the second
+/// occurrence of a key declared twice, or into the fallback check when the
+/// keys are all different or are not all string literals.
+pub(crate) fn expand_unique(input: TokenStream2) -> TokenStream2 {
+    let check: UniqueKeys = match syn::parse2(ungroup(input)) {
+        Ok(check) => check,
+        Err(err) => return err.into_compile_error(),
+    };
+    let bytes: Option<Vec<_>> = check.keys.iter().map(key_bytes).collect();
+    let bytes = match bytes {
+        Some(bytes) => bytes,
+        None => return check.fallback,
+    };
+    let duplicate = (1..bytes.len()).find(|&i| bytes[..i].contains(&bytes[i]));
+    match duplicate {
+        Some(i) => {
+            let key = &check.keys[i];
+            let message = format!("duplicate key {}", key.to_token_stream());
+            Error::new(key.span(), message).into_compile_error()
+        }
+        None => check.fallback,
+    }
+}
+
+/// The bytes of a string or byte string literal key.
+fn key_bytes(key: &Lit) -> Option<Vec<u8>> {
+    match key {
+        Lit::Str(key) => Some(key.value().into_bytes()),
+        Lit::ByteStr(key) => Some(key.value()),
+        _ => None,
+    }
+}
+
+fn has_prefix(bytes: &[u8]) -> bool {
+    bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic()
+}
+
+/// Why a key without a Windows prefix is invalid, if it is, mirroring
+/// `key_check` of the `macro_files` crate.
+fn reason(bytes: &[u8], is_root: bool) -> Option<&'static str> {
+    if bytes.contains(&b'\\') {
+        return Some("use `/` as the separator in keys");
+    }
+    let absolute = bytes.first() == Some(&b'/');
+    if absolute && !is_root {
+        return Some("the key must be a relative path");
+    }
+    let (mut len, mut depth) = (absolute as usize, 0usize);
+    for name in bytes.split(|&byte| byte == b'/') {
+        match name {
+            b".." if depth > 0 => {
+                len -= 1;
+                depth -= 1;
+            }
+            b".." if is_root => len += 1,
+            b".." => return Some("the key escapes its parent directory"),
+            b"" | b"." => {}
+            _ => {
+                len += 1;
+                depth += 1;
+            }
+        }
+    }
+    if len == 0 {
+        return Some("the key is empty");
+    }
+    None
+}
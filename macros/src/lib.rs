@@ -0,0 +1,157 @@
+//! Procedural macros of the [`macro_files`](https://crates.io/crates/macro_files)
+//! crate, re-exported by it with the features `attributes`, `diagnostics`
+//! and `include`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Error, FnArg, ItemFn, LitStr, PatType, Type};
+
+mod error;
+mod include;
+mod key;
+
+/// Create a spec within a temporary directory before running the function,
+/// passing the directory as its single parameter.
+///
+/// The arguments are the ones of `create_temp!`. The parameter is a
+/// `TempDir`, or a `TempTree` to declare nested temporary directories like
+/// with `create_temp_tree!`. The function panics if the spec cannot be
+/// created. Place the attribute above `#[test]`.
+///
+/// ```ignore
+/// use macro_files::tempfile::TempDir;
+///
+/// #[macro_files::temp_tree({
+///     "README.md": "# Project",
+/// })]
+/// #[test]
+/// fn read_readme(dir: TempDir) {
+///     assert!(dir.path().join("README.md").is_file());
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn temp_tree(args: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    expand(args.into(), function)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(args: TokenStream2, mut function: ItemFn) -> syn::Result<TokenStream2> {
+    if args.is_empty() {
+        return Err(Error::new(
+            function.sig.ident.span(),
+            "expected the spec of the temporary directory: #[temp_tree({ ... })]",
+        ));
+    }
+    let inputs = std::mem::take(&mut function.sig.inputs);
+    let (pat, ty) = match inputs.iter().collect::<Vec<_>>().as_slice() {
+        [FnArg::Typed(PatType { pat, ty, .. })] => (pat.clone(), ty.clone()),
+        _ => {
+            return Err(Error::new(
+                inputs.span(),
+                "expected a single parameter receiving the temporary directory",
+            ))
+        }
+    };
+    let create = if is_temp_tree(&ty) {
+        quote!(::macro_files::create_temp_tree!)
+    } else {
+        quote!(::macro_files::create_temp!)
+    };
+    let block = function.block;
+    function.block = syn::parse_quote!({
+        let #pat: #ty = #create(#args)
+            .expect("failed to create the temporary directory");
+        #block
+    });
+    Ok(quote!(#function))
+}
+
+/// Whether the parameter is a `TempTree`, whatever its path.
+fn is_temp_tree(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == "TempTree"),
+        _ => false,
+    }
+}
+
+/// Embed the directory at the given path, relative to the manifest directory
+/// of the crate, as a `Tree` recreating it at runtime.
+///
+/// Files are embedded with `include_bytes!`, executable ones staying
+/// executable on Unix, and symlinks are recreated with their target.
+/// Entries are sorted by name. Changes to the embedded files trigger a
+/// rebuild, files added to the directory do not.
+///
+/// ```ignore
+/// let skeleton = macro_files::include_tree!("tests/fixtures/skeleton");
+/// skeleton.create_in("new-project").unwrap();
+/// ```
+#[proc_macro]
+pub fn include_tree(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    include::expand(path)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Report an error of a spec, `"message" [tokens]`, pointing at the tokens
+/// at fault rather than at the whole macro call.
+#[doc(hidden)]
+#[proc_macro]
+pub fn spanned_error(input: TokenStream) -> TokenStream {
+    let error = parse_macro_input!(input as error::SpecError);
+    error::expand(error).into()
+}
+
+/// Report an error following the value of an entry, `"message" [key]
+/// [tokens]`, pointing at the first token after the value rather than at the
+/// key.
+#[doc(hidden)]
+#[proc_macro]
+pub fn spanned_value_error(input: TokenStream) -> TokenStream {
+    let error = parse_macro_input!(input as error::ValueError);
+    error::expand_value(error).into()
+}
+
+/// Expand to the expression of a key made of several tokens, pointing at the
+/// first token after the expression when the key is missing its `:`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn spanned_key(input: TokenStream) -> TokenStream {
+    error::expand_key(input.into()).into()
+}
+
+/// Report an error of the key of an entry, `"message" [tokens]`, pointing at
+/// the first token after the key when it is missing its `:`, and at the key
+/// otherwise.
+#[doc(hidden)]
+#[proc_macro]
+pub fn spanned_key_error(input: TokenStream) -> TokenStream {
+    let error = parse_macro_input!(input as error::SpecError);
+    error::expand_key_error(error).into()
+}
+
+/// Check a string literal key of a spec, `root "key" { check }`, pointing at
+/// the key when it is invalid and expanding to the check otherwise.
+#[doc(hidden)]
+#[proc_macro]
+pub fn check_key(input: TokenStream) -> TokenStream {
+    key::expand(input.into()).into()
+}
+
+/// Check the string literal keys of a map, `["key" ...] { check }`, pointing
+/// at the second occurrence of a key declared twice and expanding to the
+/// check otherwise.
+#[doc(hidden)]
+#[proc_macro]
+pub fn check_unique_keys(input: TokenStream) -> TokenStream {
+    key::expand_unique(input.into()).into()
+}
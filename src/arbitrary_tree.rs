@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{Entry, Tree};
+
+/// Maximum number of nested directory levels of an arbitrary tree.
+const MAX_DEPTH: u32 = 4;
+/// Maximum number of entries of a directory of an arbitrary tree.
+const MAX_ENTRIES: u32 = 8;
+/// Maximum length in characters of the names of an arbitrary tree.
+const MAX_NAME_LEN: usize = 32;
+
+/// Generate trees of directories and files from fuzzer bytes, to fuzz code
+/// walking directories with `cargo fuzz`.
+///
+/// _This requires the feature `arbitrary`._
+///
+/// The trees have up to 4 levels of directories of up to 8 entries. Keys are
+/// single names, unique within their directory, of up to 32 characters
+/// without path separators or NUL characters, and never `.` or `..`, so the
+/// trees can be created in an empty directory, preferably sandboxed, see
+/// [`Options::sandbox`](crate::Options::sandbox). Names may differ by case
+/// only, which case-insensitive filesystems cannot tell apart.
+///
+/// ```
+/// use macro_files::arbitrary::{Arbitrary, Unstructured};
+/// use macro_files::{Options, Tree};
+///
+/// let data = [3, 1, 7, 0x61, 0x62, 4, 2, 0, 0x68, 0x69, 9, 8];
+/// let tree = Tree::arbitrary(&mut Unstructured::new(&data)).unwrap();
+///
+/// let dir = macro_files::tempfile::tempdir().unwrap();
+/// tree.create_with(dir.path(), &Options::new().sandbox(true))
+///     .unwrap();
+/// ```
+impl<'a> Arbitrary<'a> for Tree {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Tree> {
+        arbitrary_tree(u, MAX_DEPTH)
+    }
+}
+
+fn arbitrary_tree(u: &mut Unstructured<'_>, depth: u32) -> Result<Tree> {
+    let mut entries = BTreeMap::new();
+    for _ in 0..u.int_in_range(0..=MAX_ENTRIES)? {
+        let name = arbitrary_name(u)?;
+        let entry = if depth > 0 && u.ratio(1, 3)? {
+            Entry::Dir(arbitrary_tree(u, depth - 1)?)
+        } else {
+            Entry::file(Vec::<u8>::arbitrary(u)?)
+        };
+        entries.insert(name, entry);
+    }
+    let mut tree = Tree::new();
+    for (name, entry) in entries {
+        tree.push(name, entry);
+    }
+    Ok(tree)
+}
+
+/// A valid entry name: path separators and NUL characters are removed, and
+/// empty names or names made of dots only are prefixed with `_`.
+fn arbitrary_name(u: &mut Unstructured<'_>) -> Result<String> {
+    let mut name: String = <&str>::arbitrary(u)?
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | '\0'))
+        .take(MAX_NAME_LEN)
+        .collect();
+    if name.chars().all(|c| c == '.') {
+        name.insert(0, '_');
+    }
+    Ok(name)
+}
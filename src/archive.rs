@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::attrs::Attrs;
+use crate::copy::read_sorted_dir;
+use crate::path::{relative_key, Declared};
+use crate::{Content, Entry, File, Tree};
+
+/// Writer of the entries of a tree into an archive, with paths relative to
+/// the root of the archive.
+pub(crate) trait ArchiveWriter {
+    /// Name of the archive format, used in error messages.
+    const FORMAT: &'static str;
+
+    fn dir(&mut self, path: &Path, attrs: &Attrs) -> Result<()>;
+
+    fn file(&mut self, path: &Path, contents: &[u8], attrs: &Attrs) -> Result<()>;
+
+    fn symlink(&mut self, path: &Path, target: &Path, attrs: &Attrs) -> Result<()>;
+
+    fn hard_link(&mut self, path: &Path, original: &Path, attrs: &Attrs) -> Result<()>;
+
+    fn special(&mut self, path: &Path, file: &File) -> Result<()>;
+}
+
+/// Unix mode of a file in an archive: its declared mode, or else `0o644`,
+/// without the write permissions if read-only, with the matching execute
+/// permissions if executable.
+pub(crate) fn file_mode(attrs: &Attrs) -> u32 {
+    let mode = attrs.flags.mode.map_or(0o644, |mode| mode & 0o7777);
+    let mode = if attrs.flags.readonly {
+        mode & !0o222
+    } else {
+        mode
+    };
+    if attrs.flags.executable {
+        mode | 0o111
+    } else {
+        mode
+    }
+}
+
+/// Error returned for an entry which cannot be written into an archive of
+/// the given format.
+pub(crate) fn unsupported(entry: &str, path: &Path, format: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "{} {:?} cannot be written into a {} archive",
+            entry, path, format
+        ),
+    )
+}
+
+/// Write the entries of the tree into an archive sorted by path, so that the
+/// same spec always gives the same archive, whatever the order its entries
+/// are declared in.
+///
+/// Keys are validated like on creation and must be relative paths. The
+/// directories implied by nested keys are written before their entries, and
+/// hard links after all the other entries so that their original is always
+/// written first.
+pub(crate) fn write_archive<W: ArchiveWriter>(tree: &Tree, writer: &mut W) -> Result<()> {
+    let mut walk = Walk {
+        format: W::FORMAT,
+        declared: Declared::new(false),
+        records: BTreeMap::new(),
+        links: BTreeMap::new(),
+    };
+    walk.entries(tree, Path::new(""))?;
+    let default = Attrs::default();
+    for (path, record) in &walk.records {
+        match record {
+            Record::Dir(attrs) => writer.dir(path, attrs.unwrap_or(&default))?,
+            Record::File(file) => write_file(writer, path, file)?,
+            Record::Copied(source) => {
+                let contents = std::fs::read(source)?;
+                writer.file(path, &contents, &default)?;
+            }
+        }
+    }
+    for (path, file) in &walk.links {
+        write_file(writer, path, file)?;
+    }
+    Ok(())
+}
+
+fn write_file<W: ArchiveWriter>(writer: &mut W, path: &Path, file: &File) -> Result<()> {
+    match file.content() {
+        Content::Symlink(target) | Content::Junction(target) => {
+            writer.symlink(path, target, file.attrs())
+        }
+        Content::HardLink(original) => {
+            let original = relative_key(original)?;
+            writer.hard_link(path, &original, file.attrs())
+        }
+        _ if file.is_special() => writer.special(path, file),
+        _ => {
+            let contents = file.read_contents()?;
+            file.verify(path, Some(&contents))?;
+            writer.file(path, &contents, file.attrs())
+        }
+    }
+}
+
+/// An entry to write into an archive.
+enum Record<'a> {
+    /// A directory with its declared attributes, if any.
+    Dir(Option<&'a Attrs>),
+    File(&'a File),
+    /// A file copied from the given path, see [`copy_dir`](crate::copy_dir).
+    Copied(PathBuf),
+}
+
+struct Walk<'a> {
+    format: &'static str,
+    declared: Declared,
+    /// Entries other than hard links, sorted by path.
+    records: BTreeMap<PathBuf, Record<'a>>,
+    links: BTreeMap<PathBuf, &'a File>,
+}
+
+impl<'a> Walk<'a> {
+    fn entries(&mut self, tree: &'a Tree, dir: &Path) -> Result<()> {
+        for (key, entry) in tree.entries() {
+            let path = dir.join(relative_key(key)?);
+            self.declared.declare(key, &path)?;
+            if let Entry::Skip(_) = entry {
+                continue;
+            }
+            self.parents(&path);
+            match entry {
+                #[cfg(feature = "tempfile")]
+                Entry::Dir(tree) if tree.is_temp() => {
+                    return Err(unsupported(
+                        "nested temporary directory",
+                        &path,
+                        self.format,
+                    ));
+                }
+                Entry::Dir(tree) => {
+                    self.records
+                        .insert(path.clone(), Record::Dir(Some(tree.attrs())));
+                    self.entries(tree, &path)?;
+                }
+                Entry::File(file) if matches!(file.content(), Content::HardLink(_)) => {
+                    self.links.insert(path, file);
+                }
+                Entry::File(file) => {
+                    self.records.insert(path, Record::File(file));
+                }
+                Entry::CopyDir(source) => {
+                    self.dir(path.clone());
+                    self.copied(source, &path)?;
+                }
+                Entry::Keep => self.dir(path),
+                Entry::Skip(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the parent directories of `path` which are not declared.
+    fn parents(&mut self, path: &Path) {
+        for ancestor in path.ancestors().skip(1) {
+            if ancestor.as_os_str().is_empty() {
+                break;
+            }
+            self.dir(ancestor.to_owned());
+        }
+    }
+
+    /// Add a directory without attributes, unless it is declared.
+    fn dir(&mut self, path: PathBuf) {
+        self.records.entry(path).or_insert(Record::Dir(None));
+    }
+
+    /// Add the entries of the `source` directory into `dir`.
+    fn copied(&mut self, source: &Path, dir: &Path) -> Result<()> {
+        for (source, is_dir) in read_sorted_dir(source)? {
+            let path = dir.join(source.file_name().unwrap_or_default());
+            if is_dir {
+                self.dir(path.clone());
+                self.copied(&source, &path)?;
+            } else {
+                self.records.insert(path, Record::Copied(source));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use std::ffi::OsStr;
+
+use crate::TempTree;
+
+impl TempTree {
+    /// An [`assert_cmd::Command`] running `program` within the main
+    /// directory, to assert the output of the binary under test run on the
+    /// created fixture, see [`TempTree::command`].
+    ///
+    /// _This requires the feature `assert-cmd`._
+    ///
+    /// ```
+    /// let tree = macro_files::create_temp_tree!({
+    ///     "app": macro_files::presets::cargo_bin("app"),
+    /// })
+    /// .unwrap();
+    ///
+    /// tree.assert_command(env!("CARGO"))
+    ///     .args(["metadata", "--no-deps", "--offline", "--manifest-path", "app/Cargo.toml"])
+    ///     .assert()
+    ///     .success();
+    /// ```
+    pub fn assert_command<S: AsRef<OsStr>>(&self, program: S) -> assert_cmd::Command {
+        assert_cmd::Command::from_std(self.command(program))
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use predicates_core::reflection::{Case, PredicateReflection, Product};
+use predicates_core::Predicate;
+
+use crate::temp::parent;
+use crate::{keeps_temp_dirs, Tree};
+
+/// Create an [`assert_fs::TempDir`] within the [`temp_root`](crate::temp_root),
+/// kept if [`keeps_temp_dirs`].
+#[doc(hidden)]
+pub fn assert_temp_dir() -> Result<assert_fs::TempDir> {
+    let keep = keeps_temp_dirs();
+    let dir = assert_fs::TempDir::new_in(parent(None)?)
+        .map_err(|err| Error::new(ErrorKind::Other, err))?
+        .into_persistent_if(keep);
+    if keep {
+        eprintln!("macro_files: keeping {}", dir.path().display());
+    }
+    Ok(dir)
+}
+
+/// A predicate on paths matching the directories whose contents match a
+/// tree, to check directories with [`assert_fs`] assertions, see
+/// [`matches_tree!`](crate::matches_tree).
+///
+/// _This requires the feature `assert-fs`._
+///
+/// Directories are compared with [`Tree::diff`], whose differences are
+/// reported when the assertion fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreePredicate {
+    tree: Tree,
+}
+
+impl Tree {
+    /// A predicate matching the directories whose contents match the tree,
+    /// see [`TreePredicate`].
+    ///
+    /// _This requires the feature `assert-fs`._
+    pub fn predicate(self) -> TreePredicate {
+        TreePredicate { tree: self }
+    }
+}
+
+impl TreePredicate {
+    /// The tree the directories are compared to.
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// The differences between the directory at `path` and the tree, as
+    /// reported by [`Tree::diff`], or the error reading the directory.
+    fn differences(&self, path: &Path) -> Option<String> {
+        match self.tree.diff(path) {
+            Ok(diff) if diff.is_empty() => None,
+            Ok(diff) => Some(diff.to_string()),
+            Err(err) => Some(err.to_string()),
+        }
+    }
+}
+
+impl Predicate<Path> for TreePredicate {
+    fn eval(&self, path: &Path) -> bool {
+        self.differences(path).is_none()
+    }
+
+    fn find_case<'a>(&'a self, expected: bool, path: &Path) -> Option<Case<'a>> {
+        let differences = self.differences(path);
+        if differences.is_none() != expected {
+            return None;
+        }
+        let case = Case::new(Some(self), expected);
+        Some(match differences {
+            Some(differences) => case.add_product(Product::new("diff", differences)),
+            None => case,
+        })
+    }
+}
+
+impl PredicateReflection for TreePredicate {}
+
+impl fmt::Display for TreePredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "directory matches the tree")
+    }
+}
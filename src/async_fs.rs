@@ -0,0 +1,564 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use tokio::task::{JoinError, JoinSet};
+
+use crate::error::{Context, Failures};
+use crate::options::{check_empty, check_prefix, claim, claim_dir, Claim};
+use crate::path::Declared;
+use crate::quota::{budget_exceeded, charge};
+use crate::remove::clean_dir;
+use crate::sandbox::{confine, resolve};
+use crate::tree::{contents_hash, Original};
+use crate::{
+    entry_path, Conflict, Content, CreateReport, Entry, EntryKind, File, FsOperation, FsOps,
+    Options, StdFs, SymlinkFallback, Tree, WriteOptions,
+};
+
+/// Create a directory and all its missing parents with [`tokio::fs`].
+pub async fn create_dir_async<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    tokio::fs::create_dir_all(path)
+        .await
+        .context(FsOperation::CreateDir, path)
+}
+
+/// Write a file, creating its missing parent directories, with
+/// [`tokio::fs`].
+pub async fn write_file_async<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()> {
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    let write = async {
+        match tokio::fs::write(path, contents).await {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                tokio::fs::create_dir_all(dir_path).await?;
+                tokio::fs::write(path, contents).await
+            }
+            result => result,
+        }
+    };
+    write.await.context(FsOperation::WriteFile, path)
+}
+
+/// Copy a file, creating the missing parent directories of the destination,
+/// with [`tokio::fs`].
+pub async fn copy_file_async<F: AsRef<Path>, T: AsRef<Path>>(from: F, to: T) -> Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let copy = async {
+        match tokio::fs::copy(from, to).await {
+            Err(err) if err.kind() == ErrorKind::NotFound && from.is_file() => {
+                let dir_path = to.parent().ok_or(err)?;
+                tokio::fs::create_dir_all(dir_path).await?;
+                tokio::fs::copy(from, to).await.map(drop)
+            }
+            result => result.map(drop),
+        }
+    };
+    copy.await.context_with(FsOperation::CopyFile, to, from)
+}
+
+/// Create a symbolic link at `path` pointing to `target`, creating the
+/// missing parent directories of `path`, on the blocking thread pool of
+/// [`tokio`].
+pub async fn create_symlink_async<T, P>(target: T, path: P) -> Result<()>
+where
+    T: AsRef<Path>,
+    P: AsRef<Path>,
+{
+    let (target, path) = (target.as_ref().to_owned(), path.as_ref().to_owned());
+    let link = (target.clone(), path.clone());
+    tokio::task::spawn_blocking(move || StdFs.create_symlink(&link.0, &link.1))
+        .await
+        .map_err(|err| std::io::Error::new(ErrorKind::Other, err))?
+        .context_with(FsOperation::CreateSymlink, &path, &target)
+}
+
+/// Create a hard link at `link` to the existing file `original`, creating
+/// the missing parent directories of `link`, with [`tokio::fs`].
+pub async fn create_hard_link_async<O, L>(original: O, link: L) -> Result<()>
+where
+    O: AsRef<Path>,
+    L: AsRef<Path>,
+{
+    let (original, link) = (original.as_ref(), link.as_ref());
+    let hard_link = async {
+        match tokio::fs::hard_link(original, link).await {
+            Err(err) if err.kind() == ErrorKind::NotFound && original.is_file() => {
+                let dir_path = link.parent().ok_or(err)?;
+                tokio::fs::create_dir_all(dir_path).await?;
+                tokio::fs::hard_link(original, link).await
+            }
+            result => result,
+        }
+    };
+    hard_link
+        .await
+        .context_with(FsOperation::CreateHardLink, link, original)
+}
+
+impl Tree {
+    /// Create the directories and files of the tree within `dir` with
+    /// [`tokio::fs`], see [`Tree::create_in`].
+    ///
+    /// Directories are created in order before their entries while files are
+    /// written concurrently, with at most [`fd_budget`](crate::fd_budget)
+    /// files being written at the same time. On error, the pending writes are
+    /// aborted.
+    ///
+    /// The filesystem operations overridden with
+    /// [`with_fs_ops`](crate::with_fs_ops) are not used.
+    pub async fn create_in_async<P: AsRef<Path>>(&self, dir: P) -> Result<CreateReport> {
+        self.create_with_async(dir, &Options::default()).await
+    }
+
+    /// Create the directories and files of the tree within `dir` with
+    /// [`tokio::fs`] and the given options, see [`Tree::create_in_async`].
+    pub async fn create_with_async<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: &Options,
+    ) -> Result<CreateReport> {
+        let start = std::time::Instant::now();
+        let mut report = CreateReport::default();
+        let max_bytes = options.declared_max_bytes();
+        if let Some(max_bytes) = max_bytes {
+            if !options.deduplicates() && self.stats().bytes > max_bytes {
+                return Err(budget_exceeded(max_bytes));
+            }
+        }
+        if let Some(prefix) = options.required_prefix() {
+            check_prefix(dir.as_ref(), prefix)?;
+        }
+        if options.requires_empty() {
+            check_empty(dir.as_ref())?;
+        }
+        if options.prints_rerun_if_changed() {
+            self.print_rerun_if_changed();
+        }
+        if options.cleans() {
+            let dir = dir.as_ref().to_owned();
+            let removed = tokio::task::spawn_blocking(move || clean_dir(&dir))
+                .await
+                .map_err(|err| std::io::Error::new(ErrorKind::Other, err))??;
+            report.set_removed(removed);
+        }
+        if let Some(quota) = options.declared_quota() {
+            let (quota, dir, needed) = (quota.clone(), dir.as_ref().to_owned(), self.stats().bytes);
+            let evicted = tokio::task::spawn_blocking(move || quota.before_create(&dir, needed))
+                .await
+                .map_err(|err| std::io::Error::new(ErrorKind::Other, err))??;
+            report.set_evicted(evicted);
+        }
+        let budget = crate::fd_budget();
+        let mut writes = JoinSet::new();
+        let mut failures = Failures::new(options);
+        // Bytes of file contents written, see `Options::max_bytes`.
+        let mut bytes = 0;
+        // Files written from their contents, see `Options::deduplicate`.
+        let mut originals: HashMap<u64, Vec<Original>> = HashMap::new();
+        let mut declared = Declared::new(options.allows_case_collisions());
+        // Directories whose attributes are applied once all files are written.
+        let mut dirs = Vec::new();
+        let root = dir.as_ref();
+        push_handles(&mut report, self, root)?;
+        let sandbox = if options.is_sandboxed() && !root.as_os_str().is_empty() {
+            Some(resolve(root)?)
+        } else {
+            None
+        };
+        let mut stack: Vec<(PathBuf, &Tree, _, _, _)> = vec![(
+            root.to_owned(),
+            self,
+            self.entries(),
+            self.origin_arc().cloned(),
+            sandbox,
+        )];
+        while let Some((dir, tree, entries, origin, sandbox)) = stack.last_mut() {
+            let (tree, origin) = (*tree, origin.clone());
+            let (key, entry) = match entries.next() {
+                Some(entry) => entry,
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            };
+            let declared_path = tree.key_path(dir, key).unwrap_or_else(|_| dir.join(key));
+            if let Err(err) = declared.declare(key, &declared_path) {
+                failures.push(declared_path, err)?;
+                continue;
+            }
+            if let Entry::Skip(reason) = entry {
+                report.push_skipped(declared_path, *reason);
+                continue;
+            }
+            let (dir, sandbox) = (dir.clone(), sandbox.clone());
+            let create = async {
+                let path = tree.key_path(&dir, key)?;
+                let is_dir = !matches!(entry, Entry::File(_));
+                if let Some(root) = &sandbox {
+                    confine(root, &path, is_dir)?;
+                }
+                // Sandboxed directories at the root of a tree created in the
+                // current directory are the root of their entries.
+                let sandbox = if options.is_sandboxed() && dir.as_os_str().is_empty() && is_dir {
+                    Some(resolve(&path)?)
+                } else {
+                    sandbox.clone()
+                };
+                match entry {
+                    #[cfg(feature = "tempfile")]
+                    Entry::Dir(tree) if tree.is_temp() => {
+                        return Err(std::io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!(
+                                "nested temporary directory {:?} is only created by create_temp_tree!",
+                                path
+                            ),
+                        ));
+                    }
+                    Entry::Dir(tree) => {
+                        let claim = claim_dir_async(options, &mut report, &path).await?;
+                        if claim == Claim::Skip {
+                            return Ok(());
+                        }
+                        let origin = tree.origin_arc().cloned().or(origin);
+                        if claim == Claim::Create {
+                            create_dir_async(&path).await?;
+                            let created =
+                                report.push_created(path.clone(), EntryKind::Dir, origin.clone());
+                            options.report_progress(created);
+                        }
+                        if claim == Claim::Create && !tree.attrs().is_empty() {
+                            dirs.push((path.clone(), tree.attrs()));
+                        }
+                        push_handles(&mut report, tree, &path)?;
+                        stack.push((path, tree, tree.entries(), origin, sandbox));
+                    }
+                    Entry::File(file) => {
+                        let conflict = file.declared_conflict().unwrap_or(options.conflict());
+                        if !file.appends()
+                            && !claim_async(conflict, &mut report, &path, file.is_link()).await?
+                        {
+                            return Ok(());
+                        }
+                        if writes.len() >= budget {
+                            join_write(writes.join_next().await, &mut failures)?;
+                        }
+                        let origin = file.origin_arc().cloned().or(origin);
+                        let created = report.push_created_file(path.clone(), file, origin);
+                        options.report_progress(created);
+                        let attrs = file.attrs().clone();
+                        let write = *options.write_options();
+                        if let (Some(max_bytes), Content::Reader(reader)) =
+                            (max_bytes, file.content())
+                        {
+                            // The remaining budget depends on the bytes read,
+                            // so the file is written before the next one.
+                            let (file, reader) = (file.clone(), reader.clone());
+                            let remaining = max_bytes.saturating_sub(bytes);
+                            let written = tokio::task::spawn_blocking(move || {
+                                file.write_reader_within(&reader, &path, &write, remaining)
+                            })
+                            .await
+                            .map_err(|err| std::io::Error::new(ErrorKind::Other, err))??;
+                            bytes += written.ok_or_else(|| budget_exceeded(max_bytes))?;
+                            return Ok(());
+                        }
+                        let size = file.len().or_else(|| {
+                            let metadata = std::fs::metadata(file.source()?).ok()?;
+                            Some(metadata.len())
+                        });
+                        // Contents are read to be compared when deduplicating,
+                        // and to be counted when their size is unknown.
+                        let read = match size {
+                            _ if options.deduplicates() && file.is_in_memory() => {
+                                Some(file.read_contents()?)
+                            }
+                            None if max_bytes.is_some() && file.is_in_memory() => {
+                                Some(file.read_contents()?)
+                            }
+                            _ => None,
+                        };
+                        if let (true, Some(contents)) = (options.deduplicates(), &read) {
+                            let originals = originals.entry(contents_hash(contents)).or_default();
+                            let index = originals.iter().position(|original| {
+                                original.contents == *contents && original.file.has_attrs_of(file)
+                            });
+                            match index {
+                                Some(index) => {
+                                    // The original file may still be being written.
+                                    while let Some(write) = writes.join_next().await {
+                                        join_write(Some(write), &mut failures)?;
+                                    }
+                                    let linked = match file.verify(&path, Some(contents)) {
+                                        Ok(()) => {
+                                            let original = originals[index].path.clone();
+                                            create_hard_link_async(original, &path).await
+                                        }
+                                        Err(err) => Err(err),
+                                    };
+                                    if linked.is_ok() {
+                                        return Ok(());
+                                    }
+                                    originals[index].path = path.clone();
+                                }
+                                None => originals.push(Original {
+                                    file,
+                                    contents: contents.clone(),
+                                    path: path.clone(),
+                                }),
+                            }
+                        }
+                        let read_len = read.as_ref().map(|contents| contents.len() as u64);
+                        charge(&mut bytes, size.or(read_len).unwrap_or(0), max_bytes)?;
+                        match file.content() {
+                            _ if file.appends() => {
+                                let file = file.clone();
+                                writes.spawn(with_path(path, move |path| {
+                                    write_blocking(file, path, write)
+                                }));
+                            }
+                            Content::CopyFrom(source) => {
+                                file.verify(&path, None)?;
+                                let source = source.to_owned();
+                                writes.spawn(with_path(path, |path| async move {
+                                    copy_file_async(source, &path).await?;
+                                    attrs.apply_async(&path).await
+                                }));
+                            }
+                            Content::Symlink(target)
+                                if write.symlink_fallback_policy() == SymlinkFallback::Error =>
+                            {
+                                let target = target.to_owned();
+                                writes.spawn(with_path(path, |path| async move {
+                                    create_symlink_async(target, &path).await?;
+                                    attrs.apply_async(&path).await
+                                }));
+                            }
+                            Content::HardLink(original) => {
+                                // The original file may still be being written.
+                                while let Some(write) = writes.join_next().await {
+                                    join_write(Some(write), &mut failures)?;
+                                }
+                                let original = entry_path(root, original)?;
+                                create_hard_link_async(original, &path).await?;
+                                attrs.apply_async(&path).await?;
+                            }
+                            #[cfg(all(feature = "unix", unix))]
+                            Content::Fifo | Content::Socket => {
+                                let file = file.clone();
+                                writes.spawn(with_path(path, move |path| {
+                                    write_blocking(file, path, write)
+                                }));
+                            }
+                            Content::Symlink(_)
+                            | Content::Junction(_)
+                            | Content::Zeros(_)
+                            | Content::Repeat { .. }
+                            | Content::Reader(_) => {
+                                let file = file.clone();
+                                writes.spawn(with_path(path, move |path| {
+                                    write_blocking(file, path, write)
+                                }));
+                            }
+                            content => {
+                                let contents = match read {
+                                    Some(contents) => contents.into_owned(),
+                                    None => content.read()?.into_owned(),
+                                };
+                                file.verify(&path, Some(&contents))?;
+                                writes.spawn(with_path(path, move |path| async move {
+                                    if write.writes_by_default() {
+                                        write_file_async(&path, contents).await?;
+                                    } else {
+                                        tokio::task::spawn_blocking({
+                                            let path = path.clone();
+                                            move || StdFs.write_file_with(&path, &contents, &write)
+                                        })
+                                        .await
+                                        .map_err(
+                                            |err| std::io::Error::new(ErrorKind::Other, err),
+                                        )??;
+                                    }
+                                    attrs.apply_async(&path).await
+                                }));
+                            }
+                        }
+                    }
+                    Entry::Keep => {
+                        if claim_dir_async(options, &mut report, &path).await? == Claim::Create {
+                            create_dir_async(&path).await?;
+                            let created = report.push_created(path, EntryKind::Dir, origin);
+                            options.report_progress(created);
+                        }
+                    }
+                    Entry::CopyDir(source) => {
+                        match claim_dir_async(options, &mut report, &path).await? {
+                            Claim::Create => {
+                                create_dir_async(&path).await?;
+                                let created = report.push_created(
+                                    path.clone(),
+                                    EntryKind::Dir,
+                                    origin.clone(),
+                                );
+                                options.report_progress(created);
+                            }
+                            Claim::Reuse => {}
+                            Claim::Replace | Claim::Skip => return Ok(()),
+                        }
+                        let mut sources = vec![(source.clone(), path)];
+                        while let Some((source, dir)) = sources.pop() {
+                            let mut entries = tokio::fs::read_dir(&source).await?;
+                            while let Some(entry) = entries.next_entry().await? {
+                                let (source, target) = (entry.path(), dir.join(entry.file_name()));
+                                let is_dir = tokio::fs::metadata(&source).await?.is_dir();
+                                if let Some(root) = &sandbox {
+                                    confine(root, &target, is_dir)?;
+                                }
+                                if is_dir {
+                                    match claim_dir_async(options, &mut report, &target).await? {
+                                        Claim::Create => {
+                                            create_dir_async(&target).await?;
+                                            let created = report.push_created(
+                                                target.clone(),
+                                                EntryKind::Dir,
+                                                origin.clone(),
+                                            );
+                                            options.report_progress(created);
+                                        }
+                                        Claim::Reuse => {}
+                                        Claim::Replace | Claim::Skip => continue,
+                                    }
+                                    sources.push((source, target));
+                                } else if claim_async(
+                                    options.conflict(),
+                                    &mut report,
+                                    &target,
+                                    false,
+                                )
+                                .await?
+                                {
+                                    if writes.len() >= budget {
+                                        join_write(writes.join_next().await, &mut failures)?;
+                                    }
+                                    let created = report.push_created(
+                                        target.clone(),
+                                        EntryKind::File,
+                                        origin.clone(),
+                                    );
+                                    options.report_progress(created);
+                                    writes.spawn(with_path(target, |target| async move {
+                                        copy_file_async(source, target).await
+                                    }));
+                                }
+                            }
+                        }
+                    }
+                    Entry::Skip(_) => {}
+                }
+                Ok(())
+            };
+            if let Err(err) = create.await {
+                failures.push(declared_path, err)?;
+            }
+        }
+        while let Some(write) = writes.join_next().await {
+            join_write(Some(write), &mut failures)?;
+        }
+        for (path, attrs) in dirs.iter().rev() {
+            if let Err(err) = attrs.apply_async(path).await {
+                failures.push(path.clone(), err)?;
+            }
+        }
+        if let Some(quota) = options.declared_quota() {
+            let (quota, dir) = (quota.clone(), root.to_owned());
+            tokio::task::spawn_blocking(move || quota.after_create(&dir))
+                .await
+                .map_err(|err| std::io::Error::new(ErrorKind::Other, err))??;
+        }
+        options.check_dangling_symlinks(report.created())?;
+        report.set_elapsed(start.elapsed());
+        failures.finish(report)
+    }
+}
+
+/// Whether the file at `path` must be created according to the `conflict`
+/// policy, removing the existing entry if it must be replaced.
+async fn claim_async(
+    conflict: Conflict,
+    report: &mut CreateReport,
+    path: &Path,
+    link: bool,
+) -> Result<bool> {
+    let existing = entry_kind_async(path).await?;
+    match claim(conflict, report, path, existing, link)? {
+        Claim::Create => Ok(true),
+        Claim::Replace => tokio::fs::remove_file(path).await.and(Ok(true)),
+        Claim::Skip | Claim::Reuse => Ok(false),
+    }
+}
+
+/// How to create the directory at `path` according to the conflict policy,
+/// see [`Conflict::Skip`].
+async fn claim_dir_async(
+    options: &Options,
+    report: &mut CreateReport,
+    path: &Path,
+) -> Result<Claim> {
+    let existing = if options.conflict() == Conflict::Skip {
+        entry_kind_async(path).await?
+    } else {
+        None
+    };
+    Ok(claim_dir(options, report, path, existing))
+}
+
+/// Kind of the entry at `path`, if any.
+async fn entry_kind_async(path: &Path) -> Result<Option<EntryKind>> {
+    match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) => Ok(Some(EntryKind::from(metadata.file_type()))),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Report the paths of the named entries of the tree created in `dir`.
+fn push_handles(report: &mut CreateReport, tree: &Tree, dir: &Path) -> Result<()> {
+    for (key, handle) in tree.handles() {
+        report.push_handle(handle.into(), tree.key_path(dir, key)?);
+    }
+    Ok(())
+}
+
+/// Write a file whose contents are not held in memory, or a link which may
+/// fall back to a copy, on the blocking thread pool of [`tokio`].
+async fn write_blocking(file: File, path: PathBuf, options: WriteOptions) -> Result<()> {
+    tokio::task::spawn_blocking(move || file.write(Path::new(""), &path, &options))
+        .await
+        .map_err(|err| std::io::Error::new(ErrorKind::Other, err))?
+}
+
+/// A write of the file at `path` returning the path with its result.
+fn with_path<F, W>(path: PathBuf, write: F) -> impl Future<Output = (PathBuf, Result<()>)>
+where
+    F: FnOnce(PathBuf) -> W,
+    W: Future<Output = Result<()>>,
+{
+    let write = write(path.clone());
+    async move { (path, write.await) }
+}
+
+/// Record the failure of a finished write, see [`Failures::push`].
+fn join_write(
+    write: Option<std::result::Result<(PathBuf, Result<()>), JoinError>>,
+    failures: &mut Failures,
+) -> Result<()> {
+    match write {
+        Some(Ok((path, Err(err)))) => failures.push(path, err),
+        Some(Ok((_, Ok(())))) | None => Ok(()),
+        Some(Err(err)) => Err(std::io::Error::new(ErrorKind::Other, err)),
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+use std::io::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::{set_file_attributes, set_times};
+
+/// Flags of a created file, see [`File::readonly`](crate::File::readonly),
+/// [`File::hidden`](crate::File::hidden),
+/// [`File::system`](crate::File::system),
+/// [`File::executable`](crate::File::executable),
+/// [`File::exact_permissions`](crate::File::exact_permissions) and
+/// [`File::mode`](crate::File::mode).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct FileAttributes {
+    /// The file cannot be written.
+    pub readonly: bool,
+    /// The file is hidden from directory listings, on Windows only.
+    pub hidden: bool,
+    /// The file is used by the operating system, on Windows only.
+    pub system: bool,
+    /// The file can be executed by whoever can read it, on Unix only.
+    pub executable: bool,
+    /// The permissions are set from `0o644`, or `0o755` for directories,
+    /// rather than from those given by the umask, on Unix only.
+    pub exact: bool,
+    /// The permission bits replacing those given by the umask, before the
+    /// other flags are applied, on Unix only.
+    pub mode: Option<u32>,
+}
+
+impl FileAttributes {
+    /// Whether no flag is set.
+    pub fn is_empty(&self) -> bool {
+        self == &FileAttributes::default()
+    }
+}
+
+/// Attributes of a created directory or file, applied once it is created
+/// with its entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Attrs {
+    pub(crate) accessed: Option<SystemTime>,
+    pub(crate) modified: Option<SystemTime>,
+    pub(crate) flags: FileAttributes,
+    #[cfg(all(feature = "unix", unix))]
+    pub(crate) owner: Option<(u32, u32)>,
+    pub(crate) streams: Vec<(String, Vec<u8>)>,
+    pub(crate) hooks: Vec<Hook>,
+}
+
+type BoxedHook = dyn Fn(&Path) -> Result<()> + Send + Sync;
+
+/// A function run with the path of an entry once created, see
+/// [`Tree::after_create`](crate::Tree::after_create) and
+/// [`File::after_create`](crate::File::after_create).
+///
+/// Two hooks are equal when they are clones of each other.
+#[derive(Clone)]
+pub(crate) struct Hook(Arc<BoxedHook>);
+
+impl Hook {
+    pub(crate) fn new<F>(hook: F) -> Hook
+    where
+        F: Fn(&Path) -> Result<()> + Send + Sync + 'static,
+    {
+        Hook(Arc::new(hook))
+    }
+}
+
+impl fmt::Debug for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+impl PartialEq for Hook {
+    fn eq(&self, other: &Hook) -> bool {
+        Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+    }
+}
+
+impl Eq for Hook {}
+
+/// Path of the alternate data stream `name` of the file at `path`, failing
+/// with an [`ErrorKind::InvalidInput`](std::io::ErrorKind) error when the name
+/// is not a valid stream name.
+#[cfg(windows)]
+fn stream_path(path: &Path, name: &str) -> Result<std::path::PathBuf> {
+    if name.is_empty() || name.contains(&[':', '/', '\\', '\0'][..]) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid alternate data stream name {:?}", name),
+        ));
+    }
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(":");
+    stream.push(name);
+    Ok(stream.into())
+}
+
+impl Attrs {
+    #[cfg(any(
+        feature = "tokio",
+        feature = "rayon",
+        all(feature = "io-uring", target_os = "linux")
+    ))]
+    pub(crate) fn is_empty(&self) -> bool {
+        self == &Attrs::default()
+    }
+
+    /// Run the hooks, then apply the attributes to the entry at `path` with
+    /// the current filesystem operations.
+    pub(crate) fn apply(&self, path: &Path) -> Result<()> {
+        // Run first as hooks may change the entry or its contents.
+        for Hook(hook) in &self.hooks {
+            hook(path)?;
+        }
+        #[cfg(all(feature = "unix", unix))]
+        if let Some((uid, gid)) = self.owner {
+            crate::set_owner(path, uid, gid)?;
+        }
+        #[cfg(windows)]
+        for (name, contents) in &self.streams {
+            crate::write_file(stream_path(path, name)?, contents)?;
+        }
+        if self.accessed.is_some() || self.modified.is_some() {
+            set_times(path, self.accessed, self.modified)?;
+        }
+        // Applied last as a read-only file may not accept other changes.
+        if !self.flags.is_empty() {
+            set_file_attributes(path, self.flags)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the attributes to the entry at `path` on the blocking thread
+    /// pool of [`tokio`].
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn apply_async(&self, path: &Path) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let (attrs, path) = (self.clone(), path.to_owned());
+        tokio::task::spawn_blocking(move || attrs.apply(&path))
+            .await
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::attrs::Attrs;
+use crate::{Content, Entry, File, Options, Tree};
+
+/// A directory of fixtures shared across test runs, each tree being created
+/// once in an entry named after the hash of its spec, then copied from there,
+/// see [`Options::cache`](crate::Options::cache).
+///
+/// Only trees whose contents are fully known from their spec are cached, see
+/// [`Tree::spec_hash`]. The fixtures are never invalidated, entries of the
+/// directory can be removed at any time, e.g. with a [`Quota`](crate::Quota)
+/// as the fixtures are touched each time they are copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// A cache of fixtures in `dir`, created when needed.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Cache {
+        Cache {
+            dir: dir.as_ref().to_owned(),
+        }
+    }
+
+    /// The directory of the cached fixtures.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the fixture of `tree`, whether it exists or not, `None` when
+    /// the tree cannot be cached.
+    pub fn fixture(&self, tree: &Tree) -> Option<PathBuf> {
+        let hash = tree.spec_hash()?;
+        Some(self.dir.join(format!("{:016x}", hash)))
+    }
+
+    /// Path of the fixture of `tree`, created first if missing, `None` when
+    /// the tree cannot be cached.
+    ///
+    /// The fixture is created in a temporary directory then renamed, so that
+    /// concurrent creations never observe a partial fixture.
+    pub(crate) fn materialize(&self, tree: &Tree, options: &Options) -> Result<Option<PathBuf>> {
+        let fixture = match self.fixture(tree) {
+            Some(fixture) => fixture,
+            None => return Ok(None),
+        };
+        if fixture.is_dir() {
+            // Best effort, the cache may be shared read-only.
+            let now = Some(SystemTime::now());
+            let _ = crate::set_times(&fixture, now, now);
+            return Ok(Some(fixture));
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        let partial = partial_dir(&self.dir, &fixture)?;
+        let options = Options::new().write(*options.write_options());
+        let created = tree.create_with(&partial, &options).map(|_| ());
+        let renamed = created.and_then(|()| std::fs::rename(&partial, &fixture));
+        if let Err(err) = renamed {
+            let _ = std::fs::remove_dir_all(&partial);
+            // Another creation renamed its fixture first.
+            if !fixture.is_dir() {
+                return Err(err);
+            }
+        }
+        Ok(Some(fixture))
+    }
+}
+
+/// An empty directory next to `fixture`, named after it and unique to the
+/// creation.
+fn partial_dir(dir: &Path, fixture: &Path) -> Result<PathBuf> {
+    let name = fixture.file_name().unwrap_or_default().to_string_lossy();
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    for attempt in 0u32.. {
+        let partial = dir.join(format!(
+            ".{}.{}.{}.{}",
+            name,
+            std::process::id(),
+            nanos,
+            attempt
+        ));
+        match std::fs::create_dir(&partial) {
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            result => return result.map(|()| partial),
+        }
+    }
+    unreachable!()
+}
+
+impl Tree {
+    /// Hash of the spec, equal for equal specs, `None` when the created
+    /// entries are not fully known from the spec: files copied, read,
+    /// generated, rendered or stamped, links, special files, hooks, times,
+    /// owners, hidden and system flags, alternate data streams, named,
+    /// temporary or copied directories, and attributes of directories.
+    ///
+    /// The hash is stable for a given version of this crate and of the Rust
+    /// standard library, not across them.
+    ///
+    /// ```
+    /// let first = macro_files::tree!({ "data.txt": "data" });
+    /// let second = macro_files::tree!({ "data.txt": "data" });
+    /// let linked = macro_files::tree!({ "link": macro_files::symlink("data.txt") });
+    ///
+    /// assert_eq!(first.spec_hash(), second.spec_hash());
+    /// assert!(first.spec_hash().is_some());
+    /// assert_eq!(linked.spec_hash(), None);
+    /// ```
+    pub fn spec_hash(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        if hash_tree(self, &mut hasher) {
+            Some(hasher.finish())
+        } else {
+            None
+        }
+    }
+}
+
+/// Hash the entries of `tree`, returning whether they can be cached.
+fn hash_tree(tree: &Tree, hasher: &mut DefaultHasher) -> bool {
+    if tree.handles().next().is_some() {
+        return false;
+    }
+    #[cfg(feature = "tempfile")]
+    if tree.is_temp() {
+        return false;
+    }
+    for (key, entry) in tree.entries() {
+        key.hash(hasher);
+        match entry {
+            Entry::Dir(tree) => {
+                0u8.hash(hasher);
+                if tree.attrs() != &Attrs::default() || !hash_tree(tree, hasher) {
+                    return false;
+                }
+                1u8.hash(hasher);
+            }
+            Entry::File(file) => {
+                2u8.hash(hasher);
+                if !hash_file(file, hasher) {
+                    return false;
+                }
+            }
+            Entry::Keep => 3u8.hash(hasher),
+            Entry::Skip(_) => 4u8.hash(hasher),
+            Entry::CopyDir(_) => return false,
+        }
+    }
+    true
+}
+
+/// Hash the contents and flags of `file`, returning whether it can be
+/// cached.
+fn hash_file(file: &File, hasher: &mut DefaultHasher) -> bool {
+    // Only the permissions are kept when the fixture is copied.
+    let flags = file.declared_attributes();
+    let attrs = Attrs {
+        flags,
+        ..Attrs::default()
+    };
+    if file.attrs() != &attrs
+        || flags.hidden
+        || flags.system
+        || file.stamp_arc().is_some()
+        || file.appends()
+    {
+        return false;
+    }
+    flags.hash(hasher);
+    match file.content() {
+        Content::Bytes(bytes) => (0u8, bytes.as_slice()).hash(hasher),
+        Content::Text(text) => (0u8, text.as_bytes()).hash(hasher),
+        Content::Zeros(len) => (1u8, len).hash(hasher),
+        Content::Random { len, seed } => (2u8, len, seed).hash(hasher),
+        Content::Repeat { pattern, len } => (3u8, len, pattern).hash(hasher),
+        _ => return false,
+    }
+    true
+}
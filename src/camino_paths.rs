@@ -0,0 +1,88 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use camino::Utf8Path;
+
+#[cfg(feature = "tempfile")]
+use crate::TempTree;
+use crate::{CreatedEntry, Manifest, SkippedEntry};
+
+/// The path as a [`Utf8Path`], to use the paths returned by this crate with
+/// [`camino`].
+///
+/// _This requires the feature `camino`._
+///
+/// Fails with an [`ErrorKind::InvalidData`] error if the path is not valid
+/// UTF-8. Paths are accepted as [`Utf8Path`] and [`Utf8PathBuf`](camino::Utf8PathBuf)
+/// everywhere, as they implement `AsRef<Path>`.
+///
+/// ```
+/// use macro_files::camino::Utf8PathBuf;
+///
+/// let dir = macro_files::create_temp!({
+///     Utf8PathBuf::from("docs"): {
+///         "README.md": "# Docs",
+///     },
+/// })
+/// .unwrap();
+///
+/// let root = macro_files::utf8_path(dir.path()).unwrap();
+/// assert!(root.join("docs/README.md").is_file());
+/// ```
+pub fn utf8_path<P: AsRef<Path> + ?Sized>(path: &P) -> Result<&Utf8Path> {
+    let path = path.as_ref();
+    Utf8Path::from_path(path).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{:?} is not valid UTF-8", path),
+        )
+    })
+}
+
+impl CreatedEntry {
+    /// Path of the entry as a [`Utf8Path`], `None` if it is not valid UTF-8.
+    ///
+    /// _This requires the feature `camino`._
+    pub fn utf8_path(&self) -> Option<&Utf8Path> {
+        Utf8Path::from_path(self.path())
+    }
+}
+
+impl SkippedEntry {
+    /// Path the entry would have as a [`Utf8Path`], `None` if it is not
+    /// valid UTF-8.
+    ///
+    /// _This requires the feature `camino`._
+    pub fn utf8_path(&self) -> Option<&Utf8Path> {
+        Utf8Path::from_path(self.path())
+    }
+}
+
+impl Manifest {
+    /// Absolute path of the entry named `handle` as a [`Utf8Path`], `None`
+    /// if there is none or it is not valid UTF-8, see [`Manifest::handle`].
+    ///
+    /// _This requires the feature `camino`._
+    pub fn utf8_handle(&self, handle: &str) -> Option<&Utf8Path> {
+        self.handle(handle).and_then(Utf8Path::from_path)
+    }
+
+    /// Absolute paths of the created directories and files as [`Utf8Path`]s,
+    /// in creation order, failing if one of them is not valid UTF-8.
+    ///
+    /// _This requires the feature `camino`._
+    pub fn utf8_paths(&self) -> Result<Vec<&Utf8Path>> {
+        self.paths().map(utf8_path).collect()
+    }
+}
+
+#[cfg(feature = "tempfile")]
+impl TempTree {
+    /// Path of the main temporary directory as a [`Utf8Path`], `None` if it
+    /// is not valid UTF-8.
+    ///
+    /// _This requires the feature `camino`._
+    pub fn utf8_path(&self) -> Option<&Utf8Path> {
+        Utf8Path::from_path(self.path())
+    }
+}
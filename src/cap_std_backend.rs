@@ -0,0 +1,253 @@
+use std::io::{self, Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use cap_std::ambient_authority;
+#[cfg(unix)]
+use cap_std::fs::FileTypeExt;
+use cap_std::fs::{Dir, File, OpenOptions};
+
+use crate::{EntryKind, FileAttributes, FsOps, WriteOptions, WriteStrategy};
+
+/// Filesystem operations creating the entries through a [`cap_std::fs::Dir`]
+/// handle, see [`create_with_fs!`](crate::create_with_fs).
+///
+/// _This requires the feature `cap-std`._
+///
+/// Every path is resolved within the opened directory by the operating
+/// system: absolute paths, `..` components and symlinks leading outside of
+/// it fail with an [`ErrorKind::PermissionDenied`] error, whatever the keys
+/// of the spec evaluate to. Symlinks must have relative targets. Files copied
+/// with [`from`](crate::from) or [`copy_dir`](crate::copy_dir) are still read
+/// from the current directory. Times, owners and FIFOs cannot be set through
+/// a directory handle and fail with an [`ErrorKind::Unsupported`] error.
+///
+/// ```
+/// use macro_files::CapStdFs;
+///
+/// let temp_dir = macro_files::tempfile::tempdir().unwrap();
+/// let key = "../escaped.txt";
+///
+/// let fs = CapStdFs::open(temp_dir.path()).unwrap();
+/// let err = macro_files::create_with_fs!(fs, {
+///     key: "outside",
+/// })
+/// .unwrap_err();
+///
+/// assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+/// ```
+#[derive(Debug)]
+pub struct CapStdFs {
+    dir: Dir,
+}
+
+impl CapStdFs {
+    /// Filesystem operations creating the entries within `dir`.
+    pub fn new(dir: Dir) -> CapStdFs {
+        CapStdFs { dir }
+    }
+
+    /// Open the directory at `path` to create the entries within it, see
+    /// [`Dir::open_ambient_dir`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<CapStdFs> {
+        Dir::open_ambient_dir(path, ambient_authority()).map(CapStdFs::new)
+    }
+
+    /// The directory the entries are created in.
+    pub fn dir(&self) -> &Dir {
+        &self.dir
+    }
+
+    /// Create the missing parent directories of `path`.
+    fn create_parent(&self, path: &Path) -> Result<()> {
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => self.dir.create_dir_all(parent),
+            _ => Ok(()),
+        }
+    }
+
+    /// Create the file at `path` with its missing parent directories.
+    fn create(&self, path: &Path) -> Result<File> {
+        match self.dir.create(path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                self.create_parent(path)?;
+                self.dir.create(path)
+            }
+            file => file,
+        }
+    }
+}
+
+fn unsupported(operation: &str) -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        format!("cannot {} through a directory handle", operation),
+    )
+}
+
+impl FsOps for CapStdFs {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        self.dir.create_dir_all(path)
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.create(path)?.write_all(contents)
+    }
+
+    fn write_file_with(&self, path: &Path, contents: &[u8], options: &WriteOptions) -> Result<()> {
+        let mut file = self.create(path)?;
+        match options.write_strategy() {
+            WriteStrategy::WriteAll => file.write_all(contents)?,
+            WriteStrategy::Chunked => {
+                for chunk in contents.chunks(options.chunk_size()) {
+                    file.write_all(chunk)?;
+                }
+            }
+        }
+        if options.syncs_data() {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn append_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        let mut file = match self.dir.open_with(path, &options) {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                self.create_parent(path)?;
+                self.dir.open_with(path, &options)?
+            }
+            file => file?,
+        };
+        file.write_all(contents)
+    }
+
+    fn write_zeros(&self, path: &Path, len: u64) -> Result<()> {
+        self.create(path)?.set_len(len)
+    }
+
+    fn write_reader(&self, path: &Path, reader: &mut dyn Read) -> Result<u64> {
+        io::copy(reader, &mut self.create(path)?)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<u64> {
+        let mut source = std::fs::File::open(from)?;
+        io::copy(&mut source, &mut self.create(to)?)
+    }
+
+    fn create_symlink(&self, target: &Path, path: &Path) -> Result<()> {
+        self.create_parent(path)?;
+        #[cfg(not(windows))]
+        return self.dir.symlink(target, path);
+        #[cfg(windows)]
+        return self.dir.symlink_file(target, path);
+    }
+
+    /// Junctions cannot be created relative to a directory handle, they are
+    /// created as symlinks to directories.
+    fn create_junction(&self, target: &Path, path: &Path) -> Result<()> {
+        #[cfg(not(windows))]
+        return self.create_symlink(target, path);
+        #[cfg(windows)]
+        {
+            self.create_parent(path)?;
+            self.dir.symlink_dir(target, path)
+        }
+    }
+
+    fn create_hard_link(&self, original: &Path, link: &Path) -> Result<()> {
+        self.create_parent(link)?;
+        self.dir.hard_link(original, &self.dir, link)
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn create_fifo(&self, _path: &Path) -> Result<()> {
+        Err(unsupported("create fifos"))
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        self.create_parent(path)?;
+        self.dir.bind_unix_listener(path).map(drop)
+    }
+
+    fn entry_kind(&self, path: &Path) -> Result<Option<EntryKind>> {
+        let file_type = match self.dir.symlink_metadata(path) {
+            Ok(metadata) => metadata.file_type(),
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        Ok(Some(if file_type.is_dir() {
+            EntryKind::Dir
+        } else if file_type.is_file() {
+            EntryKind::File
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            #[cfg(unix)]
+            if file_type.is_fifo() {
+                return Ok(Some(EntryKind::Fifo));
+            } else if file_type.is_socket() {
+                return Ok(Some(EntryKind::Socket));
+            }
+            EntryKind::Other
+        }))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        self.dir.remove_file(path)
+    }
+
+    fn set_times(
+        &self,
+        _path: &Path,
+        _accessed: Option<SystemTime>,
+        _modified: Option<SystemTime>,
+    ) -> Result<()> {
+        Err(unsupported("set times"))
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn set_owner(&self, _path: &Path, _uid: u32, _gid: u32) -> Result<()> {
+        Err(unsupported("change owners"))
+    }
+
+    fn set_file_attributes(&self, path: &Path, attributes: FileAttributes) -> Result<()> {
+        if attributes.hidden || attributes.system {
+            return Err(unsupported("set the hidden or system flags"));
+        }
+        let metadata = self.dir.metadata(path)?;
+        let permissions = metadata.permissions();
+        let mut updated = permissions.clone();
+        #[cfg(unix)]
+        if attributes.exact {
+            use cap_std::fs::PermissionsExt;
+            let mode = if metadata.is_dir() { 0o755 } else { 0o644 };
+            updated.set_mode(updated.mode() & !0o7777 | mode);
+        }
+        #[cfg(unix)]
+        if let Some(mode) = attributes.mode {
+            use cap_std::fs::PermissionsExt;
+            updated.set_mode(updated.mode() & !0o7777 | mode & 0o7777);
+        }
+        let keeps_mode = cfg!(unix) && attributes.mode.is_some();
+        if updated.readonly() != attributes.readonly && (attributes.readonly || !keeps_mode) {
+            updated.set_readonly(attributes.readonly);
+        }
+        #[cfg(unix)]
+        if attributes.executable {
+            use cap_std::fs::PermissionsExt;
+            let mode = updated.mode();
+            updated.set_mode(mode | (mode & 0o444) >> 2);
+        }
+        if updated == permissions {
+            return Ok(());
+        }
+        self.dir.set_permissions(path, updated)
+    }
+
+    fn move_path(&self, from: &Path, to: &Path) -> Result<()> {
+        self.dir.rename(from, &self.dir, to)
+    }
+}
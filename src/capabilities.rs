@@ -0,0 +1,107 @@
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::fs::std_symlink;
+use crate::platform;
+
+/// Filesystem features available on the current platform and filesystem,
+/// see [`capabilities`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Symbolic links, see [`symlink`](crate::symlink).
+    pub symlinks: bool,
+    /// Hard links, see [`hardlink`](crate::hardlink).
+    pub hard_links: bool,
+    /// Named pipes.
+    pub fifos: bool,
+    /// Extended attributes in the `user` namespace.
+    pub xattrs: bool,
+    /// Copy-on-write file clones.
+    pub reflinks: bool,
+}
+
+/// Probe the features available in the system temporary directory, where
+/// [`create_temp!`](crate::create_temp) creates trees.
+///
+/// Features which cannot be probed are reported as unavailable. Specs with
+/// optional entries can branch on the result instead of failing at creation.
+///
+/// ```
+/// let caps = macro_files::capabilities();
+/// let dir = macro_files::create_temp!({
+///     "README.md": "# Project",
+///     "index.md": if caps.symlinks {
+///         macro_files::symlink("README.md")
+///     } else {
+///         macro_files::file("# Project")
+///     },
+/// })
+/// .unwrap();
+///
+/// assert_eq!(std::fs::read_to_string(dir.path().join("index.md")).unwrap(), "# Project");
+/// ```
+pub fn capabilities() -> Capabilities {
+    Capabilities::probe(platform::temp_dir()).unwrap_or_default()
+}
+
+impl Capabilities {
+    /// Probe the features available within the existing directory `dir`, in
+    /// a scratch directory removed afterwards.
+    pub fn probe<P: AsRef<Path>>(dir: P) -> Result<Capabilities> {
+        let scratch = Scratch::create(dir.as_ref())?;
+        let file = scratch.0.join("file");
+        std::fs::write(&file, b"probe")?;
+
+        Ok(Capabilities {
+            symlinks: std_symlink(Path::new("file"), &scratch.0.join("symlink")).is_ok(),
+            hard_links: std::fs::hard_link(&file, scratch.0.join("hard_link")).is_ok(),
+            fifos: platform::mkfifo(&scratch.0.join("fifo"), 0o600).is_ok(),
+            xattrs: platform::set_xattr(&file, "user.macro_files", b"probe").is_ok(),
+            reflinks: platform::reflink(&file, &scratch.0.join("reflink")).is_ok(),
+        })
+    }
+}
+
+/// A scratch directory removed when dropped.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn create(dir: &Path) -> Result<Scratch> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            ".macro_files-probe-{}-{}",
+            platform::process_id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = dir.join(name);
+        std::fs::create_dir(&path)?;
+        Ok(Scratch(path))
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_temp_dir() {
+        let caps = Capabilities::probe(std::env::temp_dir()).unwrap();
+        assert_eq!(caps.symlinks, cfg!(unix));
+        assert_eq!(caps.fifos, cfg!(unix));
+        assert!(caps.hard_links);
+        assert_eq!(capabilities(), caps);
+    }
+
+    #[test]
+    fn probe_missing_dir() {
+        assert!(Capabilities::probe("missing").is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::{Content, Entry, File, Tree};
+
+/// Error returned when the contents of a file do not match its declared
+/// checksum.
+///
+/// It is wrapped in an [`io::Error`] of kind [`ErrorKind::InvalidData`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    path: PathBuf,
+    expected: String,
+    actual: String,
+}
+
+impl ChecksumMismatch {
+    /// Path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Declared digest, as a lowercase hexadecimal string.
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    /// Digest of the contents, as a lowercase hexadecimal string.
+    pub fn actual(&self) -> &str {
+        &self.actual
+    }
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sha256 mismatch for {:?}: expected {}, found {}",
+            self.path, self.expected, self.actual
+        )
+    }
+}
+
+impl Error for ChecksumMismatch {}
+
+/// Hexadecimal SHA-256 digest of the contents of the file written at `path`,
+/// from `file` when they are held in memory or else read back.
+pub(crate) fn file_sha256(file: Option<&File>, path: &Path) -> io::Result<String> {
+    match file.and_then(File::contents) {
+        Some(mut contents) => sha256_hex(&mut contents),
+        None => sha256_hex(&mut std::fs::File::open(path)?),
+    }
+}
+
+/// Hexadecimal SHA-256 digest of the contents read from `reader`.
+pub(crate) fn sha256_hex<R: Read + ?Sized>(reader: &mut R) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 8 * 1024];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => hasher.update(&buffer[..len]),
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+pub(crate) fn verify_sha256<R: Read + ?Sized>(
+    path: &Path,
+    expected: &str,
+    contents: &mut R,
+) -> io::Result<()> {
+    if expected.len() != 64 || !expected.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid sha256 digest for {:?}: {:?}", path, expected),
+        ));
+    }
+    let actual = sha256_hex(contents)?;
+    if actual != expected {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            ChecksumMismatch {
+                path: path.to_owned(),
+                expected: expected.to_owned(),
+                actual,
+            },
+        ));
+    }
+    Ok(())
+}
+
+/// Files of a directory whose contents don't match their checksums, see
+/// [`verify!`](crate::verify).
+///
+/// It is wrapped in an [`io::Error`] of kind [`ErrorKind::InvalidData`]. The
+/// [`Display`](fmt::Display) implementation lists one file per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumErrors {
+    mismatches: Vec<ChecksumMismatch>,
+    missing: Vec<PathBuf>,
+}
+
+impl ChecksumErrors {
+    /// The files whose contents don't match their checksums, in order.
+    pub fn mismatches(&self) -> &[ChecksumMismatch] {
+        &self.mismatches
+    }
+
+    /// The files not found, in order.
+    pub fn missing(&self) -> &[PathBuf] {
+        &self.missing
+    }
+}
+
+impl fmt::Display for ChecksumErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self.mismatches.len() + self.missing.len();
+        let s = if len == 1 { "" } else { "s" };
+        write!(f, "{} file{} do not match their checksums", len, s)?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n{}", mismatch)?;
+        }
+        for path in &self.missing {
+            write!(f, "\nmissing {:?}", path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ChecksumErrors {}
+
+impl Tree {
+    /// Verify that the files of the tree within `dir` still have the SHA-256
+    /// digest of their declared contents, or their declared digest, see
+    /// [`verify!`](crate::verify).
+    ///
+    /// Only the files are verified, the other entries and the entries
+    /// found on disk but not declared are ignored. Files streamed from a
+    /// reader have no digest unless declared.
+    pub fn verify<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let mut files = Vec::new();
+        declared_sha256s(self, dir.as_ref(), &mut files)?;
+        verify_files(files)
+    }
+}
+
+/// Verify that the files listed in `sums`, in the format of `sha256sum`, have
+/// their digest, their paths being relative to `base`.
+///
+/// Fails with an [`ErrorKind::InvalidData`] error wrapping the
+/// [`ChecksumErrors`] listing all the mismatches, or the first invalid line.
+///
+/// ```
+/// use macro_files::{Options, Tree};
+///
+/// let dir = macro_files::tempfile::tempdir().unwrap();
+/// let mut tree = Tree::new();
+/// tree.push("data.bin", [0; 10]);
+/// let report = tree
+///     .create_with(dir.path(), &Options::new().checksums(true))
+///     .unwrap();
+/// let sums = report.sha256sums(dir.path());
+///
+/// macro_files::verify_sha256sums(dir.path(), &sums).unwrap();
+/// std::fs::write(dir.path().join("data.bin"), [1; 10]).unwrap();
+/// let err = macro_files::verify_sha256sums(dir.path(), &sums).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+/// ```
+pub fn verify_sha256sums<P: AsRef<Path>>(base: P, sums: &str) -> io::Result<()> {
+    let base = base.as_ref();
+    let mut files = Vec::new();
+    for (index, line) in sums.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed = line.split_once(' ').and_then(|(digest, path)| {
+            let path = path.strip_prefix(|c| c == ' ' || c == '*')?;
+            let valid = digest.len() == 64 && digest.bytes().all(|byte| byte.is_ascii_hexdigit());
+            valid.then(|| (base.join(path), digest.to_ascii_lowercase()))
+        });
+        match parsed {
+            Some(file) => files.push(file),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid line {} of the checksums: {:?}", index + 1, line),
+                ))
+            }
+        }
+    }
+    verify_files(files)
+}
+
+/// Collect the path and expected digest of the files of `tree` within `dir`.
+fn declared_sha256s(tree: &Tree, dir: &Path, files: &mut Vec<(PathBuf, String)>) -> io::Result<()> {
+    for (key, entry) in tree.entries() {
+        let path = tree.key_path(dir, key)?;
+        match entry {
+            Entry::Dir(tree) => declared_sha256s(tree, &path, files)?,
+            Entry::File(file) => {
+                if let Some(sha256) = expected_sha256(file)? {
+                    files.push((path, sha256));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Declared digest of the file, or else the digest of its contents when
+/// they can be read without consuming them.
+fn expected_sha256(file: &File) -> io::Result<Option<String>> {
+    if let Some(sha256) = file.declared_sha256() {
+        return Ok(Some(sha256.to_owned()));
+    }
+    let sha256 = match file.content() {
+        _ if file.is_link() => return Ok(None),
+        Content::Reader(_) => return Ok(None),
+        Content::CopyFrom(source) => sha256_hex(&mut std::fs::File::open(source)?)?,
+        Content::Zeros(len) => sha256_hex(&mut io::repeat(0).take(*len))?,
+        Content::Repeat { pattern, len } => {
+            sha256_hex(&mut crate::file::RepeatReader::new(pattern, *len))?
+        }
+        _ => sha256_hex(&mut &*file.read_contents()?)?,
+    };
+    Ok(Some(sha256))
+}
+
+fn verify_files(files: Vec<(PathBuf, String)>) -> io::Result<()> {
+    let mut errors = ChecksumErrors {
+        mismatches: Vec::new(),
+        missing: Vec::new(),
+    };
+    for (path, expected) in files {
+        let actual = match std::fs::File::open(&path) {
+            Ok(mut file) => sha256_hex(&mut file)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                errors.missing.push(path);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if actual != expected {
+            errors.mismatches.push(ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            });
+        }
+    }
+    if errors.mismatches.is_empty() && errors.missing.is_empty() {
+        return Ok(());
+    }
+    Err(io::Error::new(ErrorKind::InvalidData, errors))
+}
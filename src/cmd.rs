@@ -0,0 +1,77 @@
+use std::ffi::{OsStr, OsString};
+use std::io::{Error, ErrorKind, Result};
+use std::process::{Command, Stdio};
+
+use crate::{Content, File, Generator};
+
+/// A file with the standard output of `program` run with `args` as contents,
+/// for fixtures embedding the output of a tool, such as a version or a
+/// schema.
+///
+/// _This requires the feature `cmd`._
+///
+/// The command is run in the current directory each time the file is
+/// created, not when it is declared, nor by
+/// [`Tree::plan`](crate::Tree::plan). Creation fails with the error output of
+/// the command when it cannot be run or exits with a failure status.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "rustc-version.txt": macro_files::cmd("rustc", ["--version"]),
+/// })
+/// .unwrap();
+///
+/// let version = std::fs::read_to_string(dir.path().join("rustc-version.txt")).unwrap();
+/// assert!(version.starts_with("rustc "));
+/// ```
+pub fn cmd<S, I, A>(program: S, args: I) -> File
+where
+    S: AsRef<OsStr>,
+    I: IntoIterator<Item = A>,
+    A: AsRef<OsStr>,
+{
+    let program = program.as_ref().to_owned();
+    let args: Vec<OsString> = args
+        .into_iter()
+        .map(|arg| arg.as_ref().to_owned())
+        .collect();
+    File::from(Content::Generator(Generator::new(move || {
+        run(&program, &args)
+    })))
+}
+
+/// Run `program` with `args` and return its standard output.
+fn run(program: &OsStr, args: &[OsString]) -> Result<Vec<u8>> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("cannot run {}: {}", command_line(program, args), err),
+            )
+        })?;
+    if output.status.success() {
+        return Ok(output.stdout);
+    }
+    Err(Error::new(
+        ErrorKind::Other,
+        format!(
+            "{} failed with {}: {}",
+            command_line(program, args),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ),
+    ))
+}
+
+fn command_line(program: &OsStr, args: &[OsString]) -> String {
+    let mut line = format!("`{}", program.to_string_lossy());
+    for arg in args {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    line.push('`');
+    line
+}
@@ -0,0 +1,266 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::Matcher;
+
+/// Contents of a file entry.
+///
+/// Every value of a spec ends up as one of these variants, whether it is
+/// declared in a macro or pushed into a [`Tree`](crate::Tree), so they are
+/// all created and compared the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Content {
+    /// Raw bytes.
+    Bytes(Vec<u8>),
+    /// UTF-8 text.
+    Text(String),
+    /// Contents copied from an existing file, see [`from`](crate::from).
+    CopyFrom(PathBuf),
+    /// Contents rendered from a template file with a context serialized as
+    /// JSON, see [`template`](crate::template).
+    #[cfg(feature = "tera")]
+    Template { path: PathBuf, context: tera::Value },
+    /// The given number of zero bytes, written without being held in memory,
+    /// see [`size`](crate::size).
+    Zeros(u64),
+    /// Deterministic pseudo-random bytes, see [`random`](crate::random).
+    Random { len: u64, seed: u64 },
+    /// The given number of bytes repeating a pattern, written in chunks
+    /// without being held in memory, see [`repeat`](crate::repeat).
+    Repeat { pattern: Vec<u8>, len: u64 },
+    /// Contents read from a reader, which can be consumed only once.
+    Reader(Reader),
+    /// Contents generated when the file is created.
+    Generator(Generator),
+    /// A symbolic link to the target path instead of a regular file, see
+    /// [`symlink`](crate::symlink).
+    Symlink(PathBuf),
+    /// A directory junction to the target directory on Windows, a symbolic
+    /// link elsewhere, see [`junction`](crate::junction).
+    Junction(PathBuf),
+    /// A hard link to another file of the spec, see
+    /// [`hardlink`](crate::hardlink).
+    HardLink(PathBuf),
+    /// A named pipe instead of a regular file, see [`fifo`](crate::fifo).
+    #[cfg(all(feature = "unix", unix))]
+    Fifo,
+    /// A Unix domain socket instead of a regular file, see
+    /// [`unix_socket`](crate::unix_socket).
+    #[cfg(all(feature = "unix", unix))]
+    Socket,
+    /// Contents only compared against a predicate in assertions, see
+    /// [`any`](crate::any) and [`predicate`](crate::predicate).
+    Matcher(Matcher),
+}
+
+impl Content {
+    /// The length and a reader of the contents produced on the fly when the
+    /// file is written, which are read in chunks rather than held in memory
+    /// when compared.
+    pub(crate) fn streamed(&self) -> Option<(u64, Box<dyn Read + '_>)> {
+        match self {
+            Content::Zeros(len) => Some((*len, Box::new(std::io::repeat(0).take(*len)))),
+            Content::Repeat { pattern, len } => Some((
+                *len,
+                Box::new(crate::file::RepeatReader::new(pattern, *len)),
+            )),
+            _ => None,
+        }
+    }
+
+    /// The contents as bytes, read, rendered or generated if needed.
+    ///
+    /// Fails for a [`Content::Reader`] already consumed and for links and
+    /// special files which have no contents of their own.
+    pub fn read(&self) -> Result<Cow<'_, [u8]>> {
+        match self {
+            Content::Bytes(bytes) => Ok(Cow::Borrowed(bytes)),
+            Content::Text(text) => Ok(Cow::Borrowed(text.as_bytes())),
+            Content::CopyFrom(source) => std::fs::read(source).map(Cow::Owned),
+            #[cfg(feature = "tera")]
+            Content::Template { path, context } => {
+                crate::template::render(path, context).map(Cow::Owned)
+            }
+            Content::Zeros(len) => Ok(Cow::Owned(vec![0; *len as usize])),
+            Content::Random { len, seed } => {
+                Ok(Cow::Owned(crate::random::random_bytes(*len, *seed)))
+            }
+            Content::Repeat { pattern, len } => {
+                let mut contents = Vec::with_capacity(*len as usize);
+                crate::file::RepeatReader::new(pattern, *len).read_to_end(&mut contents)?;
+                Ok(Cow::Owned(contents))
+            }
+            Content::Reader(reader) => {
+                let mut contents = Vec::new();
+                reader.take()?.read_to_end(&mut contents)?;
+                Ok(Cow::Owned(contents))
+            }
+            Content::Generator(generator) => generator.generate().map(Cow::Owned),
+            Content::Symlink(target) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("symlink to {:?} has no contents", target),
+            )),
+            Content::Junction(target) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("junction to {:?} has no contents", target),
+            )),
+            Content::HardLink(original) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("hard link to {:?} has no contents", original),
+            )),
+            #[cfg(all(feature = "unix", unix))]
+            Content::Fifo => Err(Error::new(ErrorKind::InvalidInput, "fifo has no contents")),
+            #[cfg(all(feature = "unix", unix))]
+            Content::Socket => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "socket has no contents",
+            )),
+            Content::Matcher(matcher) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("matcher of {} has no contents", matcher.description()),
+            )),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Content {
+    fn from(bytes: Vec<u8>) -> Content {
+        Content::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Content {
+    fn from(bytes: &[u8]) -> Content {
+        Content::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<String> for Content {
+    fn from(text: String) -> Content {
+        Content::Text(text)
+    }
+}
+
+impl From<&str> for Content {
+    fn from(text: &str) -> Content {
+        Content::Text(text.to_owned())
+    }
+}
+
+/// Conversion of a value into the contents of a file, which makes it usable
+/// as a value of the specs.
+///
+/// Implemented for anything which is `AsRef<[u8]>`: `&str`, `String`,
+/// `&[u8]`, `Vec<u8>`, byte arrays, etc. Implement it for your own types to
+/// declare them directly in the specs.
+///
+/// ```
+/// use macro_files::{Content, ToFileContents};
+///
+/// struct ConfigFile {
+///     debug: bool,
+/// }
+///
+/// impl ToFileContents for ConfigFile {
+///     fn to_file_contents(&self) -> Content {
+///         Content::Text(format!("debug = {}\n", self.debug))
+///     }
+/// }
+///
+/// let dir = macro_files::create_temp!({
+///     "config.toml": ConfigFile { debug: true },
+/// })
+/// .unwrap();
+///
+/// let config = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+/// assert_eq!(config, "debug = true\n");
+/// ```
+pub trait ToFileContents {
+    /// The contents of the file.
+    fn to_file_contents(&self) -> Content;
+}
+
+impl<C: AsRef<[u8]> + ?Sized> ToFileContents for C {
+    fn to_file_contents(&self) -> Content {
+        Content::Bytes(self.as_ref().to_vec())
+    }
+}
+
+type BoxedReader = Box<dyn Read + Send>;
+
+/// A reader shared by the clones of a [`Content::Reader`].
+///
+/// The first read consumes it, later reads fail. Two readers are equal when
+/// they are clones of each other.
+#[derive(Clone)]
+pub struct Reader(Arc<Mutex<Option<BoxedReader>>>);
+
+impl Reader {
+    /// Wrap a reader.
+    pub fn new<R: Read + Send + 'static>(reader: R) -> Reader {
+        Reader(Arc::new(Mutex::new(Some(Box::new(reader)))))
+    }
+
+    /// Take the reader, failing if it was already consumed.
+    pub(crate) fn take(&self) -> Result<BoxedReader> {
+        let mut reader = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        reader
+            .take()
+            .ok_or_else(|| Error::new(ErrorKind::Other, "reader contents already consumed"))
+    }
+}
+
+impl fmt::Debug for Reader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Reader")
+    }
+}
+
+impl PartialEq for Reader {
+    fn eq(&self, other: &Reader) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Reader {}
+
+type BoxedGenerator = dyn Fn() -> Result<Vec<u8>> + Send + Sync;
+
+/// A function generating the contents of a [`Content::Generator`], called
+/// each time the contents are needed.
+///
+/// Two generators are equal when they are clones of each other.
+#[derive(Clone)]
+pub struct Generator(Arc<BoxedGenerator>);
+
+impl Generator {
+    /// Wrap a function generating contents.
+    pub fn new<F>(generate: F) -> Generator
+    where
+        F: Fn() -> Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        Generator(Arc::new(generate))
+    }
+
+    pub(crate) fn generate(&self) -> Result<Vec<u8>> {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Generator")
+    }
+}
+
+impl PartialEq for Generator {
+    fn eq(&self, other: &Generator) -> bool {
+        Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+    }
+}
+
+impl Eq for Generator {}
@@ -0,0 +1,92 @@
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::tree::Creation;
+use crate::{copy_file, create_dir, CreateReport, Entry, ReadOptions, Tree};
+
+/// A directory whose entries are recursively copied from the existing
+/// directory at `path` when it is created.
+///
+/// Symlinks of the source directory are followed, and its files are cloned
+/// where the filesystem supports it like [`from`](crate::from). Relative
+/// paths are resolved against the current directory.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "generated.txt": "generated",
+///     "vendor": macro_files::copy_dir("src"),
+/// })
+/// .unwrap();
+///
+/// assert!(dir.path().join("vendor/lib.rs").is_file());
+/// ```
+pub fn copy_dir<P: AsRef<Path>>(path: P) -> Entry {
+    Entry::CopyDir(path.as_ref().to_owned())
+}
+
+/// Recreate the `source` directory at `dest` by reading it into a [`Tree`]
+/// created with the usual creation path, see [`Tree::read_from`].
+///
+/// ```
+/// let fixture = macro_files::create_temp!({
+///     "README.md": "# Project",
+///     "src/main.rs": "fn main() {}",
+/// })
+/// .unwrap();
+/// let dir = macro_files::tempfile::tempdir().unwrap();
+///
+/// macro_files::copy_tree(fixture.path(), dir.path().join("copy")).unwrap();
+///
+/// assert!(dir.path().join("copy/src/main.rs").is_file());
+/// ```
+pub fn copy_tree<S: AsRef<Path>, D: AsRef<Path>>(source: S, dest: D) -> Result<CreateReport> {
+    copy_tree_with(source, dest, &ReadOptions::default())
+}
+
+/// Recreate the `source` directory at `dest`, reading it with the given
+/// options, see [`copy_tree`] and [`Tree::read_with`].
+pub fn copy_tree_with<S, D>(source: S, dest: D, options: &ReadOptions) -> Result<CreateReport>
+where
+    S: AsRef<Path>,
+    D: AsRef<Path>,
+{
+    let tree = Tree::read_with(source, options)?;
+    create_dir(&dest)?;
+    tree.create_in(dest)
+}
+
+/// Entries of the `source` directory sorted by name, with whether they are
+/// directories.
+pub(crate) fn read_sorted_dir(source: &Path) -> Result<Vec<(PathBuf, bool)>> {
+    let mut entries = Vec::new();
+    for entry in source.read_dir()? {
+        let path = entry?.path();
+        let is_dir = path.is_dir();
+        entries.push((path, is_dir));
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Copy the entries of the `source` directory into the existing `dir`
+/// directory with the current filesystem operations.
+pub(crate) fn copy_dir_entries(
+    creation: &mut Creation<'_>,
+    source: &Path,
+    dir: &Path,
+) -> Result<()> {
+    for (path, is_dir) in read_sorted_dir(source)? {
+        let target = dir.join(path.file_name().unwrap_or_default());
+        creation.confine(&target, is_dir)?;
+        if is_dir {
+            if creation.create_dir(&target)? {
+                copy_dir_entries(creation, &path, &target)?;
+            }
+        } else if creation.claim(&target, false)? {
+            creation.charge(std::fs::metadata(&path).map_or(0, |metadata| metadata.len()))?;
+            copy_file(&path, &target)?;
+            creation.created_file(target, None, None)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::{Conflict, EntryKind, File, FileAttributes, LineEnding, Tree};
+
+/// Attributes given to the files of a directory, nested ones included,
+/// unless they declare their own, see [`Tree::defaults`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Defaults {
+    flags: FileAttributes,
+    conflict: Option<Conflict>,
+    line_ending: Option<LineEnding>,
+}
+
+impl Defaults {
+    /// No defaults.
+    pub fn new() -> Defaults {
+        Defaults::default()
+    }
+
+    /// Declare the files read-only, see [`File::readonly`].
+    pub fn readonly(mut self) -> Defaults {
+        self.flags.readonly = true;
+        self
+    }
+
+    /// Declare the files executable, see [`File::executable`].
+    pub fn executable(mut self) -> Defaults {
+        self.flags.executable = true;
+        self
+    }
+
+    /// Declare the permissions of the files exact, see
+    /// [`File::exact_permissions`].
+    pub fn exact_permissions(mut self) -> Defaults {
+        self.flags.exact = true;
+        self
+    }
+
+    /// Set what to do when the files already exist, see
+    /// [`File::on_conflict`].
+    pub fn on_conflict(mut self, conflict: Conflict) -> Defaults {
+        self.conflict = Some(conflict);
+        self
+    }
+
+    /// Set the line endings of the text files, see [`File::line_ending`].
+    pub fn line_endings(mut self, ending: LineEnding) -> Defaults {
+        self.line_ending = Some(ending);
+        self
+    }
+
+    /// Flags given to the files.
+    pub fn flags(&self) -> FileAttributes {
+        self.flags
+    }
+
+    /// What to do when the files already exist, if set.
+    pub fn conflict(&self) -> Option<Conflict> {
+        self.conflict
+    }
+
+    /// Line endings of the text files, if set.
+    pub fn line_ending(&self) -> Option<LineEnding> {
+        self.line_ending
+    }
+
+    /// `file` with the defaults it does not declare itself.
+    fn apply(&self, mut file: File) -> File {
+        if file.kind() == EntryKind::File {
+            let flags = self.flags;
+            file = file.with_flags(|declared| {
+                declared.readonly |= flags.readonly;
+                declared.executable |= flags.executable;
+                declared.exact |= flags.exact;
+            });
+        }
+        if let (Some(conflict), None) = (self.conflict, file.declared_conflict()) {
+            file = file.on_conflict(conflict);
+        }
+        if let (Some(ending), None) = (self.line_ending, file.declared_line_ending()) {
+            file = file.line_ending(ending);
+        }
+        file
+    }
+}
+
+impl Tree {
+    /// Give the files of the tree, nested ones included, the attributes of
+    /// `defaults` they do not declare themselves, so a whole directory can be
+    /// executable or use the same line endings without annotating each file.
+    ///
+    /// Flags are added to those of the regular files, while a conflict policy
+    /// or line endings declared by a file, or by the defaults of a nested
+    /// directory, are kept. Files declared afterwards are not affected.
+    ///
+    /// ```
+    /// use macro_files::{Defaults, LineEnding};
+    ///
+    /// let dir = macro_files::create_temp!({
+    ///     "bin": macro_files::tree!({
+    ///         "build.sh": "#!/bin/sh\n",
+    ///         "deploy.sh": "#!/bin/sh\n",
+    ///         "hooks": macro_files::tree!({ "pre-commit": "#!/bin/sh\n" })
+    ///             .defaults(&Defaults::new().line_endings(LineEnding::Lf)),
+    ///     })
+    ///     .defaults(&Defaults::new().executable().line_endings(LineEnding::CrLf)),
+    /// })
+    /// .unwrap();
+    ///
+    /// macro_files::assert_tree!(dir.path(), {
+    ///     "bin": {
+    ///         "build.sh": "#!/bin/sh\r\n",
+    ///         "deploy.sh": "#!/bin/sh\r\n",
+    ///         "hooks": { "pre-commit": "#!/bin/sh\n" },
+    ///     },
+    /// });
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::os::unix::fs::PermissionsExt;
+    ///
+    /// let metadata = std::fs::metadata(dir.path().join("bin/hooks/pre-commit")).unwrap();
+    /// assert_eq!(metadata.permissions().mode() & 0o111, 0o111);
+    /// # }
+    /// ```
+    pub fn defaults(self, defaults: &Defaults) -> Tree {
+        self.map_files(&mut |file| defaults.apply(file))
+    }
+}
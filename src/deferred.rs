@@ -0,0 +1,59 @@
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::attrs::Attrs;
+use crate::{Content, File, Options};
+
+/// Files and directory attributes left to be written once all the
+/// directories are created, see [`Options::parallel`] and
+/// [`Options::io_uring`].
+#[derive(Default)]
+pub(crate) struct Deferred<'a> {
+    pub(crate) files: Vec<(&'a File, PathBuf, Option<Arc<str>>)>,
+    pub(crate) dirs: Vec<(&'a Attrs, PathBuf)>,
+}
+
+impl Deferred<'_> {
+    /// Write the files in parallel or in batches, then the hard links in
+    /// order on the current thread once their originals are written. Returns
+    /// the result of each file, in order.
+    pub(crate) fn write_files(&self, root: &Path, options: &Options) -> Vec<Result<()>> {
+        let is_hard_link = |file: &File| matches!(file.content(), Content::HardLink(_));
+        let files: Vec<_> = self
+            .files
+            .iter()
+            .filter(|(file, _, _)| !is_hard_link(file))
+            .map(|(file, path, _)| (*file, path.as_path()))
+            .collect();
+        let mut results = write_batch(&files, root, options).into_iter();
+        self.files
+            .iter()
+            .map(|(file, path, _)| {
+                if is_hard_link(file) {
+                    file.write(root, path, options.write_options())
+                } else {
+                    results.next().unwrap_or(Ok(()))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Write the `files` as set by the options, returning the result of each
+/// file, in order.
+fn write_batch(files: &[(&File, &Path)], root: &Path, options: &Options) -> Vec<Result<()>> {
+    let write = options.write_options();
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    if options.uses_io_uring() {
+        return crate::uring::write_files(files, root, write);
+    }
+    #[cfg(feature = "rayon")]
+    if options.is_parallel() {
+        return crate::parallel::write_files(files, root, write);
+    }
+    files
+        .iter()
+        .map(|(file, path)| file.write(root, path, write))
+        .collect()
+}
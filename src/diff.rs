@@ -0,0 +1,701 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Read, Result};
+use std::path::{Path, PathBuf};
+
+use crate::flat::{flatten, Flat};
+use crate::{Content, Tree};
+
+/// Kind of an entry found in a spec or on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EntryKind {
+    Dir,
+    File,
+    Symlink,
+    /// A named pipe, see [`fifo`](crate::fifo).
+    Fifo,
+    /// A Unix domain socket, see [`unix_socket`](crate::unix_socket).
+    Socket,
+    /// Anything else found on disk (block or character device, ...).
+    Other,
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EntryKind::Dir => "directory",
+            EntryKind::File => "file",
+            EntryKind::Symlink => "symlink",
+            EntryKind::Fifo => "fifo",
+            EntryKind::Socket => "socket",
+            EntryKind::Other => "special file",
+        })
+    }
+}
+
+impl From<std::fs::FileType> for EntryKind {
+    fn from(file_type: std::fs::FileType) -> EntryKind {
+        if file_type.is_dir() {
+            EntryKind::Dir
+        } else if file_type.is_file() {
+            EntryKind::File
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::FileTypeExt;
+                if file_type.is_fifo() {
+                    return EntryKind::Fifo;
+                } else if file_type.is_socket() {
+                    return EntryKind::Socket;
+                }
+            }
+            EntryKind::Other
+        }
+    }
+}
+
+/// A difference between a spec and a directory.
+///
+/// Paths are relative to the compared directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Change {
+    /// Declared in the spec but not found on disk.
+    Missing { path: PathBuf, expected: EntryKind },
+    /// Found on disk but not declared in the spec.
+    Unexpected { path: PathBuf, actual: EntryKind },
+    /// Found on disk with another kind than the declared one.
+    KindMismatch {
+        path: PathBuf,
+        expected: EntryKind,
+        actual: EntryKind,
+    },
+    /// File found on disk with other contents than the declared ones.
+    Contents {
+        path: PathBuf,
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+    },
+    /// File found on disk with other contents than declared contents too
+    /// large to be held in memory, such as [`size`](crate::size) or
+    /// [`repeat`](crate::repeat), with the offset of the first differing
+    /// byte.
+    ContentsFrom { path: PathBuf, offset: u64 },
+    /// Symlink found on disk with another target than the declared one.
+    Target {
+        path: PathBuf,
+        expected: PathBuf,
+        actual: PathBuf,
+    },
+    /// File found on disk which is not a hard link to the declared original.
+    NotLinked { path: PathBuf, original: PathBuf },
+    /// File found on disk whose contents do not satisfy the declared
+    /// [`Matcher`](crate::Matcher), with its description.
+    Unmatched {
+        path: PathBuf,
+        expected: String,
+        actual: Vec<u8>,
+    },
+    /// Entries found in a directory declared with [`keep`](crate::keep),
+    /// with their paths relative to it.
+    NotEmpty {
+        path: PathBuf,
+        entries: Vec<PathBuf>,
+    },
+}
+
+impl Change {
+    /// Path of the changed entry, relative to the compared directory.
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Missing { path, .. }
+            | Change::Unexpected { path, .. }
+            | Change::KindMismatch { path, .. }
+            | Change::Contents { path, .. }
+            | Change::ContentsFrom { path, .. }
+            | Change::Target { path, .. }
+            | Change::NotLinked { path, .. }
+            | Change::Unmatched { path, .. }
+            | Change::NotEmpty { path, .. } => path,
+        }
+    }
+}
+
+/// Differences between a spec and a directory, see [`Tree::diff`].
+///
+/// Changes are sorted by path. The descendants of a missing or unexpected
+/// directory are not reported. The [`Display`](fmt::Display) implementation
+/// lists every change with line-level diffs of text contents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    changes: Vec<Change>,
+}
+
+impl TreeDiff {
+    /// Whether the directory matches the spec.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// All the changes, sorted by path.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Entries declared in the spec but not found on disk.
+    pub fn missing(&self) -> impl Iterator<Item = &Path> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::Missing { path, .. } => Some(path.as_path()),
+            _ => None,
+        })
+    }
+
+    /// Entries found on disk but not declared in the spec.
+    pub fn unexpected(&self) -> impl Iterator<Item = &Path> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::Unexpected { path, .. } => Some(path.as_path()),
+            _ => None,
+        })
+    }
+
+    /// Entries found with another kind or other contents than declared.
+    pub fn mismatched(&self) -> impl Iterator<Item = &Path> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::KindMismatch { path, .. }
+            | Change::Contents { path, .. }
+            | Change::ContentsFrom { path, .. }
+            | Change::Target { path, .. }
+            | Change::NotLinked { path, .. }
+            | Change::Unmatched { path, .. }
+            | Change::NotEmpty { path, .. } => Some(path.as_path()),
+            _ => None,
+        })
+    }
+
+    /// The changes other than the unexpected entries, for directories which
+    /// may contain more than their spec.
+    #[cfg(feature = "tempfile")]
+    pub(crate) fn without_unexpected(mut self) -> TreeDiff {
+        self.changes
+            .retain(|change| !matches!(change, Change::Unexpected { .. }));
+        self
+    }
+}
+
+impl fmt::Display for TreeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for change in &self.changes {
+            match change {
+                Change::Missing { path, expected } => {
+                    writeln!(f, "missing {}: {}", expected, path.display())?
+                }
+                Change::Unexpected { path, actual } => {
+                    writeln!(f, "unexpected {}: {}", actual, path.display())?
+                }
+                Change::KindMismatch {
+                    path,
+                    expected,
+                    actual,
+                } => writeln!(
+                    f,
+                    "expected {}, found {}: {}",
+                    expected,
+                    actual,
+                    path.display()
+                )?,
+                Change::Contents {
+                    path,
+                    expected,
+                    actual,
+                } => {
+                    writeln!(f, "contents differ: {}", path.display())?;
+                    write_contents_diff(f, expected, actual)?;
+                }
+                Change::ContentsFrom { path, offset } => writeln!(
+                    f,
+                    "contents differ from byte {}: {}",
+                    offset,
+                    path.display()
+                )?,
+                Change::Target {
+                    path,
+                    expected,
+                    actual,
+                } => {
+                    writeln!(f, "symlink target differs: {}", path.display())?;
+                    writeln!(f, "  - {}", expected.display())?;
+                    writeln!(f, "  + {}", actual.display())?;
+                }
+                Change::NotLinked { path, original } => writeln!(
+                    f,
+                    "not a hard link to {}: {}",
+                    original.display(),
+                    path.display()
+                )?,
+                Change::Unmatched {
+                    path,
+                    expected,
+                    actual,
+                } => {
+                    writeln!(f, "contents do not match {}: {}", expected, path.display())?;
+                    write_contents_diff(f, b"", actual)?;
+                }
+                Change::NotEmpty { path, entries } => {
+                    writeln!(f, "directory not empty: {}", path.display())?;
+                    for entry in entries {
+                        writeln!(f, "  + {}", entry.display())?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Tree {
+    /// Compare the tree with the directories and files within `dir`.
+    ///
+    /// Entries declared with `false` or `null` are ignored, they are reported
+    /// as unexpected if they exist on disk. Symlinks are not followed.
+    pub fn diff<P: AsRef<Path>>(&self, dir: P) -> Result<TreeDiff> {
+        self.diff_ignoring(dir, std::iter::empty::<&str>())
+    }
+
+    /// Compare the tree with the directories and files within `dir` like
+    /// [`Tree::diff`], leaving out the entries on disk or in the spec whose
+    /// path relative to `dir` matches one of the glob `patterns`, so that
+    /// comparisons against working directories can skip irrelevant entries.
+    ///
+    /// Within a path component, `*` matches any characters and `?` any one
+    /// character, and a `**` component matches any number of components,
+    /// none included. Patterns without a `/` match names at any depth. The
+    /// entries of ignored directories are not read.
+    ///
+    /// ```
+    /// let dir = macro_files::create_temp!({
+    ///     ".git": { "HEAD": "ref: refs/heads/main" },
+    ///     "Cargo.lock": "",
+    ///     "src": { "main.rs": "fn main() {}", "Cargo.lock": "" },
+    /// })
+    /// .unwrap();
+    ///
+    /// let spec = macro_files::tree!({ "src": { "main.rs": "fn main() {}" } });
+    /// let diff = spec.diff_ignoring(dir.path(), [".git/**", "*.lock"]).unwrap();
+    /// assert!(diff.is_empty());
+    /// ```
+    pub fn diff_ignoring<P, I, S>(&self, dir: P, patterns: I) -> Result<TreeDiff>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let ignore = Ignore::new(patterns);
+        let dir = dir.as_ref();
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+
+        let mut expected = flatten(self, dir)?;
+        expected.retain(|path, _| !ignore.matches(path));
+        let mut actual = BTreeMap::new();
+        walk(dir, dir, &ignore, &mut actual)?;
+
+        let mut changes = Vec::new();
+        let mut collapsed: Option<PathBuf> = None;
+        let mut paths: Vec<&PathBuf> = expected.keys().chain(actual.keys()).collect();
+        paths.sort();
+        paths.dedup();
+        for path in paths {
+            if matches!(&collapsed, Some(parent) if path.starts_with(parent)) {
+                continue;
+            }
+            let change = match (expected.get(path), actual.get(path)) {
+                (Some(expected), None) => {
+                    collapsed = Some(path.clone());
+                    Change::Missing {
+                        path: path.clone(),
+                        expected: expected.kind(),
+                    }
+                }
+                (None, Some(actual)) => {
+                    collapsed = Some(path.clone());
+                    Change::Unexpected {
+                        path: path.clone(),
+                        actual: *actual,
+                    }
+                }
+                (Some(Flat::File(expected)), Some(EntryKind::File)) => {
+                    let actual = std::fs::read(dir.join(path))?;
+                    if **expected == *actual {
+                        continue;
+                    }
+                    Change::Contents {
+                        path: path.clone(),
+                        expected: expected.to_vec(),
+                        actual,
+                    }
+                }
+                (Some(Flat::Streamed(content)), Some(EntryKind::File)) => {
+                    match streamed_change(path, content, &dir.join(path))? {
+                        Some(change) => change,
+                        None => continue,
+                    }
+                }
+                (Some(Flat::Matcher(matcher)), Some(EntryKind::File)) => {
+                    let actual = std::fs::read(dir.join(path))?;
+                    if matcher.matches(&actual) {
+                        continue;
+                    }
+                    Change::Unmatched {
+                        path: path.clone(),
+                        expected: matcher.description().to_owned(),
+                        actual,
+                    }
+                }
+                (Some(Flat::Symlink(expected)), Some(EntryKind::Symlink)) => {
+                    let actual = std::fs::read_link(dir.join(path))?;
+                    if **expected == actual {
+                        continue;
+                    }
+                    Change::Target {
+                        path: path.clone(),
+                        expected: expected.to_path_buf(),
+                        actual,
+                    }
+                }
+                (Some(Flat::Keep), Some(EntryKind::Dir)) => {
+                    let entries: Vec<PathBuf> = actual
+                        .keys()
+                        .filter(|entry| entry.parent() == Some(path))
+                        .map(|entry| entry.strip_prefix(path).unwrap_or(entry).to_owned())
+                        .collect();
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    collapsed = Some(path.clone());
+                    Change::NotEmpty {
+                        path: path.clone(),
+                        entries,
+                    }
+                }
+                (Some(Flat::HardLink(original)), Some(EntryKind::File)) => {
+                    if same_file(&dir.join(original), &dir.join(path))? {
+                        continue;
+                    }
+                    Change::NotLinked {
+                        path: path.clone(),
+                        original: original.clone(),
+                    }
+                }
+                (Some(expected), Some(actual)) if expected.kind() != *actual => {
+                    collapsed = Some(path.clone());
+                    Change::KindMismatch {
+                        path: path.clone(),
+                        expected: expected.kind(),
+                        actual: *actual,
+                    }
+                }
+                _ => continue,
+            };
+            changes.push(change);
+        }
+        Ok(TreeDiff { changes })
+    }
+}
+
+fn walk(
+    root: &Path,
+    dir: &Path,
+    ignore: &Ignore,
+    actual: &mut BTreeMap<PathBuf, EntryKind>,
+) -> Result<()> {
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        let path = entry.path();
+        let relative_path = path.strip_prefix(root).unwrap_or(&path).to_owned();
+        if ignore.matches(&relative_path) {
+            continue;
+        }
+        let kind = EntryKind::from(entry.file_type()?);
+        actual.insert(relative_path, kind);
+        if kind == EntryKind::Dir {
+            walk(root, &path, ignore, actual)?;
+        }
+    }
+    Ok(())
+}
+
+/// Glob patterns of the relative paths left out of a diff, split into
+/// components, see [`Tree::diff_ignoring`].
+struct Ignore(Vec<Vec<String>>);
+
+impl Ignore {
+    fn new<I: IntoIterator<Item = S>, S: AsRef<str>>(patterns: I) -> Ignore {
+        let patterns = patterns.into_iter().map(|pattern| {
+            let pattern = pattern.as_ref().trim_matches('/');
+            let mut components: Vec<String> = Vec::new();
+            if !pattern.contains('/') {
+                components.push("**".to_owned());
+            }
+            components.extend(pattern.split('/').map(str::to_owned));
+            components
+        });
+        Ignore(patterns.collect())
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        if self.0.is_empty() {
+            return false;
+        }
+        let components: Vec<_> = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        self.0
+            .iter()
+            .any(|pattern| matches_components(pattern, &components))
+    }
+}
+
+fn matches_components<S: AsRef<str>>(pattern: &[String], components: &[S]) -> bool {
+    match pattern.split_first() {
+        None => components.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            matches_components(rest, components)
+                || (!components.is_empty() && matches_components(pattern, &components[1..]))
+        }
+        Some((first, rest)) => match components.split_first() {
+            Some((component, others)) => {
+                let pattern: Vec<char> = first.chars().collect();
+                let name: Vec<char> = component.as_ref().chars().collect();
+                matches_name(&pattern, &name) && matches_components(rest, others)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Whether `name` matches the `pattern` of a component, with `*` and `?`
+/// wildcards.
+///
+/// A mismatch after a `*` only backtracks to the last `*`, letting it match
+/// one more character, which keeps the matching linear in practice.
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether both paths are the same file on disk, on platforms without file
+/// identifiers their contents are compared. A missing original is reported on
+/// its own, it is not the same file.
+fn same_file(original: &Path, path: &Path) -> Result<bool> {
+    if !original.is_file() {
+        return Ok(false);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let (original, path) = (original.metadata()?, path.metadata()?);
+        Ok(original.dev() == path.dev() && original.ino() == path.ino())
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(std::fs::read(original)? == std::fs::read(path)?)
+    }
+}
+
+/// Above this size, contents compared in chunks are reported by the offset
+/// of their first difference rather than held in memory.
+const MAX_REPORTED_CONTENTS: u64 = 1 << 20;
+
+/// Size of the chunks of the contents compared in chunks.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The change of the file at `full_path`, reported at `path`, when its
+/// contents differ from the streamed `content`.
+fn streamed_change(path: &Path, content: &Content, full_path: &Path) -> Result<Option<Change>> {
+    let (len, expected) = match content.streamed() {
+        Some(streamed) => streamed,
+        None => return Ok(None),
+    };
+    let offset = match first_difference(expected, std::fs::File::open(full_path)?)? {
+        Some(offset) => offset,
+        None => return Ok(None),
+    };
+    let actual_len = std::fs::metadata(full_path)?.len();
+    if len.max(actual_len) > MAX_REPORTED_CONTENTS {
+        return Ok(Some(Change::ContentsFrom {
+            path: path.to_owned(),
+            offset,
+        }));
+    }
+    Ok(Some(Change::Contents {
+        path: path.to_owned(),
+        expected: content.read()?.into_owned(),
+        actual: std::fs::read(full_path)?,
+    }))
+}
+
+/// Offset of the first byte differing between `expected` and `actual`, read
+/// in chunks, or of the end of the shorter one.
+fn first_difference(mut expected: impl Read, mut actual: impl Read) -> Result<Option<u64>> {
+    let mut expected_chunk = vec![0; CHUNK_SIZE];
+    let mut actual_chunk = vec![0; CHUNK_SIZE];
+    let mut offset = 0;
+    loop {
+        let expected_len = read_chunk(&mut expected, &mut expected_chunk)?;
+        let actual_len = read_chunk(&mut actual, &mut actual_chunk)?;
+        let same = expected_chunk[..expected_len]
+            .iter()
+            .zip(&actual_chunk[..actual_len])
+            .take_while(|(expected, actual)| expected == actual)
+            .count();
+        if same < expected_len.max(actual_len) {
+            return Ok(Some(offset + same as u64));
+        }
+        if expected_len == 0 {
+            return Ok(None);
+        }
+        offset += expected_len as u64;
+    }
+}
+
+/// Fill `chunk` from `reader` unless it ends first, returning the length read.
+fn read_chunk(reader: &mut impl Read, chunk: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < chunk.len() {
+        match reader.read(&mut chunk[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
+
+/// Above this number of compared line pairs, changed lines are listed without
+/// computing the longest common subsequence.
+const MAX_LCS_CELLS: usize = 1 << 20;
+
+fn write_contents_diff(f: &mut fmt::Formatter<'_>, expected: &[u8], actual: &[u8]) -> fmt::Result {
+    let (expected, actual) = match (std::str::from_utf8(expected), std::str::from_utf8(actual)) {
+        (Ok(expected), Ok(actual)) => (expected, actual),
+        _ => {
+            return writeln!(
+                f,
+                "  binary contents differ: expected {} bytes, found {} bytes",
+                expected.len(),
+                actual.len()
+            )
+        }
+    };
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    let prefix = expected
+        .iter()
+        .zip(&actual)
+        .take_while(|(expected, actual)| expected == actual)
+        .count();
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(actual[prefix..].iter().rev())
+        .take_while(|(expected, actual)| expected == actual)
+        .count();
+    let expected = &expected[prefix..expected.len() - suffix];
+    let actual = &actual[prefix..actual.len() - suffix];
+
+    if expected.is_empty() && actual.is_empty() {
+        // Only line endings differ.
+        return writeln!(f, "  line endings differ");
+    }
+    writeln!(f, "  @@ line {} @@", prefix + 1)?;
+    for line in diff_lines(expected, actual) {
+        match line {
+            Line::Same(line) => writeln!(f, "    {}", line)?,
+            Line::Removed(line) => writeln!(f, "  - {}", line)?,
+            Line::Added(line) => writeln!(f, "  + {}", line)?,
+        }
+    }
+    Ok(())
+}
+
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+fn diff_lines<'a>(expected: &[&'a str], actual: &[&'a str]) -> Vec<Line<'a>> {
+    let (n, m) = (expected.len(), actual.len());
+    if n.saturating_mul(m) > MAX_LCS_CELLS {
+        return expected
+            .iter()
+            .map(|line| Line::Removed(line))
+            .chain(actual.iter().map(|line| Line::Added(line)))
+            .collect();
+    }
+
+    // lcs[i][j] is the length of the longest common subsequence of
+    // expected[i..] and actual[j..].
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if expected[i] == actual[j] {
+            lines.push(Line::Same(expected[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(Line::Removed(expected[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(actual[j]));
+            j += 1;
+        }
+    }
+    lines.extend(expected[i..].iter().map(|line| Line::Removed(line)));
+    lines.extend(actual[j..].iter().map(|line| Line::Added(line)));
+    lines
+}
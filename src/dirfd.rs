@@ -0,0 +1,104 @@
+//! Creation of entries relative to the handle of their directory, which is
+//! resolved once for all its entries rather than for each of them, and
+//! can't be redirected by a symlink swapped in during the creation.
+
+use std::ffi::{CString, OsStr};
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Path, PathBuf};
+
+use crate::WriteOptions;
+
+/// An open directory whose entries are created with `mkdirat` and `openat`.
+pub(crate) struct DirHandle {
+    dir: std::fs::File,
+    path: PathBuf,
+}
+
+impl DirHandle {
+    /// Open the directory at `path`.
+    pub(crate) fn open(path: &Path) -> Result<DirHandle> {
+        let c_path = match path.as_os_str() {
+            name if name.is_empty() => c_name(OsStr::new("."))?,
+            name => c_name(name)?,
+        };
+        let dir = open_dir(libc::AT_FDCWD, &c_path, 0)?;
+        Ok(DirHandle {
+            dir,
+            path: path.to_owned(),
+        })
+    }
+
+    /// Path of the directory when it was opened.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create the directory `name`, existing directories being left as is.
+    pub(crate) fn create_dir(&self, name: &OsStr) -> Result<()> {
+        let c_name = c_name(name)?;
+        if unsafe { libc::mkdirat(self.dir.as_raw_fd(), c_name.as_ptr(), 0o777) } == 0 {
+            return Ok(());
+        }
+        let err = Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EEXIST) {
+            return Err(err);
+        }
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        if unsafe { libc::fstatat(self.dir.as_raw_fd(), c_name.as_ptr(), stat.as_mut_ptr(), 0) }
+            == -1
+        {
+            return Err(Error::last_os_error());
+        }
+        match unsafe { stat.assume_init() }.st_mode & libc::S_IFMT {
+            libc::S_IFDIR => Ok(()),
+            _ => Err(err),
+        }
+    }
+
+    /// Open the directory `name`, which must not be a symlink.
+    pub(crate) fn open_dir(&self, name: &OsStr) -> Result<DirHandle> {
+        let dir = open_dir(self.dir.as_raw_fd(), &c_name(name)?, libc::O_NOFOLLOW)?;
+        Ok(DirHandle {
+            dir,
+            path: self.path.join(name),
+        })
+    }
+
+    /// Write the file `name` with the write options, replacing the contents
+    /// of an existing file but failing on a symlink.
+    pub(crate) fn write_file(
+        &self,
+        name: &OsStr,
+        contents: &[u8],
+        options: &WriteOptions,
+    ) -> Result<()> {
+        let c_name = c_name(name)?;
+        let flags =
+            libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC | libc::O_NOFOLLOW;
+        let fd = unsafe { libc::openat(self.dir.as_raw_fd(), c_name.as_ptr(), flags, 0o666u32) };
+        if fd == -1 {
+            return Err(Error::last_os_error());
+        }
+        let mut file = unsafe { std::fs::File::from_raw_fd(fd) };
+        crate::fs::write_with(&mut file, contents, options)?;
+        if options.syncs_data() {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+fn c_name(name: &OsStr) -> Result<CString> {
+    CString::new(name.as_bytes()).map_err(|err| Error::new(ErrorKind::InvalidInput, err))
+}
+
+fn open_dir(dir: i32, name: &CString, flags: i32) -> Result<std::fs::File> {
+    let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC | flags;
+    let fd = unsafe { libc::openat(dir, name.as_ptr(), flags) };
+    if fd == -1 {
+        return Err(Error::last_os_error());
+    }
+    Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+}
@@ -0,0 +1,108 @@
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::checksum::sha256_hex;
+use crate::{Content, File, Generator};
+
+/// Name of the environment variable setting the directory of the downloaded
+/// files.
+const DOWNLOADS_VAR: &str = "MACRO_FILES_DOWNLOADS";
+
+/// A file with the contents downloaded from `url`, pinned by their SHA-256
+/// digest as a hexadecimal string, for integration tests against real-world
+/// sample files which are not worth vendoring.
+///
+/// _This requires the feature `http`._
+///
+/// The contents are downloaded once, when the file is first created, then
+/// kept in the [`downloads_dir`] under the name of their digest and read from
+/// there by later creations, across test runs. Creation fails with an
+/// [`ErrorKind::InvalidInput`] error for an invalid digest, with an
+/// [`ErrorKind::Other`] error when the download fails, and with a
+/// [`ChecksumMismatch`](crate::ChecksumMismatch) error, of kind
+/// [`ErrorKind::InvalidData`], when the downloaded contents do not match the
+/// digest.
+///
+/// ```no_run
+/// let dir = macro_files::create_temp!({
+///     "sample.csv": macro_files::download(
+///         "https://example.com/sample.csv",
+///         "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+///     ),
+/// })
+/// .unwrap();
+/// ```
+pub fn download<U: Into<String>, S: AsRef<str>>(url: U, sha256: S) -> File {
+    let url = url.into();
+    let digest = sha256.as_ref().to_ascii_lowercase();
+    let file = File::from(Content::Generator(Generator::new({
+        let digest = digest.clone();
+        move || fetch(&url, &digest)
+    })));
+    file.sha256(digest)
+}
+
+/// Directory of the files downloaded with [`download`]: the
+/// `MACRO_FILES_DOWNLOADS` environment variable if set, e.g. a directory
+/// cached by the CI, or else `macro_files-downloads` in the system temporary
+/// directory.
+///
+/// The directory is created when a file is first downloaded. Its files can
+/// be removed at any time.
+pub fn downloads_dir() -> PathBuf {
+    match std::env::var_os(DOWNLOADS_VAR) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => crate::platform::temp_dir().join("macro_files-downloads"),
+    }
+}
+
+/// Contents of `url` with the given digest, from the downloads directory or
+/// else downloaded into it.
+fn fetch(url: &str, digest: &str) -> Result<Vec<u8>> {
+    if digest.len() != 64 || !digest.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid sha256 digest for {}: {:?}", url, digest),
+        ));
+    }
+    let dir = downloads_dir();
+    let cached = dir.join(digest);
+    if let Ok(contents) = std::fs::read(&cached) {
+        if sha256_hex(&mut contents.as_slice())? == digest {
+            return Ok(contents);
+        }
+    }
+    let contents = get(url)?;
+    crate::checksum::verify_sha256(Path::new(url), digest, &mut contents.as_slice())?;
+    // Best effort, the contents are downloaded again next time.
+    let _ = store(&dir, &cached, &contents);
+    Ok(contents)
+}
+
+fn get(url: &str) -> Result<Vec<u8>> {
+    let response = ureq::get(url).call().map_err(|err| {
+        Error::new(
+            ErrorKind::Other,
+            format!("cannot download {}: {}", url, err),
+        )
+    })?;
+    let mut contents = Vec::new();
+    response.into_reader().read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Write `contents` at `cached` through a partial file renamed once written,
+/// so that concurrent creations never read a partial download.
+fn store(dir: &Path, cached: &Path, contents: &[u8]) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    let partial = cached.with_extension(format!("{}-{}.partial", std::process::id(), nanos));
+    std::fs::write(&partial, contents)?;
+    std::fs::rename(&partial, cached).map_err(|err| {
+        let _ = std::fs::remove_file(&partial);
+        err
+    })
+}
@@ -0,0 +1,77 @@
+use std::ffi::OsStr;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use crate::{Content, File};
+
+/// The error of a missing or invalid environment variable.
+fn env_error(name: &OsStr, err: std::env::VarError) -> Error {
+    match err {
+        std::env::VarError::NotPresent => Error::new(
+            ErrorKind::NotFound,
+            format!("environment variable {:?} is not set", name),
+        ),
+        std::env::VarError::NotUnicode(_) => Error::new(
+            ErrorKind::InvalidData,
+            format!("environment variable {:?} is not valid unicode", name),
+        ),
+    }
+}
+
+/// A file whose contents are the value of the environment variable `name`,
+/// read when the function is called.
+///
+/// Creating the file fails with an [`ErrorKind::NotFound`] error naming the
+/// variable if it is not set, or an [`ErrorKind::InvalidData`] error if it is
+/// not valid unicode.
+///
+/// ```
+/// std::env::set_var("APP_TOKEN", "secret");
+///
+/// let dir = macro_files::create_temp!({
+///     "token": macro_files::env("APP_TOKEN"),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "token": "secret",
+/// });
+/// ```
+pub fn env<K: AsRef<OsStr>>(name: K) -> File {
+    let name = name.as_ref();
+    match std::env::var(name) {
+        Ok(value) => File::from(Content::Text(value)),
+        Err(err) => {
+            let err = env_error(name, err);
+            File::failing(err.kind(), err.to_string())
+        }
+    }
+}
+
+/// The path in the environment variable `name`, to use in keys.
+///
+/// Fails with an [`ErrorKind::NotFound`] error naming the variable if it is
+/// not set, or is empty. The error can be returned with `?` from the key.
+///
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// let root = macro_files::tempfile::tempdir()?;
+/// std::env::set_var("FIXTURE_ROOT", root.path());
+///
+/// macro_files::create!({
+///     macro_files::env_path("FIXTURE_ROOT")?: {
+///         "README.md": "# Fixture",
+///     },
+/// })?;
+///
+/// assert!(root.path().join("README.md").is_file());
+/// # Ok(())
+/// # }
+/// ```
+pub fn env_path<K: AsRef<OsStr>>(name: K) -> Result<PathBuf> {
+    let name = name.as_ref();
+    match std::env::var_os(name) {
+        Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Err(env_error(name, std::env::VarError::NotPresent)),
+    }
+}
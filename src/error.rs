@@ -0,0 +1,280 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{CreateReport, Options};
+
+/// Error returned when a filesystem operation fails, with the entry it was
+/// performed on.
+///
+/// It is wrapped in an [`io::Error`] of the same kind as the error of the
+/// operation, returned by [`Error::source`], and can be recovered with
+/// [`io::Error::get_ref`] and `downcast_ref`.
+///
+/// ```
+/// use macro_files::{FsError, FsOperation};
+///
+/// let dir = macro_files::create_temp!({
+///     "file": "",
+/// })
+/// .unwrap();
+///
+/// let err = macro_files::create_in!(dir.path(), {
+///     "file/nested": {},
+/// })
+/// .unwrap_err();
+/// let err = err.get_ref().unwrap().downcast_ref::<FsError>().unwrap();
+///
+/// assert_eq!(err.operation(), FsOperation::CreateDir);
+/// assert_eq!(err.path(), dir.path().join("file/nested"));
+/// ```
+#[derive(Debug)]
+pub struct FsError {
+    operation: FsOperation,
+    path: PathBuf,
+    target: Option<PathBuf>,
+    source: io::Error,
+}
+
+/// A filesystem operation, see [`FsOps`](crate::FsOps).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FsOperation {
+    /// Create a directory and its missing parents.
+    CreateDir,
+    /// Write a file.
+    WriteFile,
+    /// Copy a file.
+    CopyFile,
+    /// Create a symbolic link.
+    CreateSymlink,
+    /// Create a directory junction.
+    CreateJunction,
+    /// Create a hard link.
+    CreateHardLink,
+    /// Create a named pipe.
+    CreateFifo,
+    /// Create a Unix domain socket.
+    CreateSocket,
+    /// Look up the kind of an existing entry.
+    EntryKind,
+    /// Remove a file or a symlink.
+    RemoveFile,
+    /// Remove a directory, with its entries when synchronizing a directory
+    /// with a spec.
+    RemoveDir,
+    /// Set the access and modification times of an entry.
+    SetTimes,
+    /// Change the owner and group of an entry.
+    SetOwner,
+    /// Set the flags of a file.
+    SetFileAttributes,
+    /// Move a file, directory or symlink.
+    MovePath,
+}
+
+impl FsError {
+    pub(crate) fn new(operation: FsOperation, path: &Path, source: io::Error) -> FsError {
+        FsError {
+            operation,
+            path: path.to_owned(),
+            target: None,
+            source,
+        }
+    }
+
+    pub(crate) fn with_target(mut self, target: &Path) -> FsError {
+        self.target = Some(target.to_owned());
+        self
+    }
+
+    /// The operation that failed.
+    pub fn operation(&self) -> FsOperation {
+        self.operation
+    }
+
+    /// Path of the entry the operation was performed on, the destination of
+    /// a copy or a move.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The other path of the operation, if any: the source of a copy or a
+    /// move, the target of a symlink or the original of a hard link.
+    pub fn target(&self) -> Option<&Path> {
+        self.target.as_deref()
+    }
+}
+
+impl fmt::Display for FsOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FsOperation::CreateDir => "create directory",
+            FsOperation::WriteFile => "write file",
+            FsOperation::CopyFile => "copy file",
+            FsOperation::CreateSymlink => "create symlink",
+            FsOperation::CreateJunction => "create junction",
+            FsOperation::CreateHardLink => "create hard link",
+            FsOperation::CreateFifo => "create fifo",
+            FsOperation::CreateSocket => "create socket",
+            FsOperation::EntryKind => "look up",
+            FsOperation::RemoveFile => "remove",
+            FsOperation::RemoveDir => "remove directory",
+            FsOperation::SetTimes => "set the times of",
+            FsOperation::SetOwner => "set the owner of",
+            FsOperation::SetFileAttributes => "set the attributes of",
+            FsOperation::MovePath => "move",
+        })
+    }
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to {} {:?}", self.operation, self.path)?;
+        if let Some(target) = &self.target {
+            let preposition = match self.operation {
+                FsOperation::CopyFile | FsOperation::MovePath => "from",
+                _ => "to",
+            };
+            write!(f, " {} {:?}", preposition, target)?;
+        }
+        write!(f, ": {}", self.source)
+    }
+}
+
+impl Error for FsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<FsError> for io::Error {
+    fn from(err: FsError) -> Self {
+        io::Error::new(err.source.kind(), err)
+    }
+}
+
+/// Add the operation and the entry it was performed on to the errors of
+/// filesystem operations, unless they already have them.
+pub(crate) trait Context<T> {
+    fn context(self, operation: FsOperation, path: &Path) -> io::Result<T>;
+
+    fn context_with(self, operation: FsOperation, path: &Path, target: &Path) -> io::Result<T>;
+}
+
+impl<T> Context<T> for io::Result<T> {
+    fn context(self, operation: FsOperation, path: &Path) -> io::Result<T> {
+        self.map_err(|err| wrap(err, |err| FsError::new(operation, path, err)))
+    }
+
+    fn context_with(self, operation: FsOperation, path: &Path, target: &Path) -> io::Result<T> {
+        self.map_err(|err| {
+            wrap(err, |err| {
+                FsError::new(operation, path, err).with_target(target)
+            })
+        })
+    }
+}
+
+fn wrap<F: FnOnce(io::Error) -> FsError>(err: io::Error, f: F) -> io::Error {
+    let has_context = err.get_ref().map_or(false, |inner| inner.is::<FsError>());
+    if has_context {
+        err
+    } else {
+        f(err).into()
+    }
+}
+
+/// Error returned when entries fail to be created in best-effort mode, see
+/// [`Options::best_effort`].
+///
+/// It is wrapped in an [`io::Error`] of the kind of the first failure and can
+/// be recovered with [`io::Error::get_ref`] and `downcast_ref`, or
+/// [`io::Error::into_inner`] and `downcast` to take the errors.
+///
+/// The [`Display`](fmt::Display) implementation lists one failure per line.
+#[derive(Debug)]
+pub struct CreateErrors {
+    errors: Vec<(PathBuf, io::Error)>,
+    report: CreateReport,
+}
+
+impl CreateErrors {
+    /// Path of every entry that failed to be created with its error, in
+    /// order.
+    pub fn errors(&self) -> &[(PathBuf, io::Error)] {
+        &self.errors
+    }
+
+    /// Take the errors, see [`CreateErrors::errors`].
+    pub fn into_errors(self) -> Vec<(PathBuf, io::Error)> {
+        self.errors
+    }
+
+    /// The entries created and skipped despite the failures.
+    pub fn report(&self) -> &CreateReport {
+        &self.report
+    }
+}
+
+impl fmt::Display for CreateErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = if self.errors.len() == 1 { "y" } else { "ies" };
+        write!(f, "{} entr{} failed to be created", self.errors.len(), s)?;
+        for (path, err) in &self.errors {
+            write!(f, "\n{:?}: {}", path, err)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for CreateErrors {}
+
+impl From<CreateErrors> for io::Error {
+    fn from(err: CreateErrors) -> Self {
+        let kind = err
+            .errors
+            .first()
+            .map_or(io::ErrorKind::Other, |(_, err)| err.kind());
+        io::Error::new(kind, err)
+    }
+}
+
+/// Failures of the entries of a creation, collected in best-effort mode.
+pub(crate) struct Failures {
+    best_effort: bool,
+    errors: Vec<(PathBuf, io::Error)>,
+}
+
+impl Failures {
+    pub(crate) fn new(options: &Options) -> Failures {
+        Failures {
+            best_effort: options.is_best_effort(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Record that the entry at `path` failed to be created, or return the
+    /// error unless in best-effort mode.
+    pub(crate) fn push(&mut self, path: PathBuf, err: io::Error) -> io::Result<()> {
+        if !self.best_effort {
+            return Err(err);
+        }
+        self.errors.push((path, err));
+        Ok(())
+    }
+
+    /// The report of the creation, or an error if entries failed.
+    pub(crate) fn finish(self, report: CreateReport) -> io::Result<CreateReport> {
+        if self.errors.is_empty() {
+            Ok(report)
+        } else {
+            Err(CreateErrors {
+                errors: self.errors,
+                report,
+            }
+            .into())
+        }
+    }
+}
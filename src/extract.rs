@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+
+use crate::{Entry, File, Tree};
+
+/// A directory with the entries unpacked from the archive at `path`, for
+/// fixtures stored compressed in the repository.
+///
+/// _This requires the feature `tar` for `.tar` archives, with the feature
+/// `flate2` for `.tar.gz` and `.tgz` ones, or the feature `zip` for `.zip`
+/// archives._
+///
+/// The archive is read when the entry is declared and relative paths are
+/// resolved against the current directory. Directories, files and symlinks
+/// are unpacked, hard links as copies of their original, and files with an
+/// execute permission are [executable](File::executable). Other permissions,
+/// owners and modification times are not kept. Creation fails with the
+/// error met when the archive cannot be read, its format is not supported, or
+/// an entry would be unpacked outside of the directory.
+///
+/// ```
+/// # #[cfg(all(feature = "tar", feature = "flate2"))]
+/// # {
+/// let dir = macro_files::create_temp!({
+///     "data": macro_files::extract("tests/fixtures/data.tar.gz"),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "data": {
+///         "README.md": "# Data\n",
+///         "bin/run.sh": "#!/bin/sh\necho run\n",
+///         "records": {
+///             "1.csv": "id,name\n1,first\n",
+///             "2.csv": "id,name\n2,second\n",
+///         },
+///     },
+/// });
+/// # }
+/// ```
+pub fn extract<P: AsRef<Path>>(path: P) -> Entry {
+    let path = path.as_ref();
+    match read_archive(path) {
+        Ok(tree) => Entry::Dir(tree),
+        Err(err) => Entry::File(File::failing(
+            err.kind(),
+            format!("cannot extract {:?}: {}", path, err),
+        )),
+    }
+}
+
+fn read_archive(path: &Path) -> Result<Tree> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut extracted = Extracted::default();
+    if name.ends_with(".tar") {
+        read_tar(path, false, &mut extracted)?;
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        read_tar(path, true, &mut extracted)?;
+    } else if name.ends_with(".zip") {
+        read_zip(path, &mut extracted)?;
+    } else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "unsupported archive format, expected .tar, .tar.gz, .tgz or .zip",
+        ));
+    }
+    Ok(extracted.into_tree())
+}
+
+#[cfg(feature = "tar")]
+fn read_tar(path: &Path, gzip: bool, extracted: &mut Extracted) -> Result<()> {
+    let archive = std::fs::File::open(path)?;
+    if gzip {
+        #[cfg(feature = "flate2")]
+        return crate::tar_archive::extract_tar(flate2::read::GzDecoder::new(archive), extracted);
+        #[cfg(not(feature = "flate2"))]
+        return Err(requires("flate2"));
+    }
+    crate::tar_archive::extract_tar(archive, extracted)
+}
+
+#[cfg(not(feature = "tar"))]
+fn read_tar(_path: &Path, _gzip: bool, _extracted: &mut Extracted) -> Result<()> {
+    Err(requires("tar"))
+}
+
+#[cfg(feature = "zip")]
+fn read_zip(path: &Path, extracted: &mut Extracted) -> Result<()> {
+    crate::zip_archive::extract_zip(std::fs::File::open(path)?, extracted)
+}
+
+#[cfg(not(feature = "zip"))]
+fn read_zip(_path: &Path, _extracted: &mut Extracted) -> Result<()> {
+    Err(requires("zip"))
+}
+
+#[cfg(any(not(feature = "tar"), not(feature = "zip"), not(feature = "flate2")))]
+fn requires(feature: &str) -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        format!("this archive format requires the feature `{}`", feature),
+    )
+}
+
+/// Entries unpacked from an archive, with paths relative to its root.
+#[derive(Default)]
+pub(crate) struct Extracted {
+    root: Node,
+    files: HashMap<PathBuf, File>,
+}
+
+#[derive(Default)]
+struct Node {
+    entries: Vec<(OsString, Slot)>,
+}
+
+enum Slot {
+    Dir(Node),
+    File(Box<File>),
+}
+
+impl Extracted {
+    pub(crate) fn dir(&mut self, path: &Path) -> Result<()> {
+        self.insert(path, Slot::Dir(Node::default()))
+    }
+
+    pub(crate) fn file(&mut self, path: &Path, contents: Vec<u8>, mode: u32) -> Result<()> {
+        let file = File::new(contents);
+        let file = if mode & 0o111 != 0 {
+            file.executable()
+        } else {
+            file
+        };
+        self.files.insert(archive_path(path)?, file.clone());
+        self.insert(path, Slot::File(Box::new(file)))
+    }
+
+    pub(crate) fn symlink(&mut self, path: &Path, target: &Path) -> Result<()> {
+        self.insert(path, Slot::File(Box::new(crate::symlink(target))))
+    }
+
+    #[cfg(feature = "tar")]
+    pub(crate) fn hard_link(&mut self, path: &Path, original: &Path) -> Result<()> {
+        let file = self.files.get(&archive_path(original)?).cloned();
+        let file = file.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("hard link {:?} to a missing file {:?}", path, original),
+            )
+        })?;
+        self.files.insert(archive_path(path)?, file.clone());
+        self.insert(path, Slot::File(Box::new(file)))
+    }
+
+    /// Insert `slot` at `path`, replacing an entry unpacked before unless
+    /// both are directories.
+    fn insert(&mut self, path: &Path, slot: Slot) -> Result<()> {
+        let path = archive_path(path)?;
+        let mut names: Vec<_> = path.iter().map(|name| name.to_owned()).collect();
+        let name = match names.pop() {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let mut node = &mut self.root;
+        for parent in names {
+            let index = match node.entries.iter().position(|(key, _)| *key == parent) {
+                Some(index) => index,
+                None => {
+                    node.entries.push((parent, Slot::Dir(Node::default())));
+                    node.entries.len() - 1
+                }
+            };
+            node = match &mut node.entries[index].1 {
+                Slot::Dir(dir) => dir,
+                Slot::File(_) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{:?} is within a file of the archive", path),
+                    ))
+                }
+            };
+        }
+        match node.entries.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, Slot::Dir(_))) if matches!(slot, Slot::Dir(_)) => {}
+            Some((_, existing)) => *existing = slot,
+            None => node.entries.push((name, slot)),
+        }
+        Ok(())
+    }
+
+    fn into_tree(self) -> Tree {
+        self.root.into_tree()
+    }
+}
+
+impl Node {
+    fn into_tree(self) -> Tree {
+        let mut tree = Tree::new();
+        for (name, slot) in self.entries {
+            match slot {
+                Slot::Dir(dir) => tree.push(name, dir.into_tree()),
+                Slot::File(file) => tree.push(name, *file),
+            }
+        }
+        tree
+    }
+}
+
+/// `path` relative to the root of the archive, failing if it would be
+/// unpacked outside of it.
+fn archive_path(path: &Path) -> Result<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => relative.push(name),
+            Component::CurDir => {}
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{:?} would be unpacked outside of the directory", path),
+                ))
+            }
+        }
+    }
+    Ok(relative)
+}
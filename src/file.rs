@@ -0,0 +1,1129 @@
+use std::borrow::Cow;
+#[cfg(unix)]
+use std::ffi::OsStr;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::attrs::{Attrs, FileAttributes, Hook};
+#[cfg(unix)]
+use crate::dirfd::DirHandle;
+#[cfg(unix)]
+use crate::error::{Context, FsOperation};
+use crate::{
+    copy_file, create_hard_link, create_junction, create_symlink, entry_path, write_file_with,
+    Conflict, Content, EntryKind, Generator, LineEnding, Reader, WriteOptions,
+};
+
+/// A file entry: its contents and attributes.
+///
+/// Created with [`file`], [`from`] or from a [`Content`], it can be used as a
+/// value in the macros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct File {
+    content: Content,
+    attrs: Attrs,
+    origin: Option<Arc<str>>,
+    stamp: Option<Arc<str>>,
+    append: bool,
+    conflict: Option<Conflict>,
+    line_ending: Option<LineEnding>,
+    #[cfg(feature = "checksum")]
+    sha256: Option<String>,
+}
+
+/// A file with the given contents, whose attributes can then be declared.
+///
+/// ```
+/// # #[cfg(feature = "checksum")]
+/// let dir = macro_files::create_temp!({
+///     "blob.bin": macro_files::file("contents")
+///         .sha256("d1b2a59fbea7e20077af9f91b27e95e865061b270be03ff539ab3b73587882e8"),
+/// })
+/// .unwrap();
+/// ```
+pub fn file<C: AsRef<[u8]>>(contents: C) -> File {
+    File::new(contents)
+}
+
+/// A file whose contents are copied from the existing file at `path` when it
+/// is created, which keeps large or binary fixtures out of the binary.
+///
+/// Relative paths are resolved against the current directory. On
+/// copy-on-write filesystems the copy shares the data blocks of the source,
+/// see [`FsOps::copy_file`](crate::FsOps::copy_file).
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "Cargo.toml": macro_files::from("Cargo.toml"),
+/// })
+/// .unwrap();
+///
+/// assert_eq!(
+///     std::fs::read(dir.path().join("Cargo.toml")).unwrap(),
+///     std::fs::read("Cargo.toml").unwrap()
+/// );
+/// ```
+pub fn from<P: AsRef<Path>>(path: P) -> File {
+    File::from(Content::CopyFrom(path.as_ref().to_owned()))
+}
+
+/// A symbolic link to `target`, which is resolved relative to the directory
+/// of the link when relative.
+///
+/// ```
+/// # #[cfg(unix)]
+/// # {
+/// let dir = macro_files::create_temp!({
+///     "README.md": "# Project",
+///     "docs/index.md": macro_files::symlink("../README.md"),
+/// })
+/// .unwrap();
+///
+/// let link = dir.path().join("docs/index.md");
+/// assert_eq!(std::fs::read_link(&link).unwrap(), std::path::Path::new("../README.md"));
+/// assert_eq!(std::fs::read_to_string(&link).unwrap(), "# Project");
+/// # }
+/// ```
+pub fn symlink<P: AsRef<Path>>(target: P) -> File {
+    File::from(Content::Symlink(target.as_ref().to_owned()))
+}
+
+/// A directory junction to the directory `target` on Windows, which unlike a
+/// symlink can be created without any privilege, and a symbolic link on the
+/// other platforms.
+///
+/// Relative targets are resolved relative to the directory of the junction,
+/// which only holds absolute targets: it is read back as a symlink to the
+/// absolute target on Windows.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "src/lib.rs": "",
+///     "vendor": macro_files::junction("src"),
+/// })
+/// .unwrap();
+///
+/// assert!(dir.path().join("vendor/lib.rs").is_file());
+/// ```
+pub fn junction<P: AsRef<Path>>(target: P) -> File {
+    File::from(Content::Junction(target.as_ref().to_owned()))
+}
+
+/// A hard link to the file `original` of the spec, whose path is relative to
+/// the directory the spec is created in.
+///
+/// The original file must be declared before the link.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "data.bin": [0, 1, 2],
+///     "backup": {
+///         "data.bin": macro_files::hardlink("data.bin"),
+///     },
+/// })
+/// .unwrap();
+///
+/// assert_eq!(std::fs::read(dir.path().join("backup/data.bin")).unwrap(), [0, 1, 2]);
+/// ```
+pub fn hardlink<P: AsRef<Path>>(original: P) -> File {
+    File::from(Content::HardLink(original.as_ref().to_owned()))
+}
+
+/// A file with the contents streamed from `reader` when it is created,
+/// without holding them in memory, see [`Content::Reader`].
+///
+/// The reader is consumed by the first creation, later creations fail. The
+/// contents are read in memory when a checksum is declared, to be verified
+/// before the file is written.
+///
+/// ```
+/// let dump = std::io::Read::take(std::io::repeat(b'x'), 1024);
+/// let dir = macro_files::create_temp!({
+///     "dump.bin": macro_files::reader(dump),
+/// })
+/// .unwrap();
+///
+/// assert_eq!(std::fs::metadata(dir.path().join("dump.bin")).unwrap().len(), 1024);
+/// ```
+pub fn reader<R: Read + Send + 'static>(reader: R) -> File {
+    File::from(Content::Reader(Reader::new(reader)))
+}
+
+/// A file with the contents produced by an iterator of chunks, each written
+/// as soon as it is produced, without collecting them in memory.
+///
+/// Like a [`reader`], the iterator is consumed by the first creation, later
+/// creations fail.
+///
+/// ```
+/// let lines = (0..1000).map(|i| format!("line {}\n", i));
+/// let dir = macro_files::create_temp!({
+///     "big.log": macro_files::chunks(lines),
+/// })
+/// .unwrap();
+///
+/// let log = std::fs::read_to_string(dir.path().join("big.log")).unwrap();
+/// assert!(log.starts_with("line 0\nline 1\n"));
+/// assert!(log.ends_with("line 999\n"));
+/// ```
+pub fn chunks<I>(chunks: I) -> File
+where
+    I: IntoIterator,
+    I::IntoIter: Send + 'static,
+    I::Item: AsRef<[u8]> + Send + 'static,
+{
+    reader(ChunkReader {
+        chunks: chunks.into_iter(),
+        chunk: None,
+        offset: 0,
+    })
+}
+
+/// A file with the contents produced by an iterator of bytes, written as
+/// they are produced, without collecting them in memory.
+///
+/// Like a [`reader`], the iterator is consumed by the first creation, later
+/// creations fail.
+///
+/// ```
+/// let pattern = (0..=255u8).cycle().take(4096);
+/// let dir = macro_files::create_temp!({
+///     "pattern.bin": macro_files::byte_iter(pattern),
+/// })
+/// .unwrap();
+///
+/// let pattern = std::fs::read(dir.path().join("pattern.bin")).unwrap();
+/// assert_eq!(pattern.len(), 4096);
+/// assert_eq!(pattern[256..259], [0, 1, 2]);
+/// ```
+pub fn byte_iter<I>(bytes: I) -> File
+where
+    I: IntoIterator<Item = u8>,
+    I::IntoIter: Send + 'static,
+{
+    reader(ByteReader(bytes.into_iter()))
+}
+
+/// Reads the chunks of an iterator one after the other.
+struct ChunkReader<I: Iterator> {
+    chunks: I,
+    chunk: Option<I::Item>,
+    offset: usize,
+}
+
+impl<I> Read for ChunkReader<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if let Some(chunk) = &self.chunk {
+                let rest = &chunk.as_ref()[self.offset..];
+                if !rest.is_empty() {
+                    let len = rest.len().min(buf.len());
+                    buf[..len].copy_from_slice(&rest[..len]);
+                    self.offset += len;
+                    return Ok(len);
+                }
+            }
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.chunk = Some(chunk);
+                    self.offset = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Reads the bytes of an iterator.
+struct ByteReader<I>(I);
+
+impl<I: Iterator<Item = u8>> Read for ByteReader<I> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut len = 0;
+        for (slot, byte) in buf.iter_mut().zip(&mut self.0) {
+            *slot = byte;
+            len += 1;
+        }
+        Ok(len)
+    }
+}
+
+/// A file with the contents returned by `contents`, only called when the
+/// file is first written or read, see [`Content::Generator`].
+///
+/// The contents are kept for the later creations of the file. Nothing is
+/// evaluated when the creation fails before the file, nor by
+/// [`Tree::plan`](crate::Tree::plan).
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "report.txt": macro_files::lazy(|| format!("{} tests passed", 42)),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "report.txt": "42 tests passed",
+/// });
+/// ```
+pub fn lazy<F, C>(contents: F) -> File
+where
+    F: FnOnce() -> C + Send + 'static,
+    C: Into<Vec<u8>>,
+{
+    let state = Mutex::new((Some(contents), None));
+    File::from(Content::Generator(Generator::new(move || {
+        let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(contents) = state.0.take() {
+            state.1 = Some(contents().into());
+        }
+        state
+            .1
+            .clone()
+            .ok_or_else(|| Error::new(ErrorKind::Other, "lazy contents panicked"))
+    })))
+}
+
+/// One kibibyte, 1024 bytes.
+pub const KIB: u64 = 1024;
+/// One mebibyte, 1024 kibibytes.
+pub const MIB: u64 = 1024 * KIB;
+/// One gibibyte, 1024 mebibytes.
+pub const GIB: u64 = 1024 * MIB;
+
+/// A file of `len` bytes filled with zeros, which is sparse where the
+/// filesystem supports it.
+///
+/// The contents are never held in memory when the file is created, which
+/// makes large files cheap to declare.
+///
+/// ```
+/// use macro_files::{size, MIB};
+///
+/// let dir = macro_files::create_temp!({
+///     "upload.bin": size(10 * MIB),
+/// })
+/// .unwrap();
+///
+/// let metadata = std::fs::metadata(dir.path().join("upload.bin")).unwrap();
+/// assert_eq!(metadata.len(), 10 * MIB);
+/// ```
+pub fn size(len: u64) -> File {
+    File::from(Content::Zeros(len))
+}
+
+/// A file of `len` pseudo-random bytes generated from `seed`, the same for a
+/// given seed on every platform and run.
+///
+/// The bytes are incompressible, which suits compression and hashing tests.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "a.bin": macro_files::random(1024, 42),
+///     "b.bin": macro_files::random(1024, 42),
+/// })
+/// .unwrap();
+///
+/// let a = std::fs::read(dir.path().join("a.bin")).unwrap();
+/// assert_eq!(a.len(), 1024);
+/// assert_eq!(a, std::fs::read(dir.path().join("b.bin")).unwrap());
+/// ```
+pub fn random(len: u64, seed: u64) -> File {
+    File::from(Content::Random { len, seed })
+}
+
+/// A file of `len` bytes repeating `pattern`, the last repetition being cut
+/// short, or of zeros when the pattern is empty.
+///
+/// The contents are written in chunks and never held in memory when the
+/// file is created, which makes multi-gigabyte fixtures cheap to declare.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "ones.bin": macro_files::repeat([0xff], 4 * macro_files::KIB),
+///     "lines.txt": macro_files::repeat("ab\n", 7),
+/// })
+/// .unwrap();
+///
+/// let ones = std::fs::read(dir.path().join("ones.bin")).unwrap();
+/// assert_eq!(ones.len(), 4096);
+/// assert!(ones.iter().all(|&byte| byte == 0xff));
+/// let lines = std::fs::read_to_string(dir.path().join("lines.txt")).unwrap();
+/// assert_eq!(lines, "ab\nab\na");
+/// ```
+pub fn repeat<P: AsRef<[u8]>>(pattern: P, len: u64) -> File {
+    let pattern = pattern.as_ref();
+    if pattern.is_empty() {
+        return size(len);
+    }
+    File::from(Content::Repeat {
+        pattern: pattern.to_owned(),
+        len,
+    })
+}
+
+/// Reads `remaining` bytes repeating a pattern, starting at `offset` in it.
+pub(crate) struct RepeatReader<'a> {
+    pattern: &'a [u8],
+    offset: usize,
+    remaining: u64,
+}
+
+impl RepeatReader<'_> {
+    pub(crate) fn new(pattern: &[u8], len: u64) -> RepeatReader<'_> {
+        RepeatReader {
+            pattern,
+            offset: 0,
+            remaining: len,
+        }
+    }
+}
+
+impl Read for RepeatReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let period = self.pattern.len();
+        let len = (buf.len() as u64).min(self.remaining) as usize;
+        if period == 0 || len == 0 {
+            return Ok(0);
+        }
+        let buf = &mut buf[..len];
+        let mut filled = period.min(len);
+        for (i, slot) in buf[..filled].iter_mut().enumerate() {
+            *slot = self.pattern[(self.offset + i) % period];
+        }
+        // The filled bytes are a whole number of periods, copy them after.
+        while filled < len {
+            let copied = filled.min(len - filled);
+            buf.copy_within(..copied, filled);
+            filled += copied;
+        }
+        self.offset = (self.offset + len) % period;
+        self.remaining -= len as u64;
+        Ok(len)
+    }
+}
+
+/// An executable file with the given contents, see [`File::executable`].
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "bin/hello": macro_files::exec("#!/bin/sh\necho hello\n"),
+/// })
+/// .unwrap();
+///
+/// # #[cfg(unix)]
+/// # {
+/// use std::os::unix::fs::PermissionsExt;
+///
+/// let metadata = std::fs::metadata(dir.path().join("bin/hello")).unwrap();
+/// assert_eq!(metadata.permissions().mode() & 0o111, 0o111);
+/// # }
+/// ```
+pub fn exec<C: AsRef<[u8]>>(contents: C) -> File {
+    File::new(contents).executable()
+}
+
+/// A file whose contents are appended to the existing file at its path
+/// rather than replacing it, for specs applied on top of previously created
+/// trees, see [`File::appending`].
+///
+/// ```
+/// let dir = macro_files::create_temp!({ "app.log": "first line\n" }).unwrap();
+///
+/// macro_files::tree!({ "app.log": macro_files::append("extra line\n") })
+///     .create_in(dir.path())
+///     .unwrap();
+///
+/// let log = std::fs::read_to_string(dir.path().join("app.log")).unwrap();
+/// assert_eq!(log, "first line\nextra line\n");
+/// ```
+pub fn append<C: AsRef<[u8]>>(contents: C) -> File {
+    File::new(contents).appending()
+}
+
+/// An empty file created if missing, otherwise left with its contents and
+/// its modification time bumped to the time of the creation, for testing
+/// incremental builds and freshness checks.
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// let old = SystemTime::UNIX_EPOCH + Duration::from_secs(86_400);
+/// let dir = macro_files::create_temp!({
+///     "main.rs": macro_files::file("fn main() {}").mtime(old),
+/// })
+/// .unwrap();
+///
+/// macro_files::tree!({ "main.rs": macro_files::touch(), "build.rs": macro_files::touch() })
+///     .create_in(dir.path())
+///     .unwrap();
+///
+/// let main = dir.path().join("main.rs");
+/// assert!(std::fs::metadata(&main).unwrap().modified().unwrap() > old);
+/// assert_eq!(std::fs::read_to_string(main).unwrap(), "fn main() {}");
+/// assert_eq!(std::fs::read(dir.path().join("build.rs")).unwrap(), b"");
+/// ```
+pub fn touch() -> File {
+    append(b"")
+}
+
+/// A named pipe, created with the `0o666` permissions masked by the umask.
+///
+/// ```
+/// use std::os::unix::fs::FileTypeExt;
+///
+/// let dir = macro_files::create_temp!({
+///     "events": macro_files::fifo(),
+/// })
+/// .unwrap();
+///
+/// let metadata = std::fs::symlink_metadata(dir.path().join("events")).unwrap();
+/// assert!(metadata.file_type().is_fifo());
+/// ```
+#[cfg(all(feature = "unix", unix))]
+pub fn fifo() -> File {
+    File::from(Content::Fifo)
+}
+
+/// A Unix domain socket nothing listens on.
+///
+/// Socket paths are limited to about a hundred bytes on most platforms.
+#[cfg(all(feature = "unix", unix))]
+pub fn unix_socket() -> File {
+    File::from(Content::Socket)
+}
+
+impl File {
+    /// A file with the given contents.
+    pub fn new<C: AsRef<[u8]>>(contents: C) -> File {
+        File::from(Content::Bytes(contents.as_ref().to_vec()))
+    }
+
+    /// A file whose creation fails with an error of the given kind and
+    /// message, for contents which cannot be made.
+    pub(crate) fn failing(kind: ErrorKind, message: String) -> File {
+        File::from(Content::Generator(Generator::new(move || {
+            Err(Error::new(kind, message.clone()))
+        })))
+    }
+
+    /// Contents of the file.
+    pub fn content(&self) -> &Content {
+        &self.content
+    }
+
+    /// Contents of the file when they are held in memory.
+    pub fn contents(&self) -> Option<&[u8]> {
+        match &self.content {
+            Content::Bytes(bytes) => Some(bytes),
+            Content::Text(text) => Some(text.as_bytes()),
+            _ => None,
+        }
+    }
+
+    /// The file with the same attributes and other contents.
+    pub(crate) fn with_content(mut self, content: Content) -> File {
+        self.content = content;
+        self
+    }
+
+    /// Path of the file the contents are copied from, see [`from`].
+    pub fn source(&self) -> Option<&Path> {
+        match &self.content {
+            Content::CopyFrom(source) => Some(source),
+            _ => None,
+        }
+    }
+
+    /// Kind of the entry created for the file.
+    pub(crate) fn kind(&self) -> EntryKind {
+        match self.content {
+            Content::Symlink(_) | Content::Junction(_) => EntryKind::Symlink,
+            #[cfg(all(feature = "unix", unix))]
+            Content::Fifo => EntryKind::Fifo,
+            #[cfg(all(feature = "unix", unix))]
+            Content::Socket => EntryKind::Socket,
+            _ => EntryKind::File,
+        }
+    }
+
+    /// Whether the file is a symbolic or hard link, a junction, or a special
+    /// file, which cannot be written over an existing entry.
+    pub(crate) fn is_link(&self) -> bool {
+        matches!(
+            self.content,
+            Content::Symlink(_) | Content::Junction(_) | Content::HardLink(_)
+        ) || self.is_special()
+    }
+
+    /// Whether the file is a FIFO or a socket.
+    pub(crate) fn is_special(&self) -> bool {
+        #[cfg(all(feature = "unix", unix))]
+        return matches!(self.content, Content::Fifo | Content::Socket);
+        #[cfg(not(all(feature = "unix", unix)))]
+        false
+    }
+
+    /// Size of the file when known without reading its contents.
+    pub(crate) fn len(&self) -> Option<u64> {
+        match self.content {
+            Content::Zeros(len) | Content::Random { len, .. } | Content::Repeat { len, .. } => {
+                Some(len)
+            }
+            _ => self.contents().map(|contents| contents.len() as u64),
+        }
+    }
+
+    /// Contents of the file, see [`Content::read`].
+    pub fn read_contents(&self) -> Result<Cow<'_, [u8]>> {
+        self.content.read()
+    }
+
+    /// Append the contents to the existing file at its path rather than
+    /// replacing it, creating the file when missing. Appending no contents
+    /// bumps the modification time of the file, see [`touch`]. The conflict policy
+    /// does not apply to appended files, see [`Options::conflict`](crate::Options::conflict).
+    ///
+    /// Only contents which are not links or special files can be appended.
+    pub fn appending(mut self) -> File {
+        self.append = true;
+        self
+    }
+
+    /// Whether the contents are appended to the existing file, see
+    /// [`File::appending`].
+    pub fn appends(&self) -> bool {
+        self.append && !self.is_link()
+    }
+
+    /// Declare what to do when the file already exists, rather than what the
+    /// options say, see [`Options::on_conflict`](crate::Options::on_conflict).
+    ///
+    /// ```
+    /// use macro_files::Conflict;
+    ///
+    /// let dir = macro_files::create_temp!({ "config.toml": "debug = true" }).unwrap();
+    /// macro_files::tree!({
+    ///     "config.toml": macro_files::file("debug = false").on_conflict(Conflict::Skip),
+    ///     "output.log": "",
+    /// })
+    /// .create_in(dir.path())
+    /// .unwrap();
+    ///
+    /// macro_files::assert_tree!(dir.path(), {
+    ///     "config.toml": "debug = true",
+    ///     "output.log": "",
+    /// });
+    /// ```
+    pub fn on_conflict(mut self, conflict: Conflict) -> File {
+        self.conflict = Some(conflict);
+        self
+    }
+
+    /// What to do when the file already exists, if declared, see
+    /// [`File::on_conflict`].
+    pub fn declared_conflict(&self) -> Option<Conflict> {
+        self.conflict
+    }
+
+    /// Replace the line endings of the contents, when they are text held in
+    /// memory, see [`Tree::line_endings`](crate::Tree::line_endings).
+    pub fn line_ending(self, ending: LineEnding) -> File {
+        let text = match &self.content {
+            Content::Text(text) => text,
+            Content::Bytes(bytes) if !bytes.contains(&0) => match std::str::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(_) => return self,
+            },
+            _ => return self,
+        };
+        let content = Content::Text(ending.apply(text));
+        File {
+            line_ending: Some(ending),
+            ..self.with_content(content)
+        }
+    }
+
+    /// Line ending of the contents, if declared, see [`File::line_ending`].
+    pub fn declared_line_ending(&self) -> Option<LineEnding> {
+        self.line_ending
+    }
+
+    /// Declare the modification time of the file, or of the link itself for
+    /// symlinks.
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// let old = SystemTime::UNIX_EPOCH + Duration::from_secs(86_400);
+    /// let dir = macro_files::create_temp!({
+    ///     "old.log": macro_files::file("old").mtime(old),
+    /// })
+    /// .unwrap();
+    ///
+    /// let metadata = std::fs::metadata(dir.path().join("old.log")).unwrap();
+    /// assert_eq!(metadata.modified().unwrap(), old);
+    /// ```
+    pub fn mtime(mut self, time: SystemTime) -> File {
+        self.attrs.modified = Some(time);
+        self
+    }
+
+    /// Declare the access time of the file, or of the link itself for
+    /// symlinks.
+    pub fn atime(mut self, time: SystemTime) -> File {
+        self.attrs.accessed = Some(time);
+        self
+    }
+
+    /// Declared modification time of the file.
+    pub fn declared_mtime(&self) -> Option<SystemTime> {
+        self.attrs.modified
+    }
+
+    /// Declared access time of the file.
+    pub fn declared_atime(&self) -> Option<SystemTime> {
+        self.attrs.accessed
+    }
+
+    /// Declare the owner and group of the file, or of the link itself for
+    /// symlinks.
+    ///
+    /// Creation fails with a [`PermissionDenied`](std::io::ErrorKind) error
+    /// when the process is not privileged enough to change them.
+    ///
+    /// ```no_run
+    /// let dir = macro_files::create_temp!({
+    ///     "home/user/.profile": macro_files::file("").owner(1000, 1000),
+    /// })
+    /// .unwrap();
+    /// ```
+    #[cfg(all(feature = "unix", unix))]
+    pub fn owner(mut self, uid: u32, gid: u32) -> File {
+        self.attrs.owner = Some((uid, gid));
+        self
+    }
+
+    /// Declared owner and group of the file.
+    #[cfg(all(feature = "unix", unix))]
+    pub fn declared_owner(&self) -> Option<(u32, u32)> {
+        self.attrs.owner
+    }
+
+    /// Declare a function run with the path of the file once written, before
+    /// its other attributes are applied. Hooks run in their declaration
+    /// order, and the creation fails with the first error they return.
+    /// Ignored when writing archives.
+    ///
+    /// ```
+    /// let dir = macro_files::create_temp!({
+    ///     "data.db": macro_files::file("").after_create(|path| {
+    ///         std::fs::write(path.with_extension("db-journal"), "")
+    ///     }),
+    /// })
+    /// .unwrap();
+    ///
+    /// assert!(dir.path().join("data.db-journal").is_file());
+    /// ```
+    pub fn after_create<F>(mut self, hook: F) -> File
+    where
+        F: Fn(&Path) -> Result<()> + Send + Sync + 'static,
+    {
+        self.attrs.hooks.push(Hook::new(hook));
+        self
+    }
+
+    /// Whether functions are run once the file is written.
+    pub fn has_hooks(&self) -> bool {
+        !self.attrs.hooks.is_empty()
+    }
+
+    pub(crate) fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+
+    /// Declare the file read-only, after it is written.
+    ///
+    /// ```
+    /// let dir = macro_files::create_temp!({
+    ///     "locked.txt": macro_files::file("locked").readonly(),
+    /// })
+    /// .unwrap();
+    ///
+    /// let metadata = std::fs::metadata(dir.path().join("locked.txt")).unwrap();
+    /// assert!(metadata.permissions().readonly());
+    /// # let mut permissions = metadata.permissions();
+    /// # permissions.set_readonly(false);
+    /// # std::fs::set_permissions(dir.path().join("locked.txt"), permissions).unwrap();
+    /// ```
+    pub fn readonly(mut self) -> File {
+        self.attrs.flags.readonly = true;
+        self
+    }
+
+    /// Declare the file hidden on Windows, ignored on other platforms where
+    /// hidden files are named with a leading dot.
+    pub fn hidden(mut self) -> File {
+        self.attrs.flags.hidden = true;
+        self
+    }
+
+    /// Declare the file as used by the operating system on Windows, ignored
+    /// on other platforms.
+    pub fn system(mut self) -> File {
+        self.attrs.flags.system = true;
+        self
+    }
+
+    /// Declare the file executable by whoever can read it, `0o755` with the
+    /// usual umask, on Unix only.
+    pub fn executable(mut self) -> File {
+        self.attrs.flags.executable = true;
+        self
+    }
+
+    /// Declare the permissions of the file exact, `0o644` or `0o755` if
+    /// executable, without the write permissions if read-only, whatever the
+    /// umask of the process, on Unix only.
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::os::unix::fs::PermissionsExt;
+    ///
+    /// let dir = macro_files::create_temp!({
+    ///     "run.sh": macro_files::exec("#!/bin/sh\n").exact_permissions(),
+    /// })
+    /// .unwrap();
+    ///
+    /// let metadata = std::fs::metadata(dir.path().join("run.sh")).unwrap();
+    /// assert_eq!(metadata.permissions().mode() & 0o7777, 0o755);
+    /// # }
+    /// ```
+    pub fn exact_permissions(mut self) -> File {
+        self.attrs.flags.exact = true;
+        self
+    }
+
+    /// Declare the permission bits of the file, `0o7777` at most, set after
+    /// it is written whatever the umask, on Unix only. Read-only files lose
+    /// their write permissions and executable files get the execute ones.
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::os::unix::fs::PermissionsExt;
+    ///
+    /// let dir = macro_files::create_temp!({
+    ///     ".netrc": macro_files::file("machine example.com").mode(0o600),
+    /// })
+    /// .unwrap();
+    ///
+    /// let metadata = std::fs::metadata(dir.path().join(".netrc")).unwrap();
+    /// assert_eq!(metadata.permissions().mode() & 0o7777, 0o600);
+    /// # }
+    /// ```
+    pub fn mode(mut self, mode: u32) -> File {
+        self.attrs.flags.mode = Some(mode & 0o7777);
+        self
+    }
+
+    /// Declare the NTFS alternate data stream `name` of the file with the
+    /// given contents, written after the file on Windows and ignored on other
+    /// platforms. Ignored when writing archives.
+    ///
+    /// Creation fails with an [`InvalidInput`](std::io::ErrorKind) error when
+    /// the name is empty or contains `:`, `/`, `\\` or a null character.
+    ///
+    /// ```
+    /// let dir = macro_files::create_temp!({
+    ///     "setup.exe": macro_files::file([0x4d, 0x5a])
+    ///         .ads("Zone.Identifier", "[ZoneTransfer]\r\nZoneId=3\r\n"),
+    /// })
+    /// .unwrap();
+    ///
+    /// # #[cfg(windows)]
+    /// assert_eq!(
+    ///     std::fs::read_to_string(dir.path().join("setup.exe:Zone.Identifier")).unwrap(),
+    ///     "[ZoneTransfer]\r\nZoneId=3\r\n"
+    /// );
+    /// ```
+    pub fn ads<S: Into<String>, C: AsRef<[u8]>>(mut self, name: S, contents: C) -> File {
+        self.attrs
+            .streams
+            .push((name.into(), contents.as_ref().to_owned()));
+        self
+    }
+
+    /// Declared alternate data streams of the file, with their contents, in
+    /// their declaration order.
+    pub fn declared_ads(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.attrs
+            .streams
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_slice()))
+    }
+
+    /// Declared flags of the file.
+    pub fn declared_attributes(&self) -> FileAttributes {
+        self.attrs.flags
+    }
+
+    /// Declare where the file comes from, overriding the origin of its tree,
+    /// see [`Tree::origin`](crate::Tree::origin).
+    pub fn origin<S: Into<String>>(mut self, origin: S) -> File {
+        self.origin = Some(Arc::from(origin.into()));
+        self
+    }
+
+    /// Declared origin of the file.
+    pub fn declared_origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    pub(crate) fn origin_arc(&self) -> Option<&Arc<str>> {
+        self.origin.as_ref()
+    }
+
+    /// Stamp injected in the contents of the file, see [`stamp`](crate::stamp).
+    pub fn declared_stamp(&self) -> Option<&str> {
+        self.stamp.as_deref()
+    }
+
+    pub(crate) fn with_stamp(mut self, stamp: Arc<str>) -> File {
+        self.stamp = Some(stamp);
+        self
+    }
+
+    pub(crate) fn stamp_arc(&self) -> Option<&Arc<str>> {
+        self.stamp.as_ref()
+    }
+
+    /// Declare the SHA-256 digest of the contents as a hexadecimal string.
+    ///
+    /// The contents are verified before the file is written, creation fails
+    /// with a [`ChecksumMismatch`](crate::ChecksumMismatch) error on mismatch.
+    /// Links are not verified.
+    #[cfg(feature = "checksum")]
+    pub fn sha256<S: AsRef<str>>(mut self, digest: S) -> File {
+        self.sha256 = Some(digest.as_ref().to_ascii_lowercase());
+        self
+    }
+
+    /// Declared SHA-256 digest of the contents.
+    #[cfg(feature = "checksum")]
+    pub fn declared_sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    /// Whether a checksum of the contents is declared.
+    fn has_checksum(&self) -> bool {
+        #[cfg(feature = "checksum")]
+        return self.sha256.is_some();
+        #[cfg(not(feature = "checksum"))]
+        false
+    }
+
+    /// Verify `contents`, or the source file when `None`, against the
+    /// declared checksums before writing the file at `path`.
+    #[cfg_attr(not(feature = "checksum"), allow(unused_variables))]
+    pub(crate) fn verify(&self, path: &Path, contents: Option<&[u8]>) -> Result<()> {
+        #[cfg(feature = "checksum")]
+        if let Some(expected) = &self.sha256 {
+            match (contents, self.source()) {
+                (Some(mut contents), _) => {
+                    crate::checksum::verify_sha256(path, expected, &mut contents)?
+                }
+                (None, Some(source)) => {
+                    let mut source = std::fs::File::open(source)?;
+                    crate::checksum::verify_sha256(path, expected, &mut source)?
+                }
+                (None, None) => match &self.content {
+                    Content::Zeros(len) => {
+                        let mut zeros = std::io::Read::take(std::io::repeat(0), *len);
+                        crate::checksum::verify_sha256(path, expected, &mut zeros)?
+                    }
+                    Content::Repeat { pattern, len } => {
+                        let mut repeated = RepeatReader::new(pattern, *len);
+                        crate::checksum::verify_sha256(path, expected, &mut repeated)?
+                    }
+                    _ => {}
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify and write the file at `path` with the current filesystem
+    /// operations and write options, hard links are resolved within `root`,
+    /// then apply its attributes.
+    pub(crate) fn write(&self, root: &Path, path: &Path, options: &WriteOptions) -> Result<()> {
+        self.write_contents(root, path, options)?;
+        self.attrs.apply(path)
+    }
+
+    fn write_contents(&self, root: &Path, path: &Path, options: &WriteOptions) -> Result<()> {
+        if self.appends() {
+            let contents = self.content.read()?;
+            self.verify(path, Some(&contents))?;
+            crate::append_file(path, &contents)?;
+            if contents.is_empty() {
+                let now = Some(SystemTime::now());
+                crate::set_times(path, now, now)?;
+            }
+            return Ok(());
+        }
+        match &self.content {
+            Content::CopyFrom(source) => {
+                self.verify(path, None)?;
+                copy_file(source, path).map(drop)
+            }
+            Content::Zeros(len) => {
+                self.verify(path, None)?;
+                crate::write_zeros(path, *len)
+            }
+            Content::Repeat { pattern, len } => {
+                self.verify(path, None)?;
+                crate::write_reader(path, RepeatReader::new(pattern, *len)).map(drop)
+            }
+            Content::Reader(reader) if !self.has_checksum() => {
+                crate::write_reader(path, reader.take()?).map(drop)
+            }
+            Content::Symlink(target) => match create_symlink(target, path) {
+                Err(err) => {
+                    let fallback = options.symlink_fallback_policy();
+                    crate::fs::symlink_fallback(target, path, fallback, err)
+                }
+                result => result,
+            },
+            Content::Junction(target) => create_junction(target, path),
+            Content::HardLink(original) => create_hard_link(entry_path(root, original)?, path),
+            #[cfg(all(feature = "unix", unix))]
+            Content::Fifo => crate::create_fifo(path),
+            #[cfg(all(feature = "unix", unix))]
+            Content::Socket => crate::create_socket(path),
+            content => {
+                let contents = content.read()?;
+                self.verify(path, Some(&contents))?;
+                write_file_with(path, contents, options)
+            }
+        }
+    }
+
+    /// Verify and write the file at `path` with its `contents` already read,
+    /// then apply its attributes, see [`File::write`].
+    pub(crate) fn write_read(
+        &self,
+        contents: &[u8],
+        path: &Path,
+        options: &WriteOptions,
+    ) -> Result<()> {
+        self.verify(path, Some(contents))?;
+        write_file_with(path, contents, options)?;
+        self.attrs.apply(path)
+    }
+
+    /// Write the file with the contents streamed from `reader` at `path`, up
+    /// to `max` bytes, then apply its attributes. Returns the number of bytes
+    /// written, or `None` leaving no file when the contents exceed `max`.
+    pub(crate) fn write_reader_within(
+        &self,
+        reader: &Reader,
+        path: &Path,
+        options: &WriteOptions,
+        max: u64,
+    ) -> Result<Option<u64>> {
+        let mut reader = reader.take()?.take(max.saturating_add(1));
+        if self.has_checksum() {
+            let mut contents = Vec::new();
+            reader.read_to_end(&mut contents)?;
+            if contents.len() as u64 > max {
+                return Ok(None);
+            }
+            self.write_read(&contents, path, options)?;
+            return Ok(Some(contents.len() as u64));
+        }
+        let written = crate::write_reader(path, reader)?;
+        if written > max {
+            crate::remove_file(path)?;
+            return Ok(None);
+        }
+        self.attrs.apply(path)?;
+        Ok(Some(written))
+    }
+
+    /// The file with its flags changed by `f`.
+    pub(crate) fn with_flags<F: FnOnce(&mut FileAttributes)>(mut self, f: F) -> File {
+        f(&mut self.attrs.flags);
+        self
+    }
+
+    /// Whether a hard link to `other` would have the attributes of the file.
+    pub(crate) fn has_attrs_of(&self, other: &File) -> bool {
+        self.attrs == other.attrs
+    }
+
+    /// Whether the contents of the file are written from memory, rather than
+    /// copied, streamed or created as a link or a special file.
+    pub(crate) fn is_in_memory(&self) -> bool {
+        if self.appends() {
+            return false;
+        }
+        match self.content {
+            Content::Bytes(_) | Content::Text(_) | Content::Random { .. } => true,
+            Content::Generator(_) => true,
+            #[cfg(feature = "tera")]
+            Content::Template { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Verify and write the file `name` of the directory `dir`, at `path`,
+    /// then apply its attributes. Returns `false` without writing anything
+    /// when its contents aren't in memory, see [`File::write`].
+    #[cfg(unix)]
+    pub(crate) fn write_at(
+        &self,
+        dir: &DirHandle,
+        name: &OsStr,
+        path: &Path,
+        options: &WriteOptions,
+    ) -> Result<bool> {
+        if !self.is_in_memory() {
+            return Ok(false);
+        }
+        let contents = self.content.read()?;
+        self.verify(path, Some(&contents))?;
+        let size = Some(contents.len() as u64);
+        crate::fs::traced(FsOperation::WriteFile, path, size, || {
+            dir.write_file(name, &contents, options)
+        })
+        .context(FsOperation::WriteFile, path)?;
+        self.attrs.apply(path)?;
+        Ok(true)
+    }
+}
+
+impl From<Content> for File {
+    fn from(content: Content) -> File {
+        File {
+            content,
+            attrs: Attrs::default(),
+            origin: None,
+            stamp: None,
+            append: false,
+            conflict: None,
+            line_ending: None,
+            #[cfg(feature = "checksum")]
+            sha256: None,
+        }
+    }
+}
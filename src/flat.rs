@@ -0,0 +1,118 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::copy::read_sorted_dir;
+use crate::{entry_path, Content, Entry, EntryKind, File, Matcher, Tree};
+
+/// Flatten a spec created within `root` into its entries keyed by their path
+/// relative to `root`, including the directories implied by nested keys.
+///
+/// Skipped entries are left out, later entries replace earlier ones with the
+/// same path and copied directories are read.
+pub(crate) fn flatten<'a>(tree: &'a Tree, root: &Path) -> Result<BTreeMap<PathBuf, Flat<'a>>> {
+    let mut flat = BTreeMap::new();
+    flatten_into(tree, root, root, &mut flat)?;
+    Ok(flat)
+}
+
+/// An entry of a flattened spec.
+pub(crate) enum Flat<'a> {
+    Dir,
+    Keep,
+    File(Cow<'a, [u8]>),
+    /// Contents compared in chunks, see [`Content::streamed`].
+    Streamed(&'a Content),
+    Symlink(&'a Path),
+    HardLink(PathBuf),
+    Matcher(&'a Matcher),
+    /// A special file only compared by kind.
+    #[cfg_attr(not(all(feature = "unix", unix)), allow(dead_code))]
+    Special(&'a File),
+}
+
+impl Flat<'_> {
+    pub(crate) fn kind(&self) -> EntryKind {
+        match self {
+            Flat::Dir | Flat::Keep => EntryKind::Dir,
+            Flat::File(_) | Flat::Streamed(_) | Flat::HardLink(_) | Flat::Matcher(_) => {
+                EntryKind::File
+            }
+            Flat::Symlink(_) => EntryKind::Symlink,
+            Flat::Special(file) => file.kind(),
+        }
+    }
+}
+
+fn flatten_into<'a>(
+    tree: &'a Tree,
+    root: &Path,
+    dir: &Path,
+    flat: &mut BTreeMap<PathBuf, Flat<'a>>,
+) -> Result<()> {
+    for (key, entry) in tree.entries() {
+        if let Entry::Skip(_) = entry {
+            continue;
+        }
+        let path = tree.key_path(dir, key)?;
+        let relative_path = path.strip_prefix(root).unwrap_or(&path).to_owned();
+        for ancestor in relative_path.ancestors().skip(1) {
+            if ancestor.as_os_str().is_empty() {
+                break;
+            }
+            flat.entry(ancestor.to_owned()).or_insert(Flat::Dir);
+        }
+        match entry {
+            Entry::Dir(tree) => {
+                flat.insert(relative_path, Flat::Dir);
+                flatten_into(tree, root, &path, flat)?;
+            }
+            Entry::File(file) => {
+                let file = match file.content() {
+                    Content::Symlink(target) | Content::Junction(target) => Flat::Symlink(target),
+                    Content::HardLink(original) => {
+                        let original = entry_path(root, original)?;
+                        let original = original.strip_prefix(root).unwrap_or(&original);
+                        Flat::HardLink(original.to_owned())
+                    }
+                    #[cfg(all(feature = "unix", unix))]
+                    Content::Fifo | Content::Socket => Flat::Special(file),
+                    Content::Matcher(matcher) => Flat::Matcher(matcher),
+                    content @ (Content::Zeros(_) | Content::Repeat { .. }) if !file.appends() => {
+                        Flat::Streamed(content)
+                    }
+                    _ => Flat::File(file.read_contents()?),
+                };
+                flat.insert(relative_path, file);
+            }
+            Entry::Keep => {
+                flat.insert(relative_path, Flat::Keep);
+            }
+            Entry::CopyDir(source) => {
+                flat.insert(relative_path.clone(), Flat::Dir);
+                flatten_copy(source, &relative_path, flat)?;
+            }
+            Entry::Skip(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn flatten_copy(
+    source: &Path,
+    relative_dir: &Path,
+    flat: &mut BTreeMap<PathBuf, Flat<'_>>,
+) -> Result<()> {
+    for (path, is_dir) in read_sorted_dir(source)? {
+        let relative_path = relative_dir.join(path.file_name().unwrap_or_default());
+        if is_dir {
+            flat.insert(relative_path.clone(), Flat::Dir);
+            flatten_copy(&path, &relative_path, flat)?;
+        } else {
+            let contents = std::fs::read(&path)?;
+            flat.insert(relative_path, Flat::File(Cow::Owned(contents)));
+        }
+    }
+    Ok(())
+}
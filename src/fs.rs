@@ -0,0 +1,985 @@
+use std::cell::RefCell;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use filetime::FileTime;
+
+use crate::error::Context;
+use crate::{EntryKind, FileAttributes, FsOperation, SymlinkFallback, WriteOptions, WriteStrategy};
+
+/// Filesystem operations used to create the trees.
+///
+/// Every method defaults to the standard filesystem, so an implementation
+/// only overrides the operations it wants to intercept. See
+/// [`with_fs_ops`].
+pub trait FsOps {
+    /// Create a directory and all its missing parents.
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    /// Write a file, creating its missing parent directories.
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        match std::fs::write(path, contents) {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| std::fs::write(path, contents))
+            }
+            result => result,
+        }
+    }
+
+    /// Write a file according to the write options, creating its missing
+    /// parent directories.
+    ///
+    /// Defaults to [`FsOps::write_file`] with the default options, so
+    /// implementations overriding it still intercept all the writes.
+    fn write_file_with(&self, path: &Path, contents: &[u8], options: &WriteOptions) -> Result<()> {
+        if options.writes_by_default() {
+            return self.write_file(path, contents);
+        }
+        let mut file = match std::fs::File::create(path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| std::fs::File::create(path))?
+            }
+            file => file?,
+        };
+        write_with(&mut file, contents, options)?;
+        if options.syncs_data() {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Append `contents` to a file, creating it and its missing parent
+    /// directories if missing.
+    fn append_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let open = || {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+        };
+        let mut file = match open() {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| open())?
+            }
+            file => file?,
+        };
+        file.write_all(contents)
+    }
+
+    /// Write a file of `len` zero bytes, sparse where the filesystem supports
+    /// it, creating its missing parent directories.
+    fn write_zeros(&self, path: &Path, len: u64) -> Result<()> {
+        let create = || std::fs::File::create(path)?.set_len(len);
+        match create() {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| create())
+            }
+            result => result,
+        }
+    }
+
+    /// Write a file with the contents streamed from `reader`, creating its
+    /// missing parent directories. Returns the number of bytes written.
+    fn write_reader(&self, path: &Path, reader: &mut dyn Read) -> Result<u64> {
+        let mut file = match std::fs::File::create(path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| std::fs::File::create(path))?
+            }
+            file => file?,
+        };
+        std::io::copy(reader, &mut file)
+    }
+
+    /// Copy the contents of the file `from` to the file `to`, creating the
+    /// missing parent directories of `to`. Returns the number of bytes copied.
+    ///
+    /// The copy is a copy-on-write clone sharing the data blocks of `from`
+    /// when the filesystem supports it (btrfs, XFS, APFS), falling back to
+    /// copying the contents.
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<u64> {
+        match clone_or_copy(from, to) {
+            Err(err) if err.kind() == ErrorKind::NotFound && from.is_file() => {
+                let dir_path = to.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| clone_or_copy(from, to))
+            }
+            result => result,
+        }
+    }
+
+    /// Create a symbolic link at `path` pointing to `target`, creating the
+    /// missing parent directories of `path`.
+    fn create_symlink(&self, target: &Path, path: &Path) -> Result<()> {
+        match std_symlink(target, path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| std_symlink(target, path))
+            }
+            result => result,
+        }
+    }
+
+    /// Create a directory junction at `path` pointing to the directory
+    /// `target`, creating the missing parent directories of `path`.
+    ///
+    /// Defaults to [`FsOps::create_symlink`] on the platforms other than
+    /// Windows.
+    fn create_junction(&self, target: &Path, path: &Path) -> Result<()> {
+        #[cfg(windows)]
+        return match crate::platform::create_junction(target, path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path)
+                    .and_then(|_| crate::platform::create_junction(target, path))
+            }
+            result => result,
+        };
+        #[cfg(not(windows))]
+        self.create_symlink(target, path)
+    }
+
+    /// Create a hard link at `link` to the existing file `original`, creating
+    /// the missing parent directories of `link`.
+    fn create_hard_link(&self, original: &Path, link: &Path) -> Result<()> {
+        match std::fs::hard_link(original, link) {
+            Err(err) if err.kind() == ErrorKind::NotFound && original.is_file() => {
+                let dir_path = link.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| std::fs::hard_link(original, link))
+            }
+            result => result,
+        }
+    }
+
+    /// Create a named pipe at `path`, creating its missing parent
+    /// directories.
+    #[cfg(all(feature = "unix", unix))]
+    fn create_fifo(&self, path: &Path) -> Result<()> {
+        let mkfifo = || crate::platform::mkfifo(path, 0o666);
+        match mkfifo() {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| mkfifo())
+            }
+            result => result,
+        }
+    }
+
+    /// Create a Unix domain socket at `path` nothing listens on, creating its
+    /// missing parent directories.
+    #[cfg(all(feature = "unix", unix))]
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        let bind = || std::os::unix::net::UnixListener::bind(path).map(drop);
+        match bind() {
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let dir_path = path.parent().ok_or(err)?;
+                std::fs::create_dir_all(dir_path).and_then(|_| bind())
+            }
+            result => result,
+        }
+    }
+
+    /// Kind of the entry at `path` without following symlinks, `None` when
+    /// there is none.
+    fn entry_kind(&self, path: &Path) -> Result<Option<EntryKind>> {
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) => Ok(Some(EntryKind::from(metadata.file_type()))),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Remove the file or symlink at `path`.
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    /// Set the access and modification times of the entry at `path`, without
+    /// following symlinks. The times given as `None` are left unchanged.
+    fn set_times(
+        &self,
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> Result<()> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        let accessed = accessed
+            .map(FileTime::from_system_time)
+            .unwrap_or_else(|| FileTime::from_last_access_time(&metadata));
+        let modified = modified
+            .map(FileTime::from_system_time)
+            .unwrap_or_else(|| FileTime::from_last_modification_time(&metadata));
+        if metadata.file_type().is_symlink() {
+            filetime::set_symlink_file_times(path, accessed, modified)
+        } else {
+            filetime::set_file_times(path, accessed, modified)
+        }
+    }
+
+    /// Change the owner and group of the entry at `path`, without following
+    /// symlinks.
+    ///
+    /// Fails with [`ErrorKind::PermissionDenied`] when the process is not
+    /// privileged enough.
+    #[cfg(all(feature = "unix", unix))]
+    fn set_owner(&self, path: &Path, uid: u32, gid: u32) -> Result<()> {
+        crate::platform::lchown(path, uid, gid).map_err(|err| {
+            if err.kind() == ErrorKind::PermissionDenied {
+                Error::new(
+                    ErrorKind::PermissionDenied,
+                    format!(
+                        "not privileged to change the owner of {:?} to {}:{}",
+                        path, uid, gid
+                    ),
+                )
+            } else {
+                err
+            }
+        })
+    }
+
+    /// Set the flags of the file at `path`, following symlinks.
+    ///
+    /// Read-only files are made read-only with [`std::fs::set_permissions`],
+    /// the hidden and system flags are set on Windows only, and executable
+    /// files get the execute permissions matching their read permissions on
+    /// Unix only, after their permissions are reset regardless of the umask
+    /// when exact or set to the given mode. Files with a mode are not made
+    /// writable.
+    fn set_file_attributes(&self, path: &Path, attributes: FileAttributes) -> Result<()> {
+        #[cfg(windows)]
+        {
+            crate::platform::set_windows_attributes(path, attributes)
+        }
+        #[cfg(not(windows))]
+        {
+            let metadata = std::fs::metadata(path)?;
+            let permissions = metadata.permissions();
+            let mut updated = permissions.clone();
+            #[cfg(unix)]
+            if attributes.exact {
+                crate::platform::set_exact_mode(&mut updated, metadata.is_dir());
+            }
+            #[cfg(unix)]
+            if let Some(mode) = attributes.mode {
+                crate::platform::set_mode(&mut updated, mode);
+            }
+            let keeps_mode = cfg!(unix) && attributes.mode.is_some();
+            if updated.readonly() != attributes.readonly && (attributes.readonly || !keeps_mode) {
+                updated.set_readonly(attributes.readonly);
+            }
+            #[cfg(unix)]
+            if attributes.executable {
+                crate::platform::set_executable(&mut updated);
+            }
+            if updated == permissions {
+                return Ok(());
+            }
+            std::fs::set_permissions(path, updated)
+        }
+    }
+
+    /// Move the file, directory or symlink `from` to `to`.
+    ///
+    /// When both paths are on different filesystems, `from` is copied then
+    /// removed, symlinks are recreated as is.
+    fn move_path(&self, from: &Path, to: &Path) -> Result<()> {
+        match std::fs::rename(from, to) {
+            Err(err) if is_cross_device(&err) => copy_and_remove(from, to),
+            result => result,
+        }
+    }
+}
+
+/// Whether the error was caused by an operation crossing filesystems
+/// (`EXDEV` on Unix, `ERROR_NOT_SAME_DEVICE` on Windows).
+pub(crate) fn is_cross_device(err: &Error) -> bool {
+    #[cfg(unix)]
+    return err.raw_os_error() == Some(libc::EXDEV);
+    #[cfg(windows)]
+    return err.raw_os_error() == Some(17);
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = err;
+        false
+    }
+}
+
+fn copy_and_remove(from: &Path, to: &Path) -> Result<()> {
+    copy_recursive(from, to)?;
+    if std::fs::symlink_metadata(from)?.is_dir() {
+        std::fs::remove_dir_all(from)
+    } else {
+        std::fs::remove_file(from)
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    let file_type = std::fs::symlink_metadata(from)?.file_type();
+    if file_type.is_symlink() {
+        std_symlink(&std::fs::read_link(from)?, to)
+    } else if file_type.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in from.read_dir()? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        std::fs::copy(from, to).map(drop)
+    }
+}
+
+/// Create a symbolic link with the standard library of the platform, on
+/// Windows the kind of link depends on whether the target is a directory.
+pub(crate) fn std_symlink(target: &Path, path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, path);
+    #[cfg(windows)]
+    {
+        let resolved = path.parent().unwrap_or(Path::new("")).join(target);
+        return if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(target, path)
+        } else {
+            std::os::windows::fs::symlink_file(target, path)
+        };
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (target, path);
+        Err(Error::new(
+            ErrorKind::Other,
+            "symlinks are not supported on this platform",
+        ))
+    }
+}
+
+/// Create what replaces the symlink at `path` pointing to `target` when
+/// creating it failed with `err`, see [`SymlinkFallback`], or return `err`.
+pub(crate) fn symlink_fallback(
+    target: &Path,
+    path: &Path,
+    fallback: SymlinkFallback,
+    err: Error,
+) -> Result<()> {
+    if fallback == SymlinkFallback::Error || !lacks_symlink_privilege(&err) {
+        return Err(err);
+    }
+    let dir_path = path.parent().unwrap_or_else(|| Path::new(""));
+    // The missing parent directories of `path` are not created when the
+    // symlink fails for lack of privilege, and `target` is resolved from them.
+    if !dir_path.as_os_str().is_empty() && !dir_path.is_dir() {
+        create_dir(dir_path)?;
+    }
+    let resolved = dir_path.join(target);
+    if resolved.is_dir() {
+        if fallback == SymlinkFallback::Junction {
+            create_junction(target, path)
+        } else {
+            copy_dir_all(&resolved, path)
+        }
+    } else if resolved.is_file() {
+        copy_file(&resolved, path).map(drop)
+    } else {
+        Err(err)
+    }
+}
+
+/// Whether `err` is the failure to create a symlink without the privilege
+/// to, `ERROR_PRIVILEGE_NOT_HELD` on Windows.
+fn lacks_symlink_privilege(err: &Error) -> bool {
+    const ERROR_PRIVILEGE_NOT_HELD: i32 = 1314;
+
+    let os_error = err.raw_os_error().or_else(|| {
+        let source = std::error::Error::source(err.get_ref()?)?;
+        source.downcast_ref::<Error>()?.raw_os_error()
+    });
+    err.kind() == ErrorKind::PermissionDenied
+        || (cfg!(windows) && os_error == Some(ERROR_PRIVILEGE_NOT_HELD))
+}
+
+/// Clone the file `from` to the new file `to` with its permissions, or else
+/// copy its contents like [`std::fs::copy`].
+fn clone_or_copy(from: &Path, to: &Path) -> Result<u64> {
+    let metadata = std::fs::metadata(from)?;
+    if !metadata.is_file() || crate::platform::reflink(from, to).is_err() {
+        return std::fs::copy(from, to);
+    }
+    std::fs::set_permissions(to, metadata.permissions())?;
+    Ok(metadata.len())
+}
+
+/// Write the contents to `file` according to the write strategy.
+pub(crate) fn write_with<W: Write>(
+    file: &mut W,
+    contents: &[u8],
+    options: &WriteOptions,
+) -> Result<()> {
+    match options.write_strategy() {
+        WriteStrategy::WriteAll => file.write_all(contents),
+        WriteStrategy::Chunked => contents
+            .chunks(options.chunk_size())
+            .try_for_each(|chunk| file.write_all(chunk)),
+    }
+}
+
+/// Copy the directory `from` with all its entries to `to` with the current
+/// filesystem operations.
+fn copy_dir_all(from: &Path, to: &Path) -> Result<()> {
+    create_dir(to)?;
+    for (path, is_dir) in crate::copy::read_sorted_dir(from)? {
+        let target = to.join(path.file_name().unwrap_or_default());
+        if is_dir {
+            copy_dir_all(&path, &target)?;
+        } else {
+            copy_file(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// The standard filesystem, used when no operations are overridden.
+///
+/// On Windows, paths longer than `MAX_PATH` are given the `\\?\`
+/// extended-length prefix, by the standard library since Rust 1.58 and by
+/// this crate for the attributes set through the Windows API, so deep trees
+/// do not require long paths to be enabled on the machine.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdFs;
+
+impl FsOps for StdFs {}
+
+macro_rules! forward_fs_ops {
+    ($($ty:ty),+) => {
+        $(
+            impl<T: FsOps + ?Sized> FsOps for $ty {
+                fn create_dir(&self, path: &Path) -> Result<()> {
+                    (**self).create_dir(path)
+                }
+
+                fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+                    (**self).write_file(path, contents)
+                }
+
+                fn write_file_with(
+                    &self,
+                    path: &Path,
+                    contents: &[u8],
+                    options: &WriteOptions,
+                ) -> Result<()> {
+                    (**self).write_file_with(path, contents, options)
+                }
+
+                fn append_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+                    (**self).append_file(path, contents)
+                }
+
+                fn write_zeros(&self, path: &Path, len: u64) -> Result<()> {
+                    (**self).write_zeros(path, len)
+                }
+
+                fn write_reader(&self, path: &Path, reader: &mut dyn Read) -> Result<u64> {
+                    (**self).write_reader(path, reader)
+                }
+
+                fn copy_file(&self, from: &Path, to: &Path) -> Result<u64> {
+                    (**self).copy_file(from, to)
+                }
+
+                fn create_symlink(&self, target: &Path, path: &Path) -> Result<()> {
+                    (**self).create_symlink(target, path)
+                }
+
+                fn create_junction(&self, target: &Path, path: &Path) -> Result<()> {
+                    (**self).create_junction(target, path)
+                }
+
+                fn create_hard_link(&self, original: &Path, link: &Path) -> Result<()> {
+                    (**self).create_hard_link(original, link)
+                }
+
+                #[cfg(all(feature = "unix", unix))]
+                fn create_fifo(&self, path: &Path) -> Result<()> {
+                    (**self).create_fifo(path)
+                }
+
+                #[cfg(all(feature = "unix", unix))]
+                fn create_socket(&self, path: &Path) -> Result<()> {
+                    (**self).create_socket(path)
+                }
+
+                fn entry_kind(&self, path: &Path) -> Result<Option<EntryKind>> {
+                    (**self).entry_kind(path)
+                }
+
+                fn remove_file(&self, path: &Path) -> Result<()> {
+                    (**self).remove_file(path)
+                }
+
+                fn set_times(
+                    &self,
+                    path: &Path,
+                    accessed: Option<SystemTime>,
+                    modified: Option<SystemTime>,
+                ) -> Result<()> {
+                    (**self).set_times(path, accessed, modified)
+                }
+
+                #[cfg(all(feature = "unix", unix))]
+                fn set_owner(&self, path: &Path, uid: u32, gid: u32) -> Result<()> {
+                    (**self).set_owner(path, uid, gid)
+                }
+
+                fn set_file_attributes(
+                    &self,
+                    path: &Path,
+                    attributes: FileAttributes,
+                ) -> Result<()> {
+                    (**self).set_file_attributes(path, attributes)
+                }
+
+                fn move_path(&self, from: &Path, to: &Path) -> Result<()> {
+                    (**self).move_path(from, to)
+                }
+            }
+        )+
+    };
+}
+
+forward_fs_ops!(&T, Box<T>, Rc<T>, Arc<T>);
+
+thread_local!(static FS_OPS: RefCell<Option<Rc<dyn FsOps>>> = RefCell::new(None));
+
+/// Run `f` with the filesystem operations of the current thread replaced by
+/// `ops`, for instance to record or fail the operations of the macros in
+/// tests.
+///
+/// The previous operations are restored when `f` returns or panics. Pass an
+/// [`Rc`] or an [`Arc`] to inspect `ops` afterwards.
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::io::Result;
+/// use std::path::{Path, PathBuf};
+/// use std::rc::Rc;
+///
+/// #[derive(Default)]
+/// struct Recorder(RefCell<Vec<PathBuf>>);
+///
+/// impl macro_files::FsOps for Recorder {
+///     fn write_file(&self, path: &Path, _contents: &[u8]) -> Result<()> {
+///         self.0.borrow_mut().push(path.to_owned());
+///         Ok(())
+///     }
+/// }
+///
+/// let recorder = Rc::new(Recorder::default());
+/// macro_files::with_fs_ops(recorder.clone(), || {
+///     macro_files::create_in!("project", {
+///         "README.md": "# Project",
+///     })
+/// })
+/// .unwrap();
+///
+/// assert_eq!(*recorder.0.borrow(), [PathBuf::from("project/README.md")]);
+/// ```
+pub fn with_fs_ops<O, F, R>(ops: O, f: F) -> R
+where
+    O: FsOps + 'static,
+    F: FnOnce() -> R,
+{
+    let _guard = FsOpsGuard::set(Rc::new(ops));
+    f()
+}
+
+/// Restores the previous filesystem operations when dropped.
+pub(crate) struct FsOpsGuard {
+    previous: Option<Rc<dyn FsOps>>,
+}
+
+impl FsOpsGuard {
+    pub(crate) fn set(ops: Rc<dyn FsOps>) -> FsOpsGuard {
+        let previous = FS_OPS.with(|cell| cell.replace(Some(ops)));
+        FsOpsGuard { previous }
+    }
+}
+
+impl Drop for FsOpsGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        FS_OPS.with(|cell| cell.replace(previous));
+    }
+}
+
+/// Whether the filesystem operations of the current thread are overridden.
+pub(crate) fn is_overridden() -> bool {
+    FS_OPS.with(|cell| cell.borrow().is_some())
+}
+
+fn current() -> Rc<dyn FsOps> {
+    FS_OPS
+        .with(|cell| cell.borrow().clone())
+        .unwrap_or_else(|| Rc::new(StdFs))
+}
+
+/// Number of bytes written by an operation, when it returns it.
+#[cfg(feature = "tracing")]
+pub(crate) trait Written {
+    fn written(&self) -> Option<u64>;
+}
+
+#[cfg(feature = "tracing")]
+impl Written for () {
+    fn written(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Written for u64 {
+    fn written(&self) -> Option<u64> {
+        Some(*self)
+    }
+}
+
+/// Perform a filesystem operation, emitting a `tracing` event with the path,
+/// the number of bytes written and the duration of the operation.
+#[cfg(feature = "tracing")]
+pub(crate) fn traced<T: Written, F: FnOnce() -> Result<T>>(
+    operation: FsOperation,
+    path: &Path,
+    size: Option<u64>,
+    f: F,
+) -> Result<T> {
+    let start = std::time::Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    match &result {
+        Ok(value) => tracing::debug!(
+            %operation,
+            path = %path.display(),
+            size = size.or_else(|| value.written()),
+            ?elapsed,
+            "{} {}",
+            operation,
+            path.display(),
+        ),
+        Err(err) => tracing::warn!(
+            %operation,
+            path = %path.display(),
+            ?elapsed,
+            error = %err,
+            "failed to {} {}",
+            operation,
+            path.display(),
+        ),
+    }
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn traced<T, F: FnOnce() -> Result<T>>(
+    _operation: FsOperation,
+    _path: &Path,
+    _size: Option<u64>,
+    f: F,
+) -> Result<T> {
+    f()
+}
+
+/// Create a directory and all its missing parents with the current
+/// filesystem operations, see [`with_fs_ops`].
+pub fn create_dir<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    traced(FsOperation::CreateDir, path, None, || {
+        current().create_dir(path)
+    })
+    .context(FsOperation::CreateDir, path)
+}
+
+/// Write a file, creating its missing parent directories, with the current
+/// filesystem operations, see [`with_fs_ops`].
+pub fn write_file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()> {
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    let size = Some(contents.len() as u64);
+    traced(FsOperation::WriteFile, path, size, || {
+        current().write_file(path, contents)
+    })
+    .context(FsOperation::WriteFile, path)
+}
+
+/// Write a file according to the write options, creating its missing parent
+/// directories, with the current filesystem operations, see [`with_fs_ops`].
+pub fn write_file_with<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    contents: C,
+    options: &WriteOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    let size = Some(contents.len() as u64);
+    traced(FsOperation::WriteFile, path, size, || {
+        current().write_file_with(path, contents, options)
+    })
+    .context(FsOperation::WriteFile, path)
+}
+
+/// Append contents to a file, creating it and its missing parent directories
+/// if missing, with the current filesystem operations, see [`with_fs_ops`].
+pub fn append_file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()> {
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    let size = Some(contents.len() as u64);
+    traced(FsOperation::WriteFile, path, size, || {
+        current().append_file(path, contents)
+    })
+    .context(FsOperation::WriteFile, path)
+}
+
+/// Write a file of zero bytes, sparse where the filesystem supports it,
+/// creating its missing parent directories, with the current filesystem
+/// operations, see [`with_fs_ops`].
+pub fn write_zeros<P: AsRef<Path>>(path: P, len: u64) -> Result<()> {
+    let path = path.as_ref();
+    traced(FsOperation::WriteFile, path, Some(len), || {
+        current().write_zeros(path, len)
+    })
+    .context(FsOperation::WriteFile, path)
+}
+
+/// Write a file with the contents streamed from a reader, creating its
+/// missing parent directories, with the current filesystem operations, see
+/// [`with_fs_ops`].
+pub fn write_reader<P: AsRef<Path>, R: Read>(path: P, mut reader: R) -> Result<u64> {
+    let path = path.as_ref();
+    traced(FsOperation::WriteFile, path, None, || {
+        current().write_reader(path, &mut reader)
+    })
+    .context(FsOperation::WriteFile, path)
+}
+
+/// Copy a file, creating the missing parent directories of the destination,
+/// with the current filesystem operations, see [`with_fs_ops`].
+pub fn copy_file<F: AsRef<Path>, T: AsRef<Path>>(from: F, to: T) -> Result<u64> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    traced(FsOperation::CopyFile, to, None, || {
+        current().copy_file(from, to)
+    })
+    .context_with(FsOperation::CopyFile, to, from)
+}
+
+/// Create a symbolic link at `path` pointing to `target`, creating the
+/// missing parent directories of `path`, with the current filesystem
+/// operations, see [`with_fs_ops`].
+pub fn create_symlink<T: AsRef<Path>, P: AsRef<Path>>(target: T, path: P) -> Result<()> {
+    let (target, path) = (target.as_ref(), path.as_ref());
+    traced(FsOperation::CreateSymlink, path, None, || {
+        current().create_symlink(target, path)
+    })
+    .context_with(FsOperation::CreateSymlink, path, target)
+}
+
+/// Create a directory junction at `path` pointing to the directory `target`,
+/// creating the missing parent directories of `path`, with the current
+/// filesystem operations, see [`with_fs_ops`].
+pub fn create_junction<T: AsRef<Path>, P: AsRef<Path>>(target: T, path: P) -> Result<()> {
+    let (target, path) = (target.as_ref(), path.as_ref());
+    traced(FsOperation::CreateJunction, path, None, || {
+        current().create_junction(target, path)
+    })
+    .context_with(FsOperation::CreateJunction, path, target)
+}
+
+/// Create a hard link at `link` to the existing file `original`, creating
+/// the missing parent directories of `link`, with the current filesystem
+/// operations, see [`with_fs_ops`].
+pub fn create_hard_link<O: AsRef<Path>, L: AsRef<Path>>(original: O, link: L) -> Result<()> {
+    let (original, link) = (original.as_ref(), link.as_ref());
+    traced(FsOperation::CreateHardLink, link, None, || {
+        current().create_hard_link(original, link)
+    })
+    .context_with(FsOperation::CreateHardLink, link, original)
+}
+
+/// Create a named pipe, creating its missing parent directories, with the
+/// current filesystem operations, see [`with_fs_ops`].
+#[cfg(all(feature = "unix", unix))]
+pub fn create_fifo<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    traced(FsOperation::CreateFifo, path, None, || {
+        current().create_fifo(path)
+    })
+    .context(FsOperation::CreateFifo, path)
+}
+
+/// Create a Unix domain socket nothing listens on, creating its missing
+/// parent directories, with the current filesystem operations, see
+/// [`with_fs_ops`].
+#[cfg(all(feature = "unix", unix))]
+pub fn create_socket<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    traced(FsOperation::CreateSocket, path, None, || {
+        current().create_socket(path)
+    })
+    .context(FsOperation::CreateSocket, path)
+}
+
+/// Kind of the entry at `path` without following symlinks, `None` when there
+/// is none, with the current filesystem operations, see [`with_fs_ops`].
+pub fn entry_kind<P: AsRef<Path>>(path: P) -> Result<Option<EntryKind>> {
+    let path = path.as_ref();
+    current()
+        .entry_kind(path)
+        .context(FsOperation::EntryKind, path)
+}
+
+/// Remove a file or symlink with the current filesystem operations, see
+/// [`with_fs_ops`].
+pub fn remove_file<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    traced(FsOperation::RemoveFile, path, None, || {
+        current().remove_file(path)
+    })
+    .context(FsOperation::RemoveFile, path)
+}
+
+/// Set the access and modification times of an entry without following
+/// symlinks, with the current filesystem operations, see [`with_fs_ops`].
+pub fn set_times<P: AsRef<Path>>(
+    path: P,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+) -> Result<()> {
+    let path = path.as_ref();
+    traced(FsOperation::SetTimes, path, None, || {
+        current().set_times(path, accessed, modified)
+    })
+    .context(FsOperation::SetTimes, path)
+}
+
+/// Change the owner and group of an entry without following symlinks, with
+/// the current filesystem operations, see [`with_fs_ops`].
+#[cfg(all(feature = "unix", unix))]
+pub fn set_owner<P: AsRef<Path>>(path: P, uid: u32, gid: u32) -> Result<()> {
+    let path = path.as_ref();
+    traced(FsOperation::SetOwner, path, None, || {
+        current().set_owner(path, uid, gid)
+    })
+    .context(FsOperation::SetOwner, path)
+}
+
+/// Set the flags of a file, following symlinks, with the current filesystem
+/// operations, see [`with_fs_ops`].
+pub fn set_file_attributes<P: AsRef<Path>>(path: P, attributes: FileAttributes) -> Result<()> {
+    let path = path.as_ref();
+    traced(FsOperation::SetFileAttributes, path, None, || {
+        current().set_file_attributes(path, attributes)
+    })
+    .context(FsOperation::SetFileAttributes, path)
+}
+
+/// Move a file, directory or symlink, copying then removing it when crossing
+/// filesystems, with the current filesystem operations, see [`with_fs_ops`].
+pub fn move_path<F: AsRef<Path>, T: AsRef<Path>>(from: F, to: T) -> Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    traced(FsOperation::MovePath, to, None, || {
+        current().move_path(from, to)
+    })
+    .context_with(FsOperation::MovePath, to, from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::io::{Error, ErrorKind};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::*;
+
+    struct Fail(ErrorKind);
+
+    impl FsOps for Fail {
+        fn create_dir(&self, _path: &Path) -> Result<()> {
+            Err(Error::from(self.0))
+        }
+    }
+
+    #[derive(Default)]
+    struct Count(Cell<usize>);
+
+    impl FsOps for Count {
+        fn create_dir(&self, _path: &Path) -> Result<()> {
+            self.0.set(self.0.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn nested_overrides() {
+        let count = Rc::new(Count::default());
+        with_fs_ops(count.clone(), || {
+            create_dir("outer").unwrap();
+            with_fs_ops(Fail(ErrorKind::PermissionDenied), || {
+                let err = create_dir("inner").unwrap_err();
+                assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+            });
+            create_dir("outer").unwrap();
+        });
+        assert_eq!(count.0.get(), 2);
+    }
+
+    #[test]
+    fn restore_on_panic() {
+        let count = Rc::new(Count::default());
+        with_fs_ops(count.clone(), || {
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                with_fs_ops(Fail(ErrorKind::Other), || panic!("oops"))
+            }));
+            assert!(result.is_err());
+            create_dir("outer").unwrap();
+        });
+        assert_eq!(count.0.get(), 1);
+    }
+
+    #[test]
+    fn cross_device_errors() {
+        #[cfg(unix)]
+        assert!(is_cross_device(&Error::from_raw_os_error(libc::EXDEV)));
+        assert!(!is_cross_device(&Error::from(ErrorKind::NotFound)));
+    }
+
+    #[test]
+    fn copy_and_remove_dir() {
+        let dir = std::env::temp_dir().join(format!("macro_files-move-{}", std::process::id()));
+        let (from, to) = (dir.join("from"), dir.join("to"));
+        StdFs
+            .write_file(&from.join("docs/README.md"), b"# Docs")
+            .unwrap();
+        #[cfg(unix)]
+        StdFs
+            .create_symlink(Path::new("docs/README.md"), &from.join("README.md"))
+            .unwrap();
+
+        copy_and_remove(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(std::fs::read(to.join("docs/README.md")).unwrap(), b"# Docs");
+        #[cfg(unix)]
+        assert_eq!(
+            std::fs::read_link(to.join("README.md")).unwrap(),
+            Path::new("docs/README.md")
+        );
+
+        move_path(&to, &from).unwrap();
+        assert!(from.join("docs/README.md").is_file());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
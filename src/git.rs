@@ -0,0 +1,83 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::process::Command;
+
+use crate::Tree;
+
+/// Author and committer of the initial commit, so that it does not depend on
+/// the configuration of the machine.
+const IDENTITY: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "macro_files"),
+    ("GIT_AUTHOR_EMAIL", "macro_files@localhost"),
+    ("GIT_COMMITTER_NAME", "macro_files"),
+    ("GIT_COMMITTER_EMAIL", "macro_files@localhost"),
+];
+
+impl Tree {
+    /// Declare that the directory is a git repository: once its entries are
+    /// created, `git init` is run in it and all of them are committed in an
+    /// initial commit on the `main` branch. Declared with `git { ... }` in
+    /// the macros.
+    ///
+    /// _This requires the feature `git` and the `git` command._
+    ///
+    /// The commit is authored by `macro_files <macro_files@localhost>` and
+    /// never signed. It runs on disk, whatever the current filesystem
+    /// operations, see [`with_fs_ops`](crate::with_fs_ops), and fails with the
+    /// error output of `git` if a command fails.
+    ///
+    /// ```
+    /// let dir = macro_files::create_temp!({
+    ///     "repo": git {
+    ///         "README.md": "# Project",
+    ///     },
+    /// })
+    /// .unwrap();
+    ///
+    /// assert!(dir.path().join("repo/.git").is_dir());
+    /// ```
+    pub fn git(self) -> Tree {
+        self.after_create(init_repository)
+    }
+}
+
+/// Initialize a git repository in `dir` and commit its entries.
+fn init_repository(dir: &Path) -> Result<()> {
+    git(dir, &["-c", "init.defaultBranch=main", "init", "--quiet"])?;
+    git(dir, &["add", "--all"])?;
+    git(
+        dir,
+        &[
+            "-c",
+            "commit.gpgSign=false",
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "--message",
+            "Initial commit",
+        ],
+    )
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .envs(IDENTITY.iter().copied())
+        .output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(Error::new(
+        ErrorKind::Other,
+        format!(
+            "git {} failed in {:?}: {}",
+            args.iter()
+                .rev()
+                .find(|arg| !arg.starts_with('-'))
+                .unwrap_or(&""),
+            dir,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ),
+    ))
+}
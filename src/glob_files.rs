@@ -0,0 +1,77 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+
+use crate::{copy_dir, from, Tree};
+
+/// A tree copying the files and directories matching the glob `pattern` when
+/// it is created, see [`from`] and [`copy_dir`].
+///
+/// _This requires the feature `glob`._
+///
+/// The pattern is matched when this is called, with the syntax of the
+/// [`glob`](https://docs.rs/glob) crate. Keys are the paths of the matches
+/// relative to the directories of the pattern before its first wildcard, so
+/// `"fixtures/**/*.toml"` keeps the structure within `fixtures`. A pattern
+/// matching nothing gives an empty tree.
+///
+/// Declare it as the value of a directory, or spread it with `..` to copy
+/// the matches into the directory of the map.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "configs": macro_files::from_glob("tests/fixtures/*.toml").unwrap(),
+///     ..macro_files::from_glob("tests/fixtures/v*").unwrap(),
+/// })
+/// .unwrap();
+///
+/// assert!(dir.path().join("configs/config.toml").is_file());
+/// assert!(dir.path().join("vendor/lib/lib.rs").is_file());
+/// ```
+///
+/// Fails with an [`ErrorKind::InvalidInput`] error if the pattern is invalid,
+/// or with the error of a directory which cannot be read.
+pub fn from_glob(pattern: &str) -> Result<Tree> {
+    let paths = glob::glob(pattern).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid glob pattern {:?}: {}", pattern, err),
+        )
+    })?;
+    let base = literal_dir(pattern);
+    let mut tree = Tree::new();
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        let path = path.map_err(Error::from)?;
+        // Already copied with a matching parent directory.
+        if dirs.iter().any(|dir| path.starts_with(dir)) {
+            continue;
+        }
+        let key = match path.strip_prefix(&base) {
+            Ok(key) if !key.as_os_str().is_empty() => key.to_owned(),
+            _ => PathBuf::from(path.file_name().unwrap_or_default()),
+        };
+        if path.is_dir() {
+            tree.push(key, copy_dir(&path));
+            dirs.push(path);
+        } else {
+            tree.push(key, from(&path));
+        }
+    }
+    Ok(tree)
+}
+
+/// The directories of `pattern` before the component with its first
+/// wildcard, and never its last component.
+fn literal_dir(pattern: &str) -> PathBuf {
+    let mut components: Vec<Component<'_>> = Path::new(pattern).components().collect();
+    components.pop();
+    components
+        .into_iter()
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(&['*', '?', '['][..])
+        })
+        .collect()
+}
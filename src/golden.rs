@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use crate::Tree;
+
+/// Name of the environment variable regenerating the golden directories.
+const UPDATE_VAR: &str = "UPDATE_GOLDEN";
+
+/// Whether the golden directories are regenerated rather than compared,
+/// which is the case when the `UPDATE_GOLDEN` environment variable is set to
+/// a value other than `0`, see [`assert_matches_dir!`](crate::assert_matches_dir).
+pub fn updates_golden() -> bool {
+    std::env::var_os(UPDATE_VAR).map_or(false, |update| !update.is_empty() && update != "0")
+}
+
+/// Assert that `actual` matches the `golden` directory, or make `golden`
+/// match it when [`updates_golden`].
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_matches_dir(actual: &Path, golden: &Path) {
+    let result = if updates_golden() {
+        std::fs::create_dir_all(golden)
+            .and_then(|()| Tree::read_from(actual))
+            .and_then(|tree| tree.sync_in(golden))
+            .map(drop)
+    } else {
+        Tree::read_from(golden)
+            .and_then(|tree| tree.diff(actual))
+            .map(|diff| {
+                if !diff.is_empty() {
+                    panic!(
+                        "directory {:?} does not match the golden directory {:?}, \
+                     set {}=1 to update it:\n{}",
+                        actual, golden, UPDATE_VAR, diff
+                    );
+                }
+            })
+    };
+    if let Err(err) = result {
+        panic!(
+            "failed to compare directory {:?} with the golden directory {:?}: {}",
+            actual, golden, err
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::copy::read_sorted_dir;
+use crate::{entry_kind, remove_file, CreateReport, Entry, EntryKind, Tree};
+
+/// Guard removing the entries created from a spec when dropped, see
+/// [`create_guarded!`](crate::create_guarded).
+///
+/// Only the entries which did not exist before the creation are removed,
+/// with their entries if they are directories: existing directories are
+/// left, and so are the existing files which were overwritten. Errors are
+/// ignored when dropped.
+#[derive(Debug)]
+#[must_use = "the entries are removed as soon as the guard is dropped"]
+pub struct CreateGuard {
+    /// Highest entries missing before the creation, in declaration order.
+    paths: Vec<PathBuf>,
+    report: CreateReport,
+}
+
+impl CreateGuard {
+    /// The report of the creation.
+    pub fn report(&self) -> &CreateReport {
+        &self.report
+    }
+
+    /// Paths which are removed when the guard is dropped.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Keep the created entries, returning the report of the creation.
+    pub fn keep(mut self) -> CreateReport {
+        self.paths.clear();
+        std::mem::take(&mut self.report)
+    }
+}
+
+impl Drop for CreateGuard {
+    fn drop(&mut self) {
+        for path in self.paths.iter().rev() {
+            let _ = remove_all(path);
+        }
+    }
+}
+
+impl Tree {
+    /// Create the directories and files of the tree within `dir`, returning
+    /// a guard removing them when dropped, see [`CreateGuard`].
+    ///
+    /// If the creation fails, the entries already created are removed before
+    /// returning the error.
+    ///
+    /// ```
+    /// let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    /// let mut tree = macro_files::Tree::new();
+    /// tree.push("docs/README.md", "# Documentation");
+    ///
+    /// let guard = tree.create_guarded(temp_dir.path()).unwrap();
+    /// assert!(temp_dir.path().join("docs/README.md").is_file());
+    /// drop(guard);
+    /// assert!(!temp_dir.path().join("docs").exists());
+    /// ```
+    pub fn create_guarded<P: AsRef<Path>>(&self, dir: P) -> Result<CreateGuard> {
+        let mut guard = CreateGuard {
+            paths: Vec::new(),
+            report: CreateReport::default(),
+        };
+        missing_entries(self, dir.as_ref(), &mut guard.paths)?;
+        // Dropping the guard on error removes what was created.
+        guard.report = self.create_in(dir)?;
+        Ok(guard)
+    }
+}
+
+/// Record the highest missing path of each entry of the tree created in
+/// `dir`, looking into the existing directories.
+fn missing_entries(tree: &Tree, dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for (key, entry) in tree.entries() {
+        if let Entry::Skip(_) = entry {
+            continue;
+        }
+        let path = tree.key_path(dir, key)?;
+        if let Some(missing) = highest_missing(&path, paths)? {
+            paths.push(missing);
+            continue;
+        }
+        match entry {
+            Entry::Dir(tree) if entry_kind(&path)? == Some(EntryKind::Dir) => {
+                missing_entries(tree, &path, paths)?
+            }
+            Entry::CopyDir(source) if entry_kind(&path)? == Some(EntryKind::Dir) => {
+                missing_copied(source, &path, paths)?
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Record the highest missing path of each entry copied from the `source`
+/// directory into the existing `dir` directory.
+fn missing_copied(source: &Path, dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for (path, is_dir) in read_sorted_dir(source)? {
+        let target = dir.join(path.file_name().unwrap_or_default());
+        match entry_kind(&target)? {
+            None => paths.push(target),
+            Some(EntryKind::Dir) if is_dir => missing_copied(&path, &target, paths)?,
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// The highest missing ancestor of `path`, including itself, unless it is
+/// missing because an already recorded path is.
+fn highest_missing(path: &Path, recorded: &[PathBuf]) -> Result<Option<PathBuf>> {
+    let mut missing = None;
+    for ancestor in path.ancestors() {
+        if ancestor.as_os_str().is_empty() || entry_kind(ancestor)?.is_some() {
+            break;
+        }
+        if recorded.iter().any(|recorded| recorded == ancestor) {
+            return Ok(None);
+        }
+        missing = Some(ancestor);
+    }
+    Ok(missing.map(Path::to_owned))
+}
+
+/// Remove the entry at `path` with its entries if it is a directory.
+fn remove_all(path: &Path) -> Result<()> {
+    match entry_kind(path)? {
+        Some(EntryKind::Dir) => std::fs::remove_dir_all(path),
+        Some(_) => remove_file(path),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,44 @@
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::{Compression, GzBuilder};
+
+use crate::{Content, File};
+
+/// A file with the given contents compressed with gzip.
+///
+/// _This requires the feature `flate2`._
+///
+/// The contents are compressed with the default level, without name nor
+/// modification time in the header, so the same contents always give the
+/// same file.
+///
+/// ```
+/// use std::io::Read;
+///
+/// let dir = macro_files::create_temp!({
+///     "logs": {
+///         "app.log.gz": macro_files::gz("started\nstopped\n"),
+///     },
+/// })
+/// .unwrap();
+///
+/// let compressed = std::fs::File::open(dir.path().join("logs/app.log.gz")).unwrap();
+/// let mut log = String::new();
+/// macro_files::flate2::read::GzDecoder::new(compressed)
+///     .read_to_string(&mut log)
+///     .unwrap();
+/// assert_eq!(log, "started\nstopped\n");
+/// ```
+pub fn gz<C: AsRef<[u8]>>(contents: C) -> File {
+    File::from(Content::Bytes(compress(contents.as_ref())))
+}
+
+fn compress(contents: &[u8]) -> Vec<u8> {
+    let mut encoder: GzEncoder<Vec<u8>> =
+        GzBuilder::new().write(Vec::new(), Compression::default());
+    encoder
+        .write_all(contents)
+        .and_then(|_| encoder.finish())
+        .expect("writing into memory cannot fail")
+}
@@ -0,0 +1,108 @@
+use std::ffi::OsString;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::TempTree;
+
+/// Environment variables of the home and per-user directories, with their
+/// path relative to the home directory.
+const HOME_VARS: [(&str, &str); 5] = [
+    ("HOME", ""),
+    ("XDG_CONFIG_HOME", ".config"),
+    ("XDG_DATA_HOME", ".local/share"),
+    ("XDG_STATE_HOME", ".local/state"),
+    ("XDG_CACHE_HOME", ".cache"),
+];
+
+/// Environment variables of the home and per-user directories on Windows.
+#[cfg(windows)]
+const WINDOWS_VARS: [(&str, &str); 3] = [
+    ("USERPROFILE", ""),
+    ("APPDATA", "AppData/Roaming"),
+    ("LOCALAPPDATA", "AppData/Local"),
+];
+
+/// Guard restoring the environment variables of the home and per-user
+/// directories when dropped, see [`TempTree::enter_home`].
+#[derive(Debug)]
+#[must_use = "the previous environment variables are restored as soon as the guard is dropped"]
+pub struct HomeGuard<'a> {
+    previous: Vec<(&'static str, Option<OsString>)>,
+    tree: PhantomData<&'a TempTree>,
+}
+
+impl HomeGuard<'_> {
+    /// The redirected environment variables with their value before the
+    /// redirection, if they were set.
+    pub fn previous(&self) -> &[(&'static str, Option<OsString>)] {
+        &self.previous
+    }
+}
+
+impl Drop for HomeGuard<'_> {
+    fn drop(&mut self) {
+        for (name, value) in self.previous.drain(..).rev() {
+            match value {
+                Some(value) => std::env::set_var(name, value),
+                None => std::env::remove_var(name),
+            }
+        }
+    }
+}
+
+impl TempTree {
+    /// Make the main directory the home directory of the process until the
+    /// returned guard is dropped, which restores the previous environment, for
+    /// tools reading their configuration and data from per-user directories.
+    ///
+    /// `HOME` is set to the main directory, and the XDG base directories to
+    /// their default location within it: `XDG_CONFIG_HOME` to `.config`,
+    /// `XDG_DATA_HOME` to `.local/share`, `XDG_STATE_HOME` to `.local/state`
+    /// and `XDG_CACHE_HOME` to `.cache`. On Windows, `USERPROFILE` is set to
+    /// the main directory as well, `APPDATA` to `AppData\Roaming` and
+    /// `LOCALAPPDATA` to `AppData\Local`. The directories are not created
+    /// unless declared in the spec.
+    ///
+    /// The environment is shared by the whole process while tests run in
+    /// parallel threads by default: the tests redirecting the home directory,
+    /// and those reading it, must not run concurrently, like with
+    /// [`TempTree::enter`].
+    ///
+    /// ```
+    /// let home = macro_files::create_temp_tree!({
+    ///     ".config/app/config.toml": "theme = \"dark\"",
+    /// })
+    /// .unwrap();
+    ///
+    /// {
+    ///     let _guard = home.enter_home();
+    ///     let config = std::env::var_os("XDG_CONFIG_HOME").unwrap();
+    ///     let config = std::path::Path::new(&config).join("app/config.toml");
+    ///     assert_eq!(std::fs::read_to_string(config).unwrap(), "theme = \"dark\"");
+    /// }
+    ///
+    /// assert_ne!(std::env::var_os("HOME"), Some(home.path().into()));
+    /// ```
+    pub fn enter_home(&self) -> HomeGuard<'_> {
+        let vars = HOME_VARS.iter();
+        #[cfg(windows)]
+        let vars = vars.chain(WINDOWS_VARS.iter());
+        let mut previous = Vec::new();
+        for (name, relative) in vars.copied() {
+            previous.push((name, std::env::var_os(name)));
+            std::env::set_var(name, home_dir(self.path(), relative));
+        }
+        HomeGuard {
+            previous,
+            tree: PhantomData,
+        }
+    }
+}
+
+/// The directory at `relative`, with `/` separators, within `home`.
+fn home_dir(home: &Path, relative: &str) -> PathBuf {
+    relative
+        .split('/')
+        .filter(|name| !name.is_empty())
+        .fold(home.to_owned(), |dir, name| dir.join(name))
+}
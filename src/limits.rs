@@ -0,0 +1,53 @@
+/// Budget used when the limit of open file descriptors is unknown.
+const DEFAULT_FD_BUDGET: usize = 256;
+
+/// Soft limit of open file descriptors of the process (`RLIMIT_NOFILE`), if
+/// it can be queried and is finite.
+pub fn max_open_files() -> Option<u64> {
+    #[cfg(unix)]
+    {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: `limit` is a valid `rlimit` to write to.
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } == 0
+            && limit.rlim_cur != libc::RLIM_INFINITY
+        {
+            // `rlim_t` is not `u64` on every platform.
+            #[allow(clippy::unnecessary_cast)]
+            return Some(limit.rlim_cur as u64);
+        }
+        None
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Maximum number of files the concurrent modes keep open at the same time.
+///
+/// It is half of the soft limit of open file descriptors (see
+/// [`max_open_files`]) leaving room for the descriptors used by the rest of
+/// the process, so creating tens of thousands of files does not fail with
+/// `EMFILE` ("too many open files").
+pub fn fd_budget() -> usize {
+    max_open_files()
+        .map(|limit| (limit / 2).max(1).min(usize::MAX as u64) as usize)
+        .unwrap_or(DEFAULT_FD_BUDGET)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_within_limit() {
+        let budget = fd_budget();
+        assert!(budget >= 1);
+        if let Some(limit) = max_open_files() {
+            assert!(budget as u64 <= limit);
+        }
+    }
+}
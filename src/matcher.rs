@@ -0,0 +1,116 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{Content, File};
+
+type BoxedPredicate = dyn Fn(&[u8]) -> bool + Send + Sync;
+
+/// A predicate the contents of a file must satisfy in a comparison, instead
+/// of being equal to declared contents, see [`Content::Matcher`].
+///
+/// Matchers only compare contents: creating a file declared with one fails.
+/// Two matchers are equal when they are clones of each other.
+#[derive(Clone)]
+pub struct Matcher {
+    description: Arc<str>,
+    predicate: Arc<BoxedPredicate>,
+}
+
+impl Matcher {
+    /// A matcher described by `description` in the differences, accepting
+    /// the contents for which `predicate` returns `true`.
+    pub fn new<D, F>(description: D, predicate: F) -> Matcher
+    where
+        D: Into<String>,
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+    {
+        Matcher {
+            description: description.into().into(),
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// Description of the matcher.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Whether `contents` satisfy the matcher.
+    pub fn matches(&self, contents: &[u8]) -> bool {
+        (self.predicate)(contents)
+    }
+}
+
+impl fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Matcher").field(&self.description).finish()
+    }
+}
+
+impl PartialEq for Matcher {
+    fn eq(&self, other: &Matcher) -> bool {
+        Arc::as_ptr(&self.predicate) as *const () == Arc::as_ptr(&other.predicate) as *const ()
+    }
+}
+
+impl Eq for Matcher {}
+
+/// A file with any contents, for assertions on volatile files.
+///
+/// ```
+/// let dir = macro_files::create_temp!({ "build.log": "took 3.2s" }).unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), { "build.log": macro_files::any() });
+/// ```
+pub fn any() -> File {
+    File::from(Content::Matcher(Matcher::new("any contents", |_| true)))
+}
+
+/// A file whose contents satisfy `predicate`, for assertions on volatile
+/// contents.
+///
+/// ```
+/// let dir = macro_files::create_temp!({ "pid": "4242" }).unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "pid": macro_files::predicate(|contents| contents.iter().all(u8::is_ascii_digit)),
+/// });
+/// ```
+pub fn predicate<F>(predicate: F) -> File
+where
+    F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+{
+    File::from(Content::Matcher(Matcher::new("a predicate", predicate)))
+}
+
+/// A file whose contents match the regular expression `pattern` somewhere,
+/// for assertions on volatile contents such as timestamps or hashes.
+///
+/// _This requires the feature `regex`._
+///
+/// Comparing the file fails with an
+/// [`ErrorKind::InvalidInput`](std::io::ErrorKind::InvalidInput) error if
+/// `pattern` is not a valid regular expression.
+///
+/// ```
+/// let dir = macro_files::create_temp!({ "Cargo.lock": "version = 3\n" }).unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "Cargo.lock": macro_files::matches(r"(?m)^version = \d+$"),
+/// });
+/// ```
+#[cfg(feature = "regex")]
+pub fn matches(pattern: &str) -> File {
+    let regex = match regex::bytes::Regex::new(pattern) {
+        Ok(regex) => regex,
+        Err(err) => {
+            return File::failing(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid regular expression {:?}: {}", pattern, err),
+            )
+        }
+    };
+    let description = format!("the regular expression {:?}", pattern);
+    let matcher = Matcher::new(description, move |contents| regex.is_match(contents));
+    File::from(Content::Matcher(matcher))
+}
@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use crate::{EntryKind, FileAttributes, FsOps, WriteOptions};
+
+/// Filesystem operations keeping the created entries in memory, to create
+/// trees without touching the disk, see [`create_with_fs!`](crate::create_with_fs).
+///
+/// Clones share the same entries, so a clone can be passed to
+/// [`with_fs_ops`](crate::with_fs_ops) and the original inspected afterwards.
+/// Paths are stored as given, relative paths are not resolved against the
+/// current directory and symlinks are recorded but never followed. Files
+/// copied with [`from`](crate::from) or [`copy_dir`](crate::copy_dir) are
+/// read from memory, or from the disk if they are not found there, and hard
+/// links are created as copies of their original.
+///
+/// ```
+/// use macro_files::{EntryKind, MemoryFs};
+///
+/// let fs = MemoryFs::new();
+/// macro_files::create_with_fs!(fs.clone(), {
+///     "project": {
+///         "README.md": "# Project",
+///         "src": {},
+///     },
+/// })
+/// .unwrap();
+///
+/// assert_eq!(fs.read("project/README.md").unwrap(), b"# Project");
+/// assert_eq!(fs.kind("project/src"), Some(EntryKind::Dir));
+/// assert!(!std::path::Path::new("project").exists());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    entries: Arc<Mutex<BTreeMap<PathBuf, Node>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    entry: MemoryEntry,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+    attributes: FileAttributes,
+    owner: Option<(u32, u32)>,
+}
+
+#[derive(Debug, Clone)]
+enum MemoryEntry {
+    Dir,
+    File(Vec<u8>),
+    Symlink(PathBuf),
+    #[cfg(all(feature = "unix", unix))]
+    Fifo,
+    #[cfg(all(feature = "unix", unix))]
+    Socket,
+}
+
+impl MemoryFs {
+    /// An empty in-memory filesystem.
+    pub fn new() -> MemoryFs {
+        MemoryFs::default()
+    }
+
+    /// Kind of the entry at `path`, `None` when there is none.
+    pub fn kind<P: AsRef<Path>>(&self, path: P) -> Option<EntryKind> {
+        self.lock().get(path.as_ref()).map(|node| match node.entry {
+            MemoryEntry::Dir => EntryKind::Dir,
+            MemoryEntry::File(_) => EntryKind::File,
+            MemoryEntry::Symlink(_) => EntryKind::Symlink,
+            #[cfg(all(feature = "unix", unix))]
+            MemoryEntry::Fifo => EntryKind::Fifo,
+            #[cfg(all(feature = "unix", unix))]
+            MemoryEntry::Socket => EntryKind::Socket,
+        })
+    }
+
+    /// Contents of the file at `path`.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        match &self.node(path.as_ref())?.entry {
+            MemoryEntry::File(contents) => Ok(contents.clone()),
+            _ => Err(Error::new(ErrorKind::InvalidInput, "not a file")),
+        }
+    }
+
+    /// Target of the symlink at `path`.
+    pub fn read_link<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        match &self.node(path.as_ref())?.entry {
+            MemoryEntry::Symlink(target) => Ok(target.clone()),
+            _ => Err(Error::new(ErrorKind::InvalidInput, "not a symlink")),
+        }
+    }
+
+    /// Declared modification time of the entry at `path`.
+    pub fn modified<P: AsRef<Path>>(&self, path: P) -> Option<SystemTime> {
+        self.lock()
+            .get(path.as_ref())
+            .and_then(|node| node.modified)
+    }
+
+    /// Flags of the entry at `path`.
+    pub fn attributes<P: AsRef<Path>>(&self, path: P) -> Option<FileAttributes> {
+        self.lock().get(path.as_ref()).map(|node| node.attributes)
+    }
+
+    /// Owner and group of the entry at `path`, when changed.
+    pub fn owner<P: AsRef<Path>>(&self, path: P) -> Option<(u32, u32)> {
+        self.lock().get(path.as_ref()).and_then(|node| node.owner)
+    }
+
+    /// Paths of all the entries, sorted.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.lock().keys().cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Node>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn node(&self, path: &Path) -> Result<Node> {
+        self.lock()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::from(ErrorKind::NotFound))
+    }
+
+    /// Insert the entry at `path`, creating its missing parent directories.
+    fn insert(&self, path: &Path, entry: MemoryEntry) -> Result<()> {
+        let mut entries = self.lock();
+        create_parents(&mut entries, path)?;
+        match (entries.get(path).map(|node| &node.entry), &entry) {
+            (None, _) | (Some(MemoryEntry::File(_)), MemoryEntry::File(_)) => {}
+            (Some(MemoryEntry::Dir), _) => {
+                return Err(Error::new(ErrorKind::AlreadyExists, "is a directory"))
+            }
+            (Some(_), _) => return Err(Error::from(ErrorKind::AlreadyExists)),
+        }
+        entries.insert(path.to_owned(), Node::new(entry));
+        Ok(())
+    }
+
+    fn update<F: FnOnce(&mut Node)>(&self, path: &Path, f: F) -> Result<()> {
+        let mut entries = self.lock();
+        let node = entries
+            .get_mut(path)
+            .ok_or_else(|| Error::from(ErrorKind::NotFound))?;
+        f(node);
+        Ok(())
+    }
+
+    /// Contents of the file at `path` in memory, or on disk.
+    fn contents(&self, path: &Path) -> Result<Vec<u8>> {
+        match self.node(path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => std::fs::read(path),
+            _ => self.read(path),
+        }
+    }
+}
+
+impl Node {
+    fn new(entry: MemoryEntry) -> Node {
+        Node {
+            entry,
+            accessed: None,
+            modified: None,
+            attributes: FileAttributes::default(),
+            owner: None,
+        }
+    }
+}
+
+/// Create the missing parent directories of `path`, failing if one of them
+/// is not a directory.
+fn create_parents(entries: &mut BTreeMap<PathBuf, Node>, path: &Path) -> Result<()> {
+    let parents: Vec<_> = path
+        .ancestors()
+        .skip(1)
+        .filter(|parent| parent.parent().is_some() && !parent.as_os_str().is_empty())
+        .collect();
+    for parent in parents.into_iter().rev() {
+        match entries.get(parent).map(|node| &node.entry) {
+            Some(MemoryEntry::Dir) => {}
+            Some(_) => return Err(Error::new(ErrorKind::Other, "not a directory")),
+            None => {
+                entries.insert(parent.to_owned(), Node::new(MemoryEntry::Dir));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl FsOps for MemoryFs {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        let mut entries = self.lock();
+        create_parents(&mut entries, path)?;
+        match entries.get(path).map(|node| &node.entry) {
+            Some(MemoryEntry::Dir) => Ok(()),
+            Some(_) => Err(Error::from(ErrorKind::AlreadyExists)),
+            None => {
+                entries.insert(path.to_owned(), Node::new(MemoryEntry::Dir));
+                Ok(())
+            }
+        }
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.insert(path, MemoryEntry::File(contents.to_vec()))
+    }
+
+    fn write_file_with(&self, path: &Path, contents: &[u8], _options: &WriteOptions) -> Result<()> {
+        self.write_file(path, contents)
+    }
+
+    fn append_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        if let Some(node) = self.lock().get_mut(path) {
+            if let MemoryEntry::File(existing) = &mut node.entry {
+                existing.extend_from_slice(contents);
+                return Ok(());
+            }
+        }
+        self.write_file(path, contents)
+    }
+
+    fn write_zeros(&self, path: &Path, len: u64) -> Result<()> {
+        let len = usize::try_from(len).map_err(|_| Error::from(ErrorKind::OutOfMemory))?;
+        self.insert(path, MemoryEntry::File(vec![0; len]))
+    }
+
+    fn write_reader(&self, path: &Path, reader: &mut dyn Read) -> Result<u64> {
+        let mut contents = Vec::new();
+        let len = reader.read_to_end(&mut contents)?;
+        self.insert(path, MemoryEntry::File(contents))?;
+        Ok(len as u64)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<u64> {
+        let contents = self.contents(from)?;
+        let len = contents.len() as u64;
+        self.insert(to, MemoryEntry::File(contents))?;
+        Ok(len)
+    }
+
+    fn create_symlink(&self, target: &Path, path: &Path) -> Result<()> {
+        self.insert(path, MemoryEntry::Symlink(target.to_owned()))
+    }
+
+    fn create_junction(&self, target: &Path, path: &Path) -> Result<()> {
+        self.create_symlink(target, path)
+    }
+
+    fn create_hard_link(&self, original: &Path, link: &Path) -> Result<()> {
+        let contents = self.read(original)?;
+        self.insert(link, MemoryEntry::File(contents))
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn create_fifo(&self, path: &Path) -> Result<()> {
+        self.insert(path, MemoryEntry::Fifo)
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        self.insert(path, MemoryEntry::Socket)
+    }
+
+    fn entry_kind(&self, path: &Path) -> Result<Option<EntryKind>> {
+        Ok(self.kind(path))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let mut entries = self.lock();
+        match entries.get(path).map(|node| &node.entry) {
+            Some(MemoryEntry::Dir) => Err(Error::new(ErrorKind::Other, "is a directory")),
+            Some(_) => {
+                entries.remove(path);
+                Ok(())
+            }
+            None => Err(Error::from(ErrorKind::NotFound)),
+        }
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> Result<()> {
+        self.update(path, |node| {
+            node.accessed = accessed.or(node.accessed);
+            node.modified = modified.or(node.modified);
+        })
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn set_owner(&self, path: &Path, uid: u32, gid: u32) -> Result<()> {
+        self.update(path, |node| node.owner = Some((uid, gid)))
+    }
+
+    fn set_file_attributes(&self, path: &Path, attributes: FileAttributes) -> Result<()> {
+        self.update(path, |node| node.attributes = attributes)
+    }
+
+    fn move_path(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut entries = self.lock();
+        if !entries.contains_key(from) {
+            return Err(Error::from(ErrorKind::NotFound));
+        }
+        create_parents(&mut entries, to)?;
+        let moved: Vec<_> = entries
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect();
+        for path in moved {
+            if let Some(node) = entries.remove(&path) {
+                let moved = match path.strip_prefix(from) {
+                    Ok(relative) if !relative.as_os_str().is_empty() => to.join(relative),
+                    _ => to.to_owned(),
+                };
+                entries.insert(moved, node);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+//! Recording filesystem operations, to unit test code creating directories
+//! and files through this crate without touching the disk.
+//!
+//! _This requires the feature `mock`._
+//!
+//! [`watch_fs`] replaces the filesystem operations of the current thread,
+//! see [`with_fs_ops`](crate::with_fs_ops): every operation is recorded in
+//! order as a [`Write`] instead of being performed, and [`fail_fs`] makes
+//! the operations on a path fail.
+//!
+//! ```
+//! use macro_files::mock::{self, Write};
+//!
+//! let watcher = mock::watch_fs();
+//! mock::fail_fs("project/LICENSE");
+//! let result = macro_files::create!({
+//!     "project": {
+//!         "README.md": "# Project",
+//!         "LICENSE": "MIT",
+//!     },
+//! });
+//!
+//! assert!(result.is_err());
+//! assert_eq!(
+//!     watcher.consume(),
+//!     [
+//!         Write::dir("project"),
+//!         Write::file("project/README.md", "# Project"),
+//!     ]
+//! );
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crate::fs::FsOpsGuard;
+use crate::{EntryKind, FileAttributes, FsOps, WriteOptions};
+
+thread_local!(static WATCHED: RefCell<Option<Watched>> = const { RefCell::new(None) });
+
+/// Operations recorded while watching, with the paths failing.
+#[derive(Default)]
+struct Watched {
+    writes: Vec<Write>,
+    failures: HashMap<PathBuf, ErrorKind>,
+}
+
+/// An operation recorded by [`watch_fs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Write {
+    /// A directory created with its missing parents.
+    Dir(PathBuf),
+    /// A file written with its contents, or copied.
+    File(PathBuf, Vec<u8>),
+    /// Contents appended to a file, created if missing.
+    Append(PathBuf, Vec<u8>),
+    /// A symbolic link or a junction created at a path to a target.
+    Symlink(PathBuf, PathBuf),
+    /// A hard link created at a path to an original file.
+    HardLink(PathBuf, PathBuf),
+    /// A FIFO or a socket.
+    Special(PathBuf, EntryKind),
+    /// A file or symlink removed.
+    Remove(PathBuf),
+    /// An entry moved from a path to another.
+    Move(PathBuf, PathBuf),
+}
+
+impl Write {
+    /// A directory created at `path`.
+    pub fn dir<P: AsRef<Path>>(path: P) -> Write {
+        Write::Dir(path.as_ref().to_owned())
+    }
+
+    /// A file written at `path` with `contents`.
+    pub fn file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Write {
+        Write::File(path.as_ref().to_owned(), contents.as_ref().to_owned())
+    }
+
+    /// Path of the entry the operation was performed on, the destination of
+    /// a move.
+    pub fn path(&self) -> &Path {
+        match self {
+            Write::Dir(path)
+            | Write::File(path, _)
+            | Write::Append(path, _)
+            | Write::Symlink(path, _)
+            | Write::HardLink(path, _)
+            | Write::Special(path, _)
+            | Write::Remove(path)
+            | Write::Move(_, path) => path,
+        }
+    }
+}
+
+/// Kind of the entry left at `path` by the `writes`, `None` if there is none.
+fn kind_at(writes: &[Write], path: &Path) -> Option<EntryKind> {
+    for (index, write) in writes.iter().enumerate().rev() {
+        match write {
+            Write::Move(from, to) if to == path => return kind_at(&writes[..index], from),
+            Write::Move(from, _) if from == path => return None,
+            write if write.path() != path => {}
+            Write::Dir(_) => return Some(EntryKind::Dir),
+            Write::File(..) | Write::Append(..) | Write::HardLink(..) => {
+                return Some(EntryKind::File)
+            }
+            Write::Symlink(..) => return Some(EntryKind::Symlink),
+            Write::Special(_, kind) => return Some(*kind),
+            Write::Remove(_) | Write::Move(..) => return None,
+        }
+    }
+    None
+}
+
+/// Records the filesystem operations of the current thread until dropped,
+/// see [`watch_fs`].
+#[must_use = "the operations are only recorded while the watcher lives"]
+pub struct Watcher {
+    _guard: FsOpsGuard,
+}
+
+impl Watcher {
+    /// Stop watching, returning the operations recorded in order.
+    pub fn consume(self) -> Vec<Write> {
+        WATCHED
+            .with(|cell| cell.borrow_mut().take())
+            .map(|watched| watched.writes)
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        WATCHED.with(|cell| cell.borrow_mut().take());
+    }
+}
+
+/// Record the filesystem operations of the current thread instead of
+/// performing them, until the returned [`Watcher`] is consumed or dropped.
+///
+/// Existing entries are looked up among the recorded operations, files
+/// copied with [`from`](crate::from) are read from the disk. Times, owners
+/// and flags are ignored.
+pub fn watch_fs() -> Watcher {
+    WATCHED.with(|cell| cell.replace(Some(Watched::default())));
+    Watcher {
+        _guard: FsOpsGuard::set(Rc::new(Recorder)),
+    }
+}
+
+/// Make the operations on `path` fail with an [`ErrorKind::Other`] error
+/// while watching, see [`watch_fs`].
+pub fn fail_fs<P: AsRef<Path>>(path: P) {
+    fail_fs_with(path, ErrorKind::Other);
+}
+
+/// Make the operations on `path` fail with an error of the given kind while
+/// watching, see [`watch_fs`].
+pub fn fail_fs_with<P: AsRef<Path>>(path: P, kind: ErrorKind) {
+    WATCHED.with(|cell| {
+        if let Some(watched) = cell.borrow_mut().as_mut() {
+            watched.failures.insert(path.as_ref().to_owned(), kind);
+        }
+    });
+}
+
+/// Records the operations while watching, see [`watch_fs`].
+struct Recorder;
+
+fn record(path: &Path, write: Write) -> Result<()> {
+    WATCHED.with(|cell| {
+        if let Some(watched) = cell.borrow_mut().as_mut() {
+            if let Some(kind) = watched.failures.get(path) {
+                return Err(Error::from(*kind));
+            }
+            watched.writes.push(write);
+        }
+        Ok(())
+    })
+}
+
+impl FsOps for Recorder {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        record(path, Write::dir(path))
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        record(path, Write::file(path, contents))
+    }
+
+    fn write_file_with(&self, path: &Path, contents: &[u8], _options: &WriteOptions) -> Result<()> {
+        self.write_file(path, contents)
+    }
+
+    fn append_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        record(path, Write::Append(path.to_owned(), contents.to_owned()))
+    }
+
+    fn write_zeros(&self, path: &Path, len: u64) -> Result<()> {
+        let mut contents = Vec::new();
+        std::io::repeat(0).take(len).read_to_end(&mut contents)?;
+        self.write_file(path, &contents)
+    }
+
+    fn write_reader(&self, path: &Path, reader: &mut dyn Read) -> Result<u64> {
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents)?;
+        self.write_file(path, &contents)
+            .map(|_| contents.len() as u64)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<u64> {
+        let contents = std::fs::read(from)?;
+        self.write_file(to, &contents)
+            .map(|_| contents.len() as u64)
+    }
+
+    fn create_symlink(&self, target: &Path, path: &Path) -> Result<()> {
+        record(path, Write::Symlink(path.to_owned(), target.to_owned()))
+    }
+
+    fn create_junction(&self, target: &Path, path: &Path) -> Result<()> {
+        self.create_symlink(target, path)
+    }
+
+    fn create_hard_link(&self, original: &Path, link: &Path) -> Result<()> {
+        record(link, Write::HardLink(link.to_owned(), original.to_owned()))
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn create_fifo(&self, path: &Path) -> Result<()> {
+        record(path, Write::Special(path.to_owned(), EntryKind::Fifo))
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        record(path, Write::Special(path.to_owned(), EntryKind::Socket))
+    }
+
+    fn entry_kind(&self, path: &Path) -> Result<Option<EntryKind>> {
+        Ok(WATCHED.with(|cell| {
+            cell.borrow()
+                .as_ref()
+                .and_then(|watched| kind_at(&watched.writes, path))
+        }))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        record(path, Write::Remove(path.to_owned()))
+    }
+
+    fn set_times(
+        &self,
+        _path: &Path,
+        _accessed: Option<SystemTime>,
+        _modified: Option<SystemTime>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn set_owner(&self, _path: &Path, _uid: u32, _gid: u32) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_file_attributes(&self, _path: &Path, _attributes: FileAttributes) -> Result<()> {
+        Ok(())
+    }
+
+    fn move_path(&self, from: &Path, to: &Path) -> Result<()> {
+        record(to, Write::Move(from.to_owned(), to.to_owned()))
+    }
+}
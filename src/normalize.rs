@@ -0,0 +1,154 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::flat::{flatten, Flat};
+use crate::{Content, Entry, File, Tree};
+
+impl Tree {
+    /// The normal form of the tree: the directory it creates, described with
+    /// one entry per path component, sorted by name.
+    ///
+    /// Nested keys are split, directories declared several times are merged,
+    /// directories marked with [`keep`](crate::keep) become empty directories,
+    /// skipped entries are removed, copied directories and files are read,
+    /// file contents are turned into [`Content::Bytes`] and hard links into
+    /// copies of their original. Attributes such as checksums are dropped.
+    ///
+    /// Two trees creating the same directory have equal normal forms, and the
+    /// normal form of a tree equals the tree read back from the directory it
+    /// created with [`Tree::read_from`].
+    ///
+    /// ```
+    /// let dir = macro_files::create_temp!({
+    ///     "docs/README.md": "# Documentation",
+    ///     "README.md": "# Project",
+    /// })
+    /// .unwrap();
+    ///
+    /// let mut docs = macro_files::Tree::new();
+    /// docs.push("README.md", "# Documentation");
+    /// let mut tree = macro_files::Tree::new();
+    /// tree.push("docs", docs);
+    /// tree.push("README.md", "# Project");
+    ///
+    /// assert_eq!(
+    ///     tree.normalize().unwrap(),
+    ///     macro_files::Tree::read_from(dir.path()).unwrap()
+    /// );
+    /// ```
+    pub fn normalize(&self) -> Result<Tree> {
+        let flat = flatten(self, Path::new(""))?;
+        let mut stack: Vec<(&Path, Tree)> = vec![(Path::new(""), Tree::new())];
+        for (path, entry) in &flat {
+            while !path.starts_with(stack[stack.len() - 1].0) {
+                pop_dir(&mut stack);
+            }
+            let file = match entry {
+                Flat::Dir | Flat::Keep => {
+                    stack.push((path, Tree::new()));
+                    continue;
+                }
+                Flat::File(contents) => Content::Bytes(contents.to_vec()),
+                Flat::Streamed(content) => (*content).clone(),
+                Flat::Symlink(target) => Content::Symlink(target.to_path_buf()),
+                Flat::HardLink(original) => match flat.get(original) {
+                    Some(Flat::File(contents)) => Content::Bytes(contents.to_vec()),
+                    Some(Flat::Streamed(content)) => (*content).clone(),
+                    _ => return Err(missing_original(path, original)),
+                },
+                Flat::Special(file) => file.content().clone(),
+                Flat::Matcher(matcher) => Content::Matcher((*matcher).clone()),
+            };
+            let (dir, tree) = stack.last_mut().expect("root directory");
+            tree.push(relative_key(dir, path), File::from(file));
+        }
+        while stack.len() > 1 {
+            pop_dir(&mut stack);
+        }
+        Ok(stack.pop().expect("root directory").1)
+    }
+}
+
+/// Add the directory at the top of the stack to its parent.
+fn pop_dir(stack: &mut Vec<(&Path, Tree)>) {
+    let (path, tree) = stack.pop().expect("nested directory");
+    let (dir, parent) = stack.last_mut().expect("root directory");
+    parent.push(relative_key(dir, path), Entry::Dir(tree));
+}
+
+fn relative_key(dir: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(dir).unwrap_or(path).to_owned()
+}
+
+fn missing_original(path: &Path, original: &Path) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "hard link {:?} to {:?} which is not a file of the spec",
+            path, original
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(entries: Vec<(&str, Entry)>) -> Tree {
+        let mut tree = Tree::new();
+        for (key, entry) in entries {
+            tree.push(key, entry);
+        }
+        tree
+    }
+
+    #[test]
+    fn split_merge_and_sort() {
+        let tree = crate::create_internal!(@tree {
+            "docs/guide": {
+                "intro.md": "Intro",
+            },
+            "README.md": "# Project",
+            "target": null,
+            "docs": {
+                "./guide/../README.md": true,
+            },
+        });
+
+        let expected = dir(vec![
+            ("README.md", Entry::file("# Project")),
+            (
+                "docs",
+                Entry::Dir(dir(vec![
+                    ("README.md", Entry::file("")),
+                    (
+                        "guide",
+                        Entry::Dir(dir(vec![("intro.md", Entry::file("Intro"))])),
+                    ),
+                ])),
+            ),
+        ]);
+        assert_eq!(tree.normalize().unwrap(), expected);
+        assert_eq!(expected.normalize().unwrap(), expected);
+    }
+
+    #[test]
+    fn hard_links_to_copies() {
+        let tree = crate::create_internal!(@tree {
+            "data.bin": [0, 1],
+            "copy.bin": crate::hardlink("data.bin"),
+        });
+
+        let expected = dir(vec![
+            ("copy.bin", Entry::file([0, 1])),
+            ("data.bin", Entry::file([0, 1])),
+        ]);
+        assert_eq!(tree.normalize().unwrap(), expected);
+
+        let tree = crate::create_internal!(@tree {
+            "copy.bin": crate::hardlink("data.bin"),
+        });
+        let err = tree.normalize().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,1185 @@
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::{Cache, CreateReport, CreatedEntry, EntryKind, Quota, SkipReason};
+
+/// Options of the creation of a tree, see [`create_with!`](crate::create_with)
+/// and [`Tree::create_with`](crate::Tree::create_with).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    on_conflict: Conflict,
+    require_root: bool,
+    clean: bool,
+    require_empty: bool,
+    prefix: Option<PathBuf>,
+    rerun_if_changed: bool,
+    quota: Option<Quota>,
+    cache: Option<Cache>,
+    max_bytes: Option<u64>,
+    write: WriteOptions,
+    sandbox: bool,
+    best_effort: bool,
+    allow_case_collisions: bool,
+    deduplicate: bool,
+    #[cfg(feature = "checksum")]
+    checksums: bool,
+    progress: Option<Progress>,
+    dangling_symlinks: DanglingSymlinks,
+    on_dangling_symlink: Option<OnDangling>,
+    #[cfg(feature = "rayon")]
+    parallel: bool,
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    io_uring: bool,
+}
+
+/// Callback invoked with each created entry, see [`Options::progress`].
+#[derive(Clone)]
+struct Progress(Arc<Mutex<ProgressFn>>);
+
+type ProgressFn = dyn FnMut(&CreatedEntry) + Send;
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Progress")
+    }
+}
+
+impl PartialEq for Progress {
+    fn eq(&self, other: &Progress) -> bool {
+        Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+    }
+}
+
+impl Eq for Progress {}
+
+/// Callback invoked with each dangling symlink and its target, see
+/// [`Options::on_dangling_symlink`].
+#[derive(Clone)]
+struct OnDangling(Arc<Mutex<OnDanglingFn>>);
+
+type OnDanglingFn = dyn FnMut(&Path, &Path) + Send;
+
+impl fmt::Debug for OnDangling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnDangling")
+    }
+}
+
+impl PartialEq for OnDangling {
+    fn eq(&self, other: &OnDangling) -> bool {
+        Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+    }
+}
+
+impl Eq for OnDangling {}
+
+/// How the contents of files and the symlinks are written, see
+/// [`Options::write`].
+///
+/// ```
+/// use macro_files::{Options, WriteOptions, WriteStrategy};
+///
+/// let options = Options::new().write(
+///     WriteOptions::new()
+///         .strategy(WriteStrategy::Chunked)
+///         .buffer_size(4096)
+///         .sync_data(true),
+/// );
+///
+/// let dir = macro_files::tempfile::tempdir().unwrap();
+/// macro_files::create_with!(options, {
+///     dir.path().join("data.bin"): [0; 10_000],
+/// })
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WriteOptions {
+    buffer_size: usize,
+    strategy: WriteStrategy,
+    sync_data: bool,
+    symlink_fallback: SymlinkFallback,
+}
+
+/// How the contents of a file are handed to the filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum WriteStrategy {
+    /// All the contents at once, the default.
+    #[default]
+    WriteAll,
+    /// Chunks of at most the buffer size, see [`WriteOptions::buffer_size`].
+    Chunked,
+}
+
+/// What to create instead of a symlink when the process is not privileged
+/// enough to create one, as on Windows outside of Developer Mode, see
+/// [`WriteOptions::symlink_fallback`].
+///
+/// Symlinks whose target does not exist are not replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum SymlinkFallback {
+    /// Fail with the [`ErrorKind::PermissionDenied`] error, the default.
+    #[default]
+    Error,
+    /// A directory junction for a symlink to a directory, see
+    /// [`junction`](crate::junction), and a copy of the target for a symlink
+    /// to a file.
+    Junction,
+    /// A copy of the target, with all its entries for a directory.
+    Copy,
+}
+
+impl Default for WriteOptions {
+    fn default() -> WriteOptions {
+        WriteOptions {
+            buffer_size: 64 * 1024,
+            strategy: WriteStrategy::default(),
+            sync_data: false,
+            symlink_fallback: SymlinkFallback::default(),
+        }
+    }
+}
+
+impl WriteOptions {
+    /// The default write options: all the contents at once without syncing.
+    pub fn new() -> WriteOptions {
+        WriteOptions::default()
+    }
+
+    /// Set the size of the chunks written with [`WriteStrategy::Chunked`],
+    /// 64 KiB by default.
+    pub fn buffer_size(mut self, size: usize) -> WriteOptions {
+        self.buffer_size = size.max(1);
+        self
+    }
+
+    /// Set how the contents are handed to the filesystem.
+    pub fn strategy(mut self, strategy: WriteStrategy) -> WriteOptions {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set whether the contents are flushed to the storage device with
+    /// [`std::fs::File::sync_data`] once written.
+    pub fn sync_data(mut self, sync: bool) -> WriteOptions {
+        self.sync_data = sync;
+        self
+    }
+
+    /// Set what to create instead of a symlink when the process is not
+    /// privileged enough to create one, so that specs with symlinks can be
+    /// created on unprivileged Windows runners.
+    ///
+    /// ```
+    /// use macro_files::{Options, SymlinkFallback, WriteOptions};
+    ///
+    /// let options = Options::new()
+    ///     .write(WriteOptions::new().symlink_fallback(SymlinkFallback::Junction));
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// macro_files::create_with!(options, {
+    ///     dir.path(): {
+    ///         "src/lib.rs": "",
+    ///         "vendor": macro_files::symlink("src"),
+    ///     },
+    /// })
+    /// .unwrap();
+    ///
+    /// assert!(dir.path().join("vendor/lib.rs").is_file());
+    /// ```
+    pub fn symlink_fallback(mut self, fallback: SymlinkFallback) -> WriteOptions {
+        self.symlink_fallback = fallback;
+        self
+    }
+
+    /// Size of the chunks written with [`WriteStrategy::Chunked`].
+    pub fn chunk_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// How the contents are handed to the filesystem.
+    pub fn write_strategy(&self) -> WriteStrategy {
+        self.strategy
+    }
+
+    /// Whether the contents are flushed to the storage device.
+    pub fn syncs_data(&self) -> bool {
+        self.sync_data
+    }
+
+    /// What to create instead of a symlink when the process is not
+    /// privileged enough to create one.
+    pub fn symlink_fallback_policy(&self) -> SymlinkFallback {
+        self.symlink_fallback
+    }
+
+    /// Whether the contents are written as with the default options, the
+    /// symlink fallback aside.
+    pub(crate) fn writes_by_default(&self) -> bool {
+        self.symlink_fallback(SymlinkFallback::default()) == WriteOptions::default()
+    }
+}
+
+/// How an existing directory is read into a tree, see [`Tree::read_with`].
+///
+/// ```
+/// use macro_files::{ReadOptions, Tree};
+///
+/// let dir = macro_files::create_temp!({
+///     ".git/HEAD": "ref: refs/heads/main",
+///     "README.md": "# Project",
+///     "target/debug/app": "",
+/// })
+/// .unwrap();
+///
+/// let options = ReadOptions::new()
+///     .hidden(false)
+///     .filter(|path| !path.starts_with("target"));
+/// let tree = Tree::read_with(dir.path(), &options).unwrap();
+///
+/// let keys: Vec<_> = tree.entries().map(|(key, _)| key.to_owned()).collect();
+/// assert_eq!(keys, [std::path::Path::new("README.md")]);
+/// ```
+///
+/// [`Tree::read_with`]: crate::Tree::read_with
+#[derive(Clone)]
+pub struct ReadOptions {
+    symlinks: Symlinks,
+    hidden: bool,
+    filter: Option<Arc<Filter>>,
+}
+
+type Filter = dyn Fn(&Path) -> bool + Send + Sync;
+
+impl Default for ReadOptions {
+    fn default() -> ReadOptions {
+        ReadOptions {
+            symlinks: Symlinks::default(),
+            hidden: true,
+            filter: None,
+        }
+    }
+}
+
+impl fmt::Debug for ReadOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadOptions")
+            .field("symlinks", &self.symlinks)
+            .field("hidden", &self.hidden)
+            .field("filter", &self.filter.as_ref().map(|_| "Filter"))
+            .finish()
+    }
+}
+
+/// How the symlinks found when reading a directory are read, see
+/// [`ReadOptions::symlinks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Symlinks {
+    /// Read as symlinks with their target, the default.
+    #[default]
+    Keep,
+    /// Followed and read as the file or directory they point to. A symlink
+    /// to one of its ancestors makes the read fail.
+    Follow,
+    /// Left out of the tree.
+    Skip,
+}
+
+impl ReadOptions {
+    /// The default read options: every entry is read, symlinks with their
+    /// target.
+    pub fn new() -> ReadOptions {
+        ReadOptions::default()
+    }
+
+    /// Set how the symlinks are read.
+    pub fn symlinks(mut self, symlinks: Symlinks) -> ReadOptions {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Set whether the entries whose name starts with a `.` are read, which
+    /// they are by default.
+    pub fn hidden(mut self, hidden: bool) -> ReadOptions {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Only read the entries whose path relative to the read directory
+    /// matches `filter`. The entries of a directory left out are not read.
+    pub fn filter<F>(mut self, filter: F) -> ReadOptions
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// How the symlinks are read.
+    pub fn symlink_policy(&self) -> Symlinks {
+        self.symlinks
+    }
+
+    /// Whether the entries whose name starts with a `.` are read.
+    pub fn includes_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Whether the entry at `path`, relative to the read directory, is read.
+    pub fn includes(&self, path: &Path) -> bool {
+        let hidden = path
+            .file_name()
+            .map_or(false, |name| name.to_string_lossy().starts_with('.'));
+        (self.hidden || !hidden) && self.filter.as_ref().map_or(true, |filter| filter(path))
+    }
+}
+
+/// What to do when a file to create already exists.
+///
+/// Existing directories are always reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Conflict {
+    /// Replace the existing file, the default. An existing symlink is
+    /// replaced rather than written through.
+    #[default]
+    Overwrite,
+    /// Keep the existing file, which is reported as skipped. Existing
+    /// directories are reused as they are, their declared attributes are
+    /// not applied, and other entries found instead of a directory are
+    /// skipped.
+    Skip,
+    /// Fail with an [`ErrorKind::AlreadyExists`] error.
+    Error,
+}
+
+/// What to do with the created symlinks whose target does not exist once
+/// the tree is created, see [`Options::dangling_symlinks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum DanglingSymlinks {
+    /// Keep them silently, the default.
+    #[default]
+    Allow,
+    /// Keep them, passing each one with its target to the callback set with
+    /// [`Options::on_dangling_symlink`], or else emitting a `tracing`
+    /// warning with the feature `tracing`. Nothing is printed otherwise, the
+    /// created symlinks are listed in the [`CreateReport`](crate::CreateReport).
+    Warn,
+    /// Fail with an [`ErrorKind::NotFound`] error naming the first one.
+    Error,
+}
+
+impl Options {
+    /// The default options.
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    /// Set what to do when a file to create already exists.
+    pub fn on_conflict(mut self, conflict: Conflict) -> Options {
+        self.on_conflict = conflict;
+        self
+    }
+
+    /// What to do when a file to create already exists.
+    pub fn conflict(&self) -> Conflict {
+        self.on_conflict
+    }
+
+    /// Set whether [`create_with!`](crate::create_with) fails instead of
+    /// resolving its keys against the current directory when no default root
+    /// is set, see [`with_default_root`](crate::with_default_root).
+    ///
+    /// ```
+    /// use macro_files::Options;
+    ///
+    /// let err = macro_files::create_with!(Options::new().require_root(true), {
+    ///     "README.md": "# Project",
+    /// })
+    /// .unwrap_err();
+    ///
+    /// assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    /// ```
+    pub fn require_root(mut self, require: bool) -> Options {
+        self.require_root = require;
+        self
+    }
+
+    /// Whether a default root is required.
+    pub fn requires_root(&self) -> bool {
+        self.require_root
+    }
+
+    /// Set whether the existing entries of the directory the tree is created
+    /// in are removed first, so that repeated creations start from an empty
+    /// directory. The removed entries are listed by
+    /// [`CreateReport::removed`].
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let dir = macro_files::create_temp!({
+    ///     "stale.txt": "stale",
+    ///     "out": { "old.bin": [0; 4] },
+    /// })
+    /// .unwrap();
+    /// let mut tree = Tree::new();
+    /// tree.push("fresh.txt", "fresh");
+    ///
+    /// let report = tree.create_with(dir.path(), &Options::new().clean(true)).unwrap();
+    ///
+    /// assert_eq!(report.removed().len(), 2);
+    /// assert!(!dir.path().join("stale.txt").exists());
+    /// assert!(dir.path().join("fresh.txt").is_file());
+    /// ```
+    pub fn clean(mut self, clean: bool) -> Options {
+        self.clean = clean;
+        self
+    }
+
+    /// Whether the existing entries of the directory are removed first.
+    pub fn cleans(&self) -> bool {
+        self.clean
+    }
+
+    /// Set whether the creation fails with an [`ErrorKind::AlreadyExists`]
+    /// error, before creating anything, when the directory the tree is
+    /// created in already has entries, e.g. to keep a scaffolding command
+    /// from merging into a populated directory. Checked before the directory
+    /// is emptied, see [`Options::clean`].
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let dir = macro_files::create_temp!({ "notes.txt": "mine" }).unwrap();
+    /// let mut tree = Tree::new();
+    /// tree.push("Cargo.toml", "[package]");
+    ///
+    /// let options = Options::new().require_empty(true);
+    /// let err = tree.create_with(dir.path(), &options).unwrap_err();
+    /// assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    /// assert!(!dir.path().join("Cargo.toml").exists());
+    /// ```
+    pub fn require_empty(mut self, require: bool) -> Options {
+        self.require_empty = require;
+        self
+    }
+
+    /// Whether the directory must be missing or empty.
+    pub fn requires_empty(&self) -> bool {
+        self.require_empty
+    }
+
+    /// Set a prefix the directory the tree is created in must be within,
+    /// failing with an [`ErrorKind::PermissionDenied`] error before creating
+    /// anything otherwise, e.g. to keep a scaffolding command targeting an
+    /// absolute root out of system directories, see
+    /// [`create_at!`](crate::create_at). Directories with `..` components
+    /// are never within the prefix.
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let mut tree = Tree::new();
+    /// tree.push("app.toml", "");
+    ///
+    /// let options = Options::new().require_prefix("/srv");
+    /// let err = tree.create_with(dir.path(), &options).unwrap_err();
+    /// assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    /// ```
+    pub fn require_prefix<P: AsRef<Path>>(mut self, prefix: P) -> Options {
+        self.prefix = Some(prefix.as_ref().to_owned());
+        self
+    }
+
+    /// Prefix the directory must be within.
+    pub fn required_prefix(&self) -> Option<&Path> {
+        self.prefix.as_deref()
+    }
+
+    /// Set whether the creation prints the `cargo:rerun-if-changed`
+    /// instructions of the files and directories the tree reads, so that a
+    /// build script creating it runs again when they change, see
+    /// [`Tree::sources`](crate::Tree::sources).
+    ///
+    /// ```no_run
+    /// // build.rs
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let out_dir = std::env::var("OUT_DIR").unwrap();
+    /// let tree = macro_files::tree!({
+    ///     "assets": macro_files::copy_dir("assets"),
+    ///     "LICENSE": macro_files::from("LICENSE-MIT"),
+    /// });
+    /// // Prints `cargo:rerun-if-changed=assets` and
+    /// // `cargo:rerun-if-changed=LICENSE-MIT`.
+    /// tree.create_with(out_dir, &Options::new().rerun_if_changed(true))
+    ///     .unwrap();
+    /// ```
+    pub fn rerun_if_changed(mut self, rerun: bool) -> Options {
+        self.rerun_if_changed = rerun;
+        self
+    }
+
+    /// Whether the creation prints the `cargo:rerun-if-changed` instructions
+    /// of the sources of the tree.
+    pub fn prints_rerun_if_changed(&self) -> bool {
+        self.rerun_if_changed
+    }
+
+    /// Set the quota of the directory of cached fixtures the tree is created
+    /// in, see [`Quota`].
+    ///
+    /// Before creating the tree, the least recently used fixtures are evicted
+    /// until the files whose size is known fit, see
+    /// [`Tree::stats`](crate::Tree::stats). The evicted fixtures are listed by
+    /// [`CreateReport::evicted`].
+    ///
+    /// ```
+    /// use macro_files::{Options, Quota, Tree};
+    ///
+    /// let cache = macro_files::tempfile::tempdir().unwrap();
+    /// let options = Options::new().quota(Quota::new(cache.path(), 16));
+    /// let mut fixture = Tree::new();
+    /// fixture.push("data.bin", [0; 10]);
+    ///
+    /// fixture.create_with(cache.path().join("first"), &options).unwrap();
+    /// let report = fixture.create_with(cache.path().join("second"), &options).unwrap();
+    ///
+    /// assert_eq!(report.evicted(), [cache.path().join("first")]);
+    /// ```
+    pub fn quota(mut self, quota: Quota) -> Options {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// The quota of the directory of cached fixtures, if any.
+    pub fn declared_quota(&self) -> Option<&Quota> {
+        self.quota.as_ref()
+    }
+
+    /// Set the cache of fixtures the tree is copied from, see [`Cache`].
+    ///
+    /// A tree which can be cached is created once in the cache, then its
+    /// fixture is copied, files being cloned where the filesystem supports it
+    /// like [`from`](crate::from). The copied entries are reported without
+    /// their origin. Ignored when the filesystem operations are overridden,
+    /// see [`with_fs_ops`](crate::with_fs_ops).
+    ///
+    /// ```
+    /// use macro_files::{Cache, Options};
+    ///
+    /// let cache = macro_files::tempfile::tempdir().unwrap();
+    /// let options = Options::new().cache(Cache::new(cache.path()));
+    /// let fixture = macro_files::tree!({ "data.bin": [0; 1024] });
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// fixture.create_with(dir.path().join("first"), &options).unwrap();
+    /// fixture.create_with(dir.path().join("second"), &options).unwrap();
+    ///
+    /// let cached = Cache::new(cache.path()).fixture(&fixture).unwrap();
+    /// assert!(cached.join("data.bin").is_file());
+    /// assert!(dir.path().join("second/data.bin").is_file());
+    /// ```
+    pub fn cache(mut self, cache: Cache) -> Options {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The cache of fixtures, if any.
+    pub fn declared_cache(&self) -> Option<&Cache> {
+        self.cache.as_ref()
+    }
+
+    /// Set the maximum number of bytes of file contents the creation writes,
+    /// which guards against sizes computed at runtime filling the disk.
+    ///
+    /// The creation fails with an [`ErrorKind::Other`](std::io::ErrorKind::Other)
+    /// error before writing anything when the files whose size is known
+    /// exceed the budget, see [`Tree::stats`](crate::Tree::stats), and before
+    /// writing the first file exceeding it otherwise. Streamed contents are
+    /// written up to the remaining budget, the file is removed when they
+    /// exceed it. Symlinks, hard links and directories are not counted.
+    ///
+    /// ```
+    /// use macro_files::Options;
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let err = macro_files::create_with!(Options::new().max_bytes(1 << 20), {
+    ///     dir.path(): {
+    ///         "README.md": "# Project",
+    ///         "data.bin": macro_files::size(1 << 30),
+    ///     },
+    /// })
+    /// .unwrap_err();
+    ///
+    /// assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    /// assert!(!dir.path().join("README.md").exists());
+    /// ```
+    pub fn max_bytes(mut self, max_bytes: u64) -> Options {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// The maximum number of bytes the creation writes, if any.
+    pub fn declared_max_bytes(&self) -> Option<u64> {
+        self.max_bytes
+    }
+
+    /// Set how the contents of files are written, which only applies to the
+    /// contents read into memory, see [`FsOps::write_file_with`](crate::FsOps::write_file_with),
+    /// and what replaces the symlinks that cannot be created.
+    pub fn write(mut self, write: WriteOptions) -> Options {
+        self.write = write;
+        self
+    }
+
+    /// How the contents of files are written.
+    pub fn write_options(&self) -> &WriteOptions {
+        &self.write
+    }
+
+    /// Set whether the creation fails with an [`ErrorKind::PermissionDenied`]
+    /// error before writing outside the directory the tree is created in,
+    /// which a key computed at runtime or an existing symlink could lead to.
+    ///
+    /// The paths are checked with their symlinks resolved. The entries at the
+    /// root of a tree created in the current directory, as with
+    /// [`create!`](crate::create), are each their own root.
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let outside = macro_files::tempfile::tempdir().unwrap();
+    /// let dir = macro_files::create_temp!({
+    ///     "link": macro_files::symlink(outside.path()),
+    /// })
+    /// .unwrap();
+    /// let mut tree = Tree::new();
+    /// tree.push("link/escape.txt", "!");
+    ///
+    /// let err = tree.create_with(dir.path(), &Options::new().sandbox(true)).unwrap_err();
+    /// assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    /// assert!(!outside.path().join("escape.txt").exists());
+    /// ```
+    pub fn sandbox(mut self, sandbox: bool) -> Options {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Whether the creation fails before writing outside its directory.
+    pub fn is_sandboxed(&self) -> bool {
+        self.sandbox
+    }
+
+    /// Set whether the creation goes on with the next entries when an entry
+    /// fails to be created, instead of stopping at the first error.
+    ///
+    /// The entries of a directory that failed to be created are not created.
+    /// Once all the entries are processed, the creation fails with a
+    /// [`CreateErrors`](crate::CreateErrors) error listing every failure.
+    ///
+    /// ```
+    /// use macro_files::{CreateErrors, Options};
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let err = macro_files::create_with!(Options::new().best_effort(true), {
+    ///     dir.path(): {
+    ///         "missing.txt": macro_files::from("missing.txt"),
+    ///         "README.md": "# Project",
+    ///         "empty.txt": macro_files::from("empty.txt"),
+    ///     },
+    /// })
+    /// .unwrap_err();
+    ///
+    /// let err = err.get_ref().unwrap().downcast_ref::<CreateErrors>().unwrap();
+    /// assert_eq!(err.errors().len(), 2);
+    /// assert!(dir.path().join("README.md").is_file());
+    /// ```
+    pub fn best_effort(mut self, best_effort: bool) -> Options {
+        self.best_effort = best_effort;
+        self
+    }
+
+    /// Whether the creation goes on when an entry fails to be created.
+    pub fn is_best_effort(&self) -> bool {
+        self.best_effort
+    }
+
+    /// Set whether entries whose paths only differ by case, such as
+    /// `README.md` and `readme.md`, can be declared in the same spec.
+    ///
+    /// They are rejected by default with an [`InvalidKey`](crate::InvalidKey)
+    /// error of reason [`CaseCollision`](crate::InvalidKeyReason::CaseCollision),
+    /// as one would silently overwrite the other on case-insensitive
+    /// filesystems, the default on Windows and macOS. Allow them in tests of
+    /// case-sensitive behavior.
+    ///
+    /// ```
+    /// use macro_files::Options;
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let err = macro_files::create_in!(dir.path(), {
+    ///     "README.md": "# Project",
+    ///     "readme.md": "# Project",
+    /// })
+    /// .unwrap_err();
+    /// assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    ///
+    /// let options = Options::new().allow_case_collisions(true);
+    /// # if cfg!(target_os = "linux") {
+    /// macro_files::create_with!(options, {
+    ///     dir.path(): {
+    ///         "README.md": "# Project",
+    ///         "readme.md": "# Project",
+    ///     },
+    /// })
+    /// .unwrap();
+    /// # }
+    /// ```
+    pub fn allow_case_collisions(mut self, allow: bool) -> Options {
+        self.allow_case_collisions = allow;
+        self
+    }
+
+    /// Whether entries whose paths only differ by case are allowed.
+    pub fn allows_case_collisions(&self) -> bool {
+        self.allow_case_collisions
+    }
+
+    /// Set whether the files whose contents are identical to a file already
+    /// written by the creation are created as hard links to it, which saves
+    /// disk space for specs of many duplicate files.
+    ///
+    /// Only the contents held in memory are compared, and only between files
+    /// with the same attributes, as hard links share them. A file is written
+    /// when it can't be linked, for instance past the maximum number of links
+    /// of the filesystem. Files are written in order, even when they would be
+    /// written in parallel or with `io_uring`, and a file created
+    /// asynchronously waits for the pending writes before being linked, see
+    /// [`Tree::create_with_async`](crate::Tree::create_with_async).
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let mut tree = Tree::new();
+    /// for i in 0..100 {
+    ///     tree.push(format!("{}.txt", i), "duplicate");
+    /// }
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// tree.create_with(dir.path(), &Options::new().deduplicate(true))
+    ///     .unwrap();
+    ///
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::os::unix::fs::MetadataExt;
+    /// let metadata = std::fs::metadata(dir.path().join("99.txt")).unwrap();
+    /// assert_eq!(metadata.nlink(), 100);
+    /// # }
+    /// ```
+    pub fn deduplicate(mut self, deduplicate: bool) -> Options {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Whether the files with identical contents are created as hard links.
+    pub fn deduplicates(&self) -> bool {
+        self.deduplicate
+    }
+
+    /// Set whether the SHA-256 digests of the created files are computed, to
+    /// derive cache keys or a manifest verifying the tree later without
+    /// reading it again, see [`CreateReport::checksums`] and
+    /// [`CreateReport::sha256sums`].
+    ///
+    /// Contents held in memory are hashed as they are, other files are read
+    /// back once written. Symlinks and special files have no digest, nor do
+    /// the files created asynchronously.
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let mut tree = Tree::new();
+    /// tree.push("empty.txt", "");
+    /// let report = tree
+    ///     .create_with(dir.path(), &Options::new().checksums(true))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     report.created()[0].sha256(),
+    ///     Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+    /// );
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn checksums(mut self, checksums: bool) -> Options {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Whether the SHA-256 digests of the created files are computed.
+    #[cfg(feature = "checksum")]
+    pub fn computes_checksums(&self) -> bool {
+        self.checksums
+    }
+
+    /// Set a callback invoked with each entry once created, in the order of
+    /// [`CreateReport::created`], to report the progress of the creation of
+    /// large trees.
+    ///
+    /// Clones of the options share the callback. The files written
+    /// asynchronously are reported when their write starts, see
+    /// [`Tree::create_in_async`](crate::Tree::create_in_async).
+    ///
+    /// ```
+    /// use macro_files::Options;
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let mut created = 0;
+    /// let options = Options::new().progress(move |entry| {
+    ///     created += 1;
+    ///     println!("{} {}", created, entry.path().display());
+    /// });
+    /// macro_files::create_with!(options, {
+    ///     dir.path(): {
+    ///         "README.md": "# Project",
+    ///     },
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn progress<F>(mut self, progress: F) -> Options
+    where
+        F: FnMut(&CreatedEntry) + Send + 'static,
+    {
+        self.progress = Some(Progress(Arc::new(Mutex::new(progress))));
+        self
+    }
+
+    /// Whether a progress callback is set.
+    pub fn reports_progress(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    /// Invoke the progress callback with the `created` entry, if any.
+    pub(crate) fn report_progress(&self, created: &CreatedEntry) {
+        if let Some(Progress(progress)) = &self.progress {
+            let mut progress = progress
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            (*progress)(created);
+        }
+    }
+
+    /// Set what to do with the created symlinks and junctions whose target
+    /// does not exist once the tree is created, targets declared later in
+    /// the spec being created by then. Not checked when filesystem
+    /// operations are overridden with [`with_fs_ops`](crate::with_fs_ops).
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use macro_files::{DanglingSymlinks, Options, Tree};
+    ///
+    /// let mut tree = Tree::new();
+    /// tree.push("current", macro_files::symlink("releases/v2"));
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let options = Options::new().dangling_symlinks(DanglingSymlinks::Error);
+    /// let err = tree.create_with(dir.path(), &options).unwrap_err();
+    /// assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    /// # }
+    /// ```
+    pub fn dangling_symlinks(mut self, policy: DanglingSymlinks) -> Options {
+        self.dangling_symlinks = policy;
+        self
+    }
+
+    /// What to do with dangling symlinks.
+    pub fn dangling_symlink_policy(&self) -> DanglingSymlinks {
+        self.dangling_symlinks
+    }
+
+    /// Set a callback invoked with the path and the target of each dangling
+    /// symlink, and warn about them with it, see
+    /// [`DanglingSymlinks::Warn`].
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let mut tree = Tree::new();
+    /// tree.push("current", macro_files::symlink("releases/v2"));
+    ///
+    /// let dangling = Arc::new(Mutex::new(Vec::new()));
+    /// let options = Options::new().on_dangling_symlink({
+    ///     let dangling = dangling.clone();
+    ///     move |_path, target| dangling.lock().unwrap().push(target.to_owned())
+    /// });
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// tree.create_with(dir.path(), &options).unwrap();
+    ///
+    /// assert_eq!(*dangling.lock().unwrap(), [std::path::Path::new("releases/v2")]);
+    /// # }
+    /// ```
+    pub fn on_dangling_symlink<F>(mut self, callback: F) -> Options
+    where
+        F: FnMut(&Path, &Path) + Send + 'static,
+    {
+        self.dangling_symlinks = DanglingSymlinks::Warn;
+        self.on_dangling_symlink = Some(OnDangling(Arc::new(Mutex::new(callback))));
+        self
+    }
+
+    /// Apply the dangling symlink policy to the `created` entries.
+    pub(crate) fn check_dangling_symlinks(&self, created: &[CreatedEntry]) -> Result<()> {
+        if self.dangling_symlinks == DanglingSymlinks::Allow || crate::fs::is_overridden() {
+            return Ok(());
+        }
+        for entry in created {
+            if entry.kind() != EntryKind::Symlink || entry.path().exists() {
+                continue;
+            }
+            let target = std::fs::read_link(entry.path()).unwrap_or_default();
+            match (self.dangling_symlinks, &self.on_dangling_symlink) {
+                (DanglingSymlinks::Error, _) => {
+                    return Err(Error::new(
+                        ErrorKind::NotFound,
+                        format!("dangling symbolic link {:?} to {:?}", entry.path(), target),
+                    ))
+                }
+                (_, Some(OnDangling(callback))) => {
+                    let mut callback = callback
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    (*callback)(entry.path(), &target);
+                }
+                #[cfg(feature = "tracing")]
+                (_, None) => tracing::warn!(
+                    path = %entry.path().display(),
+                    target = %target.display(),
+                    "dangling symbolic link {} to {}",
+                    entry.path().display(),
+                    target.display()
+                ),
+                #[cfg(not(feature = "tracing"))]
+                (_, None) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Set whether the files are written in parallel with [`rayon`], once
+    /// all the directories are created in order.
+    ///
+    /// Hard links are created after the other files, and the attributes of
+    /// directories are applied last. The created files are reported after
+    /// the directories. Files are written in order when filesystem operations
+    /// are overridden with [`with_fs_ops`](crate::with_fs_ops), which only
+    /// apply to the current thread.
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let mut tree = Tree::new();
+    /// for i in 0..1000 {
+    ///     tree.push(format!("{}/{}.txt", i % 10, i), i.to_string());
+    /// }
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// tree.create_with(dir.path(), &Options::new().parallel(true))
+    ///     .unwrap();
+    ///
+    /// assert!(dir.path().join("9/999.txt").is_file());
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn parallel(mut self, parallel: bool) -> Options {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Whether the files are written in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn is_parallel(&self) -> bool {
+        self.parallel
+    }
+
+    /// Set whether the files are written in batches with [`io_uring`], once
+    /// all the directories are created in order, which saves most system
+    /// calls for trees of tens of thousands of files. Takes precedence over
+    /// [`Options::parallel`].
+    ///
+    /// Files are opened, written then closed by batches of 256. Only the
+    /// contents held in memory are written with `io_uring`, other files are
+    /// written as usual, as are all files when `io_uring` is not available.
+    /// Files are deferred as with [`Options::parallel`].
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let mut tree = Tree::new();
+    /// for i in 0..1000 {
+    ///     tree.push(format!("{}/{}.txt", i % 10, i), i.to_string());
+    /// }
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// tree.create_with(dir.path(), &Options::new().io_uring(true))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(std::fs::read_to_string(dir.path().join("9/999.txt")).unwrap(), "999");
+    /// ```
+    ///
+    /// [`io_uring`]: https://man7.org/linux/man-pages/man7/io_uring.7.html
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    pub fn io_uring(mut self, io_uring: bool) -> Options {
+        self.io_uring = io_uring;
+        self
+    }
+
+    /// Whether the files are written in batches with `io_uring`.
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    pub fn uses_io_uring(&self) -> bool {
+        self.io_uring
+    }
+
+    /// Whether the files are written once all the directories are created,
+    /// see [`Options::parallel`] and [`Options::io_uring`].
+    #[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+    pub(crate) fn defers_files(&self) -> bool {
+        if self.deduplicate {
+            return false;
+        }
+        #[cfg(feature = "rayon")]
+        if self.parallel {
+            return true;
+        }
+        #[cfg(all(feature = "io-uring", target_os = "linux"))]
+        if self.io_uring {
+            return true;
+        }
+        false
+    }
+
+    /// Directory the keys of [`create!`](crate::create) and
+    /// [`create_with!`](crate::create_with) are resolved against: the default
+    /// root set with [`with_default_root`](crate::with_default_root), or the
+    /// current directory as an empty path.
+    ///
+    /// Fails with an [`ErrorKind::InvalidInput`] error when no default root is
+    /// set and one is required.
+    pub fn root_dir(&self) -> Result<PathBuf> {
+        match crate::default_root() {
+            Some(root) => Ok(root),
+            None if self.require_root => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "no default root set to create the spec in",
+            )),
+            None => Ok(PathBuf::new()),
+        }
+    }
+}
+
+/// Fail with an [`ErrorKind::AlreadyExists`] error when the directory `dir`
+/// has entries, see [`Options::require_empty`].
+pub(crate) fn check_empty(dir: &Path) -> Result<()> {
+    let path = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let mut entries = match path.read_dir() {
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        entries => entries?,
+    };
+    if entries.next().is_some() {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("directory is not empty: {:?}", dir),
+        ));
+    }
+    Ok(())
+}
+
+/// Fail with an [`ErrorKind::PermissionDenied`] error when the directory
+/// `dir` is not within `prefix`, see [`Options::require_prefix`].
+pub(crate) fn check_prefix(dir: &Path, prefix: &Path) -> Result<()> {
+    let parent_dir = dir
+        .components()
+        .any(|component| component == Component::ParentDir);
+    if parent_dir || !dir.starts_with(prefix) {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("directory {:?} is not within {:?}", dir, prefix),
+        ));
+    }
+    Ok(())
+}
+
+/// How to create a file given the entry already at its path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Claim {
+    /// Create the file.
+    Create,
+    /// Remove the existing entry, then create the file.
+    Replace,
+    /// Leave the existing entry.
+    Skip,
+    /// Leave the existing directory, creating the entries of the spec in it.
+    Reuse,
+}
+
+/// Apply the `conflict` policy to the file to create at `path` given the
+/// kind of the `existing` entry. Links cannot be written over an existing
+/// entry, they always replace it.
+pub(crate) fn claim(
+    conflict: Conflict,
+    report: &mut CreateReport,
+    path: &Path,
+    existing: Option<EntryKind>,
+    link: bool,
+) -> Result<Claim> {
+    let existing = match existing {
+        Some(existing) => existing,
+        None => return Ok(Claim::Create),
+    };
+    match conflict {
+        Conflict::Overwrite if link || existing == EntryKind::Symlink => Ok(Claim::Replace),
+        Conflict::Overwrite => Ok(Claim::Create),
+        Conflict::Skip => {
+            report.push_skipped(path.to_owned(), SkipReason::Exists);
+            Ok(Claim::Skip)
+        }
+        Conflict::Error => Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} already exists: {:?}", existing, path),
+        )),
+    }
+}
+
+/// Apply the conflict policy to the directory to create at `path` given the
+/// kind of the `existing` entry, which is only looked up when conflicts are
+/// skipped: other policies create directories over existing ones.
+pub(crate) fn claim_dir(
+    options: &Options,
+    report: &mut CreateReport,
+    path: &Path,
+    existing: Option<EntryKind>,
+) -> Claim {
+    match existing {
+        Some(EntryKind::Dir) if options.on_conflict == Conflict::Skip => Claim::Reuse,
+        Some(_) if options.on_conflict == Conflict::Skip => {
+            report.push_skipped(path.to_owned(), SkipReason::Exists);
+            Claim::Skip
+        }
+        _ => Claim::Create,
+    }
+}
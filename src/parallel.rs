@@ -0,0 +1,20 @@
+use std::io::Result;
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::{File, WriteOptions};
+
+/// Write the files in parallel, see
+/// [`Options::parallel`](crate::Options::parallel). Returns the result of
+/// each file, in order.
+pub(crate) fn write_files(
+    files: &[(&File, &Path)],
+    root: &Path,
+    options: &WriteOptions,
+) -> Vec<Result<()>> {
+    files
+        .par_iter()
+        .map(|(file, path)| file.write(root, path, options))
+        .collect()
+}
@@ -0,0 +1,308 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::{Content, Entry, SkipReason, Tree};
+
+impl Tree {
+    /// Parse a spec written with the syntax of the macros as JSON, so specs
+    /// can come from command line arguments, files or the standard input.
+    ///
+    /// Maps are directories, strings and numbers are text files, arrays of
+    /// bytes are binary files, arrays of strings are directories of empty
+    /// files with these names, `true` is an empty file and `false` or `null`
+    /// an entry which is not created. The JSON5 extensions are accepted:
+    /// comments, trailing commas, single-quoted strings and unquoted keys.
+    ///
+    /// Fails with an [`ErrorKind::InvalidData`] error giving the line and
+    /// column of the first syntax error.
+    ///
+    /// ```
+    /// let tree = macro_files::Tree::parse(
+    ///     r#"{
+    ///         // Project skeleton
+    ///         "Cargo.toml": "[package]",
+    ///         src: { "main.rs": 'fn main() {}' },
+    ///         "logo.png": [137, 80, 78, 71],
+    ///         "stamps": ["a.done", "b.done"],
+    ///         "target": null,
+    ///     }"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree,
+    ///     macro_files::tree!({
+    ///         "Cargo.toml": "[package]",
+    ///         "src": { "main.rs": "fn main() {}" },
+    ///         "logo.png": [137u8, 80, 78, 71],
+    ///         "stamps": ["a.done", "b.done"],
+    ///         "target": null,
+    ///     })
+    /// );
+    /// ```
+    pub fn parse(text: &str) -> Result<Tree> {
+        let mut parser = Parser { text, offset: 0 };
+        parser.skip_blank()?;
+        let tree = parser.tree()?;
+        parser.skip_blank()?;
+        if parser.offset < text.len() {
+            return Err(parser.error("expected the end of the spec"));
+        }
+        Ok(tree)
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.offset += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", expected)))
+        }
+    }
+
+    /// An error at the current position, with its line and column.
+    fn error(&self, message: &str) -> Error {
+        let before = &self.text[..self.offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{} at line {}, column {}", message, line, column),
+        )
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_blank(&mut self) -> Result<()> {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.offset += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.offset += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(end) => self.offset += end + 4,
+                    None => return Err(self.error("unterminated comment")),
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn tree(&mut self) -> Result<Tree> {
+        self.expect('{')?;
+        let mut tree = Tree::new();
+        loop {
+            self.skip_blank()?;
+            if self.eat('}') {
+                return Ok(tree);
+            }
+            let key = self.key()?;
+            self.skip_blank()?;
+            self.expect(':')?;
+            self.skip_blank()?;
+            let entry = self.entry()?;
+            tree.push(key, entry);
+            self.skip_blank()?;
+            if !self.eat(',') {
+                self.skip_blank()?;
+                self.expect('}')?;
+                return Ok(tree);
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => self.string(quote),
+            Some(c) if c.is_alphanumeric() || c == '_' || c == '$' => Ok(self.word().to_owned()),
+            _ => Err(self.error("expected a key")),
+        }
+    }
+
+    fn entry(&mut self) -> Result<Entry> {
+        match self.peek() {
+            Some('{') => self.tree().map(Entry::Dir),
+            Some('[') if self.is_names()? => self.names().map(Entry::Dir),
+            Some('[') => self
+                .bytes()
+                .map(|bytes| Entry::File(Content::Bytes(bytes).into())),
+            Some(quote @ ('"' | '\'')) => self.string(quote).map(Entry::file),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let number = self.word();
+                match number.parse::<f64>() {
+                    Ok(_) => Ok(Entry::file(number)),
+                    Err(_) => Err(self.error("invalid number")),
+                }
+            }
+            Some(c) if c.is_alphabetic() => {
+                let start = self.offset;
+                match self.word() {
+                    "true" => Ok(Entry::file(b"")),
+                    "false" => Ok(Entry::Skip(SkipReason::False)),
+                    "null" => Ok(Entry::Skip(SkipReason::Null)),
+                    _ => {
+                        self.offset = start;
+                        Err(self.error("expected a value"))
+                    }
+                }
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    /// A run of characters of an unquoted key, number or literal.
+    fn word(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '$' | '-' | '+' | '.')))
+            .unwrap_or(rest.len());
+        self.offset += len;
+        &rest[..len]
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>> {
+        self.expect('[')?;
+        let mut bytes = Vec::new();
+        loop {
+            self.skip_blank()?;
+            if self.eat(']') {
+                return Ok(bytes);
+            }
+            let start = self.offset;
+            match self.word().parse::<u8>() {
+                Ok(byte) => bytes.push(byte),
+                Err(_) => {
+                    self.offset = start;
+                    return Err(self.error("expected a byte from 0 to 255"));
+                }
+            }
+            self.skip_blank()?;
+            if !self.eat(',') {
+                self.skip_blank()?;
+                self.expect(']')?;
+                return Ok(bytes);
+            }
+        }
+    }
+
+    /// Whether the array at the current position starts with a string.
+    fn is_names(&mut self) -> Result<bool> {
+        let start = self.offset;
+        self.expect('[')?;
+        self.skip_blank()?;
+        let names = matches!(self.peek(), Some('"' | '\''));
+        self.offset = start;
+        Ok(names)
+    }
+
+    /// A directory of the empty files named by an array of strings.
+    fn names(&mut self) -> Result<Tree> {
+        self.expect('[')?;
+        let mut tree = Tree::new();
+        loop {
+            self.skip_blank()?;
+            if self.eat(']') {
+                return Ok(tree);
+            }
+            let name = match self.peek() {
+                Some(quote @ ('"' | '\'')) => self.string(quote)?,
+                _ => return Err(self.error("expected a name")),
+            };
+            tree.push(name, Entry::file(b""));
+            self.skip_blank()?;
+            if !self.eat(',') {
+                self.skip_blank()?;
+                self.expect(']')?;
+                return Ok(tree);
+            }
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<String> {
+        self.expect(quote)?;
+        let mut string = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => return Ok(string),
+                Some('\\') => string.push(self.escape()?),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char> {
+        let c = match self.bump() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('0') => '\0',
+            Some('u') => return self.unicode_escape(),
+            Some(c @ ('"' | '\'' | '\\' | '/')) => c,
+            _ => return Err(self.error("invalid escape")),
+        };
+        Ok(c)
+    }
+
+    /// The character of a `\uXXXX` escape, with its low surrogate if any.
+    fn unicode_escape(&mut self) -> Result<char> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if !self.rest().starts_with("\\u") {
+                return Err(self.error("expected a low surrogate"));
+            }
+            self.offset += 2;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error("invalid low surrogate"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        std::char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32> {
+        let digits = self.rest().get(..4).unwrap_or("");
+        match u32::from_str_radix(digits, 16) {
+            Ok(code) if digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+                self.offset += 4;
+                Ok(code)
+            }
+            _ => Err(self.error("invalid unicode escape")),
+        }
+    }
+}
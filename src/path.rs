@@ -0,0 +1,441 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+/// Error returned when an entry key is not a valid relative path.
+///
+/// It is wrapped in an [`io::Error`] of kind [`ErrorKind::InvalidInput`] and
+/// can be recovered with [`io::Error::get_ref`] and `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidKey {
+    key: PathBuf,
+    reason: InvalidKeyReason,
+}
+
+/// Why an entry key was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidKeyReason {
+    /// The key is empty or normalizes to an empty path (e.g. `"."`).
+    Empty,
+    /// The key is absolute or has a prefix (e.g. `"/etc"` or `"C:"`).
+    Absolute,
+    /// The key has a `..` component escaping its parent directory, see
+    /// [`Tree::allow_parent_keys`](crate::Tree::allow_parent_keys).
+    ParentDir,
+    /// The key resolves to the path of another entry of the spec.
+    Duplicate,
+    /// The key resolves to the path of another entry of the spec except for
+    /// case, see [`Options::allow_case_collisions`](crate::Options::allow_case_collisions).
+    CaseCollision,
+}
+
+impl InvalidKey {
+    /// The key as it was declared in the spec.
+    pub fn key(&self) -> &Path {
+        &self.key
+    }
+
+    /// Why the key was rejected.
+    pub fn reason(&self) -> InvalidKeyReason {
+        self.reason
+    }
+}
+
+impl fmt::Display for InvalidKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            InvalidKeyReason::Empty => "the key is empty",
+            InvalidKeyReason::Absolute => "the key must be a relative path",
+            InvalidKeyReason::ParentDir => "the key escapes its parent directory",
+            InvalidKeyReason::Duplicate => "the path is already declared in the spec",
+            InvalidKeyReason::CaseCollision => {
+                "the path only differs by case from another path of the spec"
+            }
+        };
+        write!(f, "invalid key {:?}: {}", self.key, reason)
+    }
+}
+
+impl Error for InvalidKey {}
+
+impl From<InvalidKey> for io::Error {
+    fn from(err: InvalidKey) -> Self {
+        io::Error::new(ErrorKind::InvalidInput, err)
+    }
+}
+
+/// Join an entry key to the path of its parent directory.
+///
+/// The key is normalized: `.` components are removed and `..` components are
+/// resolved against the previous components of the key. A key is rejected
+/// with an [`InvalidKey`] error if it is empty, absolute or if it escapes its
+/// parent directory.
+///
+/// Keys at the root of a spec without a base path (`dir` is empty, as in
+/// [`create!`](crate::create)) are only normalized, they can be absolute or
+/// start with `..` as they are resolved against the current directory. The
+/// keys of a tree allowing it may also start with `..`, see
+/// [`Tree::allow_parent_keys`](crate::Tree::allow_parent_keys).
+///
+/// A key made only of `.` components, such as `"."`, is empty once
+/// normalized and is rejected: the entries of a directory are declared
+/// within its own map rather than under a `"."` key.
+pub fn entry_path<D: AsRef<Path>, K: AsRef<Path>>(dir: D, key: K) -> io::Result<PathBuf> {
+    resolve_key(dir.as_ref(), key.as_ref(), false)
+}
+
+/// Join an entry key to the path of its parent directory, see
+/// [`entry_path`], letting it escape the directory with `..` components when
+/// `parent_keys` is true.
+pub(crate) fn resolve_key(dir: &Path, key: &Path, parent_keys: bool) -> io::Result<PathBuf> {
+    let key = normalize(key, dir.as_os_str().is_empty(), parent_keys)?;
+    // Leading `..` components remove the names ending the directory, so that
+    // the path of an entry is the same whatever its key.
+    let (mut dir, mut rest) = (dir.to_owned(), key.components());
+    while rest.clone().next() == Some(Component::ParentDir)
+        && matches!(dir.components().next_back(), Some(Component::Normal(_)))
+    {
+        rest.next();
+        dir.pop();
+    }
+    Ok(dir.join(rest.as_path()))
+}
+
+/// Result of the check of a string literal key of the macros, matched by
+/// them to fail to compile with a message naming the key, see [`key_check`].
+#[doc(hidden)]
+pub enum KeyCheck {
+    Valid,
+    Backslash,
+    Invalid(InvalidKeyReason),
+}
+
+/// Check a key at compile time for the string literal keys of the macros,
+/// see [`entry_path`].
+///
+/// This mirrors [`normalize`] on the bytes of the key, with the separators
+/// and prefixes of the target platform. Backslashes are rejected on every
+/// platform, as they would be separators on Windows but part of the name
+/// elsewhere, except in keys starting with a Windows prefix such as `C:`
+/// which only exist on Windows anyway.
+#[doc(hidden)]
+pub const fn key_check(bytes: &[u8], is_root: bool) -> KeyCheck {
+    if !has_prefix(bytes) && has_backslash(bytes) {
+        return KeyCheck::Backslash;
+    }
+    let absolute = (!bytes.is_empty() && is_separator(bytes[0])) || has_prefix(bytes);
+    if absolute && !is_root {
+        return KeyCheck::Invalid(InvalidKeyReason::Absolute);
+    }
+    // Number of components of the normalized key, and of its trailing
+    // normal components which `..` components can remove.
+    let (mut len, mut depth) = (absolute as usize, 0usize);
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        while i < bytes.len() && !is_separator(bytes[i]) {
+            i += 1;
+        }
+        let name_len = i - start;
+        if name_len == 2 && bytes[start] == b'.' && bytes[start + 1] == b'.' {
+            if depth > 0 {
+                len -= 1;
+                depth -= 1;
+            } else if is_root {
+                len += 1;
+            } else {
+                return KeyCheck::Invalid(InvalidKeyReason::ParentDir);
+            }
+        } else if name_len > 0 && !(name_len == 1 && bytes[start] == b'.') {
+            len += 1;
+            depth += 1;
+        }
+        i += 1;
+    }
+    if len == 0 {
+        return KeyCheck::Invalid(InvalidKeyReason::Empty);
+    }
+    KeyCheck::Valid
+}
+
+const fn is_separator(byte: u8) -> bool {
+    byte == b'/' || (cfg!(windows) && byte == b'\\')
+}
+
+const fn has_prefix(bytes: &[u8]) -> bool {
+    cfg!(windows) && bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic()
+}
+
+const fn has_backslash(bytes: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// A key of the macros: a path, or a byte string on Unix to declare names
+/// which are not valid UTF-8. String and byte string literal keys are
+/// checked at compile time from their bytes.
+#[doc(hidden)]
+pub struct Key<T>(pub T);
+
+impl Key<&'static str> {
+    pub const fn bytes(&self) -> &'static [u8] {
+        self.0.as_bytes()
+    }
+}
+
+#[cfg(unix)]
+impl<const N: usize> Key<&'static [u8; N]> {
+    pub const fn bytes(&self) -> &'static [u8] {
+        self.0
+    }
+}
+
+#[cfg(unix)]
+impl<const N: usize> Key<&[u8; N]> {
+    pub fn into_path(self) -> PathBuf {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(self.0))
+    }
+}
+
+/// Conversion of the keys of the macros into paths, see [`Key`].
+#[doc(hidden)]
+pub trait IntoKeyPath {
+    fn into_path(self) -> PathBuf;
+}
+
+impl<T: AsRef<Path>> IntoKeyPath for Key<T> {
+    fn into_path(self) -> PathBuf {
+        self.0.as_ref().to_owned()
+    }
+}
+
+/// Whether `key` is declared more than once among the string literal keys
+/// of a map, checked at compile time by the macros. Keys resolving to the
+/// same path in other ways are rejected when the tree is created.
+#[doc(hidden)]
+pub const fn is_duplicate_key(keys: &[&[u8]], key: &[u8]) -> bool {
+    let (mut i, mut count) = (0, 0);
+    while i < keys.len() {
+        if is_same_key(keys[i], key) {
+            count += 1;
+        }
+        i += 1;
+    }
+    count > 1
+}
+
+const fn is_same_key(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Paths of the entries of a spec, which must be declared once.
+#[derive(Debug)]
+pub(crate) struct Declared {
+    paths: HashSet<PathBuf>,
+    /// Lowercase paths, when paths differing only by case are rejected.
+    folded: Option<HashSet<String>>,
+}
+
+impl Declared {
+    /// Paths which must be declared once, and must differ by more than case
+    /// unless `case_collisions` are allowed.
+    pub(crate) fn new(case_collisions: bool) -> Declared {
+        Declared {
+            paths: HashSet::new(),
+            folded: if case_collisions {
+                None
+            } else {
+                Some(HashSet::new())
+            },
+        }
+    }
+
+    /// Record the path of the entry declared at `key`, failing with an
+    /// [`InvalidKey`] error if another entry was declared at the same path,
+    /// or at the same path except for case.
+    pub(crate) fn declare(&mut self, key: &Path, path: &Path) -> io::Result<()> {
+        let invalid = |reason| -> io::Error {
+            InvalidKey {
+                key: key.to_owned(),
+                reason,
+            }
+            .into()
+        };
+        if !self.paths.insert(path.to_owned()) {
+            return Err(invalid(InvalidKeyReason::Duplicate));
+        }
+        if let Some(folded) = &mut self.folded {
+            if !folded.insert(path.to_string_lossy().to_lowercase()) {
+                return Err(invalid(InvalidKeyReason::CaseCollision));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Normalize a key which must be a relative path, see [`entry_path`].
+pub(crate) fn relative_key(key: &Path) -> io::Result<PathBuf> {
+    normalize(key, false, false)
+}
+
+fn normalize(key: &Path, is_root: bool, parent_keys: bool) -> io::Result<PathBuf> {
+    let invalid = |reason| InvalidKey {
+        key: key.to_owned(),
+        reason,
+    };
+
+    let mut normalized = PathBuf::new();
+    let mut depth = 0usize;
+    for component in key.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir if is_root => {
+                normalized.push(component);
+            }
+            Component::Prefix(_) | Component::RootDir => {
+                return Err(invalid(InvalidKeyReason::Absolute).into());
+            }
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => {
+                normalized.pop();
+                depth -= 1;
+            }
+            Component::ParentDir if is_root || parent_keys => {
+                normalized.push(component);
+            }
+            Component::ParentDir => {
+                return Err(invalid(InvalidKeyReason::ParentDir).into());
+            }
+            Component::Normal(name) => {
+                normalized.push(name);
+                depth += 1;
+            }
+        }
+    }
+
+    if normalized.as_os_str().is_empty() {
+        return Err(invalid(InvalidKeyReason::Empty).into());
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(result: io::Result<PathBuf>) -> InvalidKeyReason {
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        err.get_ref()
+            .and_then(|err| err.downcast_ref::<InvalidKey>())
+            .unwrap()
+            .reason()
+    }
+
+    #[test]
+    fn normalize_keys() {
+        let dir = Path::new("dir");
+        assert_eq!(entry_path(dir, "a").unwrap(), Path::new("dir/a"));
+        assert_eq!(entry_path(dir, "./a/b/").unwrap(), Path::new("dir/a/b"));
+        assert_eq!(entry_path(dir, "a/../b").unwrap(), Path::new("dir/b"));
+        assert_eq!(
+            entry_path(dir, PathBuf::from("a").join("b")).unwrap(),
+            Path::new("dir/a/b")
+        );
+    }
+
+    #[test]
+    fn invalid_keys() {
+        let dir = Path::new("dir");
+        assert_eq!(reason(entry_path(dir, "")), InvalidKeyReason::Empty);
+        assert_eq!(reason(entry_path(dir, "a/..")), InvalidKeyReason::Empty);
+        assert_eq!(reason(entry_path(dir, "/etc")), InvalidKeyReason::Absolute);
+        assert_eq!(reason(entry_path(dir, "..")), InvalidKeyReason::ParentDir);
+        assert_eq!(
+            reason(entry_path(dir, "a/../../b")),
+            InvalidKeyReason::ParentDir
+        );
+    }
+
+    #[test]
+    fn root_keys() {
+        let root = PathBuf::default();
+        assert_eq!(entry_path(&root, "/tmp/a").unwrap(), Path::new("/tmp/a"));
+        assert_eq!(entry_path(&root, "../a").unwrap(), Path::new("../a"));
+        assert_eq!(reason(entry_path(&root, ".")), InvalidKeyReason::Empty);
+    }
+
+    #[test]
+    fn parent_keys() {
+        let dir = Path::new("root/dir");
+        let resolve = |key| resolve_key(dir, Path::new(key), true).unwrap();
+        assert_eq!(resolve("../a"), Path::new("root/a"));
+        assert_eq!(resolve("a/../../b"), Path::new("root/b"));
+        assert_eq!(resolve("../../../a"), Path::new("../a"));
+        assert_eq!(
+            reason(resolve_key(dir, Path::new("/a"), true)),
+            InvalidKeyReason::Absolute
+        );
+    }
+
+    #[test]
+    fn literal_keys() {
+        let keys = [
+            "a",
+            "./a/b/",
+            "a/../b",
+            "a//b",
+            "",
+            ".",
+            "a/..",
+            "..",
+            "../a",
+            "a/../../b",
+            "/etc",
+            "/",
+            "/..",
+            "./../a",
+            "a/./..",
+        ];
+        for key in keys.iter() {
+            for (dir, is_root) in [(Path::new(""), true), (Path::new("dir"), false)].iter() {
+                assert_eq!(
+                    matches!(key_check(key.as_bytes(), *is_root), KeyCheck::Valid),
+                    entry_path(dir, key).is_ok(),
+                    "key {:?} at root: {}",
+                    key,
+                    is_root
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn literal_keys_with_backslashes() {
+        for key in ["a\\b", "a/b\\", "..\\a", "\\etc"].iter() {
+            for is_root in [true, false] {
+                let check = key_check(key.as_bytes(), is_root);
+                assert!(matches!(check, KeyCheck::Backslash), "key {:?}", key);
+            }
+        }
+    }
+}
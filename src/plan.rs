@@ -0,0 +1,237 @@
+use std::fmt;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::path::Declared;
+use crate::{entry_path, Content, Entry, EntryKind, SkipReason, Tree};
+
+/// An operation performed when creating a spec, see [`Tree::plan`].
+///
+/// Paths are joined to the directory the tree would be created in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Create a directory and its missing parents.
+    CreateDir { path: PathBuf },
+    /// Write a file, with its size if known before it is created, see
+    /// [`Stats::unsized_files`](crate::Stats::unsized_files).
+    WriteFile { path: PathBuf, size: Option<u64> },
+    /// Copy an existing file.
+    CopyFile { from: PathBuf, to: PathBuf },
+    /// Copy the entries of an existing directory, which are not read.
+    CopyDir { from: PathBuf, to: PathBuf },
+    /// Create a symbolic link.
+    Symlink { path: PathBuf, target: PathBuf },
+    /// Create a directory junction, or a symbolic link on the platforms other
+    /// than Windows.
+    Junction { path: PathBuf, target: PathBuf },
+    /// Create a hard link to another file of the spec.
+    HardLink { path: PathBuf, original: PathBuf },
+    /// Create a FIFO or a socket.
+    Special { path: PathBuf, kind: EntryKind },
+    /// Leave out an entry declared with `false` or `null`.
+    Skip { path: PathBuf, reason: SkipReason },
+}
+
+impl Operation {
+    /// Path of the entry the operation creates.
+    pub fn path(&self) -> &Path {
+        match self {
+            Operation::CreateDir { path }
+            | Operation::WriteFile { path, .. }
+            | Operation::Symlink { path, .. }
+            | Operation::Junction { path, .. }
+            | Operation::HardLink { path, .. }
+            | Operation::Special { path, .. }
+            | Operation::Skip { path, .. } => path,
+            Operation::CopyFile { to, .. } | Operation::CopyDir { to, .. } => to,
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::CreateDir { path } => write!(f, "mkdir {}", path.display()),
+            Operation::WriteFile {
+                path,
+                size: Some(size),
+            } => write!(f, "write {} ({} bytes)", path.display(), size),
+            Operation::WriteFile { path, size: None } => write!(f, "write {}", path.display()),
+            Operation::CopyFile { from, to } => {
+                write!(f, "copy {} -> {}", from.display(), to.display())
+            }
+            Operation::CopyDir { from, to } => {
+                write!(f, "copy dir {} -> {}", from.display(), to.display())
+            }
+            Operation::Symlink { path, target } => {
+                write!(f, "symlink {} -> {}", path.display(), target.display())
+            }
+            Operation::Junction { path, target } => {
+                write!(f, "junction {} -> {}", path.display(), target.display())
+            }
+            Operation::HardLink { path, original } => {
+                write!(f, "hard link {} -> {}", path.display(), original.display())
+            }
+            Operation::Special { path, kind } => write!(f, "{} {}", kind, path.display()),
+            Operation::Skip { path, .. } => write!(f, "skip {}", path.display()),
+        }
+    }
+}
+
+/// The operations creating a spec would perform, in order, see
+/// [`Tree::plan`].
+///
+/// The [`Display`](fmt::Display) implementation lists one operation per
+/// line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    operations: Vec<Operation>,
+}
+
+impl Plan {
+    /// All the operations, in order.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Total size in bytes of the files whose size is known.
+    pub fn bytes(&self) -> u64 {
+        self.operations
+            .iter()
+            .filter_map(|operation| match operation {
+                Operation::WriteFile { size, .. } => *size,
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// Number of operations.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Whether nothing would be created.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for operation in &self.operations {
+            writeln!(f, "{}", operation)?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoIterator for Plan {
+    type Item = Operation;
+    type IntoIter = std::vec::IntoIter<Operation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.operations.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Plan {
+    type Item = &'a Operation;
+    type IntoIter = std::slice::Iter<'a, Operation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.operations.iter()
+    }
+}
+
+impl Tree {
+    /// The operations creating the tree within `dir` would perform, in order,
+    /// without touching the filesystem.
+    ///
+    /// Keys are validated like on creation, but the conflict policy is not
+    /// applied since existing entries are not looked up.
+    ///
+    /// ```
+    /// use macro_files::Tree;
+    ///
+    /// let mut tree = Tree::new();
+    /// tree.push("docs/README.md", "# Project");
+    ///
+    /// let plan = tree.plan("project").unwrap();
+    /// assert_eq!(plan.to_string(), "write project/docs/README.md (9 bytes)\n");
+    /// assert_eq!(plan.bytes(), 9);
+    /// ```
+    pub fn plan<P: AsRef<Path>>(&self, dir: P) -> Result<Plan> {
+        let mut plan = Plan::default();
+        let mut declared = Declared::new(false);
+        plan_entries(
+            self,
+            dir.as_ref(),
+            dir.as_ref(),
+            &mut declared,
+            &mut plan.operations,
+        )?;
+        Ok(plan)
+    }
+}
+
+fn plan_entries(
+    tree: &Tree,
+    root: &Path,
+    dir: &Path,
+    declared: &mut Declared,
+    operations: &mut Vec<Operation>,
+) -> Result<()> {
+    for (key, entry) in tree.entries() {
+        let declared_path = tree.key_path(dir, key).unwrap_or_else(|_| dir.join(key));
+        declared.declare(key, &declared_path)?;
+        if let Entry::Skip(reason) = entry {
+            let path = declared_path;
+            operations.push(Operation::Skip {
+                path,
+                reason: *reason,
+            });
+            continue;
+        }
+        let path = tree.key_path(dir, key)?;
+        match entry {
+            Entry::Dir(tree) => {
+                operations.push(Operation::CreateDir { path: path.clone() });
+                plan_entries(tree, root, &path, declared, operations)?;
+            }
+            Entry::File(file) => operations.push(match file.content() {
+                Content::CopyFrom(source) => Operation::CopyFile {
+                    from: source.clone(),
+                    to: path,
+                },
+                Content::Symlink(target) => Operation::Symlink {
+                    path,
+                    target: target.clone(),
+                },
+                Content::Junction(target) => Operation::Junction {
+                    path,
+                    target: target.clone(),
+                },
+                Content::HardLink(original) => Operation::HardLink {
+                    path,
+                    original: entry_path(root, original)?,
+                },
+                _ if file.is_special() => Operation::Special {
+                    path,
+                    kind: file.kind(),
+                },
+                _ => Operation::WriteFile {
+                    path,
+                    size: file.len(),
+                },
+            }),
+            Entry::CopyDir(source) => operations.push(Operation::CopyDir {
+                from: source.clone(),
+                to: path,
+            }),
+            Entry::Keep => operations.push(Operation::CreateDir { path }),
+            Entry::Skip(_) => {}
+        }
+    }
+    Ok(())
+}
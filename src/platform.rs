@@ -0,0 +1,404 @@
+//! Filesystem primitives missing from the standard library, each returning
+//! an [`ErrorKind::Other`] error on the platforms not supporting it.
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+#[cfg(unix)]
+fn c_path(path: &Path) -> Result<std::ffi::CString> {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| Error::new(ErrorKind::InvalidInput, err))
+}
+
+#[cfg(unix)]
+fn check(result: i32) -> Result<()> {
+    if result == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+fn unsupported(operation: &str) -> Error {
+    Error::new(
+        ErrorKind::Other,
+        format!("{} is not supported on this platform", operation),
+    )
+}
+
+/// The system temporary directory. WASI has none, it is the `TMPDIR`
+/// environment variable or else `/tmp`, which must be within a preopened
+/// directory.
+#[cfg(target_os = "wasi")]
+pub(crate) fn temp_dir() -> std::path::PathBuf {
+    match std::env::var_os("TMPDIR") {
+        Some(dir) if !dir.is_empty() => dir.into(),
+        _ => "/tmp".into(),
+    }
+}
+
+/// The system temporary directory.
+#[cfg(not(target_os = "wasi"))]
+pub(crate) fn temp_dir() -> std::path::PathBuf {
+    std::env::temp_dir()
+}
+
+/// The shared memory directory `/dev/shm`, a tmpfs, when it can be written.
+#[cfg(all(target_os = "linux", feature = "tempfile"))]
+pub(crate) fn ram_dir() -> Option<std::path::PathBuf> {
+    let dir = Path::new("/dev/shm");
+    let path = c_path(dir).ok()?;
+    let writable = unsafe { libc::access(path.as_ptr(), libc::W_OK | libc::X_OK) } == 0;
+    (writable && dir.is_dir()).then(|| dir.to_owned())
+}
+
+/// No memory-backed directory is known on other platforms.
+#[cfg(all(not(target_os = "linux"), feature = "tempfile"))]
+pub(crate) fn ram_dir() -> Option<std::path::PathBuf> {
+    None
+}
+
+/// Identifier of the current process, `0` on WASI which has none.
+pub(crate) fn process_id() -> u32 {
+    #[cfg(target_os = "wasi")]
+    return 0;
+    #[cfg(not(target_os = "wasi"))]
+    std::process::id()
+}
+
+/// Create a named pipe at `path` with the given permission bits.
+#[cfg(unix)]
+pub(crate) fn mkfifo(path: &Path, mode: u32) -> Result<()> {
+    let path = c_path(path)?;
+    check(unsafe { libc::mkfifo(path.as_ptr(), mode as libc::mode_t) })
+}
+
+/// Create a named pipe at `path` with the given permission bits.
+#[cfg(not(unix))]
+pub(crate) fn mkfifo(_path: &Path, _mode: u32) -> Result<()> {
+    Err(unsupported("creating FIFOs"))
+}
+
+/// Change the owner and group of the entry at `path`, without following
+/// symlinks.
+#[cfg(all(feature = "unix", unix))]
+pub(crate) fn lchown(path: &Path, uid: u32, gid: u32) -> Result<()> {
+    let path = c_path(path)?;
+    check(unsafe { libc::lchown(path.as_ptr(), uid as libc::uid_t, gid as libc::gid_t) })
+}
+
+/// Set the permissions to `0o755` for directories or `0o644` for files,
+/// whatever the umask, dropping the setuid, setgid and sticky bits.
+#[cfg(unix)]
+pub(crate) fn set_exact_mode(permissions: &mut std::fs::Permissions, is_dir: bool) {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = if is_dir { 0o755 } else { 0o644 };
+    permissions.set_mode(permissions.mode() & !0o7777 | mode);
+}
+
+/// Set the permission bits to `mode`, keeping the file type bits.
+#[cfg(unix)]
+pub(crate) fn set_mode(permissions: &mut std::fs::Permissions, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    permissions.set_mode(permissions.mode() & !0o7777 | mode & 0o7777);
+}
+
+/// Add the execute permissions matching the read permissions, `0o644`
+/// becoming `0o755`.
+#[cfg(unix)]
+pub(crate) fn set_executable(permissions: &mut std::fs::Permissions) {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = permissions.mode();
+    permissions.set_mode(mode | (mode & 0o444) >> 2);
+}
+
+/// Set the read-only, hidden and system attributes of the file at `path`,
+/// keeping its other attributes.
+#[cfg(windows)]
+pub(crate) fn set_windows_attributes(path: &Path, attributes: crate::FileAttributes) -> Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_READONLY: u32 = 0x1;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+    const FILE_ATTRIBUTE_NORMAL: u32 = 0x80;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetFileAttributesW(file_name: *const u16, attributes: u32) -> i32;
+    }
+
+    let mut flags = std::fs::metadata(path)?.file_attributes() & !FILE_ATTRIBUTE_NORMAL;
+    for (set, flag) in [
+        (attributes.readonly, FILE_ATTRIBUTE_READONLY),
+        (attributes.hidden, FILE_ATTRIBUTE_HIDDEN),
+        (attributes.system, FILE_ATTRIBUTE_SYSTEM),
+    ] {
+        if set {
+            flags |= flag;
+        } else {
+            flags &= !flag;
+        }
+    }
+    if flags == 0 {
+        flags = FILE_ATTRIBUTE_NORMAL;
+    }
+    let path = extended_length(path)?;
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    if unsafe { SetFileAttributesW(path.as_ptr(), flags) } == 0 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// `path` with the `\\?\` extended-length prefix when it is longer than
+/// `MAX_PATH`, for the calls to the Windows API which, unlike the standard
+/// library, do not add it.
+#[cfg(windows)]
+fn extended_length(path: &Path) -> Result<std::borrow::Cow<'_, Path>> {
+    use std::borrow::Cow;
+
+    const MAX_PATH: usize = 260;
+
+    if path.as_os_str().len() < MAX_PATH {
+        return Ok(Cow::Borrowed(path));
+    }
+    Ok(verbatim(path)?.map_or(Cow::Borrowed(path), Cow::Owned))
+}
+
+/// `path` made absolute, normalized and given the `\\?\` prefix, as
+/// prefixed paths are not normalized by Windows. `None` when it is already
+/// prefixed or a device path.
+#[cfg(windows)]
+fn verbatim(path: &Path) -> Result<Option<std::path::PathBuf>> {
+    use std::ffi::OsString;
+    use std::path::{Component, PathBuf, Prefix};
+
+    let absolute = if path.is_absolute() {
+        path.to_owned()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    let mut verbatim = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Disk(letter) => {
+                    verbatim.push(format!(r"\\?\{}:", letter as char));
+                }
+                Prefix::UNC(server, share) => {
+                    let mut unc = OsString::from(r"\\?\UNC\");
+                    unc.push(server);
+                    unc.push(r"\");
+                    unc.push(share);
+                    verbatim.push(unc);
+                }
+                _ => return Ok(None),
+            },
+            Component::RootDir => verbatim.push(r"\"),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                verbatim.pop();
+            }
+            Component::Normal(name) => verbatim.push(name),
+        }
+    }
+    Ok(Some(verbatim))
+}
+
+/// Create a directory junction at `path` pointing to the directory `target`,
+/// resolved relative to the directory of `path` when relative. Unlike
+/// symlinks, junctions do not require any privilege.
+#[cfg(windows)]
+pub(crate) fn create_junction(target: &Path, path: &Path) -> Result<()> {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+
+    const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x0020_0000;
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    const FSCTL_SET_REPARSE_POINT: u32 = 0x0009_00A4;
+    const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+    const MAXIMUM_REPARSE_DATA_BUFFER_SIZE: usize = 16 * 1024;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn DeviceIoControl(
+            device: *mut c_void,
+            control_code: u32,
+            in_buffer: *const c_void,
+            in_buffer_size: u32,
+            out_buffer: *mut c_void,
+            out_buffer_size: u32,
+            bytes_returned: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+    }
+
+    let resolved = path.parent().unwrap_or_else(|| Path::new("")).join(target);
+    let resolved = verbatim(&resolved)?.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("junction target {:?} is not a disk or network path", target),
+        )
+    })?;
+    // The target as the NT path `\??\C:\...` the junction holds, and as the
+    // path `C:\...` displayed for it, without the `\\?\` prefix.
+    let resolved: Vec<u16> = resolved.as_os_str().encode_wide().collect();
+    let unprefixed = &resolved[4..];
+    let substitute: Vec<u16> = r"\??\"
+        .encode_utf16()
+        .chain(unprefixed.iter().copied())
+        .collect();
+    let unc: Vec<u16> = r"UNC\".encode_utf16().collect();
+    let print: Vec<u16> = match unprefixed.strip_prefix(unc.as_slice()) {
+        Some(share) => r"\\".encode_utf16().chain(share.iter().copied()).collect(),
+        None => unprefixed.to_vec(),
+    };
+    let path_len = (substitute.len() + print.len() + 2) * 2;
+    if 16 + path_len > MAXIMUM_REPARSE_DATA_BUFFER_SIZE {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("junction target {:?} is too long", target),
+        ));
+    }
+
+    // A `REPARSE_DATA_BUFFER` with the names at the end, each terminated by
+    // a null character.
+    let (substitute_len, print_len) = (substitute.len() as u16 * 2, print.len() as u16 * 2);
+    let mut buffer = Vec::with_capacity(16 + path_len);
+    buffer.extend(IO_REPARSE_TAG_MOUNT_POINT.to_le_bytes());
+    for field in [
+        8 + path_len as u16,
+        0,
+        0,
+        substitute_len,
+        substitute_len + 2,
+        print_len,
+    ] {
+        buffer.extend(field.to_le_bytes());
+    }
+    for name in [substitute, print] {
+        buffer.extend(name.into_iter().chain(Some(0)).flat_map(u16::to_le_bytes));
+    }
+
+    std::fs::create_dir(path)?;
+    let result = std::fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_OPEN_REPARSE_POINT | FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .and_then(|dir| {
+            let mut returned = 0;
+            let result = unsafe {
+                DeviceIoControl(
+                    dir.as_raw_handle() as *mut c_void,
+                    FSCTL_SET_REPARSE_POINT,
+                    buffer.as_ptr() as *const c_void,
+                    buffer.len() as u32,
+                    std::ptr::null_mut(),
+                    0,
+                    &mut returned,
+                    std::ptr::null_mut(),
+                )
+            };
+            if result == 0 {
+                Err(Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        });
+    if result.is_err() {
+        let _ = std::fs::remove_dir(path);
+    }
+    result
+}
+
+/// Set the extended attribute `name` of the entry at `path`.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+))]
+pub(crate) fn set_xattr(path: &Path, name: &str, value: &[u8]) -> Result<()> {
+    let path = c_path(path)?;
+    let name =
+        std::ffi::CString::new(name).map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+    let value_ptr = value.as_ptr() as *const libc::c_void;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let result = unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value_ptr, value.len(), 0) };
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    let result =
+        unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value_ptr, value.len(), 0, 0) };
+    check(result)
+}
+
+/// Set the extended attribute `name` of the entry at `path`.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+)))]
+pub(crate) fn set_xattr(_path: &Path, _name: &str, _value: &[u8]) -> Result<()> {
+    Err(unsupported("extended attributes"))
+}
+
+/// `_IOW(0x94, 9, int)`, whose direction bits depend on the architecture.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const FICLONE: u32 = if cfg!(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc",
+    target_arch = "sparc64"
+)) {
+    0x8004_9409
+} else {
+    0x4004_9409
+};
+
+/// Create the file `to` sharing the data blocks of the file `from`
+/// (copy-on-write clone), failing when the filesystem does not support it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn reflink(from: &Path, to: &Path) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let source = std::fs::File::open(from)?;
+    let target = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(to)?;
+    let result =
+        check(unsafe { libc::ioctl(target.as_raw_fd(), FICLONE as _, source.as_raw_fd()) });
+    if result.is_err() {
+        drop(target);
+        let _ = std::fs::remove_file(to);
+    }
+    result
+}
+
+/// Create the file `to` sharing the data blocks of the file `from`
+/// (copy-on-write clone), failing when the filesystem does not support it.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub(crate) fn reflink(from: &Path, to: &Path) -> Result<()> {
+    let (from, to) = (c_path(from)?, c_path(to)?);
+    check(unsafe { libc::clonefile(from.as_ptr(), to.as_ptr(), 0) })
+}
+
+/// Create the file `to` sharing the data blocks of the file `from`
+/// (copy-on-write clone), failing when the filesystem does not support it.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+)))]
+pub(crate) fn reflink(_from: &Path, _to: &Path) -> Result<()> {
+    Err(unsupported("reflinks"))
+}
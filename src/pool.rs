@@ -0,0 +1,120 @@
+use std::io::Result;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use tempfile::{Builder, TempDir};
+
+use crate::temp::temp_dir;
+use crate::Tree;
+
+/// A pool of temporary directories created from a spec, handed out to tests
+/// running in parallel and synced back to the spec once they are done with
+/// them, see [`Tree::sync_in`].
+///
+/// Syncing a directory only rewrites what the test changed, which amortizes
+/// the creation of large fixtures over the tests using them.
+///
+/// ```
+/// use macro_files::TempTreePool;
+///
+/// let fixture = macro_files::tree!({ "config.toml": "debug = false" });
+/// let pool = TempTreePool::new(fixture, 2).unwrap();
+///
+/// {
+///     let dir = pool.get().unwrap();
+///     std::fs::write(dir.path().join("config.toml"), "debug = true").unwrap();
+///     std::fs::write(dir.path().join("output.log"), "").unwrap();
+/// }
+///
+/// let dir = pool.get().unwrap();
+/// let config = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+/// assert_eq!(config, "debug = false");
+/// assert!(!dir.path().join("output.log").exists());
+/// ```
+#[derive(Debug)]
+pub struct TempTreePool {
+    tree: Tree,
+    idle: Mutex<Vec<TempDir>>,
+}
+
+impl TempTreePool {
+    /// A pool of `size` temporary directories of the [`temp_root`](crate::temp_root)
+    /// created from `tree`.
+    pub fn new(tree: Tree, size: usize) -> Result<TempTreePool> {
+        let pool = TempTreePool {
+            tree,
+            idle: Mutex::new(Vec::with_capacity(size)),
+        };
+        for _ in 0..size {
+            let dir = pool.create()?;
+            pool.lock().push(dir);
+        }
+        Ok(pool)
+    }
+
+    /// The spec of the directories.
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Number of directories waiting to be handed out.
+    pub fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Take a directory of the pool, or a new one when they are all in use,
+    /// which returns to the pool once dropped.
+    pub fn get(&self) -> Result<PooledDir<'_>> {
+        let dir = match self.lock().pop() {
+            Some(dir) => dir,
+            None => self.create()?,
+        };
+        Ok(PooledDir {
+            pool: self,
+            dir: Some(dir),
+        })
+    }
+
+    fn create(&self) -> Result<TempDir> {
+        let dir = temp_dir(&Builder::new(), None)?;
+        self.tree.create_in(dir.path())?;
+        Ok(dir)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<TempDir>> {
+        self.idle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A directory taken from a [`TempTreePool`], synced back to the spec of the
+/// pool and returned to it when dropped, or removed if it cannot be synced.
+#[derive(Debug)]
+pub struct PooledDir<'a> {
+    pool: &'a TempTreePool,
+    dir: Option<TempDir>,
+}
+
+impl PooledDir<'_> {
+    /// Path of the directory.
+    pub fn path(&self) -> &Path {
+        self.dir.as_ref().map_or(Path::new(""), TempDir::path)
+    }
+}
+
+impl AsRef<Path> for PooledDir<'_> {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for PooledDir<'_> {
+    fn drop(&mut self) {
+        if let Some(dir) = self.dir.take() {
+            if self.pool.tree.sync_in(dir.path()).is_ok() {
+                self.pool.lock().push(dir);
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Minimal project layouts to splice into specs, for tests of code
+//! detecting or walking projects.
+//!
+//! Each preset is a [`Tree`] declaring the fewest files the tools recognize,
+//! to use as the value of a directory or to spread with `..preset` and
+//! complete with more entries. Names are written as is in the manifests and
+//! must be valid package names.
+//!
+//! ```
+//! use macro_files::presets;
+//!
+//! let dir = macro_files::create_temp!({
+//!     ..presets::cargo_workspace(["app", "core"]),
+//!     "app": {
+//!         ..presets::cargo_bin("app"),
+//!         "build.rs": "fn main() {}\n",
+//!     },
+//!     "core": presets::cargo_lib("core"),
+//!     "web": presets::npm_package("web"),
+//!     ".git": presets::git_repo(),
+//! })
+//! .unwrap();
+//!
+//! assert!(dir.path().join("app/src/main.rs").is_file());
+//! assert!(dir.path().join("core/src/lib.rs").is_file());
+//! assert!(dir.path().join("web/package.json").is_file());
+//! assert!(dir.path().join(".git/HEAD").is_file());
+//! ```
+
+use crate::Tree;
+
+/// A Cargo package with a binary target, as created by `cargo new`.
+pub fn cargo_bin(name: &str) -> Tree {
+    crate::tree!({
+        "Cargo.toml": cargo_manifest(name),
+        "src/main.rs": "fn main() {\n    println!(\"Hello, world!\");\n}\n",
+    })
+}
+
+/// A Cargo package with a library target, as created by `cargo new --lib`
+/// without its example function.
+pub fn cargo_lib(name: &str) -> Tree {
+    crate::tree!({
+        "Cargo.toml": cargo_manifest(name),
+        "src/lib.rs": "",
+    })
+}
+
+/// The manifest of a Cargo workspace with the given members, which are not
+/// created, to spread next to them.
+pub fn cargo_workspace<I, S>(members: I) -> Tree
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let members: Vec<_> = members
+        .into_iter()
+        .map(|member| format!("\"{}\"", member.as_ref()))
+        .collect();
+    crate::tree!({
+        "Cargo.toml": format!(
+            "[workspace]\nresolver = \"2\"\nmembers = [{}]\n",
+            members.join(", ")
+        ),
+    })
+}
+
+/// An npm package with its `package.json` manifest and an empty
+/// `index.js` entry point.
+pub fn npm_package(name: &str) -> Tree {
+    crate::tree!({
+        "package.json": format!(
+            "{{\n  \"name\": \"{}\",\n  \"version\": \"1.0.0\",\n  \"main\": \"index.js\"\n}}\n",
+            name
+        ),
+        "index.js": "",
+    })
+}
+
+/// The skeleton of an empty git repository, to use as a `.git` directory:
+/// git recognizes it as a repository on the `main` branch without any
+/// commit.
+///
+/// Nothing is run, unlike the `git { ... }` directories of the `git`
+/// feature, so the `git` command is not needed.
+pub fn git_repo() -> Tree {
+    crate::tree!({
+        "HEAD": "ref: refs/heads/main\n",
+        "config": "[core]\n\trepositoryformatversion = 0\n\tfilemode = true\n\tbare = false\n",
+        "objects": { "info": {}, "pack": {} },
+        "refs": { "heads": {}, "tags": {} },
+    })
+}
+
+fn cargo_manifest(name: &str) -> String {
+    format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+        name
+    )
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use crate::{Entry, Tree};
+
+/// Parameters of the trees generated by [`proptest`], see the
+/// implementation of [`Arbitrary`] for [`Tree`].
+///
+/// _This requires the feature `proptest`._
+///
+/// The generated trees are valid specs: keys are single names, unique within
+/// their directory, so they can be created with [`Tree::create_in`] in an
+/// empty directory.
+///
+/// ```
+/// use macro_files::proptest::prelude::*;
+/// use macro_files::{Tree, TreeParams};
+///
+/// proptest!(|(tree in any_with::<Tree>(TreeParams::new().depth(2).file_size(16)))| {
+///     let dir = macro_files::tempfile::tempdir().unwrap();
+///     tree.create_in(dir.path()).unwrap();
+///     prop_assert_eq!(Tree::read_from(dir.path()).unwrap(), tree.normalize().unwrap());
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeParams {
+    depth: u32,
+    entries: usize,
+    alphabet: Vec<char>,
+    name_len: usize,
+    file_size: usize,
+}
+
+impl Default for TreeParams {
+    fn default() -> TreeParams {
+        TreeParams {
+            depth: 3,
+            entries: 4,
+            alphabet: "abcdefghijklmnopqrstuvwxyz0123456789_-".chars().collect(),
+            name_len: 8,
+            file_size: 256,
+        }
+    }
+}
+
+impl TreeParams {
+    /// The default parameters: up to 3 levels of directories of up to 4
+    /// entries, named with up to 8 lowercase ASCII letters, digits, `_` and
+    /// `-`, and files of up to 256 bytes.
+    pub fn new() -> TreeParams {
+        TreeParams::default()
+    }
+
+    /// Set the maximum number of nested directory levels.
+    pub fn depth(mut self, depth: u32) -> TreeParams {
+        self.depth = depth;
+        self
+    }
+
+    /// Set the maximum number of entries of a directory.
+    pub fn entries(mut self, entries: usize) -> TreeParams {
+        self.entries = entries;
+        self
+    }
+
+    /// Set the characters the names of the entries are made of. Path
+    /// separators and NUL characters are left out, and names made of dots
+    /// only are never generated. Falls back to `_` when no character is
+    /// left.
+    pub fn alphabet(mut self, alphabet: &str) -> TreeParams {
+        self.alphabet = alphabet
+            .chars()
+            .filter(|c| !matches!(c, '/' | '\\' | '\0'))
+            .collect();
+        self.alphabet.sort_unstable();
+        self.alphabet.dedup();
+        if self.alphabet.is_empty() {
+            self.alphabet.push('_');
+        }
+        self
+    }
+
+    /// Set the maximum length of the names of the entries, at least 1.
+    pub fn name_len(mut self, len: usize) -> TreeParams {
+        self.name_len = len.max(1);
+        self
+    }
+
+    /// Set the maximum size of the files in bytes.
+    pub fn file_size(mut self, size: usize) -> TreeParams {
+        self.file_size = size;
+        self
+    }
+
+    /// Maximum number of nested directory levels.
+    pub fn max_depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Maximum number of entries of a directory.
+    pub fn max_entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Characters the names of the entries are made of.
+    pub fn name_alphabet(&self) -> &[char] {
+        &self.alphabet
+    }
+
+    /// Maximum length of the names of the entries.
+    pub fn max_name_len(&self) -> usize {
+        self.name_len
+    }
+
+    /// Maximum size of the files in bytes.
+    pub fn max_file_size(&self) -> usize {
+        self.file_size
+    }
+}
+
+/// Generate trees of directories and files, see [`TreeParams`].
+impl Arbitrary for Tree {
+    type Parameters = TreeParams;
+    type Strategy = BoxedStrategy<Tree>;
+
+    fn arbitrary_with(params: TreeParams) -> BoxedStrategy<Tree> {
+        let name = vec(select(params.alphabet.clone()), 1..=params.name_len)
+            .prop_map(|name| name.into_iter().collect::<String>())
+            .prop_filter("name made of dots", |name| name.chars().any(|c| c != '.'));
+        let entries = params.entries;
+        let file = vec(any::<u8>(), 0..=params.file_size).prop_map(Entry::file);
+        let entry = file.prop_recursive(params.depth, 64, entries as u32, {
+            let name = name.clone();
+            move |entry| {
+                btree_map(name.clone(), entry, 0..=entries)
+                    .prop_map(|entries| Entry::Dir(tree(entries)))
+            }
+        });
+        btree_map(name, entry, 0..=entries).prop_map(tree).boxed()
+    }
+}
+
+fn tree(entries: BTreeMap<String, Entry>) -> Tree {
+    let mut tree = Tree::new();
+    for (name, entry) in entries {
+        tree.push(name, entry);
+    }
+    tree
+}
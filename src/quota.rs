@@ -0,0 +1,155 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A size limit of a directory shared by cached fixtures, each one being an
+/// entry of the directory, see [`Options::quota`](crate::Options::quota).
+///
+/// The fixtures are evicted in least recently used order, given by their
+/// modification time which is updated each time a tree is created in them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quota {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl Quota {
+    /// A limit of `max_bytes` for the total size of the files within `dir`.
+    pub fn new<P: AsRef<Path>>(dir: P, max_bytes: u64) -> Quota {
+        Quota {
+            dir: dir.as_ref().to_owned(),
+            max_bytes,
+        }
+    }
+
+    /// The directory of the cached fixtures.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The maximum total size of the files within the directory.
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Total size of the files within the directory, symlinks are not
+    /// followed.
+    pub fn usage(&self) -> Result<u64> {
+        match disk_usage(&self.dir) {
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(0),
+            result => result,
+        }
+    }
+
+    /// Evict the least recently used fixtures, `keep` aside, until `needed`
+    /// more bytes fit within the limit. Returns the paths of the evicted
+    /// fixtures.
+    ///
+    /// Fails with an [`ErrorKind::Other`] error, without evicting anything,
+    /// when the bytes cannot fit even once the other fixtures are evicted.
+    pub fn make_room(&self, needed: u64, keep: Option<&Path>) -> Result<Vec<PathBuf>> {
+        let mut fixtures = Vec::new();
+        let mut usage = 0;
+        let mut kept = 0;
+        let entries = match self.dir.read_dir() {
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            entries => entries?,
+        };
+        for entry in entries {
+            let path = entry?.path();
+            let metadata = std::fs::symlink_metadata(&path)?;
+            let size = disk_usage(&path)?;
+            usage += size;
+            if Some(path.as_path()) == keep {
+                kept += size;
+            } else {
+                let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                fixtures.push((used, path, size));
+            }
+        }
+        if kept.saturating_add(needed) > self.max_bytes {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "{} bytes do not fit within the {} bytes quota of {:?}",
+                    needed, self.max_bytes, self.dir
+                ),
+            ));
+        }
+
+        fixtures.sort();
+        let mut evicted = Vec::new();
+        for (_, path, size) in fixtures {
+            if usage.saturating_add(needed) <= self.max_bytes {
+                break;
+            }
+            if std::fs::symlink_metadata(&path)?.is_dir() {
+                std::fs::remove_dir_all(&path)?;
+            } else {
+                std::fs::remove_file(&path)?;
+            }
+            usage -= size;
+            evicted.push(path);
+        }
+        Ok(evicted)
+    }
+
+    /// The fixture of the directory containing `path`, if any.
+    pub(crate) fn fixture(&self, path: &Path) -> Option<PathBuf> {
+        let name = path.strip_prefix(&self.dir).ok()?.components().next()?;
+        Some(self.dir.join(name))
+    }
+
+    /// Evict fixtures to make room for `needed` bytes before creating a tree
+    /// in `dir`.
+    pub(crate) fn before_create(&self, dir: &Path, needed: u64) -> Result<Vec<PathBuf>> {
+        self.make_room(needed, self.fixture(dir).as_deref())
+    }
+
+    /// Mark the fixture containing `dir` as the most recently used one.
+    pub(crate) fn after_create(&self, dir: &Path) -> Result<()> {
+        match self.fixture(dir) {
+            Some(fixture) => crate::set_times(fixture, None, Some(SystemTime::now())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Count `size` more bytes written by a creation which already wrote
+/// `bytes`, failing when they exceed `max_bytes`, see
+/// [`Options::max_bytes`](crate::Options::max_bytes).
+pub(crate) fn charge(bytes: &mut u64, size: u64, max_bytes: Option<u64>) -> Result<()> {
+    if let Some(max_bytes) = max_bytes {
+        let charged = bytes.saturating_add(size);
+        if charged > max_bytes {
+            return Err(budget_exceeded(max_bytes));
+        }
+        *bytes = charged;
+    }
+    Ok(())
+}
+
+/// Error of a creation writing more bytes than its budget, see
+/// [`Options::max_bytes`](crate::Options::max_bytes).
+pub(crate) fn budget_exceeded(max_bytes: u64) -> Error {
+    Error::new(
+        ErrorKind::Other,
+        format!("files exceed the budget of {} bytes", max_bytes),
+    )
+}
+
+fn disk_usage(path: &Path) -> Result<u64> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        });
+    }
+    let mut size = 0;
+    for entry in path.read_dir()? {
+        size += disk_usage(&entry?.path())?;
+    }
+    Ok(size)
+}
@@ -0,0 +1,31 @@
+/// `len` pseudo-random bytes generated from `seed` with SplitMix64, which is
+/// fast and good enough to make the bytes incompressible.
+pub(crate) fn random_bytes(len: u64, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    let mut bytes = Vec::with_capacity(len as usize);
+    while (bytes.len() as u64) < len {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        let remaining = (len - bytes.len() as u64).min(8) as usize;
+        bytes.extend_from_slice(&z.to_le_bytes()[..remaining]);
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        assert_eq!(random_bytes(13, 42), random_bytes(13, 42));
+        assert_ne!(random_bytes(13, 42), random_bytes(13, 43));
+        assert_eq!(random_bytes(13, 42)[..8], random_bytes(8, 42)[..]);
+        assert!(random_bytes(0, 42).is_empty());
+        // First output of SplitMix64 seeded with 0.
+        assert_eq!(random_bytes(8, 0), 0xe220_a839_7b1d_cdafu64.to_le_bytes());
+    }
+}
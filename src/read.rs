@@ -0,0 +1,114 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::{Content, Entry, File, ReadOptions, Symlinks, Tree};
+
+impl Tree {
+    /// Read the directories, files and symlinks within `dir` into a tree, in
+    /// the normal form described in [`Tree::normalize`].
+    ///
+    /// Symlinks are not followed, hard links are read as regular files, FIFOs
+    /// and sockets are read with the `unix` feature and other special files
+    /// fail with an [`ErrorKind::Other`] error.
+    pub fn read_from<P: AsRef<Path>>(dir: P) -> Result<Tree> {
+        Tree::read_with(dir, &ReadOptions::default())
+    }
+
+    /// Read the entries within `dir` into a tree with the given options, see
+    /// [`Tree::read_from`].
+    ///
+    /// ```
+    /// use macro_files::{ReadOptions, Symlinks, Tree};
+    ///
+    /// let dir = macro_files::create_temp!({
+    ///     "README.md": "# Project",
+    ///     "docs/index.md": macro_files::symlink("../README.md"),
+    /// })
+    /// .unwrap();
+    ///
+    /// let options = ReadOptions::new().symlinks(Symlinks::Follow);
+    /// let tree = Tree::read_with(dir.path(), &options).unwrap();
+    ///
+    /// let mut expected = Tree::new();
+    /// expected.push("README.md", "# Project");
+    /// expected.push("docs/index.md", "# Project");
+    /// assert_eq!(tree, expected.normalize().unwrap());
+    /// ```
+    pub fn read_with<P: AsRef<Path>>(dir: P, options: &ReadOptions) -> Result<Tree> {
+        read_dir(dir.as_ref(), Path::new(""), options, &mut Vec::new())
+    }
+}
+
+/// Read the entries within `dir`, at `relative_dir` in the read directory,
+/// `ancestors` being the canonical paths of the directories read through
+/// followed symlinks.
+fn read_dir(
+    dir: &Path,
+    relative_dir: &Path,
+    options: &ReadOptions,
+    ancestors: &mut Vec<PathBuf>,
+) -> Result<Tree> {
+    let follow = options.symlink_policy() == Symlinks::Follow;
+    if follow {
+        let canonical = crate::sandbox::canonicalize(dir)?;
+        if ancestors.contains(&canonical) {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("symlink loop: {:?}", dir),
+            ));
+        }
+        ancestors.push(canonical);
+    }
+    let mut entries = dir.read_dir()?.collect::<Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut tree = Tree::new();
+    for entry in entries {
+        let path = entry.path();
+        let relative_path = relative_dir.join(entry.file_name());
+        if !options.includes(&relative_path) {
+            continue;
+        }
+        let mut file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            match options.symlink_policy() {
+                Symlinks::Follow => file_type = std::fs::metadata(&path)?.file_type(),
+                Symlinks::Skip => continue,
+                Symlinks::Keep => {}
+            }
+        }
+        let entry = if file_type.is_dir() {
+            Entry::Dir(read_dir(&path, &relative_path, options, ancestors)?)
+        } else if file_type.is_file() {
+            Entry::File(File::from(Content::Bytes(std::fs::read(&path)?)))
+        } else if file_type.is_symlink() {
+            Entry::File(File::from(Content::Symlink(std::fs::read_link(&path)?)))
+        } else if let Some(file) = special_file(file_type) {
+            Entry::File(file)
+        } else {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("unsupported file type: {:?}", path),
+            ));
+        };
+        tree.push(path.file_name().unwrap_or_default(), entry);
+    }
+    if follow {
+        ancestors.pop();
+    }
+    Ok(tree)
+}
+
+#[cfg(all(feature = "unix", unix))]
+fn special_file(file_type: std::fs::FileType) -> Option<File> {
+    match crate::EntryKind::from(file_type) {
+        crate::EntryKind::Fifo => Some(crate::fifo()),
+        crate::EntryKind::Socket => Some(crate::unix_socket()),
+        _ => None,
+    }
+}
+
+#[cfg(not(all(feature = "unix", unix)))]
+fn special_file(_file_type: std::fs::FileType) -> Option<File> {
+    None
+}
@@ -0,0 +1,160 @@
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::copy::read_sorted_dir;
+use crate::error::Context;
+use crate::{entry_kind, remove_file, Entry, EntryKind, FsOperation, Tree};
+
+impl Tree {
+    /// Remove the entries of the tree from `dir`, the reverse of
+    /// [`Tree::create_in`], returning the removed paths in removal order.
+    ///
+    /// Files, symlinks and special files declared in the spec are removed,
+    /// then the directories it declares or implies with nested keys once they
+    /// are empty: entries which are not declared are left with their parent
+    /// directories. Missing entries are ignored, and so are entries of
+    /// another kind than declared.
+    ///
+    /// ```
+    /// use macro_files::Tree;
+    ///
+    /// let dir = macro_files::create_temp!({
+    ///     "docs/README.md": "# Documentation",
+    ///     "notes.txt": "kept",
+    /// })
+    /// .unwrap();
+    ///
+    /// let mut tree = Tree::new();
+    /// tree.push("docs/README.md", "# Documentation");
+    /// let removed = tree.remove_in(dir.path()).unwrap();
+    ///
+    /// assert_eq!(removed, [dir.path().join("docs/README.md"), dir.path().join("docs")]);
+    /// assert!(dir.path().join("notes.txt").is_file());
+    /// ```
+    pub fn remove_in<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        remove_entries(self, dir.as_ref(), &mut removed)?;
+        Ok(removed)
+    }
+}
+
+fn remove_entries(tree: &Tree, dir: &Path, removed: &mut Vec<PathBuf>) -> Result<()> {
+    for (key, entry) in tree.entries() {
+        let path = tree.key_path(dir, key)?;
+        match entry {
+            Entry::Dir(tree) => {
+                if entry_kind(&path)? == Some(EntryKind::Dir) {
+                    if tree.declared_readonly() {
+                        make_writable(&path)?;
+                    }
+                    remove_entries(tree, &path, removed)?;
+                    remove_empty_dir(&path, removed)?;
+                }
+            }
+            Entry::File(_) => match entry_kind(&path)? {
+                Some(EntryKind::Dir) | None => {}
+                Some(_) => {
+                    remove_file(&path)?;
+                    removed.push(path.clone());
+                }
+            },
+            Entry::CopyDir(source) => {
+                if entry_kind(&path)? == Some(EntryKind::Dir) {
+                    remove_copied(source, &path, removed)?;
+                    remove_empty_dir(&path, removed)?;
+                }
+            }
+            Entry::Keep => remove_empty_dir(&path, removed)?,
+            Entry::Skip(_) => continue,
+        }
+        // Directories implied by a nested key, absolute keys are only
+        // removed themselves.
+        if key.is_relative() {
+            for ancestor in key.ancestors().skip(1) {
+                if ancestor.as_os_str().is_empty() || ancestor.ends_with("..") {
+                    break;
+                }
+                remove_empty_dir(&tree.key_path(dir, ancestor)?, removed)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Remove the entries copied from the `source` directory into `dir`.
+fn remove_copied(source: &Path, dir: &Path, removed: &mut Vec<PathBuf>) -> Result<()> {
+    for (path, is_dir) in read_sorted_dir(source)? {
+        let target = dir.join(path.file_name().unwrap_or_default());
+        match entry_kind(&target)? {
+            Some(EntryKind::Dir) if is_dir => {
+                remove_copied(&path, &target, removed)?;
+                remove_empty_dir(&target, removed)?;
+            }
+            Some(EntryKind::Dir) | None => {}
+            Some(_) if is_dir => {}
+            Some(_) => {
+                remove_file(&target)?;
+                removed.push(target);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Remove the entry at `path` with its entries if it is a directory.
+pub(crate) fn remove_entry(path: &Path) -> Result<()> {
+    match entry_kind(path)? {
+        Some(EntryKind::Dir) => std::fs::remove_dir_all(path).context(FsOperation::RemoveDir, path),
+        Some(_) => remove_file(path),
+        None => Ok(()),
+    }
+}
+
+/// Remove the entries of the directory `dir` if it exists, returning the
+/// removed paths, see [`Options::clean`](crate::Options::clean).
+pub(crate) fn clean_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    if entry_kind(dir)? != Some(EntryKind::Dir) {
+        return Ok(Vec::new());
+    }
+    let mut removed = Vec::new();
+    for (path, _) in read_sorted_dir(dir)? {
+        remove_entry(&path)?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// Make the read-only directory at `path` writable by its owner again, see
+/// [`Tree::readonly`].
+fn make_writable(path: &Path) -> Result<()> {
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    if !permissions.readonly() {
+        return Ok(());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    std::fs::set_permissions(path, permissions).context(FsOperation::SetFileAttributes, path)
+}
+
+/// Remove the directory at `path` if it exists and is empty.
+fn remove_empty_dir(path: &Path, removed: &mut Vec<PathBuf>) -> Result<()> {
+    if entry_kind(path)? != Some(EntryKind::Dir) {
+        return Ok(());
+    }
+    if path.read_dir()?.next().is_none() {
+        std::fs::remove_dir(path).context(FsOperation::RemoveDir, path)?;
+        removed.push(path.to_owned());
+    }
+    Ok(())
+}
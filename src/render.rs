@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Component, Path};
+
+use crate::{Content, Entry, Tree};
+
+impl Tree {
+    /// Render the tree in the format of `tree(1)`, one entry per line sorted
+    /// by name, without touching the filesystem.
+    ///
+    /// Nested keys are split into directories, directories end with a `/`,
+    /// symlinks show their target and copied directories their source, whose
+    /// entries are not read. Skipped entries are left out.
+    ///
+    /// ```
+    /// let mut tree = macro_files::Tree::new();
+    /// tree.push("docs/index.md", macro_files::symlink("../README.md"));
+    /// tree.push("README.md", "# Project");
+    ///
+    /// assert_eq!(
+    ///     tree.render_tree(),
+    ///     "\
+    /// ├── README.md
+    /// └── docs/
+    ///     └── index.md -> ../README.md
+    /// "
+    /// );
+    /// ```
+    pub fn render_tree(&self) -> String {
+        let mut root = Node::default();
+        root.insert_entries(self);
+        let mut output = String::new();
+        root.render("", &mut output);
+        output
+    }
+}
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    dir: bool,
+    /// Rendered after the name, such as the target of a symlink.
+    suffix: String,
+}
+
+impl Node {
+    fn insert_entries(&mut self, tree: &Tree) {
+        for (key, entry) in tree.entries() {
+            let (dir, suffix) = match entry {
+                Entry::Dir(_) | Entry::Keep => (true, String::new()),
+                Entry::File(file) => match file.content() {
+                    Content::Symlink(target) | Content::Junction(target) => {
+                        (false, format!(" -> {}", target.display()))
+                    }
+                    _ => (false, String::new()),
+                },
+                Entry::CopyDir(source) => (true, format!(" (copy of {})", source.display())),
+                Entry::Skip(_) => continue,
+            };
+            let node = self.node(key);
+            if !dir {
+                node.children.clear();
+            }
+            node.dir = dir;
+            node.suffix = suffix;
+            if let Entry::Dir(tree) = entry {
+                node.insert_entries(tree);
+            }
+        }
+    }
+
+    /// The node of the entry at `key`, creating it and its missing parent
+    /// directories. Keys with other components than names, such as absolute
+    /// paths, are kept whole.
+    fn node(&mut self, key: &Path) -> &mut Node {
+        let names: Option<Vec<_>> = key
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        let names = names.unwrap_or_else(|| vec![key.display().to_string()]);
+        let mut node = self;
+        for name in names {
+            node.dir = true;
+            node = node.children.entry(name).or_default();
+        }
+        node
+    }
+
+    fn render(&self, prefix: &str, output: &mut String) {
+        let mut children = self.children.iter().peekable();
+        while let Some((name, node)) = children.next() {
+            let last = children.peek().is_none();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            let slash = if node.dir { "/" } else { "" };
+            let _ = writeln!(
+                output,
+                "{}{}{}{}{}",
+                prefix, branch, name, slash, node.suffix
+            );
+            node.render(&format!("{}{}", prefix, indent), output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn render_nested_entries() {
+        let tree = crate::create_internal!(@tree {
+            "/tmp/project": {
+                "src/main.rs": "fn main() {}",
+                "target": false,
+                "src": {
+                    "lib.rs": "",
+                },
+                "vendor": crate::copy_dir("tests/fixtures/vendor"),
+                "empty": {},
+            },
+        });
+
+        assert_eq!(
+            tree.render_tree(),
+            "\
+└── /tmp/project/
+    ├── empty/
+    ├── src/
+    │   ├── lib.rs
+    │   └── main.rs
+    └── vendor/ (copy of tests/fixtures/vendor)
+"
+        );
+    }
+}
@@ -0,0 +1,412 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{EntryKind, File};
+
+/// Why an entry of a spec was not created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// Declared with `false`.
+    False,
+    /// Declared with `null`.
+    Null,
+    /// Declared with [`maybe`](crate::maybe) of `None`.
+    None,
+    /// Already existing, see [`Conflict::Skip`](crate::Conflict::Skip).
+    Exists,
+}
+
+/// An entry created by [`Tree::create_in`](crate::Tree::create_in).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedEntry {
+    path: PathBuf,
+    kind: EntryKind,
+    origin: Option<Arc<str>>,
+    stamp: Option<Arc<str>>,
+    size: Option<u64>,
+    #[cfg(feature = "checksum")]
+    sha256: Option<String>,
+}
+
+impl CreatedEntry {
+    /// Path of the entry, joined to the directory the tree is created in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Kind of the entry.
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    /// Origin of the spec fragment the entry was declared in, see
+    /// [`Tree::origin`](crate::Tree::origin).
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// Stamp injected in the contents of the file, see
+    /// [`stamp`](crate::stamp).
+    pub fn stamp(&self) -> Option<&str> {
+        self.stamp.as_deref()
+    }
+
+    /// Size in bytes of the written file, unknown for the files written
+    /// asynchronously from contents whose size is only known once written.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    pub(crate) fn set_size(&mut self, size: u64) {
+        self.size = Some(size);
+    }
+
+    /// SHA-256 digest of the contents of the file, as a lowercase hexadecimal
+    /// string, see [`Options::checksums`](crate::Options::checksums).
+    #[cfg(feature = "checksum")]
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    #[cfg(feature = "checksum")]
+    pub(crate) fn set_sha256(&mut self, sha256: String) {
+        self.sha256 = Some(sha256);
+    }
+}
+
+/// An entry of a spec that was not created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedEntry {
+    path: PathBuf,
+    reason: SkipReason,
+}
+
+impl SkippedEntry {
+    /// Path the entry would have, joined to the directory the tree is created
+    /// in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Why the entry was not created.
+    pub fn reason(&self) -> SkipReason {
+        self.reason
+    }
+}
+
+/// The entries created from a spec with absolute paths, in creation order,
+/// see [`create_manifest!`](crate::create_manifest).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    entries: Vec<CreatedEntry>,
+    handles: Vec<(Arc<str>, PathBuf)>,
+}
+
+impl Manifest {
+    /// The created directories and files, in creation order.
+    pub fn entries(&self) -> &[CreatedEntry] {
+        &self.entries
+    }
+
+    /// Absolute paths of the created directories and files, in creation
+    /// order.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().map(CreatedEntry::path)
+    }
+
+    /// Absolute path of the entry named `handle`, see
+    /// [`Tree::name`](crate::Tree::name). The last entry named `handle` wins.
+    pub fn handle(&self, handle: &str) -> Option<&Path> {
+        find_handle(&self.handles, handle)
+    }
+
+    /// Number of created entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing was created.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The absolute path of the entry named `handle`.
+///
+/// # Panics
+///
+/// Panics if there is no entry named `handle`.
+impl std::ops::Index<&str> for Manifest {
+    type Output = Path;
+
+    fn index(&self, handle: &str) -> &Path {
+        self.handle(handle)
+            .unwrap_or_else(|| panic!("no entry named {:?} in the manifest", handle))
+    }
+}
+
+impl IntoIterator for Manifest {
+    type Item = CreatedEntry;
+    type IntoIter = std::vec::IntoIter<CreatedEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Manifest {
+    type Item = &'a CreatedEntry;
+    type IntoIter = std::slice::Iter<'a, CreatedEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+/// Statistics of a creation, see [`CreateReport::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CreateStats {
+    /// Number of created directories, the parents of nested keys excluded.
+    pub dirs: usize,
+    /// Number of written regular files, hard links and symbolic links
+    /// excluded.
+    pub files: usize,
+    /// Total size in bytes of the written files whose size is known, see
+    /// [`CreatedEntry::size`].
+    pub bytes: u64,
+    /// Time taken by the creation.
+    pub elapsed: Duration,
+}
+
+/// What was created from a spec, in creation order, and what was skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CreateReport {
+    created: Vec<CreatedEntry>,
+    skipped: Vec<SkippedEntry>,
+    evicted: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+    handles: Vec<(Arc<str>, PathBuf)>,
+    elapsed: Duration,
+}
+
+impl CreateReport {
+    /// The created directories and files, in creation order.
+    pub fn created(&self) -> &[CreatedEntry] {
+        &self.created
+    }
+
+    /// The entries declared with `false` or `null` and the existing files
+    /// kept, in declaration order.
+    pub fn skipped(&self) -> &[SkippedEntry] {
+        &self.skipped
+    }
+
+    /// Path of the entry named `handle`, see [`Tree::name`](crate::Tree::name).
+    /// The last entry named `handle` wins.
+    pub fn handle(&self, handle: &str) -> Option<&Path> {
+        find_handle(&self.handles, handle)
+    }
+
+    /// The manifest of the created entries, relative paths being resolved
+    /// against the current directory.
+    pub fn into_manifest(self) -> std::io::Result<Manifest> {
+        let (mut entries, mut handles) = (self.created, self.handles);
+        let relative = entries.iter().map(|entry| &entry.path);
+        if relative
+            .chain(handles.iter().map(|(_, path)| path))
+            .any(|path| path.is_relative())
+        {
+            let current_dir = std::env::current_dir()?;
+            for entry in &mut entries {
+                entry.path = current_dir.join(&entry.path);
+            }
+            for (_, path) in &mut handles {
+                *path = current_dir.join(&*path);
+            }
+        }
+        Ok(Manifest { entries, handles })
+    }
+
+    /// The SHA-256 digests of the created files, in creation order, see
+    /// [`Options::checksums`](crate::Options::checksums).
+    #[cfg(feature = "checksum")]
+    pub fn checksums(&self) -> impl Iterator<Item = (&Path, &str)> {
+        let created = self.created.iter();
+        created.filter_map(|entry| Some((entry.path(), entry.sha256()?)))
+    }
+
+    /// The digests of the created files in the format of `sha256sum`, which
+    /// `sha256sum --check` verifies from `base`. Paths are relative to `base`
+    /// when they are within it.
+    ///
+    /// ```
+    /// use macro_files::{Options, Tree};
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let mut tree = Tree::new();
+    /// tree.push("hello.txt", "hello\n");
+    /// let report = tree
+    ///     .create_with(dir.path(), &Options::new().checksums(true))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     report.sha256sums(dir.path()),
+    ///     "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  hello.txt\n"
+    /// );
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn sha256sums<P: AsRef<Path>>(&self, base: P) -> String {
+        let base = base.as_ref();
+        let mut sums = String::new();
+        for (path, sha256) in self.checksums() {
+            let path = path.strip_prefix(base).unwrap_or(path);
+            sums.push_str(&format!("{}  {}\n", sha256, path.display()));
+        }
+        sums
+    }
+
+    /// The number of directories and files created, the bytes written and
+    /// the time taken, e.g. to summarize the creation.
+    ///
+    /// ```
+    /// let tree = macro_files::tree!({
+    ///     "src": { "main.rs": "fn main() {}\n" },
+    ///     "README.md": "# Project\n",
+    /// });
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let stats = tree.create_in(dir.path()).unwrap().stats();
+    ///
+    /// assert_eq!((stats.dirs, stats.files, stats.bytes), (1, 2, 23));
+    /// println!(
+    ///     "Created {} files ({} bytes) in {} ms",
+    ///     stats.files,
+    ///     stats.bytes,
+    ///     stats.elapsed.as_millis()
+    /// );
+    /// ```
+    pub fn stats(&self) -> CreateStats {
+        let mut stats = CreateStats {
+            elapsed: self.elapsed,
+            ..CreateStats::default()
+        };
+        for entry in &self.created {
+            match entry.kind {
+                EntryKind::Dir => stats.dirs += 1,
+                EntryKind::File => {
+                    stats.files += 1;
+                    stats.bytes += entry.size.unwrap_or(0);
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    pub(crate) fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    pub(crate) fn push_handle(&mut self, handle: Arc<str>, path: PathBuf) {
+        self.handles.push((handle, path));
+    }
+
+    /// The cached fixtures evicted to make room for the tree, see
+    /// [`Options::quota`](crate::Options::quota).
+    pub fn evicted(&self) -> &[PathBuf] {
+        &self.evicted
+    }
+
+    pub(crate) fn set_evicted(&mut self, evicted: Vec<PathBuf>) {
+        self.evicted = evicted;
+    }
+
+    /// The entries removed before the creation: those which were not
+    /// declared or differed from the spec, see
+    /// [`Tree::sync_in`](crate::Tree::sync_in), or all of them, see
+    /// [`Options::clean`](crate::Options::clean).
+    pub fn removed(&self) -> &[PathBuf] {
+        &self.removed
+    }
+
+    pub(crate) fn set_removed(&mut self, removed: Vec<PathBuf>) {
+        self.removed = removed;
+    }
+
+    pub(crate) fn push_created(
+        &mut self,
+        path: PathBuf,
+        kind: EntryKind,
+        origin: Option<Arc<str>>,
+    ) -> &mut CreatedEntry {
+        self.push(CreatedEntry {
+            path,
+            kind,
+            origin,
+            stamp: None,
+            size: None,
+            #[cfg(feature = "checksum")]
+            sha256: None,
+        })
+    }
+
+    pub(crate) fn push_created_file(
+        &mut self,
+        path: PathBuf,
+        file: &File,
+        origin: Option<Arc<str>>,
+    ) -> &mut CreatedEntry {
+        self.push(CreatedEntry {
+            path,
+            kind: file.kind(),
+            origin,
+            stamp: file.stamp_arc().cloned(),
+            size: match file.kind() {
+                EntryKind::File => file.len(),
+                _ => None,
+            },
+            #[cfg(feature = "checksum")]
+            sha256: None,
+        })
+    }
+
+    fn push(&mut self, entry: CreatedEntry) -> &mut CreatedEntry {
+        self.created.push(entry);
+        let last = self.created.len() - 1;
+        &mut self.created[last]
+    }
+
+    pub(crate) fn push_skipped(&mut self, path: PathBuf, reason: SkipReason) {
+        self.skipped.push(SkippedEntry { path, reason });
+    }
+}
+
+fn find_handle<'a>(handles: &'a [(Arc<str>, PathBuf)], handle: &str) -> Option<&'a Path> {
+    handles
+        .iter()
+        .rev()
+        .find(|(name, _)| &**name == handle)
+        .map(|(_, path)| path.as_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_manifest() {
+        let mut report = CreateReport::default();
+        report.push_created("relative".into(), EntryKind::Dir, None);
+        report.push_created(std::env::temp_dir(), EntryKind::Dir, None);
+        let manifest = report.into_manifest().unwrap();
+
+        let current_dir = std::env::current_dir().unwrap();
+        let paths: Vec<_> = manifest.paths().collect();
+        assert_eq!(
+            paths,
+            [&current_dir.join("relative"), &std::env::temp_dir()]
+        );
+    }
+}
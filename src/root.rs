@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+thread_local!(static DEFAULT_ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) });
+
+/// Call `f` with `root` as the directory the specs of [`create!`],
+/// [`create_with!`] and [`create_async!`](crate::create_async) are created
+/// in, instead of the current directory, on the current thread.
+///
+/// Keys at the root of these specs are then validated like the keys of
+/// [`create_in!`](crate::create_in): absolute keys and keys escaping `root`
+/// are rejected. The previous default root is restored when `f` returns or
+/// panics.
+///
+/// ```
+/// let temp_dir = macro_files::tempfile::tempdir().unwrap();
+/// macro_files::with_default_root(temp_dir.path(), || {
+///     macro_files::create!({
+///         "README.md": "# Project",
+///     })
+/// })
+/// .unwrap();
+///
+/// assert!(temp_dir.path().join("README.md").is_file());
+/// ```
+///
+/// [`create!`]: crate::create
+/// [`create_with!`]: crate::create_with
+pub fn with_default_root<P, F, R>(root: P, f: F) -> R
+where
+    P: AsRef<Path>,
+    F: FnOnce() -> R,
+{
+    let _guard = RootGuard::set(root.as_ref().to_owned());
+    f()
+}
+
+/// The directory set with [`with_default_root`] on the current thread.
+pub fn default_root() -> Option<PathBuf> {
+    DEFAULT_ROOT.with(|cell| cell.borrow().clone())
+}
+
+/// Restores the previous default root when dropped.
+struct RootGuard {
+    previous: Option<PathBuf>,
+}
+
+impl RootGuard {
+    fn set(root: PathBuf) -> RootGuard {
+        let previous = DEFAULT_ROOT.with(|cell| cell.replace(Some(root)));
+        RootGuard { previous }
+    }
+}
+
+impl Drop for RootGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        DEFAULT_ROOT.with(|cell| cell.replace(previous));
+    }
+}
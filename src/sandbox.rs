@@ -0,0 +1,91 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// The absolute path of the existing entry at `path` with its symlinks
+/// resolved.
+#[cfg(not(target_os = "wasi"))]
+pub(crate) fn canonicalize(path: &Path) -> Result<PathBuf> {
+    path.canonicalize()
+}
+
+/// The absolute path of the existing entry at `path`, normalized as WASI
+/// cannot resolve symlinks. The runtime already confines the paths to the
+/// preopened directories.
+#[cfg(target_os = "wasi")]
+pub(crate) fn canonicalize(path: &Path) -> Result<PathBuf> {
+    use std::path::Component;
+
+    std::fs::metadata(path)?;
+    let mut normalized = PathBuf::new();
+    for component in std::env::current_dir()?.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    Ok(normalized)
+}
+
+/// The path with the symlinks of its deepest existing ancestor resolved.
+///
+/// Fails if an ancestor is a dangling symlink, whose target is unknown.
+pub(crate) fn resolve(path: &Path) -> Result<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        match canonicalize(existing) {
+            Ok(resolved) => {
+                return Ok(missing
+                    .iter()
+                    .rev()
+                    .fold(resolved, |path, name| path.join(name)))
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                if existing.symlink_metadata().is_ok() {
+                    return Err(Error::new(
+                        ErrorKind::PermissionDenied,
+                        format!("cannot resolve dangling symlink: {:?}", existing),
+                    ));
+                }
+                match (existing.parent(), existing.file_name()) {
+                    (Some(parent), Some(name)) => {
+                        missing.push(name);
+                        existing = if parent.as_os_str().is_empty() {
+                            Path::new(".")
+                        } else {
+                            parent
+                        };
+                    }
+                    _ => return Err(err),
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Fail unless the entry at `path` is within `root`, itself resolved with
+/// [`resolve`].
+///
+/// The symlinks of the ancestors of `path` are resolved. The entry itself is
+/// only resolved for a directory, whose entries would be written through a
+/// symlink, since an existing symlink is replaced by a file.
+pub(crate) fn confine(root: &Path, path: &Path, dir: bool) -> Result<()> {
+    let resolved = match (dir, path.parent(), path.file_name()) {
+        (false, Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            resolve(parent)?.join(name)
+        }
+        _ => resolve(path)?,
+    };
+    if resolved.starts_with(root) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{:?} is outside of the root directory {:?}", path, root),
+        ))
+    }
+}
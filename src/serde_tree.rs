@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use serde::ser::{Error, Serialize, SerializeMap, Serializer};
+
+use crate::{Content, Entry, SkipReason, Tree};
+
+/// Serialize the tree with the syntax of [`Tree::parse`], keys in
+/// declaration order: directories as maps, UTF-8 contents as strings, other
+/// contents as arrays of bytes, and entries which are not created as `false`
+/// or `null`. Directories which must stay empty, see [`keep`](crate::keep),
+/// are serialized as empty maps.
+///
+/// _This requires one of the features `json`, `toml`, `yaml` or `snapshot`._
+///
+/// Attributes and hooks are not serialized. Serialization fails for the
+/// entries whose contents are not held in memory as is, like copied, linked
+/// or generated files.
+///
+/// ```
+/// # #[cfg(feature = "json")]
+/// # {
+/// let tree = macro_files::tree!({
+///     "README.md": "# Project",
+///     "src": { "main.rs": "fn main() {}" },
+///     "target": null,
+/// });
+///
+/// let json = serde_json::to_string(&tree).unwrap();
+/// assert_eq!(
+///     json,
+///     r##"{"README.md":"# Project","src":{"main.rs":"fn main() {}"},"target":null}"##
+/// );
+/// assert_eq!(macro_files::Tree::parse(&json).unwrap(), tree);
+/// # }
+/// ```
+impl Serialize for Tree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (key, entry) in self.entries() {
+            map.serialize_entry(&key.to_string_lossy(), &SerializedEntry { key, entry })?;
+        }
+        map.end()
+    }
+}
+
+struct SerializedEntry<'a> {
+    key: &'a Path,
+    entry: &'a Entry,
+}
+
+impl Serialize for SerializedEntry<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.entry {
+            Entry::Dir(tree) => tree.serialize(serializer),
+            Entry::Keep => serializer.serialize_map(Some(0))?.end(),
+            Entry::Skip(SkipReason::False) => serializer.serialize_bool(false),
+            Entry::Skip(_) => serializer.serialize_unit(),
+            Entry::File(file) => match file.content() {
+                Content::Text(text) => serializer.serialize_str(text),
+                Content::Bytes(bytes) => match std::str::from_utf8(bytes) {
+                    Ok(text) => serializer.serialize_str(text),
+                    Err(_) => serializer.collect_seq(bytes),
+                },
+                _ => Err(S::Error::custom(format!(
+                    "cannot serialize the {} {:?}, its contents are not in memory",
+                    file.kind(),
+                    self.key
+                ))),
+            },
+            Entry::CopyDir(_) => Err(S::Error::custom(format!(
+                "cannot serialize the copied directory {:?}",
+                self.key
+            ))),
+        }
+    }
+}
+
+/// Convert the tree to a JSON value, see the [`Serialize`] implementation of
+/// [`Tree`].
+///
+/// _This requires the feature `json`._
+///
+/// The keys of the value are sorted unless the `preserve_order` feature of
+/// [`serde_json`] is enabled.
+#[cfg(feature = "json")]
+impl std::convert::TryFrom<&Tree> for serde_json::Value {
+    type Error = serde_json::Error;
+
+    fn try_from(tree: &Tree) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(tree)
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::io::ErrorKind;
+
+use serde::Serialize;
+
+use crate::{Content, File};
+
+/// A file whose contents are the serialization of a value, or which fails
+/// to be created with the serialization error.
+fn serialized<E: fmt::Display>(result: Result<String, E>) -> File {
+    match result {
+        Ok(text) => File::from(Content::Text(text)),
+        Err(err) => File::failing(ErrorKind::InvalidData, err.to_string()),
+    }
+}
+
+/// A JSON file with `value` serialized with [`serde_json`], pretty-printed
+/// and ending with a newline.
+///
+/// _This requires the feature `json`._
+///
+/// The value is serialized when the function is called, so it can be
+/// borrowed. If it cannot be serialized, like a map with non-string keys,
+/// creating the file fails with an [`ErrorKind::InvalidData`] error.
+///
+/// ```
+/// #[derive(serde::Serialize)]
+/// struct Settings {
+///     port: u16,
+/// }
+///
+/// let dir = macro_files::create_temp!({
+///     "settings.json": macro_files::json(&Settings { port: 8080 }),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "settings.json": "{\n  \"port\": 8080\n}\n",
+/// });
+/// ```
+#[cfg(feature = "json")]
+pub fn json<T: Serialize + ?Sized>(value: &T) -> File {
+    serialized(serde_json::to_string_pretty(value).map(|json| json + "\n"))
+}
+
+/// A YAML file with `value` serialized with [`serde_yaml`].
+///
+/// _This requires the feature `yaml`._
+///
+/// The value is serialized when the function is called. If it cannot be
+/// serialized, creating the file fails with an [`ErrorKind::InvalidData`]
+/// error.
+///
+/// ```
+/// let mut services = std::collections::BTreeMap::new();
+/// services.insert("web", vec!["8080:80"]);
+///
+/// let dir = macro_files::create_temp!({
+///     "compose.yaml": macro_files::yaml(&services),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "compose.yaml": "web:\n- 8080:80\n",
+/// });
+/// ```
+#[cfg(feature = "yaml")]
+pub fn yaml<T: Serialize + ?Sized>(value: &T) -> File {
+    serialized(serde_yaml::to_string(value))
+}
+
+/// A TOML file with `value` serialized with the [`toml`](toml_crate) crate.
+///
+/// _This requires the feature `toml`._
+///
+/// The value is serialized when the function is called. If it cannot be
+/// serialized, like a value which is not a table, creating the file fails
+/// with an [`ErrorKind::InvalidData`] error.
+///
+/// ```
+/// let mut package = std::collections::BTreeMap::new();
+/// package.insert("name", "app");
+/// let mut manifest = std::collections::BTreeMap::new();
+/// manifest.insert("package", package);
+///
+/// let dir = macro_files::create_temp!({
+///     "Cargo.toml": macro_files::toml(&manifest),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "Cargo.toml": "[package]\nname = \"app\"\n",
+/// });
+/// ```
+#[cfg(feature = "toml")]
+pub fn toml<T: Serialize + ?Sized>(value: &T) -> File {
+    // Other values than tables are serialized as they are by `toml` 0.5.
+    match toml_crate::Value::try_from(value) {
+        Ok(toml_crate::Value::Table(_)) => serialized(toml_crate::to_string(value)),
+        Ok(_) => File::failing(
+            ErrorKind::InvalidData,
+            "only a table is a TOML document".to_owned(),
+        ),
+        Err(err) => serialized(Err(err)),
+    }
+}
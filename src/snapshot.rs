@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::checksum::sha256_hex;
+use crate::{Content, Entry, EntryKind, ReadOptions, Tree};
+
+/// A serializable view of a directory: its entries by name, with the
+/// contents of text files and the size and SHA-256 digest of binary files,
+/// to snapshot a generated directory with [`insta`].
+///
+/// _This requires the feature `snapshot`._
+///
+/// Entries are serialized as a map sorted by name, the names of directories
+/// ending with a `/`. Text files are serialized as their contents, binary
+/// files as a map with their `size` and `sha256`, symlinks as a map with
+/// their `symlink` target and special files as a map with their `kind`.
+///
+/// ```
+/// use macro_files::Snapshot;
+///
+/// let dir = macro_files::create_temp!({
+///     "README.md": "# Project",
+///     "src/main.rs": "fn main() {}",
+/// })
+/// .unwrap();
+///
+/// let snapshot = Snapshot::read(dir.path()).unwrap();
+/// // insta::assert_yaml_snapshot!(snapshot);
+/// assert_eq!(snapshot.get("README.md").unwrap().text(), Some("# Project"));
+/// ```
+///
+/// [`insta`]: https://crates.io/crates/insta
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    entries: BTreeMap<String, SnapshotEntry>,
+}
+
+/// An entry of a [`Snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SnapshotEntry {
+    /// A directory with its entries.
+    Dir(Snapshot),
+    /// A file whose contents are valid UTF-8.
+    Text(String),
+    /// A file whose contents are not valid UTF-8.
+    Binary {
+        /// Size of the contents in bytes.
+        size: u64,
+        /// SHA-256 digest of the contents, as a lowercase hexadecimal string.
+        sha256: String,
+    },
+    /// A symbolic link with its target.
+    Symlink(PathBuf),
+    /// A FIFO or a socket.
+    Special(EntryKind),
+}
+
+impl Snapshot {
+    /// Read a snapshot of the directory `dir`, see [`Tree::read_from`].
+    pub fn read<P: AsRef<Path>>(dir: P) -> Result<Snapshot> {
+        Tree::read_from(dir)?.snapshot()
+    }
+
+    /// Read a snapshot of the directory `dir` with the given options, see
+    /// [`Tree::read_with`].
+    pub fn read_with<P: AsRef<Path>>(dir: P, options: &ReadOptions) -> Result<Snapshot> {
+        Tree::read_with(dir, options)?.snapshot()
+    }
+
+    /// The entry named `name`.
+    pub fn get(&self, name: &str) -> Option<&SnapshotEntry> {
+        self.entries.get(name)
+    }
+
+    /// Iterate over the entries sorted by name.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &SnapshotEntry)> {
+        self.entries
+            .iter()
+            .map(|(name, entry)| (name.as_str(), entry))
+    }
+
+    /// Whether the directory is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl SnapshotEntry {
+    /// Contents of the text file.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            SnapshotEntry::Text(contents) => Some(contents),
+            _ => None,
+        }
+    }
+
+    /// Kind of the entry.
+    pub fn kind(&self) -> EntryKind {
+        match self {
+            SnapshotEntry::Dir(_) => EntryKind::Dir,
+            SnapshotEntry::Text(_) | SnapshotEntry::Binary { .. } => EntryKind::File,
+            SnapshotEntry::Symlink(_) => EntryKind::Symlink,
+            SnapshotEntry::Special(kind) => *kind,
+        }
+    }
+}
+
+impl Tree {
+    /// A snapshot of the directory the tree creates, from its normal form,
+    /// see [`Tree::normalize`] and [`Snapshot`].
+    ///
+    /// _This requires the feature `snapshot`._
+    pub fn snapshot(&self) -> Result<Snapshot> {
+        snapshot(&self.normalize()?)
+    }
+}
+
+/// Snapshot of a tree in normal form, whose keys are names.
+fn snapshot(tree: &Tree) -> Result<Snapshot> {
+    let mut entries = BTreeMap::new();
+    for (key, entry) in tree.entries() {
+        let entry = match entry {
+            Entry::Dir(tree) => SnapshotEntry::Dir(snapshot(tree)?),
+            Entry::File(file) => match file.content() {
+                Content::Symlink(target) | Content::Junction(target) => {
+                    SnapshotEntry::Symlink(target.to_owned())
+                }
+                Content::Bytes(contents) => match std::str::from_utf8(contents) {
+                    Ok(text) => SnapshotEntry::Text(text.to_owned()),
+                    Err(_) => SnapshotEntry::Binary {
+                        size: contents.len() as u64,
+                        sha256: sha256_hex(&mut contents.as_slice())?,
+                    },
+                },
+                _ => SnapshotEntry::Special(file.kind()),
+            },
+            _ => continue,
+        };
+        entries.insert(key.to_string_lossy().into_owned(), entry);
+    }
+    Ok(Snapshot { entries })
+}
+
+impl Serialize for Snapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (name, entry) in &self.entries {
+            match entry {
+                SnapshotEntry::Dir(_) => map.serialize_entry(&format!("{}/", name), entry)?,
+                _ => map.serialize_entry(name, entry)?,
+            }
+        }
+        map.end()
+    }
+}
+
+impl Serialize for SnapshotEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            SnapshotEntry::Dir(snapshot) => snapshot.serialize(serializer),
+            SnapshotEntry::Text(contents) => serializer.serialize_str(contents),
+            SnapshotEntry::Binary { size, sha256 } => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("size", size)?;
+                map.serialize_entry("sha256", sha256)?;
+                map.end()
+            }
+            SnapshotEntry::Symlink(target) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("symlink", &target.to_string_lossy())?;
+                map.end()
+            }
+            SnapshotEntry::Special(kind) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("kind", &kind.to_string())?;
+                map.end()
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use crate::{Content, Entry, Tree};
+
+impl Tree {
+    /// Paths the tree reads when created, in declaration order without
+    /// duplicates: the files copied with [`from`](crate::from), the
+    /// directories copied with [`copy_dir`](crate::copy_dir) and the
+    /// templates rendered with `template`.
+    ///
+    /// ```
+    /// let tree = macro_files::tree!({
+    ///     "LICENSE": macro_files::from("LICENSE-MIT"),
+    ///     "vendor": macro_files::copy_dir("vendor"),
+    ///     "README.md": "# Project",
+    /// });
+    ///
+    /// assert_eq!(tree.sources(), ["LICENSE-MIT", "vendor"].map(std::path::PathBuf::from));
+    /// ```
+    pub fn sources(&self) -> Vec<PathBuf> {
+        let mut sources = Vec::new();
+        collect_sources(self, &mut sources);
+        sources
+    }
+
+    /// Print the `cargo:rerun-if-changed` instructions of the sources of the
+    /// tree, see [`Options::rerun_if_changed`](crate::Options::rerun_if_changed).
+    pub(crate) fn print_rerun_if_changed(&self) {
+        for source in self.sources() {
+            println!("cargo:rerun-if-changed={}", source.display());
+        }
+    }
+}
+
+fn collect_sources(tree: &Tree, sources: &mut Vec<PathBuf>) {
+    for (_, entry) in tree.entries() {
+        let source = match entry {
+            Entry::Dir(tree) => {
+                collect_sources(tree, sources);
+                continue;
+            }
+            Entry::CopyDir(source) => source,
+            Entry::File(file) => match file.content() {
+                Content::CopyFrom(source) => source,
+                #[cfg(feature = "tera")]
+                Content::Template { path, .. } => path,
+                _ => continue,
+            },
+            Entry::Keep | Entry::Skip(_) => continue,
+        };
+        if !sources.contains(source) {
+            sources.push(source.to_owned());
+        }
+    }
+}
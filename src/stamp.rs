@@ -0,0 +1,68 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::{Content, File};
+
+/// Placeholder replaced with the stamp in the template given to [`stamp`].
+const PLACEHOLDER: &str = "{stamp}";
+
+/// A file whose contents are `template` with each `{stamp}` placeholder
+/// replaced by a stamp unique to this call and to the current run.
+///
+/// The stamp is reported by [`CreatedEntry::stamp`](crate::CreatedEntry::stamp),
+/// so tests can verify a tool read the freshly created file rather than a
+/// stale one.
+///
+/// ```
+/// let dir = macro_files::tempfile::tempdir().unwrap();
+/// let mut tree = macro_files::Tree::new();
+/// tree.push("config.toml", macro_files::stamp("run = \"{stamp}\""));
+/// let report = tree.create_in(dir.path()).unwrap();
+///
+/// let stamp = report.created()[0].stamp().unwrap();
+/// let contents = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+/// assert_eq!(contents, format!("run = \"{}\"", stamp));
+/// ```
+pub fn stamp<S: AsRef<str>>(template: S) -> File {
+    let stamp = new_stamp();
+    let contents = template.as_ref().replace(PLACEHOLDER, &stamp);
+    File::from(Content::Text(contents)).with_stamp(Arc::from(stamp))
+}
+
+/// A new stamp made of the start time of the run, the process id and a
+/// counter.
+fn new_stamp() -> String {
+    static RUN: AtomicU64 = AtomicU64::new(0);
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut run = RUN.load(Ordering::Relaxed);
+    if run == 0 {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(1, |elapsed| elapsed.as_nanos() as u64 | 1);
+        run = match RUN.compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => now,
+            Err(run) => run,
+        };
+    }
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!(
+        "{:016x}-{:08x}-{:08x}",
+        run,
+        crate::platform::process_id(),
+        count
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_stamps_of_one_run() {
+        let (first, second) = (new_stamp(), new_stamp());
+        assert_ne!(first, second);
+        assert_eq!(first[..25], second[..25]);
+    }
+}
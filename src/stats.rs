@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use crate::{Content, Entry, Tree};
+
+/// Statistics of a spec, see [`Tree::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Number of declared directories, including copied ones.
+    pub dirs: usize,
+    /// Number of regular files.
+    pub files: usize,
+    /// Number of symbolic links.
+    pub symlinks: usize,
+    /// Number of hard links.
+    pub hard_links: usize,
+    /// Number of FIFOs and sockets.
+    pub special_files: usize,
+    /// Number of directories copied from an existing directory, whose entries
+    /// are not counted.
+    pub copied_dirs: usize,
+    /// Number of entries declared with `false` or `null`.
+    pub skipped: usize,
+    /// Total size in bytes of the files whose size is known before they are
+    /// created: contents held in memory or declared with
+    /// [`size`](crate::size).
+    pub bytes: u64,
+    /// Number of files whose size is only known when they are created, such
+    /// as files copied from another file or generated.
+    pub unsized_files: usize,
+    /// Number of path components of the deepest entry.
+    pub max_depth: usize,
+    /// Path and size of the largest file whose size is known.
+    pub largest_file: Option<(PathBuf, u64)>,
+}
+
+impl Tree {
+    /// Count the entries of the spec without touching the filesystem.
+    ///
+    /// Nested keys count as nested directories for the depth, not for the
+    /// number of directories.
+    ///
+    /// ```
+    /// let mut docs = macro_files::Tree::new();
+    /// docs.push("guide/intro.md", "Intro");
+    /// let mut tree = macro_files::Tree::new();
+    /// tree.push("README.md", "# Project");
+    /// tree.push("docs", docs);
+    ///
+    /// let stats = tree.stats();
+    /// assert_eq!((stats.dirs, stats.files, stats.bytes), (1, 2, 14));
+    /// assert_eq!(stats.max_depth, 3);
+    /// assert_eq!(stats.largest_file, Some(("README.md".into(), 9)));
+    /// ```
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        count(self, Path::new(""), &mut stats);
+        stats
+    }
+}
+
+fn count(tree: &Tree, dir: &Path, stats: &mut Stats) {
+    for (key, entry) in tree.entries() {
+        let path = dir.join(key);
+        let depth = path.components().count();
+        if let Entry::Skip(_) = entry {
+            stats.skipped += 1;
+            continue;
+        }
+        stats.max_depth = stats.max_depth.max(depth);
+        match entry {
+            Entry::Dir(tree) => {
+                stats.dirs += 1;
+                count(tree, &path, stats);
+            }
+            Entry::File(file) => match file.content() {
+                Content::Symlink(_) | Content::Junction(_) => stats.symlinks += 1,
+                Content::HardLink(_) => stats.hard_links += 1,
+                _ if file.is_special() => stats.special_files += 1,
+                _ => {
+                    stats.files += 1;
+                    match file.len() {
+                        Some(size) => {
+                            stats.bytes += size;
+                            if stats
+                                .largest_file
+                                .as_ref()
+                                .map_or(true, |(_, max)| size > *max)
+                            {
+                                stats.largest_file = Some((path, size));
+                            }
+                        }
+                        None => stats.unsized_files += 1,
+                    }
+                }
+            },
+            Entry::Keep => stats.dirs += 1,
+            Entry::CopyDir(_) => {
+                stats.dirs += 1;
+                stats.copied_dirs += 1;
+            }
+            Entry::Skip(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_entries() {
+        let tree = crate::create_internal!(@tree {
+            "README.md": "# Project",
+            "empty": true,
+            "target": false,
+            "docs": {
+                "large.bin": [0; 64],
+                "index.md": crate::symlink("large.bin"),
+                "copy.bin": crate::hardlink("docs/large.bin"),
+                "generated": crate::Content::Generator(crate::Generator::new(|| Ok(Vec::new()))),
+                "vendor": crate::copy_dir("tests/fixtures/vendor"),
+            },
+        });
+
+        let stats = tree.stats();
+        assert_eq!(
+            stats,
+            Stats {
+                dirs: 2,
+                files: 4,
+                symlinks: 1,
+                hard_links: 1,
+                special_files: 0,
+                copied_dirs: 1,
+                skipped: 1,
+                bytes: 73,
+                unsized_files: 1,
+                max_depth: 2,
+                largest_file: Some((PathBuf::from("docs/large.bin"), 64)),
+            }
+        );
+    }
+}
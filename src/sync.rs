@@ -0,0 +1,55 @@
+use std::io::Result;
+use std::path::Path;
+
+use crate::remove::remove_entry;
+use crate::{entry_kind, Change, CreateReport, Tree};
+
+impl Tree {
+    /// Make the directory `dir` match the tree: remove the entries which are
+    /// not declared or differ from the spec, then create the missing ones,
+    /// see [`Tree::diff`] and [`Tree::merge_in`].
+    ///
+    /// Entries matching the spec are left untouched and reported as skipped,
+    /// the removed ones are listed by [`CreateReport::removed`]. Declared
+    /// attributes are only applied to the created entries.
+    ///
+    /// ```
+    /// use macro_files::Tree;
+    ///
+    /// let dir = macro_files::create_temp!({
+    ///     "config.toml": "debug = true",
+    ///     "stale.toml": "",
+    /// })
+    /// .unwrap();
+    ///
+    /// let mut tree = Tree::new();
+    /// tree.push("config.toml", "debug = false");
+    /// let report = tree.sync_in(dir.path()).unwrap();
+    ///
+    /// assert_eq!(report.removed().len(), 2);
+    /// assert!(tree.diff(dir.path()).unwrap().is_empty());
+    /// ```
+    pub fn sync_in<P: AsRef<Path>>(&self, dir: P) -> Result<CreateReport> {
+        let dir = dir.as_ref();
+        let mut removed = Vec::new();
+        if entry_kind(dir)?.is_some() {
+            for change in self.diff(dir)?.changes() {
+                match change {
+                    Change::Missing { .. } => {}
+                    Change::NotEmpty { path, entries } => {
+                        for entry in entries {
+                            removed.push(dir.join(path).join(entry));
+                        }
+                    }
+                    change => removed.push(dir.join(change.path())),
+                }
+            }
+        }
+        for path in &removed {
+            remove_entry(path)?;
+        }
+        let mut report = self.merge_in(dir)?;
+        report.set_removed(removed);
+        Ok(report)
+    }
+}
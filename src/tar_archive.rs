@@ -0,0 +1,133 @@
+use std::io::{self, Read, Result, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use tar::{Archive, Builder, EntryType, Header};
+
+use crate::archive::{file_mode, unsupported, write_archive, ArchiveWriter};
+use crate::attrs::Attrs;
+use crate::extract::Extracted;
+use crate::{EntryKind, File, Tree};
+
+impl Tree {
+    /// Write the directories and files of the tree into a tar archive,
+    /// returning the writer once the archive is finished, see
+    /// [`create_tar!`](crate::create_tar).
+    ///
+    /// Keys must be relative paths, resolved against the root of the archive
+    /// like hard link originals. Directories are written with the mode
+    /// `0o755` and files with `0o644`, or `0o444` if read-only, plus the
+    /// execute permissions if executable, owned by root unless declared
+    /// otherwise. Directories copied with [`copy_dir`](crate::copy_dir) are
+    /// read when the archive is written.
+    ///
+    /// Archives are reproducible: the entries are sorted by path, hard links
+    /// coming last, and the modification times default to the Unix epoch,
+    /// so the same spec always gives the same bytes, whatever the order of
+    /// its entries, and archives can be cached and compared by hash.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// let mut tree = macro_files::Tree::new();
+    /// tree.push("docs/README.md", "# Documentation");
+    ///
+    /// let archive = tree.write_tar(Vec::new()).unwrap();
+    /// let mut archive = macro_files::tar::Archive::new(archive.as_slice());
+    /// let paths: Vec<_> = archive
+    ///     .entries()
+    ///     .unwrap()
+    ///     .map(|entry| entry.unwrap().path().unwrap().into_owned())
+    ///     .collect();
+    /// assert_eq!(paths, [Path::new("docs"), Path::new("docs/README.md")]);
+    /// ```
+    pub fn write_tar<W: Write>(&self, writer: W) -> Result<W> {
+        let mut builder = Builder::new(writer);
+        write_archive(self, &mut builder)?;
+        builder.into_inner()
+    }
+}
+
+impl<W: Write> ArchiveWriter for Builder<W> {
+    const FORMAT: &'static str = "tar";
+
+    fn dir(&mut self, path: &Path, attrs: &Attrs) -> Result<()> {
+        let mut header = header(EntryType::Directory, 0o755, attrs);
+        self.append_data(&mut header, path, io::empty())
+    }
+
+    fn file(&mut self, path: &Path, contents: &[u8], attrs: &Attrs) -> Result<()> {
+        let mode = file_mode(attrs);
+        let mut header = header(EntryType::Regular, mode, attrs);
+        header.set_size(contents.len() as u64);
+        self.append_data(&mut header, path, contents)
+    }
+
+    fn symlink(&mut self, path: &Path, target: &Path, attrs: &Attrs) -> Result<()> {
+        let mut header = header(EntryType::Symlink, 0o777, attrs);
+        self.append_link(&mut header, path, target)
+    }
+
+    fn hard_link(&mut self, path: &Path, original: &Path, attrs: &Attrs) -> Result<()> {
+        let mut header = header(EntryType::Link, 0o644, attrs);
+        self.append_link(&mut header, path, original)
+    }
+
+    fn special(&mut self, path: &Path, file: &File) -> Result<()> {
+        if file.kind() != EntryKind::Fifo {
+            return Err(unsupported(&file.kind().to_string(), path, Self::FORMAT));
+        }
+        let mut header = header(EntryType::Fifo, 0o644, file.attrs());
+        self.append_data(&mut header, path, io::empty())
+    }
+}
+
+/// Header of an entry without contents, its path is set when appended.
+fn header(entry_type: EntryType, mode: u32, attrs: &Attrs) -> Header {
+    let mut header = Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_mode(mode);
+    header.set_size(0);
+    let mtime = attrs
+        .modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |mtime| mtime.as_secs());
+    header.set_mtime(mtime);
+    #[cfg(all(feature = "unix", unix))]
+    if let Some((uid, gid)) = attrs.owner {
+        header.set_uid(u64::from(uid));
+        header.set_gid(u64::from(gid));
+    }
+    header
+}
+
+/// Unpack the entries of the tar archive read from `reader`, see
+/// [`extract`](crate::extract).
+pub(crate) fn extract_tar<R: Read>(reader: R, extracted: &mut Extracted) -> Result<()> {
+    let mut archive = Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            extracted.dir(&path)?;
+        } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+            let target = entry.link_name()?.unwrap_or_default().into_owned();
+            if entry_type.is_symlink() {
+                extracted.symlink(&path, &target)?;
+            } else {
+                extracted.hard_link(&path, &target)?;
+            }
+        } else if entry_type.is_file() {
+            let mode = entry.header().mode()?;
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            extracted.file(&path, contents, mode)?;
+        } else if !entry_type.is_pax_global_extensions() && !entry_type.is_pax_local_extensions() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?} is not a directory, a file or a link", path),
+            ));
+        }
+    }
+    Ok(())
+}
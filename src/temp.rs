@@ -0,0 +1,496 @@
+use std::ffi::OsStr;
+use std::io::{Error, ErrorKind, Result};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tempfile::{Builder, NamedTempFile, TempDir};
+
+use crate::{CreateReport, Entry, EntryKind, Manifest, Tree, TreeDiff, WriteOptions};
+
+/// Name of the environment variable keeping the temporary directories.
+const KEEP_VAR: &str = "MACRO_FILES_KEEP";
+
+/// Name of the environment variable setting the parent directory of the
+/// temporary directories.
+const TMPDIR_VAR: &str = "MACRO_FILES_TMPDIR";
+
+/// Name of the environment variable setting the parent directory of the
+/// temporary directories held in memory.
+const RAMDIR_VAR: &str = "MACRO_FILES_RAMDIR";
+
+/// Parent directory of the temporary directories created by the macros when
+/// none is given with `in = expr`: the `MACRO_FILES_TMPDIR` environment
+/// variable if set, e.g. a RAM disk or a project-local `target/tmp`, or else
+/// the system temporary directory. WASI has none: the `TMPDIR` environment
+/// variable or else `/tmp` is used, which must be within a directory
+/// preopened by the runtime.
+///
+/// The directory is created if missing.
+pub fn temp_root() -> PathBuf {
+    match std::env::var_os(TMPDIR_VAR) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => crate::platform::temp_dir(),
+    }
+}
+
+/// Parent directory of the temporary directories created by the macros with
+/// `ram = true`, held in memory to speed up I/O-bound test suites: the
+/// `MACRO_FILES_RAMDIR` environment variable if set, e.g. a tmpfs mount, or
+/// else `/dev/shm` on Linux when it can be written.
+///
+/// Falls back to the [`temp_root`] when there is none or the directory of
+/// the environment variable cannot be created. Memory-backed directories are
+/// often small, 64 MiB in Docker containers by default, so large fixtures
+/// are better kept on disk.
+pub fn ram_root() -> PathBuf {
+    match std::env::var_os(RAMDIR_VAR) {
+        Some(dir) if !dir.is_empty() => {
+            let dir = PathBuf::from(dir);
+            if std::fs::create_dir_all(&dir).is_ok() {
+                return dir;
+            }
+        }
+        _ => {
+            if let Some(dir) = crate::platform::ram_dir() {
+                return dir;
+            }
+        }
+    }
+    temp_root()
+}
+
+/// Parent directory of a temporary entry created by the macros, the
+/// [`ram_root`] if `ram` or else the [`temp_root`], created if missing.
+#[doc(hidden)]
+pub fn ram_parent(ram: bool) -> PathBuf {
+    let root = if ram { ram_root() } else { temp_root() };
+    // A failure is reported when creating the temporary entry within.
+    let _ = std::fs::create_dir_all(&root);
+    root
+}
+
+/// Whether the temporary directories created by the macros are kept rather
+/// than removed when dropped, which is the case when the `MACRO_FILES_KEEP`
+/// environment variable is set to a value other than `0`.
+///
+/// The path of every kept directory is printed to the standard error, which
+/// the test harness only shows for failing tests, so that their fixtures can
+/// be inspected:
+///
+/// ```text
+/// MACRO_FILES_KEEP=1 cargo test
+/// ```
+pub fn keeps_temp_dirs() -> bool {
+    std::env::var_os(KEEP_VAR).map_or(false, |keep| !keep.is_empty() && keep != "0")
+}
+
+/// Keep the temporary directory `dir` rather than removing it when dropped,
+/// e.g. to generate a tree then move it into place, returning its path and
+/// the manifest of its entries, parents first and siblings sorted by name.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "release": { "app.toml": "port = 8080" },
+/// })
+/// .unwrap();
+///
+/// let (path, manifest) = macro_files::persist(dir).unwrap();
+/// assert_eq!(manifest.len(), 2);
+/// assert_eq!(manifest.entries()[1].path(), path.join("release/app.toml"));
+/// assert_eq!(manifest.entries()[1].size(), Some(11));
+/// # std::fs::remove_dir_all(path).unwrap();
+/// ```
+pub fn persist(dir: TempDir) -> Result<(PathBuf, Manifest)> {
+    let mut report = CreateReport::default();
+    push_entries(&mut report, dir.path())?;
+    #[allow(deprecated)]
+    let path = dir.into_path();
+    Ok((path, report.into_manifest()?))
+}
+
+/// Push the entries of `dir` to `report` as if they were created, parents
+/// first and siblings sorted by name, without following symlinks.
+fn push_entries(report: &mut CreateReport, dir: &Path) -> Result<()> {
+    let mut entries = dir.read_dir()?.collect::<Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let metadata = entry.metadata()?;
+        let kind = EntryKind::from(metadata.file_type());
+        let created = report.push_created(entry.path(), kind, None);
+        if kind == EntryKind::File {
+            created.set_size(metadata.len());
+        }
+        if kind == EntryKind::Dir {
+            push_entries(report, &entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Create a temporary directory with `builder` within `dir` or else the
+/// [`temp_root`], kept if [`keeps_temp_dirs`].
+#[doc(hidden)]
+pub fn temp_dir(builder: &Builder<'_, '_>, dir: Option<&Path>) -> Result<TempDir> {
+    let (builder, keep) = keeping(builder);
+    let dir = builder.tempdir_in(parent(dir)?)?;
+    if keep {
+        eprintln!("macro_files: keeping {}", dir.path().display());
+    }
+    Ok(dir)
+}
+
+/// Create a temporary file with `builder` within `dir` or else the
+/// [`temp_root`], kept if [`keeps_temp_dirs`], and write the file `entry`
+/// to it.
+///
+/// Fails with an [`ErrorKind::InvalidInput`] error if the entry is not a
+/// file with contents.
+#[doc(hidden)]
+pub fn temp_file(
+    builder: &Builder<'_, '_>,
+    dir: Option<&Path>,
+    entry: Entry,
+) -> Result<NamedTempFile> {
+    let file = match entry {
+        Entry::File(file) if !file.is_link() && !file.is_special() => file,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "a temporary file must be declared with its contents",
+            ))
+        }
+    };
+    let (builder, keep) = keeping(builder);
+    let temp_file = builder.tempfile_in(parent(dir)?)?;
+    if keep {
+        eprintln!("macro_files: keeping {}", temp_file.path().display());
+    }
+    file.write(Path::new(""), temp_file.path(), &WriteOptions::default())?;
+    Ok(temp_file)
+}
+
+/// The `builder` keeping the temporary entries if [`keeps_temp_dirs`], with
+/// whether it does.
+///
+/// `Builder::keep`, added in tempfile 3.12 which sets the minimum supported
+/// Rust version to 1.63, is deprecated since 3.20 in favor of
+/// `disable_cleanup`, which is not required to keep older versions allowed.
+#[allow(deprecated)]
+fn keeping<'a, 'b>(builder: &Builder<'a, 'b>) -> (Builder<'a, 'b>, bool) {
+    let keep = keeps_temp_dirs();
+    let mut builder = builder.clone();
+    builder.keep(keep);
+    (builder, keep)
+}
+
+/// The directory to create a temporary entry in, `dir` or else the
+/// [`temp_root`] which is created if missing.
+pub(crate) fn parent(dir: Option<&Path>) -> Result<PathBuf> {
+    match dir {
+        Some(dir) => Ok(dir.to_owned()),
+        None => {
+            let root = temp_root();
+            std::fs::create_dir_all(&root)?;
+            Ok(root)
+        }
+    }
+}
+
+/// Create a unique temporary directory for `id` within the shared `base`
+/// directory, `base` is created if missing.
+///
+/// The directory name starts with `id`, where characters other than ASCII
+/// alphanumerics, `-`, `_` and `.` are replaced by `_` (e.g. test paths like
+/// `module::test`), followed by a random suffix. It is removed when the
+/// returned [`TempDir`] is dropped, unless [`keeps_temp_dirs`].
+pub fn namespaced_dir<B: AsRef<Path>>(base: B, id: &str) -> Result<TempDir> {
+    let base = base.as_ref();
+    std::fs::create_dir_all(base)?;
+    let prefix: String = id
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .chain(std::iter::once('-'))
+        .collect();
+    temp_dir(Builder::new().prefix(&prefix), Some(base))
+}
+
+/// A temporary directory created from a spec with the nested temporary
+/// directories declared with `temp { ... }`, see
+/// [`create_temp_tree!`](crate::create_temp_tree).
+///
+/// Each nested directory is linked in the main one with a symlink and lives
+/// as long as its [`TempDir`], which can be dropped on its own to simulate an
+/// external volume which disappears.
+///
+/// The spec is kept along with the directory, and paths relative to the
+/// directory are accepted by its helpers:
+///
+/// ```
+/// let tree = macro_files::create_temp_tree!({
+///     "docs": { "README.md": "# Documentation" },
+/// })
+/// .unwrap();
+///
+/// std::fs::write(tree.child("notes.txt"), "notes").unwrap();
+/// assert_eq!(tree.read_to_string("docs/README.md").unwrap(), "# Documentation");
+/// tree.assert_contains(&macro_files::tree!({ "notes.txt": "notes" }));
+/// tree.assert_contains(tree.spec());
+/// ```
+#[derive(Debug)]
+pub struct TempTree {
+    dir: TempDir,
+    nested: Vec<(PathBuf, TempDir)>,
+    spec: Tree,
+}
+
+/// Guard restoring the previous current directory when dropped, see
+/// [`TempTree::enter`].
+#[derive(Debug)]
+#[must_use = "the previous current directory is restored as soon as the guard is dropped"]
+pub struct CurrentDirGuard<'a> {
+    previous: PathBuf,
+    tree: PhantomData<&'a TempTree>,
+}
+
+impl CurrentDirGuard<'_> {
+    /// The current directory before entering the temporary directory.
+    pub fn previous(&self) -> &Path {
+        &self.previous
+    }
+}
+
+impl Drop for CurrentDirGuard<'_> {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(&self.previous);
+    }
+}
+
+impl TempTree {
+    /// Path of the main temporary directory.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// The spec the directory was created from.
+    pub fn spec(&self) -> &Tree {
+        &self.spec
+    }
+
+    /// Path of `path` relative to the main directory, or `path` if it is
+    /// absolute, whether it exists or not.
+    pub fn child<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.dir.path().join(path)
+    }
+
+    /// Contents of the file at `path` relative to the main directory.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        std::fs::read(self.child(path))
+    }
+
+    /// Contents of the text file at `path` relative to the main directory.
+    pub fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        std::fs::read_to_string(self.child(path))
+    }
+
+    /// Assert that the main directory contains the entries of `spec`, whose
+    /// keys are relative to it, panicking with all the differences otherwise.
+    ///
+    /// Unlike [`assert_tree!`](crate::assert_tree), entries which are not
+    /// declared in `spec` are ignored.
+    pub fn assert_contains(&self, spec: &Tree) {
+        match spec.diff(self.path()) {
+            Ok(diff) => {
+                let diff = diff.without_unexpected();
+                if !diff.is_empty() {
+                    panic!(
+                        "directory {:?} does not contain the spec:\n{}",
+                        self.path(),
+                        diff
+                    );
+                }
+            }
+            Err(err) => panic!(
+                "failed to compare directory {:?} with the spec: {}",
+                self.path(),
+                err
+            ),
+        }
+    }
+
+    /// A command running `program` within the main directory, to run the
+    /// binary under test on the created fixture.
+    ///
+    /// The current directory of the tests is left as is, unlike with
+    /// [`TempTree::enter`], so tests running commands can run concurrently.
+    ///
+    /// ```
+    /// let tree = macro_files::create_temp_tree!({
+    ///     "app": macro_files::presets::cargo_bin("app"),
+    /// })
+    /// .unwrap();
+    ///
+    /// let output = tree
+    ///     .command(env!("CARGO"))
+    ///     .args(["metadata", "--no-deps", "--offline", "--manifest-path", "app/Cargo.toml"])
+    ///     .output()
+    ///     .unwrap();
+    /// assert!(output.status.success());
+    /// ```
+    pub fn command<S: AsRef<OsStr>>(&self, program: S) -> Command {
+        let mut command = Command::new(program);
+        command.current_dir(self.path());
+        command
+    }
+
+    /// The current entries of the main directory, read into a tree to find
+    /// the changes made afterwards with [`TempTree::changes_since`].
+    ///
+    /// The contents of the files are held in memory, see
+    /// [`Tree::read_from`].
+    pub fn snapshot(&self) -> Result<Tree> {
+        Tree::read_from(self.path())
+    }
+
+    /// The changes made to the main directory since `snapshot` was taken,
+    /// with [`TempTree::snapshot`] or declared as a spec, such as the
+    /// [`spec`](TempTree::spec) the directory was created from.
+    ///
+    /// The entries created since are the [`unexpected`](TreeDiff::unexpected)
+    /// ones, those deleted the [`missing`](TreeDiff::missing) ones, and those
+    /// modified the [`mismatched`](TreeDiff::mismatched) ones, which lets a
+    /// test assert what the code under test touched without walking the
+    /// directory.
+    ///
+    /// ```
+    /// use std::path::Path;
+    ///
+    /// let tree = macro_files::create_temp_tree!({
+    ///     "Cargo.toml": "[package]",
+    ///     "Cargo.lock": "# old",
+    ///     "notes.txt": "",
+    /// })
+    /// .unwrap();
+    /// let snapshot = tree.snapshot().unwrap();
+    ///
+    /// std::fs::write(tree.child("Cargo.lock"), "# new").unwrap();
+    /// std::fs::remove_file(tree.child("notes.txt")).unwrap();
+    /// std::fs::create_dir(tree.child("target")).unwrap();
+    ///
+    /// let changes = tree.changes_since(&snapshot).unwrap();
+    /// assert!(changes.mismatched().eq([Path::new("Cargo.lock")]));
+    /// assert!(changes.missing().eq([Path::new("notes.txt")]));
+    /// assert!(changes.unexpected().eq([Path::new("target")]));
+    /// ```
+    pub fn changes_since(&self, snapshot: &Tree) -> Result<TreeDiff> {
+        snapshot.diff(self.path())
+    }
+
+    /// The main temporary directory.
+    pub fn dir(&self) -> &TempDir {
+        &self.dir
+    }
+
+    /// The nested temporary directories with the path of their symlink, in
+    /// creation order.
+    pub fn nested(&self) -> &[(PathBuf, TempDir)] {
+        &self.nested
+    }
+
+    /// Take the nested temporary directory linked at `path`, relative to the
+    /// main directory or absolute, so that it can be dropped.
+    pub fn take_nested<P: AsRef<Path>>(&mut self, path: P) -> Option<TempDir> {
+        let path = self.dir.path().join(path);
+        let index = self.nested.iter().position(|(link, _)| *link == path)?;
+        Some(self.nested.remove(index).1)
+    }
+
+    /// Make the main directory the current directory of the process until
+    /// the returned guard is dropped, which restores the previous one, for
+    /// code resolving paths against the current directory.
+    ///
+    /// The current directory is shared by the whole process while tests run
+    /// in parallel threads by default: the tests entering a directory, and
+    /// those using relative paths, must not run concurrently, e.g. with
+    /// `--test-threads=1`. The guard cannot outlive the directory, and a
+    /// failure to restore the previous directory is ignored when dropped.
+    ///
+    /// ```
+    /// let tree = macro_files::create_temp_tree!({ "config.toml": "debug = true" }).unwrap();
+    /// let previous = std::env::current_dir().unwrap();
+    ///
+    /// {
+    ///     let _guard = tree.enter().unwrap();
+    ///     assert_eq!(std::fs::read_to_string("config.toml").unwrap(), "debug = true");
+    /// }
+    ///
+    /// assert_eq!(std::env::current_dir().unwrap(), previous);
+    /// ```
+    pub fn enter(&self) -> Result<CurrentDirGuard<'_>> {
+        let previous = std::env::current_dir()?;
+        std::env::set_current_dir(self.path())?;
+        Ok(CurrentDirGuard {
+            previous,
+            tree: PhantomData,
+        })
+    }
+
+    /// The main temporary directory and the nested ones.
+    pub fn into_parts(self) -> (TempDir, Vec<(PathBuf, TempDir)>) {
+        (self.dir, self.nested)
+    }
+
+    /// Keep the main temporary directory and the nested ones rather than
+    /// removing them when dropped, returning the path of the main directory
+    /// and the manifest of its entries, see [`persist`].
+    pub fn persist(self) -> Result<(PathBuf, Manifest)> {
+        for (_, nested) in self.nested {
+            #[allow(deprecated)]
+            let _ = nested.into_path();
+        }
+        persist(self.dir)
+    }
+}
+
+impl AsRef<Path> for TempTree {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Tree {
+    /// Create the tree within a new temporary directory of the
+    /// [`temp_root`], like [`create_temp!`](crate::create_temp), returning
+    /// the directory.
+    ///
+    /// ```
+    /// let fixture = macro_files::tree!({
+    ///     "README.md": "# Project",
+    /// });
+    ///
+    /// let first = fixture.create_temp().unwrap();
+    /// let second = fixture.create_temp().unwrap();
+    /// assert!(first.path().join("README.md").is_file());
+    /// assert!(second.path().join("README.md").is_file());
+    /// ```
+    pub fn create_temp(&self) -> Result<TempDir> {
+        let dir = temp_dir(&Builder::new(), None)?;
+        self.create_in(dir.path())?;
+        Ok(dir)
+    }
+
+    /// Create the tree within the temporary directory `dir`, with its nested
+    /// temporary directories, see [`Tree::temp`](crate::Tree::temp).
+    pub fn create_in_temp(&self, dir: TempDir) -> Result<TempTree> {
+        let mut nested = Vec::new();
+        self.create_nested(dir.path(), &mut nested)?;
+        Ok(TempTree {
+            dir,
+            nested,
+            spec: self.clone(),
+        })
+    }
+}
@@ -0,0 +1,48 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use tera::{Context, Tera, Value};
+
+use crate::{Content, File};
+
+/// A file whose contents are rendered from the [`tera`] template at `path`
+/// with `context` when it is created.
+///
+/// Relative paths are resolved against the current directory. Contents are
+/// not escaped, and creation fails with an [`ErrorKind::InvalidData`] error
+/// when the template cannot be rendered.
+///
+/// ```
+/// let mut context = tera::Context::new();
+/// context.insert("name", "fixture");
+/// context.insert("version", "0.1.0");
+///
+/// let dir = macro_files::create_temp!({
+///     "Cargo.toml": macro_files::template("tests/fixtures/Cargo.toml.tera", &context),
+/// })
+/// .unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+///     "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\n"
+/// );
+/// ```
+pub fn template<P: AsRef<Path>>(path: P, context: &Context) -> File {
+    File::from(Content::Template {
+        path: path.as_ref().to_owned(),
+        context: context.clone().into_json(),
+    })
+}
+
+/// Render the template at `path` with the context serialized as `context`.
+pub(crate) fn render(path: &Path, context: &Value) -> Result<Vec<u8>> {
+    let input = std::fs::read_to_string(path)?;
+    let context = Context::from_value(context.clone()).map_err(invalid_data)?;
+    Tera::one_off(&input, &context, false)
+        .map(String::into_bytes)
+        .map_err(invalid_data)
+}
+
+fn invalid_data(err: tera::Error) -> Error {
+    Error::new(ErrorKind::InvalidData, err)
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::ErrorKind;
+
+use crate::{Content, File, Tree};
+
+/// A text file whose lines are stripped of their common leading indentation,
+/// so that multi-line contents can be indented with the spec.
+///
+/// The first line is removed when empty, so the text can start on the line
+/// after the opening quote, and lines holding only whitespace are emptied.
+/// Tabs and spaces are both counted as one column, mixing them is not
+/// supported.
+///
+/// ```
+/// use macro_files::dedent;
+///
+/// let dir = macro_files::create_temp!({
+///     "src": {
+///         "main.rs": dedent("
+///             fn main() {
+///                 println!(\"Hello\");
+///             }
+///         "),
+///     },
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "src/main.rs": "fn main() {\n    println!(\"Hello\");\n}\n",
+/// });
+/// ```
+pub fn dedent<S: AsRef<str>>(text: S) -> File {
+    File::from(Content::Text(dedent_str(text.as_ref())))
+}
+
+/// Strip the common leading indentation of the lines of `text`, see [`dedent`].
+fn dedent_str(text: &str) -> String {
+    let text = text
+        .strip_prefix("\r\n")
+        .or_else(|| text.strip_prefix('\n'))
+        .unwrap_or(text);
+    let is_indent = |c: char| c == ' ' || c == '\t';
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim_start_matches(is_indent).is_empty())
+        .map(|line| line.len() - line.trim_start_matches(is_indent).len())
+        .min()
+        .unwrap_or(0);
+    let mut dedented = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_start_matches(is_indent);
+        if content.trim_end_matches(&['\r', '\n'][..]).is_empty() {
+            dedented.push_str(content);
+        } else {
+            dedented.push_str(&line[indent..]);
+        }
+    }
+    dedented
+}
+
+/// Line endings of text contents, see [`Tree::line_endings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// `\n`, used on Unix.
+    Lf,
+    /// `\r\n`, used on Windows.
+    CrLf,
+}
+
+impl LineEnding {
+    /// The line ending used on the current platform.
+    pub fn native() -> LineEnding {
+        if cfg!(windows) {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// The line ending as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    /// `text` with all its line endings, `\n` or `\r\n`, replaced by this one.
+    pub fn apply(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => text,
+            LineEnding::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// A text file whose line endings, `\n` or `\r\n`, are all `\r\n`.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "input.csv": macro_files::crlf("id,name\n1,Ada\n"),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "input.csv": "id,name\r\n1,Ada\r\n",
+/// });
+/// ```
+pub fn crlf<S: AsRef<str>>(text: S) -> File {
+    File::from(Content::Text(text.as_ref().to_owned())).line_ending(LineEnding::CrLf)
+}
+
+/// A text file whose line endings, `\n` or `\r\n`, are all `\n`.
+pub fn lf<S: AsRef<str>>(text: S) -> File {
+    File::from(Content::Text(text.as_ref().to_owned())).line_ending(LineEnding::Lf)
+}
+
+/// A text file encoded in UTF-16 little-endian, preceded by its byte order
+/// mark `FF FE`.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "legacy.txt": macro_files::utf16le("Hé"),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "legacy.txt": [0xFF, 0xFE, b'H', 0x00, 0xE9, 0x00],
+/// });
+/// ```
+pub fn utf16le<S: AsRef<str>>(text: S) -> File {
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in text.as_ref().encode_utf16() {
+        bytes.extend_from_slice(&unit.to_le_bytes());
+    }
+    File::from(Content::Bytes(bytes))
+}
+
+/// A text file encoded in UTF-16 big-endian, preceded by its byte order mark
+/// `FE FF`.
+pub fn utf16be<S: AsRef<str>>(text: S) -> File {
+    let mut bytes = vec![0xFE, 0xFF];
+    for unit in text.as_ref().encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    File::from(Content::Bytes(bytes))
+}
+
+/// A UTF-8 text file preceded by the byte order mark `EF BB BF`, as written
+/// by some Windows editors.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "notes.txt": macro_files::with_bom("notes"),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "notes.txt": b"\xEF\xBB\xBFnotes",
+/// });
+/// ```
+pub fn with_bom<S: AsRef<str>>(text: S) -> File {
+    let mut bytes = vec![0xEF, 0xBB, 0xBF];
+    bytes.extend_from_slice(text.as_ref().as_bytes());
+    File::from(Content::Bytes(bytes))
+}
+
+/// A text file made of `template` with each `{{name}}` placeholder replaced
+/// by the value of `name` in `values`, a map or a list of name and value
+/// pairs.
+///
+/// Spaces around the names are ignored, `{{ name }}` works too. Creating the
+/// file fails with an [`InvalidData`](std::io::ErrorKind) error naming the
+/// placeholder if a name has no value or a placeholder is not closed. Loops
+/// and conditions need the `template` function of the feature `tera`.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut values = HashMap::new();
+/// values.insert("name", "Ada");
+///
+/// let dir = macro_files::create_temp!({
+///     "greeting.txt": macro_files::subst("Hello {{name}}!", &values),
+///     "port.txt": macro_files::subst("port = {{ port }}", [("port", 8080)]),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "greeting.txt": "Hello Ada!",
+///     "port.txt": "port = 8080",
+/// });
+/// ```
+pub fn subst<S, I, K, V>(template: S, values: I) -> File
+where
+    S: AsRef<str>,
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: fmt::Display,
+{
+    let values: HashMap<String, String> = values
+        .into_iter()
+        .map(|(name, value)| (name.as_ref().to_owned(), value.to_string()))
+        .collect();
+    match substitute(template.as_ref(), &values) {
+        Ok(text) => File::from(Content::Text(text)),
+        Err(message) => File::failing(ErrorKind::InvalidData, message),
+    }
+}
+
+/// Replace the placeholders of `template` by their values, see [`subst`].
+fn substitute(template: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        text.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..];
+        let end = placeholder
+            .find("}}")
+            .ok_or_else(|| format!("unclosed placeholder in {:?}", &rest[start..]))?;
+        let name = placeholder[..end].trim();
+        let value = values
+            .get(name)
+            .ok_or_else(|| format!("no value for the placeholder {{{{{}}}}}", name))?;
+        text.push_str(value);
+        rest = &placeholder[end + 2..];
+    }
+    text.push_str(rest);
+    Ok(text)
+}
+
+impl Tree {
+    /// Replace the line endings of the text files of the tree, nested ones
+    /// included, see [`LineEnding::apply`].
+    ///
+    /// Contents held in memory are text when they are valid UTF-8 without
+    /// NUL bytes, other files are kept as is, including files created from
+    /// other files or generated when created. Files declared afterwards are
+    /// not converted.
+    ///
+    /// ```
+    /// use macro_files::LineEnding;
+    ///
+    /// let mut tree = macro_files::Tree::new();
+    /// tree.push("a.txt", "one\ntwo\n");
+    /// tree.push("b.txt", macro_files::lf("three\r\n"));
+    /// let tree = tree.line_endings(LineEnding::CrLf);
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// tree.create_in(dir.path()).unwrap();
+    /// macro_files::assert_tree!(dir.path(), {
+    ///     "a.txt": "one\r\ntwo\r\n",
+    ///     "b.txt": "three\r\n",
+    /// });
+    /// ```
+    pub fn line_endings(self, ending: LineEnding) -> Tree {
+        self.map_files(&mut |file| file.line_ending(ending))
+    }
+}
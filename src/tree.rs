@@ -0,0 +1,1279 @@
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use crate::attrs::{Attrs, Hook};
+
+use crate::copy::copy_dir_entries;
+#[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+use crate::deferred::Deferred;
+#[cfg(unix)]
+use crate::dirfd::DirHandle;
+use crate::error::Failures;
+#[cfg(unix)]
+use crate::error::{Context, FsOperation};
+use crate::options::{check_empty, check_prefix, claim, claim_dir, Claim};
+use crate::path::{relative_key, resolve_key, Declared};
+use crate::quota::{budget_exceeded, charge};
+use crate::remove::clean_dir;
+use crate::sandbox::{confine, resolve};
+use crate::{
+    create_dir, create_hard_link, entry_kind, remove_file, Conflict, Content, CreateReport,
+    EntryKind, File, Options, SkipReason, ToFileContents,
+};
+#[cfg(feature = "tempfile")]
+use tempfile::TempDir;
+
+/// In-memory representation of a spec: the ordered entries of a directory.
+///
+/// This is what the macros build before creating anything, entries are
+/// created in their declaration order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tree {
+    entries: Vec<(PathBuf, Entry)>,
+    attrs: Attrs,
+    origin: Option<Arc<str>>,
+    handles: Vec<(PathBuf, Arc<str>)>,
+    temp: bool,
+    parent_keys: bool,
+}
+
+/// Value of a spec entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    /// A directory with its entries, declared with a map.
+    Dir(Tree),
+    /// A file with its contents, declared with an expression or `true`.
+    File(File),
+    /// A directory recursively copied from an existing directory, see
+    /// [`copy_dir`](crate::copy_dir).
+    CopyDir(PathBuf),
+    /// An empty directory which must stay empty, see [`keep`].
+    Keep,
+    /// Nothing to create, declared with `false`, `null` or [`maybe`] of
+    /// `None`.
+    Skip(SkipReason),
+}
+
+/// An empty directory which must stay empty.
+///
+/// It is created like `{}`, but compared strictly: [`Tree::diff`] reports
+/// any entry found in it as a single [`Change::NotEmpty`](crate::Change)
+/// change, which makes intentionally empty directories stand out in large
+/// specs.
+///
+/// ```
+/// let dir = macro_files::create_temp!({
+///     "cache": macro_files::keep(),
+/// })
+/// .unwrap();
+///
+/// macro_files::assert_tree!(dir.path(), {
+///     "cache": macro_files::keep(),
+/// });
+/// ```
+pub fn keep() -> Entry {
+    Entry::Keep
+}
+
+/// The entry of `value` when it is `Some`, or else an entry which is not
+/// created, as if declared with `false`.
+///
+/// ```
+/// let overrides: Option<&str> = None;
+/// let dir = macro_files::create_temp!({
+///     "config.toml": "debug = false",
+///     "override.toml": macro_files::maybe(overrides),
+/// })
+/// .unwrap();
+///
+/// assert!(!dir.path().join("override.toml").exists());
+/// ```
+pub fn maybe<E: IntoEntry>(value: Option<E>) -> Entry {
+    match value {
+        Some(value) => value.into_entry(),
+        None => Entry::Skip(SkipReason::None),
+    }
+}
+
+impl Entry {
+    /// A file entry with the given contents.
+    pub fn file<C: AsRef<[u8]>>(contents: C) -> Entry {
+        Entry::File(File::new(contents))
+    }
+}
+
+/// Conversion of the values of a spec into entries.
+///
+/// Implemented for file contents (any [`ToFileContents`] and [`Content`]),
+/// [`File`], [`Tree`] (a directory) and [`Entry`]. The macros also accept
+/// any other [`Display`](fmt::Display) value, written as text.
+pub trait IntoEntry {
+    fn into_entry(self) -> Entry;
+}
+
+impl<C: ToFileContents> IntoEntry for C {
+    fn into_entry(self) -> Entry {
+        Entry::File(File::from(self.to_file_contents()))
+    }
+}
+
+impl IntoEntry for Entry {
+    fn into_entry(self) -> Entry {
+        self
+    }
+}
+
+impl IntoEntry for Content {
+    fn into_entry(self) -> Entry {
+        Entry::File(File::from(self))
+    }
+}
+
+impl IntoEntry for File {
+    fn into_entry(self) -> Entry {
+        Entry::File(self)
+    }
+}
+
+impl IntoEntry for Tree {
+    fn into_entry(self) -> Entry {
+        Entry::Dir(self)
+    }
+}
+
+/// A value of the macros, converted with [`IntoEntry`] or else written as
+/// text with its [`Display`](fmt::Display) implementation.
+///
+/// The conversion is picked by method resolution: `IntoEntryValue` takes the
+/// value itself and `DisplayValue` a reference, which comes second. Arrays of
+/// names are directories of empty files with `NamesValue`.
+#[doc(hidden)]
+pub struct Value<T>(pub T);
+
+#[doc(hidden)]
+pub trait IntoEntryValue {
+    fn into_entry(self) -> Entry;
+}
+
+impl<T: IntoEntry> IntoEntryValue for Value<T> {
+    fn into_entry(self) -> Entry {
+        self.0.into_entry()
+    }
+}
+
+#[doc(hidden)]
+pub trait NamesValue {
+    fn into_entry(self) -> Entry;
+}
+
+impl<S: AsRef<str>, const N: usize> NamesValue for Value<[S; N]> {
+    fn into_entry(self) -> Entry {
+        let mut tree = Tree::new();
+        for name in &self.0 {
+            tree.push(name.as_ref(), Entry::file(b""));
+        }
+        Entry::Dir(tree)
+    }
+}
+
+#[doc(hidden)]
+pub trait DisplayValue {
+    fn into_entry(self) -> Entry;
+}
+
+impl<T: fmt::Display> DisplayValue for &Value<T> {
+    fn into_entry(self) -> Entry {
+        Entry::file(self.0.to_string())
+    }
+}
+
+/// The file declared by [`entry!`](crate::entry), whose creation fails when
+/// its contents are not a file.
+#[doc(hidden)]
+pub fn entry_file(entry: Entry) -> File {
+    match entry {
+        Entry::File(file) => file,
+        _ => File::failing(
+            ErrorKind::InvalidInput,
+            "the contents of `entry!` must be a file".to_owned(),
+        ),
+    }
+}
+
+/// The entry of a key ending with a separator, which declares a directory,
+/// see [`Tree::push`].
+fn dir_entry(key: &Path, entry: Entry) -> Entry {
+    match entry {
+        Entry::File(file) if file.contents() == Some(&[]) => Entry::Dir(Tree::new()),
+        Entry::File(_) => Entry::File(File::failing(
+            ErrorKind::InvalidInput,
+            format!("key {:?} ending with a separator declares a directory", key),
+        )),
+        entry => entry,
+    }
+}
+
+impl Tree {
+    /// An empty tree.
+    pub fn new() -> Tree {
+        Tree::default()
+    }
+
+    /// Add an entry after the existing ones.
+    ///
+    /// The key is validated when the tree is used, see
+    /// [`entry_path`](crate::entry_path). A key ending with a separator
+    /// declares a directory: an empty file, declared with `true` in the
+    /// macros, is an empty directory instead, and other files fail to be
+    /// created with an [`ErrorKind::InvalidInput`] error.
+    ///
+    /// ```
+    /// let dir = macro_files::create_temp!({
+    ///     "assets/": true,
+    ///     "logs/": { "app.log": "" },
+    /// })
+    /// .unwrap();
+    ///
+    /// assert!(dir.path().join("assets").is_dir());
+    /// assert!(dir.path().join("logs/app.log").is_file());
+    /// ```
+    pub fn push<K: AsRef<Path>, E: IntoEntry>(&mut self, key: K, entry: E) {
+        let key = key.as_ref();
+        let entry = entry.into_entry();
+        let entry = if key.to_string_lossy().ends_with(std::path::is_separator) {
+            dir_entry(key, entry)
+        } else {
+            entry
+        };
+        self.entries.push((key.to_owned(), entry));
+    }
+
+    /// Add the same entry under each of the keys, after the existing ones.
+    /// Declared with `[key, ...]: value` in the macros.
+    ///
+    /// The entry is cloned for each key, a [`reader`](crate::reader) is then
+    /// consumed by the first key and fails for the others.
+    ///
+    /// ```
+    /// let tree = macro_files::tree!({
+    ///     ["a.txt", "b.txt", "c.txt"]: "same contents",
+    ///     ["src", "tests"]: { "mod.rs": "" },
+    /// });
+    ///
+    /// let dir = tree.create_temp().unwrap();
+    /// macro_files::assert_tree!(dir.path(), {
+    ///     "a.txt": "same contents",
+    ///     "b.txt": "same contents",
+    ///     "c.txt": "same contents",
+    ///     "src/mod.rs": "",
+    ///     "tests/mod.rs": "",
+    /// });
+    /// ```
+    pub fn push_each<I, E>(&mut self, keys: I, entry: E)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+        E: IntoEntry,
+    {
+        let entry = entry.into_entry();
+        for key in keys {
+            self.push(key, entry.clone());
+        }
+    }
+
+    /// Add the entries of `other` after the existing ones, with their named
+    /// keys. Declared with `..other` in the macros.
+    ///
+    /// The attributes, hooks and origin of `other` are ignored, and its
+    /// entries cannot be declared again, like any entry.
+    ///
+    /// ```
+    /// let mut base = macro_files::Tree::new();
+    /// base.push("Cargo.toml", "[package]");
+    ///
+    /// let dir = macro_files::create_temp!({
+    ///     ..base.clone(),
+    ///     "src/main.rs": "fn main() {}",
+    /// })
+    /// .unwrap();
+    ///
+    /// macro_files::assert_tree!(dir.path(), {
+    ///     "Cargo.toml": "[package]",
+    ///     "src/main.rs": "fn main() {}",
+    /// });
+    /// ```
+    pub fn append(&mut self, other: Tree) {
+        self.entries.extend(other.entries);
+        self.handles.extend(other.handles);
+    }
+
+    /// Name the entry with the given key, so its path can be retrieved with
+    /// [`Manifest::handle`](crate::Manifest::handle) once created. Declared
+    /// with `"key" as name: value` in the macros.
+    ///
+    /// ```
+    /// let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    /// let manifest = macro_files::create_manifest!({
+    ///     temp_dir.path(): {
+    ///         "config" as config_dir: {
+    ///             "app.toml" as config: "debug = true",
+    ///         },
+    ///     },
+    /// })
+    /// .unwrap();
+    ///
+    /// assert_eq!(manifest["config"], temp_dir.path().join("config/app.toml"));
+    /// assert_eq!(manifest["config_dir"], temp_dir.path().join("config"));
+    /// ```
+    pub fn name<K: AsRef<Path>>(&mut self, key: K, handle: &str) {
+        self.handles
+            .push((key.as_ref().to_owned(), Arc::from(handle)));
+    }
+
+    /// Iterate over the named keys of the tree, see [`Tree::name`].
+    pub fn handles(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.handles
+            .iter()
+            .map(|(key, handle)| (key.as_path(), &**handle))
+    }
+
+    /// The tree with each file, nested ones included, replaced by `f`.
+    pub(crate) fn map_files<F: FnMut(File) -> File>(mut self, f: &mut F) -> Tree {
+        self.entries = self
+            .entries
+            .into_iter()
+            .map(|(key, entry)| {
+                let entry = match entry {
+                    Entry::File(file) => Entry::File(f(file)),
+                    Entry::Dir(tree) => Entry::Dir(tree.map_files(f)),
+                    entry => entry,
+                };
+                (key, entry)
+            })
+            .collect();
+        self
+    }
+
+    /// Iterate over the entries in their declaration order.
+    pub fn entries(&self) -> impl Iterator<Item = (&Path, &Entry)> {
+        self.entries
+            .iter()
+            .map(|(key, entry)| (key.as_path(), entry))
+    }
+
+    /// Declare the modification time of the directory, set once its entries
+    /// are created. Ignored for the tree given to the creation functions.
+    pub fn mtime(mut self, time: SystemTime) -> Tree {
+        self.attrs.modified = Some(time);
+        self
+    }
+
+    /// Declare the access time of the directory, set once its entries are
+    /// created. Ignored for the tree given to the creation functions.
+    pub fn atime(mut self, time: SystemTime) -> Tree {
+        self.attrs.accessed = Some(time);
+        self
+    }
+
+    /// Declared modification time of the directory.
+    pub fn declared_mtime(&self) -> Option<SystemTime> {
+        self.attrs.modified
+    }
+
+    /// Declared access time of the directory.
+    pub fn declared_atime(&self) -> Option<SystemTime> {
+        self.attrs.accessed
+    }
+
+    /// Declare the owner and group of the directory, set once its entries
+    /// are created. Ignored for the tree given to the creation functions.
+    #[cfg(all(feature = "unix", unix))]
+    pub fn owner(mut self, uid: u32, gid: u32) -> Tree {
+        self.attrs.owner = Some((uid, gid));
+        self
+    }
+
+    /// Declared owner and group of the directory.
+    #[cfg(all(feature = "unix", unix))]
+    pub fn declared_owner(&self) -> Option<(u32, u32)> {
+        self.attrs.owner
+    }
+
+    /// Declare the directory read-only once its entries are created, so that
+    /// no entry can be added to, removed from or renamed within it, e.g. to
+    /// simulate permission errors. Windows ignores the read-only flag of
+    /// directories. Ignored for the tree given to the creation functions.
+    ///
+    /// Its entries can only be removed once the directory is writable again,
+    /// which [`Tree::remove_in`] does.
+    ///
+    /// ```
+    /// use macro_files::Tree;
+    ///
+    /// let mut locked = Tree::new().readonly();
+    /// locked.push("data.txt", "data");
+    /// let mut spec = Tree::new();
+    /// spec.push("locked", locked);
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// spec.create_in(dir.path()).unwrap();
+    /// let metadata = std::fs::metadata(dir.path().join("locked")).unwrap();
+    /// assert!(metadata.permissions().readonly());
+    ///
+    /// spec.remove_in(dir.path()).unwrap();
+    /// assert!(!dir.path().join("locked").exists());
+    /// ```
+    pub fn readonly(mut self) -> Tree {
+        self.attrs.flags.readonly = true;
+        self
+    }
+
+    /// Whether the directory is declared read-only.
+    pub fn declared_readonly(&self) -> bool {
+        self.attrs.flags.readonly
+    }
+
+    /// Declare the permissions of the directory exact, `0o755`, or `0o555`
+    /// if read-only, whatever the umask of the process, on Unix only. Ignored
+    /// for the tree given to the creation functions.
+    pub fn exact_permissions(mut self) -> Tree {
+        self.attrs.flags.exact = true;
+        self
+    }
+
+    /// Whether the permissions of the directory are declared exact.
+    pub fn declares_exact_permissions(&self) -> bool {
+        self.attrs.flags.exact
+    }
+
+    /// Declare a function run with the path of the directory once its
+    /// entries are created, before its other attributes are applied, e.g. to
+    /// initialize a repository or a lock file in a project skeleton. Hooks
+    /// run in their declaration order, and the creation fails with the first
+    /// error they return. Ignored for the tree given to the creation
+    /// functions and when writing archives.
+    ///
+    /// ```
+    /// use macro_files::Tree;
+    ///
+    /// let mut project = Tree::new().after_create(|path| {
+    ///     let manifest = std::fs::read_to_string(path.join("Cargo.toml"))?;
+    ///     std::fs::write(path.join("Cargo.lock"), format!("# {}", manifest.len()))
+    /// });
+    /// project.push("Cargo.toml", "[package]");
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// let mut spec = Tree::new();
+    /// spec.push("project", project);
+    /// spec.create_in(dir.path()).unwrap();
+    ///
+    /// assert!(dir.path().join("project/Cargo.lock").is_file());
+    /// ```
+    pub fn after_create<F>(mut self, hook: F) -> Tree
+    where
+        F: Fn(&Path) -> Result<()> + Send + Sync + 'static,
+    {
+        self.attrs.hooks.push(Hook::new(hook));
+        self
+    }
+
+    /// Whether functions are run once the directory is created.
+    pub fn has_hooks(&self) -> bool {
+        !self.attrs.hooks.is_empty()
+    }
+
+    pub(crate) fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
+
+    /// Declare where the entries of the tree come from (e.g. the name of a
+    /// preset), which is reported for each entry created from it, unless the
+    /// entry is a file or a nested tree declaring its own origin.
+    ///
+    /// ```
+    /// let mut rust = macro_files::Tree::new().origin("preset:rust");
+    /// rust.push("Cargo.toml", "[package]");
+    ///
+    /// let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    /// let mut spec = macro_files::Tree::new();
+    /// spec.push("README.md", "# Project");
+    /// spec.push("app", rust);
+    /// let report = spec.create_in(temp_dir.path()).unwrap();
+    ///
+    /// let origins: Vec<_> = report.created().iter().map(|entry| entry.origin()).collect();
+    /// assert_eq!(origins, [None, Some("preset:rust"), Some("preset:rust")]);
+    /// ```
+    pub fn origin<S: Into<String>>(mut self, origin: S) -> Tree {
+        self.origin = Some(Arc::from(origin.into()));
+        self
+    }
+
+    /// Declared origin of the entries of the tree.
+    pub fn declared_origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// Declare that the directory is created in its own temporary directory,
+    /// linked at its path with a symlink, so that it can be dropped on its
+    /// own like an external volume. Declared with `temp { ... }` in the
+    /// macros.
+    ///
+    /// Only [`create_temp_tree!`](crate::create_temp_tree) creates nested
+    /// temporary directories, which it returns with the main one, see
+    /// [`TempTree`](crate::TempTree). Other creations fail with an
+    /// [`ErrorKind::InvalidInput`] error.
+    #[cfg(feature = "tempfile")]
+    pub fn temp(mut self) -> Tree {
+        self.temp = true;
+        self
+    }
+
+    /// Whether the directory is created in its own temporary directory.
+    #[cfg(feature = "tempfile")]
+    pub fn is_temp(&self) -> bool {
+        self.temp
+    }
+
+    /// Allow the keys of the tree to escape their parent directory with `..`
+    /// components, which are resolved against it, see
+    /// [`entry_path`](crate::entry_path). The keys of its nested directories
+    /// are still rejected unless they allow it too.
+    ///
+    /// Such keys may resolve outside of the directory the tree is created
+    /// in, which [`Options::sandbox`] prevents.
+    ///
+    /// ```
+    /// use macro_files::Tree;
+    ///
+    /// let mut docs = Tree::new().allow_parent_keys();
+    /// docs.push("../README.md", "# Project");
+    /// let mut tree = Tree::new();
+    /// tree.push("docs", docs);
+    ///
+    /// let dir = macro_files::tempfile::tempdir().unwrap();
+    /// tree.create_in(dir.path()).unwrap();
+    ///
+    /// assert!(dir.path().join("README.md").is_file());
+    /// ```
+    pub fn allow_parent_keys(mut self) -> Tree {
+        self.parent_keys = true;
+        self
+    }
+
+    /// Whether the keys of the tree may escape their parent directory.
+    pub fn allows_parent_keys(&self) -> bool {
+        self.parent_keys
+    }
+
+    /// Join a key of the tree to the path of its directory, see
+    /// [`entry_path`](crate::entry_path).
+    pub(crate) fn key_path(&self, dir: &Path, key: &Path) -> Result<PathBuf> {
+        resolve_key(dir, key, self.parent_keys)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn origin_arc(&self) -> Option<&Arc<str>> {
+        self.origin.as_ref()
+    }
+
+    /// Whether the tree has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Move the entries of the tree under the relative path `prefix`.
+    ///
+    /// The attributes and origin of the tree move with its entries. Fails
+    /// with an [`InvalidKey`](crate::InvalidKey) error if `prefix` is not a
+    /// valid key.
+    ///
+    /// ```
+    /// let mut fixture = macro_files::Tree::new();
+    /// fixture.push("Cargo.toml", "[package]");
+    /// let tree = fixture.rebase("crates/app").unwrap();
+    ///
+    /// let (key, _) = tree.entries().next().unwrap();
+    /// assert_eq!(key, std::path::Path::new("crates/app"));
+    /// ```
+    pub fn rebase<P: AsRef<Path>>(self, prefix: P) -> Result<Tree> {
+        let prefix = relative_key(prefix.as_ref())?;
+        let mut tree = Tree::new();
+        tree.push(prefix, self);
+        Ok(tree)
+    }
+
+    /// Move the entries of the tree out of the relative path `prefix`, the
+    /// reverse of [`Tree::rebase`].
+    ///
+    /// Nested keys are split and directories declared several times are
+    /// merged, the attributes of the directory at `prefix` become the ones of
+    /// the tree. Fails with an [`ErrorKind::InvalidInput`] error if an entry
+    /// is not within `prefix`, skipped entries aside.
+    ///
+    /// ```
+    /// let mut crates = macro_files::Tree::new();
+    /// crates.push("app/Cargo.toml", "[package]");
+    /// let mut tree = macro_files::Tree::new();
+    /// tree.push("crates", crates);
+    /// tree.push("crates/app/src/main.rs", "fn main() {}");
+    /// let app = tree.strip_prefix("crates/app").unwrap();
+    ///
+    /// let keys: Vec<_> = app.entries().map(|(key, _)| key).collect();
+    /// assert_eq!(keys, ["Cargo.toml", "src/main.rs"]);
+    /// ```
+    pub fn strip_prefix<P: AsRef<Path>>(self, prefix: P) -> Result<Tree> {
+        let prefix = relative_key(prefix.as_ref())?;
+        let mut tree = self;
+        for component in prefix.components() {
+            tree = tree.strip_component(component.as_os_str(), &prefix)?;
+        }
+        Ok(tree)
+    }
+
+    fn strip_component(self, name: &OsStr, prefix: &Path) -> Result<Tree> {
+        let mut stripped = Tree {
+            origin: self.origin,
+            ..Tree::default()
+        };
+        for (key, handle) in self.handles {
+            let key = relative_key(&key)?;
+            let mut components = key.components();
+            if components.next().map(|component| component.as_os_str()) == Some(name) {
+                let rest = components.as_path();
+                if !rest.as_os_str().is_empty() {
+                    stripped.handles.push((rest.to_owned(), handle));
+                }
+            }
+        }
+        for (key, entry) in self.entries {
+            if let Entry::Skip(_) = entry {
+                continue;
+            }
+            let key = relative_key(&key)?;
+            let mut components = key.components();
+            if components.next().map(|component| component.as_os_str()) != Some(name) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("entry {:?} is not within {:?}", key, prefix),
+                ));
+            }
+            let rest = components.as_path();
+            if !rest.as_os_str().is_empty() {
+                stripped.entries.push((rest.to_owned(), entry));
+                continue;
+            }
+            match entry {
+                Entry::Dir(tree) => {
+                    stripped.attrs = tree.attrs;
+                    stripped.handles.extend(tree.handles);
+                    let origin = tree.origin;
+                    for (key, entry) in tree.entries {
+                        stripped.entries.push((key, with_origin(entry, &origin)));
+                    }
+                }
+                Entry::Keep => {}
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("entry {:?} is not a declared directory", key),
+                    ))
+                }
+            }
+        }
+        Ok(stripped)
+    }
+
+    /// Create the directories and files of the tree within `dir`.
+    ///
+    /// Creation stops at the first error. On success, the returned report
+    /// lists the created and skipped entries.
+    pub fn create_in<P: AsRef<Path>>(&self, dir: P) -> Result<CreateReport> {
+        self.create_with(dir, &Options::default())
+    }
+
+    /// Create the directories and files of the tree within `dir` with the
+    /// given options, see [`Tree::create_in`].
+    pub fn create_with<P: AsRef<Path>>(&self, dir: P, options: &Options) -> Result<CreateReport> {
+        Creation::new(dir.as_ref(), options).run(self)
+    }
+
+    /// Create the directories and files of the tree within the absolute,
+    /// existing directory `root` with the given options, see
+    /// [`create_at!`](crate::create_at).
+    ///
+    /// Fails before creating anything with an [`ErrorKind::InvalidInput`]
+    /// error when `root` is relative or not a directory, or with an
+    /// [`ErrorKind::NotFound`] error when it is missing.
+    pub fn create_at<P: AsRef<Path>>(&self, root: P, options: &Options) -> Result<CreateReport> {
+        let root = root.as_ref();
+        if !root.is_absolute() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("root is not an absolute path: {:?}", root),
+            ));
+        }
+        if !std::fs::metadata(root)?.is_dir() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("root is not a directory: {:?}", root),
+            ));
+        }
+        self.create_with(root, options)
+    }
+
+    /// Create the tree within `dir`, adding the nested temporary directories
+    /// to `nested`, see [`Tree::temp`].
+    #[cfg(feature = "tempfile")]
+    pub(crate) fn create_nested(
+        &self,
+        dir: &Path,
+        nested: &mut Vec<(PathBuf, TempDir)>,
+    ) -> Result<CreateReport> {
+        let options = Options::default();
+        let mut creation = Creation::new(dir, &options);
+        creation.nested = Some(nested);
+        creation.run(self)
+    }
+
+    /// Create the missing directories and files of the tree within `dir`,
+    /// leaving the existing ones untouched, see
+    /// [`Conflict::Skip`](crate::Conflict::Skip).
+    ///
+    /// The returned report lists the existing entries as skipped.
+    pub fn merge_in<P: AsRef<Path>>(&self, dir: P) -> Result<CreateReport> {
+        self.create_with(dir, &Options::new().on_conflict(Conflict::Skip))
+    }
+}
+
+/// The entry declaring `origin` unless it declares its own, for entries moved
+/// out of the tree declaring it.
+fn with_origin(entry: Entry, origin: &Option<Arc<str>>) -> Entry {
+    let origin = match origin {
+        Some(origin) => origin,
+        None => return entry,
+    };
+    match entry {
+        Entry::Dir(tree) if tree.origin.is_none() => Entry::Dir(Tree {
+            origin: Some(origin.clone()),
+            ..tree
+        }),
+        Entry::File(file) if file.declared_origin().is_none() => {
+            Entry::File(file.origin(origin.to_string()))
+        }
+        entry => entry,
+    }
+}
+
+/// State of the creation of a tree with the current filesystem operations.
+pub(crate) struct Creation<'a> {
+    root: &'a Path,
+    options: &'a Options,
+    pub(crate) report: CreateReport,
+    /// Origin of the tree whose entries are being created.
+    origin: Option<Arc<str>>,
+    /// Resolved directory the entries must be created in, see
+    /// [`Options::sandbox`].
+    sandbox: Option<PathBuf>,
+    /// Entries that failed to be created, see [`Options::best_effort`].
+    failures: Failures,
+    /// Paths of the entries of the spec, which must be declared once.
+    declared: Declared,
+    /// Nested temporary directories created, see [`Tree::temp`].
+    #[cfg(feature = "tempfile")]
+    nested: Option<&'a mut Vec<(PathBuf, TempDir)>>,
+    /// Files left to be written once all the directories are created, see
+    /// [`Options::parallel`] and [`Options::io_uring`].
+    #[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+    deferred: Option<Deferred<'a>>,
+    /// Directory whose entries are being created relative to it, unless the
+    /// filesystem operations are overridden.
+    #[cfg(unix)]
+    dir: Option<DirHandle>,
+    /// Files written from their contents, by hash of the contents, see
+    /// [`Options::deduplicate`].
+    originals: HashMap<u64, Vec<Original<'a>>>,
+    /// Bytes of file contents written, see [`Options::max_bytes`].
+    bytes: u64,
+}
+
+/// A file written from its contents, which duplicates are linked to.
+pub(crate) struct Original<'a> {
+    pub(crate) file: &'a File,
+    pub(crate) contents: Cow<'a, [u8]>,
+    pub(crate) path: PathBuf,
+}
+
+/// The hash of `contents` under which the originals with these contents are
+/// looked up.
+pub(crate) fn contents_hash(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<'a> Creation<'a> {
+    fn new(root: &'a Path, options: &'a Options) -> Creation<'a> {
+        Creation {
+            root,
+            options,
+            report: CreateReport::default(),
+            origin: None,
+            sandbox: None,
+            failures: Failures::new(options),
+            declared: Declared::new(options.allows_case_collisions()),
+            #[cfg(feature = "tempfile")]
+            nested: None,
+            #[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+            deferred: None,
+            #[cfg(unix)]
+            dir: None,
+            originals: HashMap::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Create the entries of `tree` within the root directory.
+    fn run(mut self, tree: &'a Tree) -> Result<CreateReport> {
+        let (root, options) = (self.root, self.options);
+        let start = Instant::now();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("create", root = %root.display()).entered();
+        #[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+        if options.defers_files() && !crate::fs::is_overridden() {
+            self.deferred = Some(Deferred::default());
+        }
+        if options.is_sandboxed() && !root.as_os_str().is_empty() {
+            self.sandbox = Some(resolve(root)?);
+        }
+        if let Some(max_bytes) = options.declared_max_bytes() {
+            if !options.deduplicates() && tree.stats().bytes > max_bytes {
+                return Err(budget_exceeded(max_bytes));
+            }
+        }
+        if let Some(prefix) = options.required_prefix() {
+            check_prefix(root, prefix)?;
+        }
+        if options.requires_empty() {
+            check_empty(root)?;
+        }
+        if options.prints_rerun_if_changed() {
+            tree.print_rerun_if_changed();
+        }
+        if options.cleans() {
+            let removed = clean_dir(root)?;
+            self.report.set_removed(removed);
+        }
+        if let Some(quota) = options.declared_quota() {
+            let evicted = quota.before_create(root, tree.stats().bytes)?;
+            self.report.set_evicted(evicted);
+        }
+        let fixture = match options.declared_cache() {
+            Some(cache) if !crate::fs::is_overridden() => cache.materialize(tree, options)?,
+            _ => None,
+        };
+        match fixture {
+            Some(fixture) => copy_dir_entries(&mut self, &fixture, root)?,
+            None => self.create_entries(tree, root)?,
+        }
+        #[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+        self.write_deferred()?;
+        if let Some(quota) = options.declared_quota() {
+            quota.after_create(root)?;
+        }
+        options.check_dangling_symlinks(self.report.created())?;
+        self.report.set_elapsed(start.elapsed());
+        self.failures.finish(self.report)
+    }
+
+    fn create_entries(&mut self, tree: &'a Tree, dir: &Path) -> Result<()> {
+        let origin = tree.origin.clone().or_else(|| self.origin.clone());
+        let parent = std::mem::replace(&mut self.origin, origin);
+        #[cfg(unix)]
+        let parent_dir = {
+            let handle = self.open_dir(dir);
+            std::mem::replace(&mut self.dir, handle)
+        };
+        let result = self.create_tree_entries(tree, dir);
+        self.origin = parent;
+        #[cfg(unix)]
+        {
+            self.dir = parent_dir;
+        }
+        result
+    }
+
+    /// Open the directory at `path` to create its entries relative to it,
+    /// relative to the directory containing it when it is open.
+    #[cfg(unix)]
+    fn open_dir(&self, path: &Path) -> Option<DirHandle> {
+        if crate::fs::is_overridden() {
+            return None;
+        }
+        match self.parent_dir(path) {
+            Some((dir, name)) => dir.open_dir(name).ok(),
+            None => DirHandle::open(path).ok(),
+        }
+    }
+
+    /// The open directory containing the entry at `path`, with its name.
+    #[cfg(unix)]
+    fn parent_dir<'p>(&self, path: &'p Path) -> Option<(&DirHandle, &'p OsStr)> {
+        let dir = self.dir.as_ref()?;
+        match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) if dir.path() == parent => Some((dir, name)),
+            _ => None,
+        }
+    }
+
+    /// Create the directory at `path`, relative to the open directory
+    /// containing it if any.
+    fn make_dir(&self, path: &Path) -> Result<()> {
+        #[cfg(unix)]
+        if let Some((dir, name)) = self.parent_dir(path) {
+            return crate::fs::traced(FsOperation::CreateDir, path, None, || dir.create_dir(name))
+                .context(FsOperation::CreateDir, path);
+        }
+        create_dir(path)
+    }
+
+    /// Write `file` at `path` within the budget of the creation, as a hard
+    /// link to a file with the same contents when deduplicating, see
+    /// [`Options::max_bytes`] and [`Options::deduplicate`].
+    fn write_file(&mut self, file: &'a File, path: &Path) -> Result<()> {
+        let options = self.options;
+        if let (Some(max_bytes), Content::Reader(reader)) =
+            (options.declared_max_bytes(), file.content())
+        {
+            let remaining = max_bytes.saturating_sub(self.bytes);
+            let written =
+                file.write_reader_within(reader, path, options.write_options(), remaining)?;
+            self.bytes += written.ok_or_else(|| budget_exceeded(max_bytes))?;
+            return Ok(());
+        }
+        let size = file.len().or_else(|| {
+            let metadata = std::fs::metadata(file.source()?).ok()?;
+            Some(metadata.len())
+        });
+        let unknown_size = options.declared_max_bytes().is_some() && size.is_none();
+        if !file.is_in_memory() || !(options.deduplicates() || unknown_size) {
+            self.charge(size.unwrap_or(0))?;
+            return self.write_contents(file, path);
+        }
+        let contents = file.read_contents()?;
+        if !options.deduplicates() {
+            self.charge(contents.len() as u64)?;
+            return file.write_read(&contents, path, options.write_options());
+        }
+        let hash = contents_hash(&contents);
+        let originals = self.originals.entry(hash).or_default();
+        let index = originals
+            .iter()
+            .position(|original| original.contents == contents && original.file.has_attrs_of(file));
+        if let Some(index) = index {
+            let linked = file
+                .verify(path, Some(&contents))
+                .and_then(|()| create_hard_link(&originals[index].path, path));
+            if linked.is_ok() {
+                return Ok(());
+            }
+        }
+        self.charge(contents.len() as u64)?;
+        file.write_read(&contents, path, options.write_options())?;
+        let originals = self.originals.entry(hash).or_default();
+        match index {
+            Some(index) => originals[index].path = path.to_owned(),
+            None => originals.push(Original {
+                file,
+                contents,
+                path: path.to_owned(),
+            }),
+        }
+        Ok(())
+    }
+
+    /// Count `size` more bytes written by the creation, failing when they
+    /// exceed its budget, see [`Options::max_bytes`].
+    pub(crate) fn charge(&mut self, size: u64) -> Result<()> {
+        charge(&mut self.bytes, size, self.options.declared_max_bytes())
+    }
+
+    /// Write `file` at `path`, relative to the open directory containing it
+    /// if any and its contents are in memory.
+    fn write_contents(&self, file: &File, path: &Path) -> Result<()> {
+        let options = self.options.write_options();
+        #[cfg(unix)]
+        if let Some((dir, name)) = self.parent_dir(path) {
+            if file.write_at(dir, name, path, options)? {
+                return Ok(());
+            }
+        }
+        file.write(self.root, path, options)
+    }
+
+    fn create_tree_entries(&mut self, tree: &'a Tree, dir: &Path) -> Result<()> {
+        for (key, handle) in &tree.handles {
+            self.report
+                .push_handle(handle.clone(), tree.key_path(dir, key)?);
+        }
+        for (key, entry) in &tree.entries {
+            let path = tree.key_path(dir, key).unwrap_or_else(|_| dir.join(key));
+            let result = self
+                .declared
+                .declare(key, &path)
+                .and_then(|()| match entry {
+                    Entry::Skip(reason) => {
+                        self.report.push_skipped(path.clone(), *reason);
+                        Ok(())
+                    }
+                    entry => tree
+                        .key_path(dir, key)
+                        .and_then(|path| self.create_entry(entry, dir, path)),
+                });
+            if let Err(err) = result {
+                self.failures.push(path, err)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn create_entry(&mut self, entry: &'a Entry, dir: &Path, path: PathBuf) -> Result<()> {
+        self.confine(&path, !matches!(entry, Entry::File(_)))?;
+        match entry {
+            #[cfg(feature = "tempfile")]
+            Entry::Dir(tree) if tree.temp => self.create_temp_dir(tree, path)?,
+            Entry::Dir(tree) => {
+                let claim = self.claim_dir(&path)?;
+                if claim == Claim::Skip {
+                    return Ok(());
+                }
+                if claim == Claim::Create {
+                    self.make_dir(&path)?;
+                    self.created(path.clone(), EntryKind::Dir, tree.origin.as_ref());
+                }
+                self.within(dir, &path, |creation| creation.create_entries(tree, &path))?;
+                if claim == Claim::Reuse {
+                    return Ok(());
+                }
+                #[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+                if let Some(deferred) = &mut self.deferred {
+                    if !tree.attrs.is_empty() {
+                        deferred.dirs.push((&tree.attrs, path));
+                    }
+                    return Ok(());
+                }
+                tree.attrs.apply(&path)?;
+            }
+            Entry::File(file) => {
+                if file.appends() || self.claim_file(&path, file)? {
+                    #[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+                    if self.deferred.is_some()
+                        && (self.options.declared_max_bytes().is_none() || file.len().is_some())
+                    {
+                        self.charge(file.len().unwrap_or(0))?;
+                        let origin = file.origin_arc().or(self.origin.as_ref()).cloned();
+                        if let Some(deferred) = &mut self.deferred {
+                            deferred.files.push((file, path, origin));
+                        }
+                        return Ok(());
+                    }
+                    self.write_file(file, &path)?;
+                    let origin = file.origin_arc().or(self.origin.as_ref()).cloned();
+                    self.created_file(path, Some(file), origin)?;
+                }
+            }
+            Entry::CopyDir(source) => {
+                if !self.create_dir(&path)? {
+                    return Ok(());
+                }
+                self.within(dir, &path, |creation| {
+                    copy_dir_entries(creation, source, &path)
+                })?;
+            }
+            Entry::Keep => {
+                self.create_dir(&path)?;
+            }
+            Entry::Skip(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Write the deferred files, then apply the deferred attributes of
+    /// directories, which are deferred after their entries.
+    #[cfg(any(feature = "rayon", all(feature = "io-uring", target_os = "linux")))]
+    fn write_deferred(&mut self) -> Result<()> {
+        let deferred = match self.deferred.take() {
+            Some(deferred) => deferred,
+            None => return Ok(()),
+        };
+        let results = deferred.write_files(self.root, self.options);
+        for ((file, path, origin), result) in deferred.files.into_iter().zip(results) {
+            let result = result.and_then(|()| self.created_file(path.clone(), Some(file), origin));
+            if let Err(err) = result {
+                self.failures.push(path, err)?;
+            }
+        }
+        for (attrs, path) in deferred.dirs {
+            if let Err(err) = attrs.apply(&path) {
+                self.failures.push(path, err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the directory of `tree` in its own temporary directory linked
+    /// at `path`, see [`Tree::temp`].
+    #[cfg(feature = "tempfile")]
+    fn create_temp_dir(&mut self, tree: &'a Tree, path: PathBuf) -> Result<()> {
+        if self.nested.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "nested temporary directory {:?} is only created by create_temp_tree!",
+                    path
+                ),
+            ));
+        }
+        let dir = crate::temp_dir(&tempfile::Builder::new(), None)?;
+        crate::create_symlink(dir.path(), &path)?;
+        self.created(path.clone(), EntryKind::Symlink, tree.origin.as_ref());
+        let sandbox = match &self.sandbox {
+            Some(_) => Some(resolve(dir.path())?),
+            None => None,
+        };
+        let parent = std::mem::replace(&mut self.sandbox, sandbox);
+        let result = self.create_entries(tree, dir.path());
+        self.sandbox = parent;
+        result?;
+        tree.attrs.apply(dir.path())?;
+        if let Some(nested) = &mut self.nested {
+            nested.push((path, dir));
+        }
+        Ok(())
+    }
+
+    /// Run `create` for the entries of the directory at `path`, created in
+    /// `dir`. Sandboxed directories at the root of a tree created in the
+    /// current directory are the root of their entries.
+    fn within<F>(&mut self, dir: &Path, path: &Path, create: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        if !self.options.is_sandboxed() || !dir.as_os_str().is_empty() {
+            return create(self);
+        }
+        let parent = self.sandbox.replace(resolve(path)?);
+        let result = create(self);
+        self.sandbox = parent;
+        result
+    }
+
+    /// Fail if the entry at `path` is outside of the sandbox, see
+    /// [`Options::sandbox`].
+    pub(crate) fn confine(&self, path: &Path, dir: bool) -> Result<()> {
+        match &self.sandbox {
+            Some(root) => confine(root, path, dir),
+            None => Ok(()),
+        }
+    }
+
+    /// Report the entry created at `path`, from `origin` or else from the
+    /// origin of the tree being created.
+    pub(crate) fn created(&mut self, path: PathBuf, kind: EntryKind, origin: Option<&Arc<str>>) {
+        let origin = origin.or(self.origin.as_ref()).cloned();
+        let created = self.report.push_created(path, kind, origin);
+        self.options.report_progress(created);
+    }
+
+    /// Report the file created at `path` from `file` or else copied, from
+    /// `origin` or else from the origin of the tree being created, with its
+    /// size and the digest of its contents when computing checksums, see
+    /// [`Options::checksums`].
+    pub(crate) fn created_file(
+        &mut self,
+        path: PathBuf,
+        file: Option<&File>,
+        origin: Option<Arc<str>>,
+    ) -> Result<()> {
+        #[cfg(feature = "checksum")]
+        let sha256 = match file.map_or(EntryKind::File, File::kind) {
+            EntryKind::File if self.options.computes_checksums() => {
+                Some(crate::checksum::file_sha256(file, &path)?)
+            }
+            _ => None,
+        };
+        // Sizes only known once written are read back from the file.
+        let size = match file {
+            Some(file) if file.kind() != EntryKind::File || file.len().is_some() => None,
+            _ => std::fs::metadata(&path).ok().map(|metadata| metadata.len()),
+        };
+        let origin = origin.or_else(|| self.origin.clone());
+        let created = match file {
+            Some(file) => self.report.push_created_file(path, file, origin),
+            None => self.report.push_created(path, EntryKind::File, origin),
+        };
+        if let Some(size) = size {
+            created.set_size(size);
+        }
+        #[cfg(feature = "checksum")]
+        if let Some(sha256) = sha256 {
+            created.set_sha256(sha256);
+        }
+        self.options.report_progress(created);
+        Ok(())
+    }
+
+    /// Whether the file at `path` must be created according to the conflict
+    /// policy, removing the existing entry if it must be replaced.
+    pub(crate) fn claim(&mut self, path: &Path, link: bool) -> Result<bool> {
+        self.claim_with(path, link, self.options.conflict())
+    }
+
+    /// Whether the file at `path` must be created according to the conflict
+    /// policy declared by `file`, or else by the options.
+    fn claim_file(&mut self, path: &Path, file: &File) -> Result<bool> {
+        let conflict = file.declared_conflict().unwrap_or(self.options.conflict());
+        self.claim_with(path, file.is_link(), conflict)
+    }
+
+    fn claim_with(&mut self, path: &Path, link: bool, conflict: Conflict) -> Result<bool> {
+        let existing = entry_kind(path)?;
+        match claim(conflict, &mut self.report, path, existing, link)? {
+            Claim::Create => Ok(true),
+            Claim::Replace => remove_file(path).and(Ok(true)),
+            Claim::Skip | Claim::Reuse => Ok(false),
+        }
+    }
+
+    /// How to create the directory at `path` according to the conflict
+    /// policy, see [`Conflict::Skip`](crate::Conflict::Skip).
+    pub(crate) fn claim_dir(&mut self, path: &Path) -> Result<Claim> {
+        let existing = if self.options.conflict() == Conflict::Skip {
+            entry_kind(path)?
+        } else {
+            None
+        };
+        Ok(claim_dir(self.options, &mut self.report, path, existing))
+    }
+
+    /// Create the directory at `path` unless it must be left as is, see
+    /// [`Creation::claim_dir`]. Returns whether its entries must be created.
+    pub(crate) fn create_dir(&mut self, path: &Path) -> Result<bool> {
+        match self.claim_dir(path)? {
+            Claim::Create => {
+                self.make_dir(path)?;
+                self.created(path.to_owned(), EntryKind::Dir, None);
+                Ok(true)
+            }
+            Claim::Reuse => Ok(true),
+            Claim::Replace | Claim::Skip => Ok(false),
+        }
+    }
+}
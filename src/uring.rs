@@ -0,0 +1,243 @@
+use std::borrow::Cow;
+use std::ffi::CString;
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use io_uring::{opcode, squeue, types, IoUring};
+
+use crate::error::Context;
+use crate::{File, FsOperation, WriteOptions, WriteStrategy};
+
+/// Number of files opened at once, which is also the number of entries of
+/// the ring.
+const BATCH_SIZE: usize = 256;
+
+/// Largest write submitted at once.
+const MAX_WRITE: usize = 1 << 30;
+
+/// Write the files in batches with `io_uring`, see
+/// [`Options::io_uring`](crate::Options::io_uring). Returns the result of
+/// each file, in order.
+pub(crate) fn write_files(
+    files: &[(&File, &Path)],
+    root: &Path,
+    options: &WriteOptions,
+) -> Vec<Result<()>> {
+    let mut ring = match IoUring::new(BATCH_SIZE as u32) {
+        Ok(ring) => ring,
+        Err(_) => {
+            return files
+                .iter()
+                .map(|(file, path)| file.write(root, path, options))
+                .collect()
+        }
+    };
+    let mut results: Vec<Result<()>> = files.iter().map(|_| Ok(())).collect();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for (index, (file, path)) in files.iter().enumerate() {
+        if !file.is_in_memory() {
+            results[index] = file.write(root, path, options);
+            continue;
+        }
+        let contents = file
+            .read_contents()
+            .and_then(|contents| file.verify(path, Some(&contents)).map(|()| contents));
+        match contents {
+            Ok(contents) => batch.push(Pending::new(index, path, contents)),
+            Err(err) => results[index] = Err(err),
+        }
+        if batch.len() == BATCH_SIZE {
+            write_batch(&mut ring, &mut batch, options);
+            finish_batch(files, &mut batch, &mut results, root, options);
+        }
+    }
+    write_batch(&mut ring, &mut batch, options);
+    finish_batch(files, &mut batch, &mut results, root, options);
+    results
+}
+
+/// A file of the batch being written.
+struct Pending<'a> {
+    index: usize,
+    path: &'a Path,
+    contents: Cow<'a, [u8]>,
+    written: usize,
+    fd: Option<i32>,
+    result: Result<()>,
+}
+
+impl<'a> Pending<'a> {
+    fn new(index: usize, path: &'a Path, contents: Cow<'a, [u8]>) -> Pending<'a> {
+        Pending {
+            index,
+            path,
+            contents,
+            written: 0,
+            fd: None,
+            result: Ok(()),
+        }
+    }
+
+    /// Record the result of an operation, keeping the first error.
+    fn fail(&mut self, err: Error) {
+        if self.result.is_ok() {
+            self.result = Err(err);
+        }
+    }
+}
+
+/// Open, write then close the files of the batch, recording their results.
+fn write_batch(ring: &mut IoUring, batch: &mut [Pending<'_>], options: &WriteOptions) {
+    let paths: Vec<_> = batch
+        .iter()
+        .map(|pending| CString::new(pending.path.as_os_str().as_bytes()))
+        .collect();
+    let flags = libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC;
+    let opens = paths.iter().map(|path| {
+        let path = path.as_ref().ok()?;
+        let open = opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), path.as_ptr())
+            .flags(flags)
+            .mode(0o666);
+        Some(open.build())
+    });
+    let results = submit(ring, opens.collect());
+    for ((pending, path), result) in batch.iter_mut().zip(&paths).zip(results) {
+        match (path, result) {
+            (Err(err), _) => pending.fail(Error::new(ErrorKind::InvalidInput, err.clone())),
+            (Ok(_), Some(Ok(fd))) => pending.fd = Some(fd),
+            (Ok(_), Some(Err(err))) => pending.fail(err),
+            (Ok(_), None) => {}
+        }
+    }
+
+    let max_write = match options.write_strategy() {
+        WriteStrategy::WriteAll => MAX_WRITE,
+        WriteStrategy::Chunked => options.chunk_size().min(MAX_WRITE),
+    };
+    loop {
+        let writes: Vec<_> = batch
+            .iter()
+            .map(|pending| match pending.fd {
+                Some(fd) if pending.result.is_ok() && pending.written < pending.contents.len() => {
+                    let remaining = &pending.contents[pending.written..];
+                    let len = remaining.len().min(max_write) as u32;
+                    Some(
+                        opcode::Write::new(types::Fd(fd), remaining.as_ptr(), len)
+                            .offset(pending.written as u64)
+                            .build(),
+                    )
+                }
+                _ => None,
+            })
+            .collect();
+        if writes.iter().all(Option::is_none) {
+            break;
+        }
+        for (pending, result) in batch.iter_mut().zip(submit(ring, writes)) {
+            match result {
+                Some(Ok(0)) => pending.fail(Error::from(ErrorKind::WriteZero)),
+                Some(Ok(written)) => pending.written += written as usize,
+                Some(Err(err)) => pending.fail(err),
+                None => {}
+            }
+        }
+    }
+
+    if options.syncs_data() {
+        let syncs = batch
+            .iter()
+            .map(|pending| match pending.fd {
+                Some(fd) if pending.result.is_ok() => Some(
+                    opcode::Fsync::new(types::Fd(fd))
+                        .flags(types::FsyncFlags::DATASYNC)
+                        .build(),
+                ),
+                _ => None,
+            })
+            .collect();
+        for (pending, result) in batch.iter_mut().zip(submit(ring, syncs)) {
+            if let Some(Err(err)) = result {
+                pending.fail(err);
+            }
+        }
+    }
+
+    let closes = batch
+        .iter()
+        .map(|pending| {
+            pending
+                .fd
+                .map(|fd| opcode::Close::new(types::Fd(fd)).build())
+        })
+        .collect();
+    for (pending, result) in batch.iter_mut().zip(submit(ring, closes)) {
+        if let Some(Err(err)) = result {
+            pending.fail(err);
+        }
+    }
+}
+
+/// Record the results of the written batch and apply the attributes of
+/// the files. The files whose directory is missing are written as usual,
+/// which creates it.
+fn finish_batch(
+    files: &[(&File, &Path)],
+    batch: &mut Vec<Pending<'_>>,
+    results: &mut [Result<()>],
+    root: &Path,
+    options: &WriteOptions,
+) {
+    for pending in batch.drain(..) {
+        let (file, path) = files[pending.index];
+        results[pending.index] = match pending.result {
+            Err(err) if err.kind() == ErrorKind::NotFound => file.write(root, path, options),
+            Err(err) => Err(err).context(FsOperation::WriteFile, path),
+            Ok(()) => file.attrs().apply(path),
+        };
+    }
+}
+
+/// Submit the operations and wait for their completion. Returns the result
+/// of each operation, in order, `None` for the operations left out.
+fn submit(ring: &mut IoUring, operations: Vec<Option<squeue::Entry>>) -> Vec<Option<Result<i32>>> {
+    let mut results: Vec<_> = operations.iter().map(|_| None).collect();
+    let mut submitted = Vec::new();
+    for (index, operation) in operations.into_iter().enumerate() {
+        if let Some(operation) = operation {
+            let operation = operation.user_data(index as u64);
+            // The batch never exceeds the entries of the ring, which are all
+            // completed before the next submission.
+            if unsafe { ring.submission().push(&operation) }.is_err() {
+                results[index] = Some(Err(Error::new(ErrorKind::Other, "io_uring queue full")));
+                continue;
+            }
+            submitted.push(index);
+        }
+    }
+    let mut completed = 0;
+    while completed < submitted.len() {
+        match ring.submit_and_wait(submitted.len() - completed) {
+            Ok(_) => {}
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => {
+                for &index in &submitted {
+                    if results[index].is_none() {
+                        results[index] = Some(Err(Error::new(err.kind(), err.to_string())));
+                    }
+                }
+                return results;
+            }
+        }
+        for entry in ring.completion() {
+            let result = entry.result();
+            results[entry.user_data() as usize] = Some(if result < 0 {
+                Err(Error::from_raw_os_error(-result))
+            } else {
+                Ok(result)
+            });
+            completed += 1;
+        }
+    }
+    results
+}
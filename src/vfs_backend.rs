@@ -0,0 +1,213 @@
+use std::io::{self, Error, ErrorKind, Read, Result, Write};
+use std::path::{Component, Path};
+use std::time::SystemTime;
+
+use vfs::error::VfsErrorKind;
+use vfs::{VfsError, VfsFileType, VfsPath};
+
+use crate::{EntryKind, FileAttributes, FsOps, WriteOptions};
+
+/// Filesystem operations creating the entries within a [`vfs::VfsPath`], so
+/// that specs can be created into any [`vfs`] filesystem, physical, in memory
+/// or overlaid, see [`create_with_fs!`](crate::create_with_fs).
+///
+/// _This requires the feature `vfs`._
+///
+/// Paths are resolved against the given root, absolute paths included.
+/// Files copied with [`from`](crate::from) or [`copy_dir`](crate::copy_dir)
+/// are read from the filesystem, or from the disk if they are not found
+/// there, and hard links are created as copies of their original. Symlinks,
+/// special files, times, owners and flags are not supported by [`vfs`] and
+/// fail with an [`ErrorKind::Unsupported`] error.
+///
+/// ```
+/// use macro_files::vfs::{MemoryFS, VfsPath};
+/// use macro_files::VfsFs;
+///
+/// let root = VfsPath::new(MemoryFS::new());
+/// macro_files::create_with_fs!(VfsFs::new(root.clone()), {
+///     "project": {
+///         "README.md": "# Project",
+///     },
+/// })
+/// .unwrap();
+///
+/// let readme = root.join("project/README.md").unwrap();
+/// assert_eq!(readme.read_to_string().unwrap(), "# Project");
+/// ```
+#[derive(Debug, Clone)]
+pub struct VfsFs {
+    root: VfsPath,
+}
+
+impl VfsFs {
+    /// Filesystem operations creating the entries within `root`.
+    pub fn new(root: VfsPath) -> VfsFs {
+        VfsFs { root }
+    }
+
+    /// The root the entries are created in.
+    pub fn root(&self) -> &VfsPath {
+        &self.root
+    }
+
+    /// The path of the [`vfs`] filesystem matching `path`.
+    fn path(&self, path: &Path) -> Result<VfsPath> {
+        let mut joined = self.root.clone();
+        for component in path.components() {
+            if let Component::Normal(name) = component {
+                let name = name.to_str().ok_or_else(|| {
+                    Error::new(ErrorKind::InvalidInput, "paths must be valid UTF-8")
+                })?;
+                joined = joined.join(name).map_err(io_error)?;
+            }
+        }
+        Ok(joined)
+    }
+
+    /// Create the file at `path` with its missing parent directories.
+    fn create_file(&self, path: &Path) -> Result<Box<dyn Write + Send>> {
+        let path = self.path(path)?;
+        path.parent().create_dir_all().map_err(io_error)?;
+        path.create_file().map_err(io_error)
+    }
+
+    /// Contents of the file at `path`, or of the file on disk if missing.
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        let vfs_path = self.path(path)?;
+        if !vfs_path.exists().map_err(io_error)? {
+            return std::fs::read(path);
+        }
+        let mut contents = Vec::new();
+        vfs_path
+            .open_file()
+            .map_err(io_error)?
+            .read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+}
+
+/// Convert the errors of [`vfs`] into the I/O error of the matching kind.
+fn io_error(err: VfsError) -> Error {
+    let kind = match err.kind() {
+        VfsErrorKind::IoError(err) => err.kind(),
+        VfsErrorKind::FileNotFound => ErrorKind::NotFound,
+        VfsErrorKind::InvalidPath => ErrorKind::InvalidInput,
+        VfsErrorKind::DirectoryExists | VfsErrorKind::FileExists => ErrorKind::AlreadyExists,
+        VfsErrorKind::NotSupported => ErrorKind::Unsupported,
+        _ => ErrorKind::Other,
+    };
+    Error::new(kind, err)
+}
+
+fn unsupported(operation: &str) -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        format!("vfs filesystems cannot {}", operation),
+    )
+}
+
+impl FsOps for VfsFs {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        self.path(path)?.create_dir_all().map_err(io_error)
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.create_file(path)?.write_all(contents)
+    }
+
+    fn write_file_with(&self, path: &Path, contents: &[u8], _options: &WriteOptions) -> Result<()> {
+        self.write_file(path, contents)
+    }
+
+    fn append_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let vfs_path = self.path(path)?;
+        if !vfs_path.exists().map_err(io_error)? {
+            return self.write_file(path, contents);
+        }
+        vfs_path
+            .append_file()
+            .map_err(io_error)?
+            .write_all(contents)
+    }
+
+    fn write_zeros(&self, path: &Path, len: u64) -> Result<()> {
+        let mut file = self.create_file(path)?;
+        io::copy(&mut io::repeat(0).take(len), &mut file).map(drop)
+    }
+
+    fn write_reader(&self, path: &Path, reader: &mut dyn Read) -> Result<u64> {
+        io::copy(reader, &mut self.create_file(path)?)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<u64> {
+        let contents = self.read(from)?;
+        self.write_file(to, &contents)?;
+        Ok(contents.len() as u64)
+    }
+
+    fn create_symlink(&self, _target: &Path, _path: &Path) -> Result<()> {
+        Err(unsupported("create symlinks"))
+    }
+
+    fn create_junction(&self, target: &Path, path: &Path) -> Result<()> {
+        self.create_symlink(target, path)
+    }
+
+    fn create_hard_link(&self, original: &Path, link: &Path) -> Result<()> {
+        let contents = self.read(original)?;
+        self.write_file(link, &contents)
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn create_fifo(&self, _path: &Path) -> Result<()> {
+        Err(unsupported("create fifos"))
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn create_socket(&self, _path: &Path) -> Result<()> {
+        Err(unsupported("create sockets"))
+    }
+
+    fn entry_kind(&self, path: &Path) -> Result<Option<EntryKind>> {
+        let path = self.path(path)?;
+        if !path.exists().map_err(io_error)? {
+            return Ok(None);
+        }
+        Ok(Some(match path.metadata().map_err(io_error)?.file_type {
+            VfsFileType::File => EntryKind::File,
+            VfsFileType::Directory => EntryKind::Dir,
+        }))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        self.path(path)?.remove_file().map_err(io_error)
+    }
+
+    fn set_times(
+        &self,
+        _path: &Path,
+        _accessed: Option<SystemTime>,
+        _modified: Option<SystemTime>,
+    ) -> Result<()> {
+        Err(unsupported("set times"))
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    fn set_owner(&self, _path: &Path, _uid: u32, _gid: u32) -> Result<()> {
+        Err(unsupported("change owners"))
+    }
+
+    fn set_file_attributes(&self, _path: &Path, _attributes: FileAttributes) -> Result<()> {
+        Err(unsupported("set file attributes"))
+    }
+
+    fn move_path(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (self.path(from)?, self.path(to)?);
+        if from.is_dir().map_err(io_error)? {
+            from.move_dir(&to).map_err(io_error)
+        } else {
+            from.move_file(&to).map_err(io_error)
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::io::{Cursor, Read, Result, Seek, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use zip::write::FileOptions;
+use zip::{DateTime, ZipArchive, ZipWriter};
+
+use crate::archive::{file_mode, unsupported, write_archive, ArchiveWriter};
+use crate::attrs::Attrs;
+use crate::extract::Extracted;
+use crate::{File, Tree};
+
+impl Tree {
+    /// Write the directories and files of the tree into a zip archive,
+    /// returning the writer once the archive is finished, see
+    /// [`create_zip!`](crate::create_zip).
+    ///
+    /// Keys must be relative paths, resolved against the root of the archive.
+    /// Files are compressed with deflate and written with the Unix
+    /// permissions `0o644`, or `0o444` if read-only, plus the execute
+    /// permissions if executable, and directories with `0o755`. Declared
+    /// owners are ignored. Hard links and special files cannot be written
+    /// into a zip archive and fail with an
+    /// [`InvalidInput`](std::io::ErrorKind) error.
+    ///
+    /// Archives are reproducible like with [`Tree::write_tar`]: the entries
+    /// are sorted by path and the modification times default to 1980-01-01,
+    /// the earliest zip date.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// let mut tree = macro_files::Tree::new();
+    /// tree.push("docs/README.md", "# Documentation");
+    ///
+    /// let archive = tree.write_zip(Cursor::new(Vec::new())).unwrap();
+    /// let archive = macro_files::zip::ZipArchive::new(archive).unwrap();
+    /// let names: Vec<_> = archive.file_names().collect();
+    /// assert_eq!(names.len(), 2);
+    /// assert!(names.contains(&"docs/README.md"));
+    /// ```
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> Result<W> {
+        let mut writer = ZipWriter::new(writer);
+        write_archive(self, &mut writer)?;
+        Ok(writer.finish()?)
+    }
+
+    /// Write the directories and files of the tree into a zip archive held
+    /// in memory, see [`Tree::write_zip`].
+    pub fn to_zip(&self) -> Result<Vec<u8>> {
+        self.write_zip(Cursor::new(Vec::new()))
+            .map(Cursor::into_inner)
+    }
+}
+
+impl<W: Write + Seek> ArchiveWriter for ZipWriter<W> {
+    const FORMAT: &'static str = "zip";
+
+    fn dir(&mut self, path: &Path, attrs: &Attrs) -> Result<()> {
+        let options = options(0o755, attrs);
+        Ok(self.add_directory(name(path), options)?)
+    }
+
+    fn file(&mut self, path: &Path, contents: &[u8], attrs: &Attrs) -> Result<()> {
+        let mode = file_mode(attrs);
+        self.start_file(name(path), options(mode, attrs))?;
+        self.write_all(contents)
+    }
+
+    fn symlink(&mut self, path: &Path, target: &Path, attrs: &Attrs) -> Result<()> {
+        let options = options(0o777, attrs);
+        Ok(self.add_symlink(name(path), target.to_string_lossy(), options)?)
+    }
+
+    fn hard_link(&mut self, path: &Path, _original: &Path, _attrs: &Attrs) -> Result<()> {
+        Err(unsupported("hard link", path, Self::FORMAT))
+    }
+
+    fn special(&mut self, path: &Path, file: &File) -> Result<()> {
+        Err(unsupported(&file.kind().to_string(), path, Self::FORMAT))
+    }
+}
+
+/// Name of the entry at `path` in the archive, with `/` separators.
+fn name(path: &Path) -> String {
+    let components: Vec<_> = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    components.join("/")
+}
+
+fn options(mode: u32, attrs: &Attrs) -> FileOptions {
+    // Always set, as the default time is the current one with the `time`
+    // feature of `zip`.
+    let secs = attrs
+        .modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |mtime| mtime.as_secs());
+    FileOptions::default()
+        .unix_permissions(mode)
+        .last_modified_time(date_time(secs))
+}
+
+/// The zip date and time of `secs` seconds since the Unix epoch in UTC, or
+/// 1980-01-01 if out of the range of zip dates.
+fn date_time(secs: u64) -> DateTime {
+    // Civil date of the days since the Unix epoch, from Howard Hinnant's
+    // `civil_from_days` algorithm.
+    let (days, time) = (secs / 86_400, secs % 86_400);
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    DateTime::from_date_and_time(
+        u16::try_from(year).unwrap_or(0),
+        month as u8,
+        day as u8,
+        (time / 3600) as u8,
+        (time / 60 % 60) as u8,
+        (time % 60) as u8,
+    )
+    .unwrap_or_default()
+}
+
+/// Unpack the entries of the zip archive read from `reader`, see
+/// [`extract`](crate::extract).
+pub(crate) fn extract_zip<R: Read + Seek>(reader: R, extracted: &mut Extracted) -> Result<()> {
+    let mut archive = ZipArchive::new(reader)?;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let path = Path::new(file.name()).to_owned();
+        let mode = file.unix_mode().unwrap_or(0o644);
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        if file.is_dir() {
+            extracted.dir(&path)?;
+        } else if mode & 0o170_000 == 0o120_000 {
+            let target = String::from_utf8_lossy(&contents).into_owned();
+            extracted.symlink(&path, Path::new(&target))?;
+        } else {
+            extracted.file(&path, contents, mode)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "assert-fs")]
+
+use macro_files::assert_fs::prelude::*;
+use macro_files::assert_fs::TempDir;
+use macro_files::predicates_core::Predicate;
+use macro_files::symlink;
+
+#[test]
+fn create_within_assert_temp_dir() {
+    let temp = macro_files::create_assert_temp!({
+        "README.md": "# Project",
+        "src": {
+            "main.rs": "fn main() {}",
+        },
+    })
+    .unwrap();
+
+    assert!(temp.path().starts_with(macro_files::temp_root()));
+    temp.child("README.md").assert("# Project");
+    temp.child("src/main.rs").assert("fn main() {}");
+}
+
+#[test]
+fn create_within_child_path() {
+    let temp = TempDir::new().unwrap();
+    let child = temp.child("project");
+
+    macro_files::create_in!(&child, {
+        "README.md": "# Project",
+    })
+    .unwrap();
+
+    child.child("README.md").assert("# Project");
+}
+
+#[test]
+fn assert_matches_tree() {
+    let temp = macro_files::create_assert_temp!({
+        "README.md": "# Project",
+        "latest": symlink("README.md"),
+    })
+    .unwrap();
+
+    temp.assert(macro_files::matches_tree!({
+        "README.md": "# Project",
+        "latest": symlink("README.md"),
+    }));
+}
+
+#[test]
+fn report_differences() {
+    let temp = macro_files::create_assert_temp!({
+        "README.md": "# Project",
+    })
+    .unwrap();
+    let predicate = macro_files::matches_tree!({
+        "README.md": "# Other",
+    });
+
+    assert!(!predicate.eval(temp.path()));
+    let case = predicate.find_case(false, temp.path()).unwrap();
+    let diff = case.products().find(|product| product.name() == "diff");
+    assert!(diff.unwrap().value().to_string().contains("README.md"));
+    assert!(predicate.find_case(true, temp.path()).is_none());
+}
@@ -0,0 +1,297 @@
+#![cfg(all(feature = "tokio", feature = "tempfile"))]
+
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn create_async() {
+    let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    let root = temp_dir.path().join("root");
+    macro_files::create_async!({
+        &root: {
+            "README.md": "# Project",
+            "docs": {
+                "assets": {},
+            },
+            "not-created": false,
+            "path/as/name": true,
+        },
+    })
+    .await
+    .unwrap();
+
+    macro_files::assert_tree!(temp_dir.path(), {
+        "root": {
+            "README.md": "# Project",
+            "docs": {
+                "assets": {},
+            },
+            "path/as/name": true,
+        },
+    });
+}
+
+#[tokio::test]
+async fn create_temp_async() {
+    let dir = macro_files::create_temp_async!({
+        ["long", "path"].join("/"): {
+            "README.md": "# Project",
+        },
+        PathBuf::from("other").join("LICENSE"): "MIT",
+    })
+    .await
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "long/path/README.md": "# Project",
+        "other/LICENSE": "MIT",
+    });
+}
+
+#[tokio::test]
+async fn invalid_key_fails() {
+    let result = macro_files::create_temp_async!({
+        "docs": {
+            String::from("../../escape"): "!",
+        },
+    })
+    .await;
+
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn create_many_files_async() {
+    let mut files = macro_files::Tree::new();
+    for i in 0..(macro_files::fd_budget() + 10).min(5_000) {
+        files.push(format!("{}.txt", i), i.to_string());
+    }
+    let dir = macro_files::create_temp_async!({
+        "files": files.clone(),
+    })
+    .await
+    .unwrap();
+
+    let mut expected = macro_files::Tree::new();
+    expected.push("files", files);
+    assert!(expected.diff(dir.path()).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn copy_async() {
+    let dir = macro_files::create_temp_async!({
+        "config.toml": macro_files::from("tests/fixtures/config.toml"),
+        "vendor": macro_files::copy_dir("tests/fixtures/vendor"),
+    })
+    .await
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "config.toml": macro_files::from("tests/fixtures/config.toml"),
+        "vendor": {
+            "README.md": "# Vendor\n",
+            "lib/lib.rs": "pub fn lib() {}\n",
+        },
+    });
+}
+
+#[tokio::test]
+async fn hard_links_async() {
+    let dir = macro_files::create_temp_async!({
+        "data.bin": [0, 1, 2],
+        "backup/data.bin": macro_files::hardlink("data.bin"),
+    })
+    .await
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "data.bin": [0, 1, 2],
+        "backup/data.bin": macro_files::hardlink("data.bin"),
+    });
+}
+
+#[tokio::test]
+async fn conflict_async() {
+    use macro_files::{Conflict, Options, Tree};
+
+    let dir = macro_files::create_temp!({
+        "README.md": "# Existing",
+    })
+    .unwrap();
+    let mut tree = Tree::new();
+    tree.push("README.md", "# Project");
+
+    let options = Options::new().on_conflict(Conflict::Skip);
+    let report = tree.create_with_async(dir.path(), &options).await.unwrap();
+    assert_eq!(report.skipped().len(), 1);
+
+    let mut docs = Tree::new();
+    docs.push("guide.md", "# Guide");
+    let mut merged = Tree::new();
+    merged.push(dir.path().file_name().unwrap(), docs);
+    let parent = dir.path().parent().unwrap();
+    let report = merged.create_with_async(parent, &options).await.unwrap();
+    let created: Vec<_> = report.created().iter().map(|entry| entry.path()).collect();
+    assert_eq!(created, [dir.path().join("guide.md")]);
+    std::fs::remove_file(dir.path().join("guide.md")).unwrap();
+
+    let options = Options::new().on_conflict(Conflict::Error);
+    let err = tree
+        .create_with_async(dir.path(), &options)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+    tree.create_in_async(dir.path()).await.unwrap();
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Project",
+    });
+}
+
+#[tokio::test]
+async fn times_async() {
+    use std::time::{Duration, SystemTime};
+
+    let old = SystemTime::UNIX_EPOCH + Duration::from_secs(86_400);
+    let mut logs = macro_files::Tree::new().mtime(old);
+    logs.push("old.log", macro_files::file("old").mtime(old));
+    let dir = macro_files::create_temp_async!({
+        "logs": logs,
+    })
+    .await
+    .unwrap();
+
+    for path in ["logs", "logs/old.log"] {
+        let metadata = std::fs::metadata(dir.path().join(path)).unwrap();
+        assert_eq!(metadata.modified().unwrap(), old);
+    }
+}
+
+#[tokio::test]
+async fn named_entries_async() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut tree = macro_files::Tree::new();
+    tree.push("README.md", "# Project");
+    tree.name("README.md", "readme");
+    let report = tree.create_in_async(dir.path()).await.unwrap();
+
+    assert_eq!(
+        report.handle("readme"),
+        Some(dir.path().join("README.md").as_path())
+    );
+}
+
+#[tokio::test]
+async fn best_effort_async() {
+    let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    let mut tree = macro_files::Tree::new();
+    tree.push("docs/copy.txt", macro_files::from("missing.txt"));
+    tree.push("README.md", "# Project");
+    tree.push("assets/logo.svg", macro_files::from("missing.svg"));
+    let options = macro_files::Options::new().best_effort(true);
+
+    let err = tree
+        .create_with_async(temp_dir.path(), &options)
+        .await
+        .unwrap_err();
+
+    let err = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<macro_files::CreateErrors>())
+        .unwrap();
+    let mut paths: Vec<_> = err.errors().iter().map(|(path, _)| path.clone()).collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            temp_dir.path().join("assets/logo.svg"),
+            temp_dir.path().join("docs/copy.txt"),
+        ]
+    );
+    assert!(temp_dir.path().join("README.md").is_file());
+}
+
+#[tokio::test]
+async fn byte_budget_async() {
+    use macro_files::{Content, Generator, Options, Reader, Tree};
+    use std::io::ErrorKind;
+
+    let options = Options::new().max_bytes(20);
+    let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    let mut tree = Tree::new();
+    tree.push("data.bin", vec![0; 10]);
+    tree.push(
+        "generated.bin",
+        Content::Generator(Generator::new(|| Ok(vec![1; 10]))),
+    );
+    tree.create_with_async(temp_dir.path(), &options)
+        .await
+        .unwrap();
+
+    let known = temp_dir.path().join("known");
+    let err = macro_files::tree!({ "data.bin": vec![0; 30] })
+        .create_with_async(&known, &options)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(!known.exists());
+
+    let mut tree = Tree::new();
+    tree.push("data.bin", vec![0; 10]);
+    tree.push(
+        "generated.bin",
+        Content::Generator(Generator::new(|| Ok(vec![1; 20]))),
+    );
+    let generated = temp_dir.path().join("generated");
+    let err = tree
+        .create_with_async(&generated, &options)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(!generated.join("generated.bin").exists());
+
+    let mut tree = Tree::new();
+    tree.push("data.bin", vec![0; 10]);
+    tree.push(
+        "streamed.bin",
+        Content::Reader(Reader::new(std::io::repeat(1))),
+    );
+    let streamed = temp_dir.path().join("streamed");
+    let err = tree
+        .create_with_async(&streamed, &options)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(!streamed.join("streamed.bin").exists());
+}
+
+#[tokio::test]
+async fn deduplicate_async() {
+    use macro_files::{Options, Tree};
+
+    let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    let mut tree = Tree::new();
+    for i in 0..10 {
+        tree.push(format!("{}.txt", i), "duplicate");
+    }
+    tree.push("other.txt", "other");
+    let options = Options::new().deduplicate(true).max_bytes(20);
+    tree.create_with_async(temp_dir.path(), &options)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(temp_dir.path().join("9.txt")).unwrap(),
+        "duplicate"
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let nlink = |name| {
+            std::fs::metadata(temp_dir.path().join(name))
+                .unwrap()
+                .nlink()
+        };
+        assert_eq!(nlink("9.txt"), 10);
+        assert_eq!(nlink("other.txt"), 1);
+    }
+}
@@ -0,0 +1,266 @@
+#![cfg(feature = "tempfile")]
+
+use std::path::Path;
+
+use macro_files::file;
+
+fn set_writable(path: &Path) {
+    let mut permissions = std::fs::metadata(path).unwrap().permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    std::fs::set_permissions(path, permissions).unwrap();
+}
+
+#[test]
+fn readonly() {
+    let dir = macro_files::create_temp!({
+        "locked.txt": file("locked").readonly(),
+        "open.txt": file("open").hidden().system(),
+    })
+    .unwrap();
+
+    let locked = dir.path().join("locked.txt");
+    assert!(std::fs::metadata(&locked).unwrap().permissions().readonly());
+    assert!(!std::fs::metadata(dir.path().join("open.txt"))
+        .unwrap()
+        .permissions()
+        .readonly());
+    assert_eq!(std::fs::read_to_string(&locked).unwrap(), "locked");
+    set_writable(&locked);
+}
+
+#[test]
+#[cfg(windows)]
+fn windows_attributes() {
+    use std::os::windows::fs::MetadataExt;
+
+    let dir = macro_files::create_temp!({
+        "desktop.ini": file("[.ShellClassInfo]").hidden().system(),
+    })
+    .unwrap();
+
+    let attributes = std::fs::metadata(dir.path().join("desktop.ini"))
+        .unwrap()
+        .file_attributes();
+    assert_eq!(attributes & 0x6, 0x6);
+}
+
+#[test]
+#[cfg(windows)]
+fn alternate_data_streams() {
+    let dir = macro_files::create_temp!({
+        "setup.exe": file("MZ")
+            .ads("Zone.Identifier", "[ZoneTransfer]\r\nZoneId=3\r\n")
+            .ads("empty", ""),
+    })
+    .unwrap();
+
+    let path = dir.path().join("setup.exe");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "MZ");
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("setup.exe:Zone.Identifier")).unwrap(),
+        "[ZoneTransfer]\r\nZoneId=3\r\n"
+    );
+    assert_eq!(
+        std::fs::read(dir.path().join("setup.exe:empty")).unwrap(),
+        b""
+    );
+
+    let err = macro_files::create_temp!({
+        "setup.exe": file("MZ").ads("Zone:Identifier", ""),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+#[cfg(unix)]
+fn executable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = macro_files::create_temp!({
+        "bin": {
+            "run.sh": macro_files::exec("#!/bin/sh\necho hi\n"),
+            "locked.sh": file("#!/bin/sh\n").executable().readonly(),
+        },
+        "data.txt": "data",
+    })
+    .unwrap();
+
+    let mode = |name: &str| {
+        let metadata = std::fs::metadata(dir.path().join(name)).unwrap();
+        metadata.permissions().mode() & 0o777
+    };
+    let run = mode("bin/run.sh");
+    assert_eq!(run & 0o111, (run & 0o444) >> 2);
+    assert_ne!(run & 0o100, 0);
+    assert_eq!(mode("bin/locked.sh") & 0o322, 0o100);
+    assert_eq!(mode("data.txt") & 0o111, 0);
+    let output = std::process::Command::new(dir.path().join("bin/run.sh"))
+        .output()
+        .unwrap();
+    assert_eq!(output.stdout, b"hi\n");
+    set_writable(&dir.path().join("bin/locked.sh"));
+}
+
+#[test]
+#[cfg(unix)]
+fn readonly_dirs() {
+    let mut locked = macro_files::Tree::new().readonly();
+    locked.push("data.txt", "data");
+    locked.push("nested", macro_files::Tree::new().readonly());
+    let mut spec = macro_files::Tree::new();
+    spec.push("locked", locked);
+    spec.push("open", macro_files::Tree::new());
+
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    spec.create_in(dir.path()).unwrap();
+    let readonly = |name: &str| {
+        let metadata = std::fs::metadata(dir.path().join(name)).unwrap();
+        metadata.permissions().readonly()
+    };
+    assert!(readonly("locked"));
+    assert!(readonly("locked/nested"));
+    assert!(!readonly("locked/data.txt"));
+    assert!(!readonly("open"));
+
+    spec.remove_in(dir.path()).unwrap();
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+#[cfg(unix)]
+fn exact_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut shared = macro_files::Tree::new().exact_permissions();
+    shared.push("data.txt", file("data").exact_permissions());
+    shared.push(
+        "run.sh",
+        macro_files::exec("#!/bin/sh\n").exact_permissions(),
+    );
+    shared.push("locked.txt", file("locked").readonly().exact_permissions());
+    let mut spec = macro_files::Tree::new();
+    spec.push("shared", shared);
+
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("shared")).unwrap();
+    std::fs::set_permissions(
+        dir.path().join("shared"),
+        std::fs::Permissions::from_mode(0o1777),
+    )
+    .unwrap();
+    spec.create_in(dir.path()).unwrap();
+
+    let mode = |name: &str| {
+        let metadata = std::fs::metadata(dir.path().join(name)).unwrap();
+        metadata.permissions().mode() & 0o7777
+    };
+    assert_eq!(mode("shared"), 0o755);
+    assert_eq!(mode("shared/data.txt"), 0o644);
+    assert_eq!(mode("shared/run.sh"), 0o755);
+    assert_eq!(mode("shared/locked.txt"), 0o444);
+    set_writable(&dir.path().join("shared/locked.txt"));
+}
+
+#[test]
+fn subtree_defaults() {
+    use macro_files::{crlf, Conflict, Defaults, LineEnding};
+
+    let dir = macro_files::create_temp!({
+        "bin": { "run.sh": "old", "keep.sh": "old" },
+    })
+    .unwrap();
+
+    let bin = macro_files::tree!({
+        "run.sh": "#!/bin/sh\necho run\n",
+        "keep.sh": file("new").on_conflict(Conflict::Overwrite),
+        "windows.bat": crlf("@echo off\n"),
+    })
+    .defaults(
+        &Defaults::new()
+            .executable()
+            .on_conflict(Conflict::Skip)
+            .line_endings(LineEnding::Lf),
+    );
+    assert_eq!(
+        bin.entries()
+            .map(|(_, entry)| match entry {
+                macro_files::Entry::File(file) => file.declared_conflict(),
+                _ => None,
+            })
+            .collect::<Vec<_>>(),
+        [
+            Some(Conflict::Skip),
+            Some(Conflict::Overwrite),
+            Some(Conflict::Skip),
+        ]
+    );
+    macro_files::tree!({ "bin": bin })
+        .create_in(dir.path())
+        .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "bin": {
+            "run.sh": "old",
+            "keep.sh": "new",
+            "windows.bat": "@echo off\r\n",
+        },
+    });
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = |name: &str| {
+            let metadata = std::fs::metadata(dir.path().join("bin").join(name)).unwrap();
+            metadata.permissions().mode() & 0o111
+        };
+        assert_eq!(mode("keep.sh"), 0o111);
+        assert_eq!(mode("windows.bat"), 0o111);
+        assert_eq!(mode("run.sh"), 0);
+    }
+}
+
+#[test]
+fn entry_attributes() {
+    use std::time::{Duration, SystemTime};
+
+    use macro_files::{entry, Conflict, LineEnding};
+
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let dir = macro_files::create_temp!({ "kept.txt": "old" }).unwrap();
+    let err = macro_files::tree!({
+        "secret.key": entry! { contents: "key", mode: 0o600, mtime: time },
+        "run.sh": entry! {
+            contents: "#!/bin/sh\r\n",
+            line_ending: LineEnding::Lf,
+            executable: true,
+            readonly: false,
+        },
+        "kept.txt": entry! { contents: "new", on_conflict: Conflict::Skip },
+        "empty": entry! { atime: time },
+        "marker": entry! { contents: true },
+        "dir": entry! { contents: macro_files::tree!({ "a": "" }) },
+    })
+    .create_in(dir.path())
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    macro_files::assert_tree!(dir.path(), {
+        "secret.key": "key",
+        "run.sh": "#!/bin/sh\n",
+        "kept.txt": "old",
+        "empty": "",
+        "marker": "",
+    });
+    let metadata = std::fs::metadata(dir.path().join("secret.key")).unwrap();
+    assert_eq!(metadata.modified().unwrap(), time);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        assert_eq!(metadata.permissions().mode() & 0o7777, 0o600);
+        let metadata = std::fs::metadata(dir.path().join("run.sh")).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o111, 0o111);
+    }
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "tempfile")]
+
+use macro_files::{Cache, EntryKind, Options};
+
+#[test]
+fn copy_cached_fixtures() {
+    let cache = macro_files::tempfile::tempdir().unwrap();
+    let options = Options::new().cache(Cache::new(cache.path()));
+    let fixture = macro_files::tree!({
+        "README.md": "# Fixture",
+        "data": {
+            "zeros.bin": macro_files::size(64),
+            "random.bin": macro_files::random(32, 7),
+        },
+        "empty": {},
+        "target": null,
+    });
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    fixture
+        .create_with(dir.path().join("first"), &options)
+        .unwrap();
+    let cached = Cache::new(cache.path()).fixture(&fixture).unwrap();
+    assert_eq!(std::fs::read_dir(cache.path()).unwrap().count(), 1);
+    std::fs::write(cached.join("README.md"), "# Cached").unwrap();
+    let report = fixture
+        .create_with(dir.path().join("second"), &options)
+        .unwrap();
+
+    let second = dir.path().join("second");
+    assert_eq!(
+        std::fs::read_to_string(second.join("README.md")).unwrap(),
+        "# Cached"
+    );
+    assert_eq!(
+        std::fs::read(second.join("data/zeros.bin")).unwrap(),
+        [0; 64]
+    );
+    assert!(second.join("empty").is_dir());
+    let kinds: Vec<_> = report.created().iter().map(|entry| entry.kind()).collect();
+    assert_eq!(
+        kinds
+            .iter()
+            .filter(|kind| **kind == EntryKind::File)
+            .count(),
+        3
+    );
+    assert_eq!(
+        kinds.iter().filter(|kind| **kind == EntryKind::Dir).count(),
+        2
+    );
+
+    std::fs::write(second.join("README.md"), "# Changed").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(cached.join("README.md")).unwrap(),
+        "# Cached"
+    );
+}
+
+#[test]
+fn skip_uncacheable_trees() {
+    let cache = macro_files::tempfile::tempdir().unwrap();
+    let options = Options::new().cache(Cache::new(cache.path()));
+    let tree = macro_files::tree!({
+        "data.txt": "data",
+        "link": macro_files::symlink("data.txt"),
+    });
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    tree.create_with(dir.path(), &options).unwrap();
+
+    assert_eq!(tree.spec_hash(), None);
+    assert_eq!(std::fs::read_dir(cache.path()).unwrap().count(), 0);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("data.txt")).unwrap(),
+        "data"
+    );
+}
+
+#[test]
+fn hash_specs() {
+    let tree = macro_files::tree!({ "a.txt": "a", "b": { "c.txt": "c" } });
+
+    assert_eq!(
+        tree.spec_hash(),
+        macro_files::tree!({ "a.txt": "a", "b": { "c.txt": "c" } }).spec_hash()
+    );
+    assert_ne!(
+        tree.spec_hash(),
+        macro_files::tree!({ "a.txt": "a", "b": { "c.txt": "C" } }).spec_hash()
+    );
+    assert_ne!(
+        tree.spec_hash(),
+        macro_files::tree!({ "a.txt": "a", "b/c.txt": "c" }).spec_hash()
+    );
+    assert_ne!(
+        macro_files::tree!({ "run.sh": "" }).spec_hash(),
+        macro_files::tree!({ "run.sh": macro_files::exec("") }).spec_hash()
+    );
+    assert_eq!(
+        macro_files::tree!({ "log": macro_files::file("").after_create(|_| Ok(())) }).spec_hash(),
+        None
+    );
+}
@@ -0,0 +1,75 @@
+#![cfg(all(feature = "camino", feature = "tempfile"))]
+
+use macro_files::camino::{Utf8Path, Utf8PathBuf};
+use macro_files::{from, symlink, Tree};
+
+#[test]
+fn accept_utf8_paths() {
+    let parent_dir = macro_files::tempfile::tempdir().unwrap();
+    let parent = Utf8PathBuf::try_from(parent_dir.path().to_owned()).unwrap();
+    let key = Utf8Path::new("config");
+
+    let dir = macro_files::create_temp!(in = parent, {
+        key: {
+            "app.toml": from(Utf8Path::new("tests/fixtures/config.toml")),
+        },
+        "latest": symlink(Utf8Path::new("config/app.toml")),
+    })
+    .unwrap();
+    let mut tree = Tree::new();
+    tree.push(Utf8Path::new("data/blob.txt"), "blob");
+    tree.create_in(macro_files::utf8_path(dir.path()).unwrap().join("nested"))
+        .unwrap();
+    macro_files::create_in!(Utf8PathBuf::try_from(dir.path().to_owned()).unwrap(), {
+        "README.md": "# Project",
+    })
+    .unwrap();
+
+    assert!(dir.path().starts_with(&parent));
+    macro_files::assert_tree!(dir.path(), {
+        "config/app.toml": from("tests/fixtures/config.toml"),
+        "latest": symlink("config/app.toml"),
+        "nested/data/blob.txt": "blob",
+        "README.md": "# Project",
+    });
+}
+
+#[test]
+fn utf8_returned_paths() {
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    let root = macro_files::utf8_path(dir.path()).unwrap();
+
+    let manifest = macro_files::create_manifest!({
+        root: {
+            "src" as src: {
+                "main.rs": "fn main() {}",
+            },
+        },
+    })
+    .unwrap();
+
+    assert_eq!(
+        manifest.utf8_handle("src"),
+        Some(root.join("src").as_path())
+    );
+    assert_eq!(
+        manifest.utf8_paths().unwrap(),
+        [root.to_owned(), root.join("src"), root.join("src/main.rs")]
+    );
+    assert_eq!(
+        manifest.entries()[2].utf8_path(),
+        Some(root.join("src/main.rs").as_path())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_paths() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::path::Path::new(OsStr::from_bytes(b"invalid-\xff"));
+    let err = macro_files::utf8_path(path).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
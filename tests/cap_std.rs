@@ -0,0 +1,53 @@
+#![cfg(all(feature = "cap-std", feature = "tempfile"))]
+
+use std::io::ErrorKind;
+
+use macro_files::{copy_dir, file, symlink, CapStdFs};
+
+#[test]
+fn create_through_dir_handle() {
+    let dir = tempfile::tempdir().unwrap();
+
+    macro_files::create_with_fs!(CapStdFs::open(dir.path()).unwrap(), {
+        "README.md": file("# Project").readonly(),
+        "src/bin": {
+            "main.rs": "fn main() {}",
+        },
+        "vendor": copy_dir("tests/fixtures/vendor"),
+        "latest": symlink("README.md"),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Project",
+        "src/bin/main.rs": "fn main() {}",
+        "vendor": copy_dir("tests/fixtures/vendor"),
+        "latest": symlink("README.md"),
+    });
+    let metadata = std::fs::metadata(dir.path().join("README.md")).unwrap();
+    assert!(metadata.permissions().readonly());
+}
+
+#[cfg(unix)]
+#[test]
+fn cannot_escape_dir_handle() {
+    let outside = tempfile::tempdir().unwrap();
+    let dir = macro_files::create_temp!({
+        "outside": symlink(outside.path()),
+    })
+    .unwrap();
+    let absolute = outside.path().join("absolute.txt");
+
+    for key in [
+        std::path::Path::new("../escaped.txt"),
+        std::path::Path::new("outside/linked.txt"),
+        absolute.as_path(),
+    ] {
+        let err = macro_files::create_with_fs!(CapStdFs::open(dir.path()).unwrap(), {
+            key: "escaped",
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied, "{:?}", key);
+    }
+    assert_eq!(outside.path().read_dir().unwrap().count(), 0);
+}
@@ -0,0 +1,130 @@
+#![cfg(all(feature = "checksum", feature = "tempfile"))]
+
+use std::io::ErrorKind;
+
+use macro_files::{file, ChecksumMismatch};
+
+const CONTENTS_SHA256: &str = "d1b2a59fbea7e20077af9f91b27e95e865061b270be03ff539ab3b73587882e8";
+
+#[test]
+fn checksum_matches() {
+    let dir = macro_files::create_temp!({
+        "blob.bin": file("contents").sha256(CONTENTS_SHA256.to_uppercase()),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "blob.bin": "contents",
+    });
+}
+
+#[test]
+fn checksum_mismatch() {
+    let err = macro_files::create_temp!({
+        "first.txt": "created",
+        "blob.bin": file("corrupted").sha256(CONTENTS_SHA256),
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<ChecksumMismatch>())
+        .unwrap();
+    assert!(err.path().ends_with("blob.bin"));
+    assert_eq!(err.expected(), CONTENTS_SHA256);
+    assert_eq!(
+        err.actual(),
+        "3dbb3963d11aa418de8b61f846c3dbd5af43b40d252842adb823f90936fe6920"
+    );
+}
+
+#[test]
+fn invalid_checksum() {
+    let err = macro_files::create_temp!({
+        "blob.bin": file("contents").sha256("not hexadecimal"),
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn checksums() {
+    use macro_files::{Options, Tree};
+
+    let mut tree = Tree::new();
+    tree.push("contents.txt", "contents");
+    tree.push("copy.txt", macro_files::from("tests/fixtures/config.toml"));
+    tree.push("vendor", macro_files::copy_dir("tests/fixtures/vendor"));
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    let report = tree
+        .create_with(dir.path(), &Options::new().checksums(true))
+        .unwrap();
+
+    let checksums: Vec<_> = report.checksums().collect();
+    assert_eq!(
+        checksums[0],
+        (dir.path().join("contents.txt").as_path(), CONTENTS_SHA256)
+    );
+    let relative: Vec<_> = checksums
+        .iter()
+        .map(|(path, _)| path.strip_prefix(dir.path()).unwrap())
+        .collect();
+    assert_eq!(
+        relative,
+        [
+            "contents.txt",
+            "copy.txt",
+            "vendor/README.md",
+            "vendor/lib/lib.rs",
+        ]
+        .map(std::path::Path::new)
+    );
+    let sums = report.sha256sums(dir.path());
+    assert!(sums.starts_with(&format!("{}  contents.txt\n", CONTENTS_SHA256)));
+    assert_eq!(sums.lines().count(), 4);
+}
+
+#[test]
+fn verify_tree() {
+    use macro_files::ChecksumErrors;
+
+    let dir = macro_files::create_temp!({
+        "blob.bin": file("contents").sha256(CONTENTS_SHA256),
+        "docs": {
+            "README.md": "# Project",
+            "guide.md": "# Guide",
+        },
+        "config.toml": macro_files::from("tests/fixtures/config.toml"),
+    })
+    .unwrap();
+    macro_files::verify!(dir.path(), {
+        "blob.bin": file("other").sha256(CONTENTS_SHA256),
+        "docs/README.md": "# Project",
+        "config.toml": macro_files::from("tests/fixtures/config.toml"),
+    })
+    .unwrap();
+
+    std::fs::write(dir.path().join("blob.bin"), "tampered").unwrap();
+    std::fs::remove_file(dir.path().join("docs/guide.md")).unwrap();
+    let err = macro_files::verify!(dir.path(), {
+        "blob.bin": "contents",
+        "docs": {
+            "README.md": "# Project",
+            "guide.md": "# Guide",
+        },
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = err
+        .into_inner()
+        .unwrap()
+        .downcast::<ChecksumErrors>()
+        .unwrap();
+    assert_eq!(err.mismatches().len(), 1);
+    assert_eq!(err.mismatches()[0].path(), dir.path().join("blob.bin"));
+    assert_eq!(err.mismatches()[0].expected(), CONTENTS_SHA256);
+    assert_eq!(err.missing(), [dir.path().join("docs/guide.md")]);
+}
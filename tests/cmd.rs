@@ -0,0 +1,35 @@
+#![cfg(all(feature = "cmd", feature = "tempfile"))]
+
+use std::io::ErrorKind;
+
+use macro_files::{cmd, Tree};
+
+#[test]
+fn command_output_contents() {
+    let dir = macro_files::create_temp!({
+        "version.txt": cmd("rustc", ["--version"]),
+    })
+    .unwrap();
+
+    let version = std::fs::read_to_string(dir.path().join("version.txt")).unwrap();
+    assert!(version.starts_with("rustc "));
+    assert!(version.ends_with('\n'));
+}
+
+#[test]
+fn command_failures() {
+    let mut tree = Tree::new();
+    tree.push("version.txt", cmd("rustc", ["--unknown-flag"]));
+    let err = tree.create_temp().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(err.to_string().contains("`rustc --unknown-flag` failed"));
+
+    let mut tree = Tree::new();
+    tree.push(
+        "version.txt",
+        cmd("macro-files-missing-command", ["--version"]),
+    );
+    tree.plan(std::env::temp_dir()).unwrap();
+    let err = tree.create_temp().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
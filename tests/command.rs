@@ -0,0 +1,41 @@
+#![cfg(feature = "tempfile")]
+
+use macro_files::presets;
+
+#[test]
+fn command_in_temp_tree() {
+    let tree = macro_files::create_temp_tree!({
+        "app": presets::cargo_bin("app"),
+    })
+    .unwrap();
+
+    let output = tree
+        .command("rustc")
+        .args(["--crate-name", "app", "--out-dir", "out", "app/src/main.rs"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(tree.child("out").is_dir());
+}
+
+#[test]
+#[cfg(feature = "assert-cmd")]
+fn assert_command_in_temp_tree() {
+    let tree = macro_files::create_temp_tree!({
+        "app": presets::cargo_bin("app"),
+    })
+    .unwrap();
+
+    tree.assert_command("rustc")
+        .args(["--crate-name", "app", "--out-dir", "out", "app/src/main.rs"])
+        .assert()
+        .success();
+    tree.assert_command("rustc")
+        .arg("missing.rs")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "tempfile")]
+
+#[test]
+fn conditional_entries() {
+    let verbose = false;
+
+    let dir = macro_files::create_temp!({
+        "README.md": "# Project",
+        if true => {
+            "config": {
+                "app.toml": "debug = true",
+            },
+            "LICENSE": "MIT",
+        },
+        if verbose => {
+            "debug.log": "",
+        },
+        "src": {
+            "lib.rs": "",
+            if !verbose => {
+                "main.rs": "fn main() {}",
+            }
+        },
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Project",
+        "config/app.toml": "debug = true",
+        "LICENSE": "MIT",
+        "src": {
+            "lib.rs": "",
+            "main.rs": "fn main() {}",
+        },
+    });
+}
+
+#[test]
+fn conditional_entries_at_root() {
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    macro_files::create!({
+        if cfg!(any()) => {
+            dir.path(): {
+                "never.txt": "",
+            },
+        },
+        if !cfg!(any()) => {
+            dir.path().join("always.txt"): "",
+        }
+    })
+    .unwrap();
+
+    assert!(dir.path().join("always.txt").is_file());
+    assert!(!dir.path().join("never.txt").exists());
+}
@@ -0,0 +1,179 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+
+use macro_files::{Conflict, Options, SkipReason, Tree};
+
+fn spec() -> Tree {
+    let mut tree = Tree::new();
+    tree.push("README.md", "# Project");
+    tree.push("LICENSE", "MIT");
+    tree
+}
+
+fn existing() -> macro_files::tempfile::TempDir {
+    macro_files::create_temp!({
+        "README.md": "# Existing",
+    })
+    .unwrap()
+}
+
+#[test]
+fn overwrite() {
+    let dir = existing();
+    let report = spec()
+        .create_with(dir.path(), &Options::new().on_conflict(Conflict::Overwrite))
+        .unwrap();
+
+    assert_eq!(report.created().len(), 2);
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Project",
+        "LICENSE": "MIT",
+    });
+}
+
+#[test]
+fn skip() {
+    let dir = existing();
+    let report = spec()
+        .create_with(dir.path(), &Options::new().on_conflict(Conflict::Skip))
+        .unwrap();
+
+    assert_eq!(report.created().len(), 1);
+    assert_eq!(report.skipped()[0].path(), dir.path().join("README.md"));
+    assert_eq!(report.skipped()[0].reason(), SkipReason::Exists);
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Existing",
+        "LICENSE": "MIT",
+    });
+}
+
+#[test]
+fn error() {
+    let dir = existing();
+    let err = spec()
+        .create_with(dir.path(), &Options::new().on_conflict(Conflict::Error))
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Existing",
+    });
+}
+
+#[test]
+fn copy_dir_skip() {
+    let dir = macro_files::create_temp!({
+        "vendor/README.md": "# Existing",
+    })
+    .unwrap();
+    let mut tree = Tree::new();
+    tree.push("vendor", macro_files::copy_dir("tests/fixtures/vendor"));
+    tree.create_with(dir.path(), &Options::new().on_conflict(Conflict::Skip))
+        .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "vendor": {
+            "README.md": "# Existing",
+            "lib/lib.rs": "pub fn lib() {}\n",
+        },
+    });
+}
+
+#[test]
+#[cfg(unix)]
+fn overwrite_replaces_symlinks() {
+    let dir = macro_files::create_temp!({
+        "data.txt": "data",
+        "link.txt": macro_files::symlink("data.txt"),
+    })
+    .unwrap();
+    macro_files::create_in!(dir.path(), {
+        "link.txt": "replaced",
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "data.txt": "data",
+        "link.txt": "replaced",
+    });
+}
+
+#[test]
+fn merge_leaves_existing_entries() {
+    let dir = macro_files::create_temp!({
+        "docs": {
+            "README.md": "# Existing",
+        },
+        "src": "not a directory",
+    })
+    .unwrap();
+
+    let mut docs = Tree::new().mtime(std::time::UNIX_EPOCH);
+    docs.push("README.md", "# Project");
+    docs.push("guide.md", "# Guide");
+    let mut tree = Tree::new();
+    tree.push("docs", docs);
+    tree.push("src", Tree::new());
+    let report = tree.merge_in(dir.path()).unwrap();
+
+    let created: Vec<_> = report.created().iter().map(|entry| entry.path()).collect();
+    assert_eq!(created, [dir.path().join("docs/guide.md")]);
+    let skipped: Vec<_> = report.skipped().iter().map(|entry| entry.path()).collect();
+    assert_eq!(
+        skipped,
+        [dir.path().join("docs/README.md"), dir.path().join("src")]
+    );
+    let modified = std::fs::metadata(dir.path().join("docs"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_ne!(modified, std::time::UNIX_EPOCH);
+    macro_files::assert_tree!(dir.path(), {
+        "docs": {
+            "README.md": "# Existing",
+            "guide.md": "# Guide",
+        },
+        "src": "not a directory",
+    });
+}
+
+#[test]
+fn require_empty_dir() {
+    let options = Options::new().require_empty(true);
+    let tree = macro_files::tree!({ "Cargo.toml": "[package]" });
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    tree.create_with(dir.path().join("missing"), &options)
+        .unwrap();
+    std::fs::create_dir(dir.path().join("empty")).unwrap();
+    tree.create_with(dir.path().join("empty"), &options)
+        .unwrap();
+    let err = tree
+        .create_with(dir.path().join("empty"), &options)
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    let cleaned = options.clean(true);
+    let err = tree
+        .create_with(dir.path().join("empty"), &cleaned)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert!(dir.path().join("empty/Cargo.toml").is_file());
+}
+
+#[test]
+fn append_to_existing_files() {
+    let dir = existing();
+    macro_files::tree!({
+        "README.md": macro_files::append("\nAppended"),
+        "CHANGELOG.md": macro_files::append("# Changelog"),
+    })
+    .create_in(dir.path())
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Existing\nAppended",
+        "CHANGELOG.md": "# Changelog",
+    });
+}
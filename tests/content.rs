@@ -0,0 +1,447 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::{Cursor, ErrorKind};
+use std::path::PathBuf;
+
+use macro_files::{Change, Content, Generator, Reader, Tree};
+
+#[test]
+fn content_values() {
+    let dir = macro_files::create_temp!({
+        "text.txt": Content::Text("text".to_string()),
+        "bytes.bin": Content::Bytes(vec![0, 1, 2]),
+        "generated.txt": Content::Generator(Generator::new(|| Ok(b"generated".to_vec()))),
+        "read.txt": Content::Reader(Reader::new(Cursor::new("read"))),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "text.txt": "text",
+        "bytes.bin": [0, 1, 2],
+        "generated.txt": "generated",
+        "read.txt": "read",
+    });
+}
+
+#[test]
+fn reader_consumed_once() {
+    let mut tree = Tree::new();
+    tree.push(
+        "read.txt",
+        Content::Reader(Reader::new(Cursor::new("read"))),
+    );
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    tree.create_in(dir.path().join("first")).unwrap();
+    let err = tree.create_in(dir.path().join("second")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+}
+
+#[test]
+#[cfg(unix)]
+fn symlinks() {
+    let dir = macro_files::create_temp!({
+        "README.md": "# Project",
+        "docs": {
+            "index.md": macro_files::symlink("../README.md"),
+        },
+    })
+    .unwrap();
+
+    let link = dir.path().join("docs/index.md");
+    assert_eq!(
+        std::fs::read_link(&link).unwrap(),
+        PathBuf::from("../README.md")
+    );
+    assert_eq!(std::fs::read_to_string(&link).unwrap(), "# Project");
+
+    let mut docs = Tree::new();
+    docs.push("index.md", macro_files::symlink("README.md"));
+    let mut tree = Tree::new();
+    tree.push("README.md", "# Project");
+    tree.push("docs", docs);
+    assert_eq!(
+        tree.diff(dir.path()).unwrap().changes(),
+        &[Change::Target {
+            path: PathBuf::from("docs/index.md"),
+            expected: PathBuf::from("README.md"),
+            actual: PathBuf::from("../README.md"),
+        }]
+    );
+}
+
+#[test]
+fn sized_files() {
+    use macro_files::{size, GIB, KIB};
+
+    let dir = macro_files::create_temp!({
+        "empty.bin": size(0),
+        "small.bin": size(3),
+        "large.bin": size(GIB),
+    })
+    .unwrap();
+
+    assert_eq!(std::fs::read(dir.path().join("small.bin")).unwrap(), [0; 3]);
+    let large = std::fs::metadata(dir.path().join("large.bin")).unwrap();
+    assert_eq!(large.len(), GIB);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        assert!(large.blocks() * 512 < 64 * KIB);
+    }
+
+    let mut tree = macro_files::Tree::new();
+    tree.push("small.bin", size(3));
+    tree.push("large.bin", size(GIB));
+    let stats = tree.stats();
+    assert_eq!(stats.bytes, GIB + 3);
+    assert_eq!(stats.largest_file, Some(("large.bin".into(), GIB)));
+}
+
+#[test]
+fn sized_files_diff() {
+    use macro_files::{size, MIB};
+    use std::io::{Seek, SeekFrom, Write};
+
+    let dir = macro_files::create_temp!({
+        "small.bin": size(3),
+        "large.bin": size(8 * MIB),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "small.bin": size(3),
+        "large.bin": size(8 * MIB),
+    });
+
+    std::fs::write(dir.path().join("small.bin"), [0, 1, 0]).unwrap();
+    let mut large = std::fs::OpenOptions::new()
+        .write(true)
+        .open(dir.path().join("large.bin"))
+        .unwrap();
+    large.seek(SeekFrom::Start(5 * MIB + 7)).unwrap();
+    large.write_all(&[1]).unwrap();
+    drop(large);
+
+    let tree = macro_files::tree!({
+        "small.bin": size(3),
+        "large.bin": size(8 * MIB),
+    });
+    let diff = tree.diff(dir.path()).unwrap();
+    assert_eq!(
+        diff.changes(),
+        &[
+            Change::ContentsFrom {
+                path: PathBuf::from("large.bin"),
+                offset: 5 * MIB + 7,
+            },
+            Change::Contents {
+                path: PathBuf::from("small.bin"),
+                expected: vec![0; 3],
+                actual: vec![0, 1, 0],
+            },
+        ]
+    );
+    assert!(diff.to_string().starts_with(&format!(
+        "contents differ from byte {}: large.bin\n",
+        5 * MIB + 7
+    )));
+}
+
+#[test]
+fn repeated_files() {
+    use macro_files::{repeat, KIB};
+
+    let dir = macro_files::create_temp!({
+        "lines.txt": repeat("ab\n", 7),
+        "ones.bin": repeat([0xff], 4),
+        "empty.bin": repeat("", 3),
+        "large.bin": repeat("0123456789", 20 * KIB + 3),
+    })
+    .unwrap();
+
+    let read = |name| std::fs::read(dir.path().join(name)).unwrap();
+    assert_eq!(read("lines.txt"), b"ab\nab\na");
+    assert_eq!(read("ones.bin"), [0xff; 4]);
+    assert_eq!(read("empty.bin"), [0; 3]);
+    let large = read("large.bin");
+    assert_eq!(large.len() as u64, 20 * KIB + 3);
+    assert!(large
+        .chunks(10)
+        .all(|chunk| *chunk == b"0123456789"[..chunk.len()]));
+
+    let tree = macro_files::tree!({ "large.bin": repeat("0123456789", 20 * KIB + 3) });
+    assert_eq!(tree.stats().bytes, 20 * KIB + 3);
+}
+
+#[test]
+fn repeated_files_diff() {
+    use macro_files::{repeat, MIB};
+
+    let dir = macro_files::create_temp!({
+        "lines.txt": repeat("ab\n", 7),
+        "large.bin": repeat("0123456789", 8 * MIB),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "lines.txt": repeat("ab\n", 7),
+        "large.bin": repeat("0123456789", 8 * MIB),
+    });
+
+    let tree = macro_files::tree!({
+        "lines.txt": repeat("ab\n", 6),
+        "large.bin": repeat("0123456789", 8 * MIB + 1),
+    });
+    assert_eq!(
+        tree.diff(dir.path()).unwrap().changes(),
+        &[
+            Change::ContentsFrom {
+                path: PathBuf::from("large.bin"),
+                offset: 8 * MIB,
+            },
+            Change::Contents {
+                path: PathBuf::from("lines.txt"),
+                expected: b"ab\nab\n".to_vec(),
+                actual: b"ab\nab\na".to_vec(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn write_options() {
+    use macro_files::{Options, WriteOptions, WriteStrategy};
+
+    let options = Options::new().write(
+        WriteOptions::new()
+            .strategy(WriteStrategy::Chunked)
+            .buffer_size(7)
+            .sync_data(true),
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let mut tree = Tree::new();
+    tree.push("nested/data.bin", macro_files::random(100, 1));
+    tree.create_with(dir.path(), &options).unwrap();
+
+    assert_eq!(
+        std::fs::read(dir.path().join("nested/data.bin")).unwrap(),
+        macro_files::random(100, 1)
+            .read_contents()
+            .unwrap()
+            .as_ref()
+    );
+}
+
+#[test]
+fn reader_streamed() {
+    use std::cell::Cell;
+    use std::io::{Read, Result};
+    use std::path::Path;
+    use std::rc::Rc;
+
+    #[derive(Default)]
+    struct Streamed(Cell<u64>);
+
+    impl macro_files::FsOps for Streamed {
+        fn write_file(&self, _path: &Path, _contents: &[u8]) -> Result<()> {
+            panic!("contents buffered");
+        }
+
+        fn write_reader(&self, _path: &Path, reader: &mut dyn Read) -> Result<u64> {
+            let len = std::io::copy(reader, &mut std::io::sink())?;
+            self.0.set(self.0.get() + len);
+            Ok(len)
+        }
+    }
+
+    let streamed = Rc::new(Streamed::default());
+    let mut tree = Tree::new();
+    let dump = std::io::repeat(b'x').take(64 * macro_files::MIB);
+    tree.push("dump.bin", macro_files::reader(dump));
+    macro_files::with_fs_ops(streamed.clone(), || tree.create_in("fixtures")).unwrap();
+
+    assert_eq!(streamed.0.get(), 64 * macro_files::MIB);
+}
+
+#[test]
+fn iterators_streamed() {
+    let dir = macro_files::create_temp!({
+        "chunks.txt": macro_files::chunks(vec!["a", "", "bc", "def"]),
+        "owned.txt": macro_files::chunks((1..=3).map(|i| vec![b'0' + i; i as usize])),
+        "bytes.bin": macro_files::byte_iter((0..5u8).rev()),
+        "empty.bin": macro_files::byte_iter(std::iter::empty()),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "chunks.txt": "abcdef",
+        "owned.txt": "122333",
+        "bytes.bin": [4, 3, 2, 1, 0],
+        "empty.bin": "",
+    });
+
+    let chunk = vec![b'x'; macro_files::MIB as usize];
+    let chunks = std::iter::repeat(chunk).take(16);
+    let tree = macro_files::tree!({ "dump.bin": macro_files::chunks(chunks) });
+    let dir = tree.create_temp().unwrap();
+    let len = std::fs::metadata(dir.path().join("dump.bin"))
+        .unwrap()
+        .len();
+    assert_eq!(len, 16 * macro_files::MIB);
+    let err = tree.create_temp().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+}
+
+#[test]
+fn lazy_contents() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let lazy = {
+        let calls = calls.clone();
+        macro_files::lazy(move || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            "rendered"
+        })
+    };
+    let mut tree = Tree::new();
+    tree.push("missing.txt", macro_files::from("missing.txt"));
+    tree.push("report.txt", lazy.clone());
+    let dir = tempfile::tempdir().unwrap();
+
+    tree.plan(dir.path()).unwrap();
+    tree.create_in(dir.path()).unwrap_err();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let mut tree = Tree::new();
+    tree.push("first.txt", lazy.clone());
+    tree.push("second.txt", lazy);
+    tree.create_in(dir.path()).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    macro_files::assert_tree!(dir.path(), {
+        "first.txt": "rendered",
+        "second.txt": "rendered",
+    });
+}
+
+#[test]
+fn display_values() {
+    struct Version(u32, u32);
+
+    impl std::fmt::Display for Version {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}.{}", self.0, self.1)
+        }
+    }
+
+    let port: u16 = 8080;
+    let dir = macro_files::create_temp!({
+        "port": port,
+        "ratio": 0.5,
+        "version": Version(1, 2),
+        "initial": 'x',
+        "bytes": [0; 3],
+        "text": "text",
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "port": "8080",
+        "ratio": "0.5",
+        "version": "1.2",
+        "initial": "x",
+        "bytes": [0, 0, 0],
+        "text": "text",
+    });
+}
+
+#[test]
+fn custom_contents() {
+    struct PemCertificate(Vec<u8>);
+
+    impl macro_files::ToFileContents for PemCertificate {
+        fn to_file_contents(&self) -> Content {
+            Content::Text(format!(
+                "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+                String::from_utf8_lossy(&self.0)
+            ))
+        }
+    }
+
+    // Preferred over the `Display` implementation.
+    impl std::fmt::Display for PemCertificate {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("certificate")
+        }
+    }
+
+    let mut tree = macro_files::tree!({
+        "cert.pem": PemCertificate(b"MIIB".to_vec()),
+    });
+    tree.push("other.pem", PemCertificate(b"MIIC".to_vec()));
+    let dir = tree.create_temp().unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "cert.pem": "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n",
+        "other.pem": "-----BEGIN CERTIFICATE-----\nMIIC\n-----END CERTIFICATE-----\n",
+    });
+}
+
+#[test]
+fn maybe_entries() {
+    use macro_files::{maybe, SkipReason};
+
+    let overrides: Option<&str> = None;
+    let mut nested = Tree::new();
+    nested.push("main.rs", "fn main() {}");
+    let mut tree = Tree::new();
+    tree.push("override.toml", maybe(overrides));
+    tree.push("config.toml", maybe(Some("debug = true")));
+    tree.push("src", maybe(Some(nested)));
+    let dir = tempfile::tempdir().unwrap();
+
+    let report = tree.create_in(dir.path()).unwrap();
+
+    let skipped: Vec<_> = report
+        .skipped()
+        .iter()
+        .map(|entry| entry.reason())
+        .collect();
+    assert_eq!(skipped, [SkipReason::None]);
+    macro_files::assert_tree!(dir.path(), {
+        "config.toml": "debug = true",
+        "src": {
+            "main.rs": "fn main() {}",
+        },
+    });
+}
+
+#[test]
+#[cfg(unix)]
+fn copies_keep_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let source = macro_files::create_temp!({
+        "run.sh": macro_files::exec("#!/bin/sh"),
+        "vendor/lib.rs": "pub fn lib() {}",
+    })
+    .unwrap();
+    let dir = macro_files::create_temp!({
+        "run.sh": macro_files::from(source.path().join("run.sh")),
+        "vendor": macro_files::copy_dir(source.path().join("vendor")),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "run.sh": "#!/bin/sh",
+        "vendor/lib.rs": "pub fn lib() {}",
+    });
+    let mode = |dir: &std::path::Path| {
+        let metadata = std::fs::metadata(dir.join("run.sh")).unwrap();
+        metadata.permissions().mode() & 0o777
+    };
+    assert_eq!(mode(dir.path()), mode(source.path()));
+    assert_ne!(mode(dir.path()) & 0o100, 0);
+}
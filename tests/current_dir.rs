@@ -0,0 +1,32 @@
+#![cfg(feature = "tempfile")]
+
+// The only test of this binary, as it changes the current directory of the
+// process.
+#[test]
+fn enter_temp_tree() {
+    let previous = std::env::current_dir().unwrap();
+    let tree = macro_files::create_temp_tree!({
+        "config.toml": "debug = true",
+        "src": { "main.rs": "fn main() {}" },
+    })
+    .unwrap();
+
+    {
+        let guard = tree.enter().unwrap();
+        assert_eq!(guard.previous(), previous);
+        assert_eq!(
+            std::env::current_dir().unwrap().canonicalize().unwrap(),
+            tree.path().canonicalize().unwrap()
+        );
+        assert!(std::path::Path::new("src/main.rs").is_file());
+
+        let nested = macro_files::create_temp_tree!({ "nested.txt": "" }).unwrap();
+        {
+            let _guard = nested.enter().unwrap();
+            assert!(std::path::Path::new("nested.txt").is_file());
+        }
+        assert!(std::path::Path::new("config.toml").is_file());
+    }
+
+    assert_eq!(std::env::current_dir().unwrap(), previous);
+}
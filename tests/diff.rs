@@ -0,0 +1,224 @@
+#![cfg(feature = "tempfile")]
+
+use std::path::{Path, PathBuf};
+
+use macro_files::{Change, Entry, EntryKind, SkipReason, Tree};
+
+fn spec() -> Tree {
+    let mut docs = Tree::new();
+    docs.push("README.md", Entry::file("# Documentation"));
+    docs.push("assets", Entry::Dir(Tree::new()));
+    let mut tree = Tree::new();
+    tree.push(
+        "README.md",
+        Entry::file("# Project\n\nSome text.\nThe end.\n"),
+    );
+    tree.push("LICENSE", Entry::file("MIT"));
+    tree.push("docs", Entry::Dir(docs));
+    tree.push("target", Entry::Skip(SkipReason::Null));
+    tree
+}
+
+#[test]
+fn diff() {
+    let dir = macro_files::create_temp!({
+        "README.md": "# Project\n\nOther text.\nThe end.\n",
+        "LICENSE": {},
+        "docs": {
+            "README.md": "# Documentation",
+        },
+        "target": {
+            "debug": {},
+        },
+    })
+    .unwrap();
+
+    let diff = spec().diff(dir.path()).unwrap();
+    assert_eq!(
+        diff.changes(),
+        &[
+            Change::KindMismatch {
+                path: "LICENSE".into(),
+                expected: EntryKind::File,
+                actual: EntryKind::Dir,
+            },
+            Change::Contents {
+                path: "README.md".into(),
+                expected: b"# Project\n\nSome text.\nThe end.\n".to_vec(),
+                actual: b"# Project\n\nOther text.\nThe end.\n".to_vec(),
+            },
+            Change::Missing {
+                path: "docs/assets".into(),
+                expected: EntryKind::Dir,
+            },
+            Change::Unexpected {
+                path: "target".into(),
+                actual: EntryKind::Dir,
+            },
+        ]
+    );
+    assert_eq!(
+        diff.missing().collect::<Vec<_>>(),
+        vec![Path::new("docs/assets")]
+    );
+    assert_eq!(
+        diff.to_string(),
+        [
+            "expected file, found directory: LICENSE",
+            "contents differ: README.md",
+            "  @@ line 3 @@",
+            "  - Some text.",
+            "  + Other text.",
+            "missing directory: docs/assets",
+            "unexpected directory: target",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn no_diff() {
+    let dir = macro_files::create_temp!({
+        "README.md": "# Project\n\nSome text.\nThe end.\n",
+        "LICENSE": "MIT",
+        "docs/assets": {},
+        PathBuf::from("docs").join("README.md"): "# Documentation",
+    })
+    .unwrap();
+
+    assert!(spec().diff(dir.path()).unwrap().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "missing file: docs/README.md")]
+fn assert_tree() {
+    let dir = macro_files::create_temp!({
+        "docs": {},
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "docs": {
+            "README.md": "# Documentation",
+        },
+    });
+}
+
+#[test]
+fn keep_not_empty() {
+    let dir = macro_files::create_temp!({
+        "cache": {
+            "index": "1",
+            "entries": {
+                "a": "a",
+            },
+        },
+        "empty": {},
+    })
+    .unwrap();
+
+    let mut tree = Tree::new();
+    tree.push("cache", macro_files::keep());
+    tree.push("empty", macro_files::keep());
+    let diff = tree.diff(dir.path()).unwrap();
+    assert_eq!(
+        diff.changes(),
+        &[Change::NotEmpty {
+            path: "cache".into(),
+            entries: vec!["entries".into(), "index".into()],
+        }]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "directory not empty: cache\n  + entries\n  + index\n"
+    );
+}
+
+#[test]
+fn ignore_patterns() {
+    let dir = macro_files::create_temp!({
+        ".git": { "objects": { "ab": "" } },
+        "Cargo.lock": "",
+        "src": { "main.rs": "fn main() {}", "gen": { "a.rs": "" } },
+        "target": { "debug": { "app": "" } },
+        "notes.txt": "",
+    })
+    .unwrap();
+
+    let spec = macro_files::tree!({
+        "src": { "main.rs": "fn main() {}" },
+        "target": { "release": {} },
+    });
+    let diff = spec
+        .diff_ignoring(dir.path(), [".git/**", "target/**", "*.lock", "src/g?n"])
+        .unwrap();
+    let unexpected: Vec<_> = diff.unexpected().collect();
+    assert_eq!(unexpected, [Path::new("notes.txt")]);
+    assert_eq!(diff.changes().len(), 1);
+
+    macro_files::assert_tree!(dir.path(), ignore = [".git", "target", "*.lock", "**/gen/*", "*.txt"], {
+        "src": { "main.rs": "fn main() {}", "gen": {} },
+    });
+}
+
+#[test]
+fn ignore_patterns_without_backtracking() {
+    let name = "a".repeat(64);
+    let dir = macro_files::create_temp!({ name.as_str(): "", "abcb": "" }).unwrap();
+
+    let spec = macro_files::tree!({});
+    let diff = spec
+        .diff_ignoring(dir.path(), ["*a*a*a*a*a*a*a*a*a*a*b", "a?c*"])
+        .unwrap();
+    let unexpected: Vec<_> = diff.unexpected().collect();
+    assert_eq!(unexpected, [Path::new(&name)]);
+
+    let diff = spec.diff_ignoring(dir.path(), ["*a*a*a*", "*b"]).unwrap();
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn content_matchers() {
+    let dir = macro_files::create_temp!({
+        "build.log": "took 3.2s",
+        "pid": "42a",
+        "Cargo.lock": "version = 3\n",
+    })
+    .unwrap();
+
+    let spec = macro_files::tree!({
+        "build.log": macro_files::any(),
+        "pid": macro_files::predicate(|contents| contents.iter().all(u8::is_ascii_digit)),
+        "Cargo.lock": macro_files::any(),
+    });
+    let diff = spec.diff(dir.path()).unwrap();
+    assert_eq!(
+        diff.changes(),
+        [Change::Unmatched {
+            path: PathBuf::from("pid"),
+            expected: "a predicate".to_owned(),
+            actual: b"42a".to_vec(),
+        }]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "contents do not match a predicate: pid\n  @@ line 1 @@\n  + 42a\n"
+    );
+
+    #[cfg(feature = "regex")]
+    macro_files::assert_tree!(dir.path(), ignore = ["pid"], {
+        "build.log": macro_files::matches(r"^took \d+\.\d+s$"),
+        "Cargo.lock": macro_files::matches(r"(?m)^version = \d+$"),
+    });
+
+    #[cfg(feature = "regex")]
+    {
+        let invalid = macro_files::tree!({ "build.log": macro_files::matches(r"took (\d+") });
+        let err = invalid.diff(dir.path()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    let err = spec.create_in(dir.path().join("out")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
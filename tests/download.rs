@@ -0,0 +1,72 @@
+#![cfg(all(feature = "http", feature = "tempfile"))]
+
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use macro_files::download;
+
+const CSV: &str = "id,name\n1,first\n";
+const CSV_SHA256: &str = "18adc24e12f1b68315a9a08012afd07b8e518ff5551fba8e91a0ad3d1e0b0b58";
+
+/// Serve `CSV` to every request on a local port, counting the requests.
+fn serve() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            counter.fetch_add(1, Ordering::SeqCst);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                CSV.len(),
+                CSV
+            )
+            .unwrap();
+        }
+    });
+    (url, requests)
+}
+
+#[test]
+fn downloaded_contents() {
+    let downloads = tempfile::tempdir().unwrap();
+    std::env::set_var("MACRO_FILES_DOWNLOADS", downloads.path());
+    assert_eq!(macro_files::downloads_dir(), downloads.path());
+    let (url, requests) = serve();
+
+    let spec = macro_files::tree!({
+        "sample.csv": download(format!("{}/sample.csv", url), CSV_SHA256.to_uppercase()),
+    });
+    let dir = spec.create_temp().unwrap();
+    macro_files::assert_tree!(dir.path(), { "sample.csv": CSV });
+    assert!(downloads.path().join(CSV_SHA256).is_file());
+
+    spec.create_temp().unwrap();
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    let digest = "0".repeat(64);
+    let err = macro_files::create_temp!({
+        "sample.csv": download(format!("{}/other.csv", url), &digest),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    assert!(!downloads.path().join(&digest).exists());
+
+    let err = macro_files::create_temp!({
+        "sample.csv": download(format!("{}/other.csv", url), "not a digest"),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "tempfile")]
+
+// The only test of this file as it sets the environment of the process.
+#[test]
+fn temp_dirs_env() {
+    assert!(!macro_files::keeps_temp_dirs());
+    std::env::set_var("MACRO_FILES_KEEP", "1");
+    assert!(macro_files::keeps_temp_dirs());
+
+    let dir = macro_files::create_temp!(prefix = "kept-", {
+        "README.md": "# Project",
+    })
+    .unwrap();
+    let path = dir.path().to_owned();
+    drop(dir);
+    assert!(path.join("README.md").is_file());
+    std::fs::remove_dir_all(&path).unwrap();
+
+    std::env::set_var("MACRO_FILES_KEEP", "0");
+    assert!(!macro_files::keeps_temp_dirs());
+    let dir = macro_files::create_temp!({}).unwrap();
+    let path = dir.path().to_owned();
+    drop(dir);
+    assert!(!path.exists());
+
+    let root = tempfile::tempdir().unwrap();
+    let tmpdir = root.path().join("tmp");
+    std::env::set_var("MACRO_FILES_TMPDIR", &tmpdir);
+    assert_eq!(macro_files::temp_root(), tmpdir);
+    let dir = macro_files::create_temp!({}).unwrap();
+    assert_eq!(dir.path().parent(), Some(tmpdir.as_path()));
+    std::env::remove_var("MACRO_FILES_TMPDIR");
+    assert_eq!(macro_files::temp_root(), std::env::temp_dir());
+
+    let ramdir = root.path().join("ram");
+    std::env::set_var("MACRO_FILES_RAMDIR", &ramdir);
+    assert_eq!(macro_files::ram_root(), ramdir);
+    let dir = macro_files::create_temp!(ram = true, {}).unwrap();
+    assert_eq!(dir.path().parent(), Some(ramdir.as_path()));
+    let dir = macro_files::create_temp!(ram = false, {}).unwrap();
+    assert_eq!(dir.path().parent(), Some(std::env::temp_dir().as_path()));
+    let file = root.path().join("file");
+    std::fs::write(&file, "").unwrap();
+    std::env::set_var("MACRO_FILES_RAMDIR", &file);
+    assert_ne!(macro_files::ram_root(), file);
+    std::env::remove_var("MACRO_FILES_RAMDIR");
+}
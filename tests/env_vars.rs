@@ -0,0 +1,55 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+
+use macro_files::{env, env_path};
+
+#[test]
+fn env_contents() {
+    std::env::set_var("MACRO_FILES_TEST_GREETING", "hello");
+
+    let dir = macro_files::create_temp!({
+        "greeting.txt": env("MACRO_FILES_TEST_GREETING"),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "greeting.txt": "hello",
+    });
+}
+
+#[test]
+fn missing_env_contents() {
+    let file = env("MACRO_FILES_TEST_MISSING_CONTENTS");
+
+    let err = macro_files::create_temp!({ "missing.txt": file }).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(err
+        .to_string()
+        .contains("MACRO_FILES_TEST_MISSING_CONTENTS"));
+}
+
+#[test]
+fn env_keys() {
+    let root = macro_files::tempfile::tempdir().unwrap();
+    std::env::set_var("MACRO_FILES_TEST_ROOT", root.path());
+
+    let create = || -> std::io::Result<()> {
+        macro_files::create!({
+            env_path("MACRO_FILES_TEST_ROOT")?.join("data"): {
+                "a.txt": "a",
+            },
+        })
+    };
+    create().unwrap();
+
+    assert!(root.path().join("data/a.txt").is_file());
+}
+
+#[test]
+fn missing_env_keys() {
+    let err = env_path("MACRO_FILES_TEST_MISSING_KEY").unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(err.to_string().contains("MACRO_FILES_TEST_MISSING_KEY"));
+}
@@ -0,0 +1,202 @@
+#![cfg(feature = "tempfile")]
+
+use std::error::Error;
+use std::io::{ErrorKind, Result};
+use std::path::Path;
+
+use macro_files::{from, CreateErrors, FsError, FsOperation, Options, StdFs};
+
+#[test]
+fn error_context() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.txt");
+
+    let err = macro_files::create_in!(dir.path(), {
+        "created.txt": "created",
+        "docs": {
+            "copy.txt": from(&missing),
+        },
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "failed to copy file {:?} from {:?}: {}",
+            dir.path().join("docs/copy.txt"),
+            missing,
+            err.source().unwrap()
+        )
+    );
+    let err = err.get_ref().unwrap().downcast_ref::<FsError>().unwrap();
+    assert_eq!(err.operation(), FsOperation::CopyFile);
+    assert_eq!(err.path(), dir.path().join("docs/copy.txt"));
+    assert_eq!(err.target(), Some(missing.as_path()));
+}
+
+struct Delegate;
+
+impl macro_files::FsOps for Delegate {
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        macro_files::with_fs_ops(StdFs, || macro_files::create_dir(path))
+    }
+}
+
+#[test]
+fn error_context_not_nested() {
+    let dir = tempfile::tempdir().unwrap();
+    macro_files::write_file(dir.path().join("file"), "").unwrap();
+
+    let err = macro_files::with_fs_ops(Delegate, || {
+        macro_files::create_in!(dir.path(), {
+            "file/nested": {},
+        })
+    })
+    .unwrap_err();
+
+    assert_eq!(err.to_string().matches("failed to").count(), 1);
+    let err = err.get_ref().unwrap().downcast_ref::<FsError>().unwrap();
+    assert_eq!(err.operation(), FsOperation::CreateDir);
+    assert_eq!(err.path(), dir.path().join("file/nested"));
+}
+
+#[test]
+fn best_effort_collects_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    macro_files::write_file(dir.path().join("file"), "").unwrap();
+
+    let err = macro_files::create_with!(Options::new().best_effort(true), {
+        dir.path(): {
+            "file/nested": {
+                "lost.txt": "",
+            },
+            "created.txt": "created",
+            "docs": {
+                "copy.txt": from("missing.txt"),
+                "index.md": "# Index",
+            },
+        },
+    })
+    .unwrap_err();
+
+    let err = err
+        .into_inner()
+        .unwrap()
+        .downcast::<CreateErrors>()
+        .unwrap();
+    assert_eq!(err.report().created().len(), 4);
+    let paths: Vec<_> = err.errors().iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(
+        paths,
+        [
+            dir.path().join("file/nested"),
+            dir.path().join("docs/copy.txt")
+        ]
+    );
+    assert!(err
+        .to_string()
+        .starts_with("2 entries failed to be created\n"));
+    assert!(dir.path().join("docs/index.md").is_file());
+    assert!(!dir.path().join("file/nested").exists());
+}
+
+#[test]
+fn duplicate_keys() {
+    use macro_files::{InvalidKey, InvalidKeyReason, Tree};
+
+    let dir = tempfile::tempdir().unwrap();
+    let name = "config.toml";
+    let err = macro_files::create_in!(dir.path(), {
+        "config.toml": "first",
+        "src": {},
+        name: "second",
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+    assert_eq!(key.key(), Path::new("config.toml"));
+    assert_eq!(key.reason(), InvalidKeyReason::Duplicate);
+
+    let mut src = Tree::new();
+    src.push("main.rs", "fn main() {}");
+    let mut tree = Tree::new();
+    tree.push("src/./main.rs", "fn main() {}");
+    tree.push("src", src);
+    let err = tree.plan(dir.path()).unwrap_err();
+    let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+    assert_eq!(key.key(), Path::new("main.rs"));
+}
+
+#[test]
+fn parent_keys() {
+    use macro_files::{InvalidKey, InvalidKeyReason, Tree};
+
+    let dir = tempfile::tempdir().unwrap();
+    let shared = "../shared.txt";
+    let err = macro_files::create_in!(dir.path(), {
+        "docs": { shared: "shared" },
+    })
+    .unwrap_err();
+    let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+    assert_eq!(key.reason(), InvalidKeyReason::ParentDir);
+    assert!(!dir.path().join("shared.txt").exists());
+
+    let mut docs = Tree::new().allow_parent_keys();
+    docs.push(shared, "shared");
+    let tree = macro_files::tree!({ "docs": docs.clone() });
+    tree.create_in(dir.path()).unwrap();
+    assert!(tree.diff(dir.path()).unwrap().is_empty());
+    macro_files::assert_tree!(dir.path(), {
+        "docs": {},
+        "shared.txt": "shared",
+    });
+
+    let tree = macro_files::tree!({ "docs": docs, "shared.txt": "other" });
+    let err = tree.plan(dir.path()).unwrap_err();
+    let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+    assert_eq!(key.reason(), InvalidKeyReason::Duplicate);
+}
+
+#[test]
+fn current_dir_keys() {
+    use macro_files::{InvalidKey, InvalidKeyReason};
+
+    let dir = tempfile::tempdir().unwrap();
+    for key in [".", "./.", "docs/.."] {
+        let err = macro_files::create_in!(dir.path(), { key: {} }).unwrap_err();
+        let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+        assert_eq!(key.reason(), InvalidKeyReason::Empty);
+    }
+}
+
+#[test]
+fn case_collisions() {
+    use macro_files::{InvalidKey, InvalidKeyReason};
+
+    let dir = tempfile::tempdir().unwrap();
+    let err = macro_files::create_in!(dir.path(), {
+        "docs": {
+            "README.md": "# Docs",
+        },
+        "Docs/readme.md": "# Docs",
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+    assert_eq!(key.key(), Path::new("Docs/readme.md"));
+    assert_eq!(key.reason(), InvalidKeyReason::CaseCollision);
+    assert!(!dir.path().join("Docs/readme.md").exists());
+
+    let options = Options::new().allow_case_collisions(true);
+    let tree = macro_files::tree!({
+        "LICENSE": "MIT",
+        "license": "MIT",
+    });
+    if cfg!(target_os = "linux") {
+        tree.create_with(dir.path(), &options).unwrap();
+        assert!(dir.path().join("license").is_file());
+    }
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "tempfile")]
+
+use std::cell::Cell;
+use std::fs;
+
+fn evaluate<'a>(evaluated: &Cell<bool>, contents: &'a str) -> &'a str {
+    evaluated.set(true);
+    contents
+}
+
+#[test]
+fn values_are_evaluated_before_creation() {
+    let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    let readme = temp_dir.path().join("README.md");
+
+    macro_files::create!({
+        temp_dir.path(): {
+            "README.md": "# Project",
+            "readme-exists": readme.exists().to_string(),
+        }
+    })
+    .unwrap();
+
+    assert_eq!(fs::read_to_string(&readme).unwrap(), "# Project");
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("readme-exists")).unwrap(),
+        "false"
+    );
+}
+
+#[test]
+fn values_are_evaluated_when_creation_fails() {
+    let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    fs::write(temp_dir.path().join("docs"), "").unwrap();
+    let evaluated = Cell::new(false);
+
+    let result = macro_files::create!({
+        temp_dir.path(): {
+            "docs": {
+                "README.md": "# Documentation",
+            },
+            "LICENSE": evaluate(&evaluated, "MIT"),
+        }
+    });
+
+    assert!(result.is_err());
+    assert!(evaluated.get());
+    assert!(!temp_dir.path().join("LICENSE").exists());
+}
@@ -0,0 +1,153 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+
+use macro_files::from;
+
+#[test]
+fn copy_from_files() {
+    let dir = macro_files::create_temp!({
+        "config.toml": from("tests/fixtures/config.toml"),
+        "data": {
+            "blob.bin": from("tests/fixtures/blob.bin"),
+        },
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "config.toml": "name = \"fixture\"\nversion = 1\n",
+        "data/blob.bin": b"\x00\x01\x02\xff\xfe binary\n",
+    });
+    macro_files::assert_tree!(dir.path(), {
+        "config.toml": from("tests/fixtures/config.toml"),
+        "data/blob.bin": from("tests/fixtures/blob.bin"),
+    });
+}
+
+#[test]
+fn missing_source_fails() {
+    let err = macro_files::create_temp!({
+        "config.toml": from("tests/fixtures/missing.toml"),
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[cfg(feature = "checksum")]
+#[test]
+fn copy_from_with_checksum() {
+    let blob_sha256 = "9c842c25d26a76dc9d8c8e1f93231dba34b70d7a5f1bfd937300e157bd057891";
+    macro_files::create_temp!({
+        "blob.bin": from("tests/fixtures/blob.bin").sha256(blob_sha256),
+    })
+    .unwrap();
+
+    let err = macro_files::create_temp!({
+        "blob.bin": from("tests/fixtures/config.toml").sha256(blob_sha256),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn copy_dir() {
+    let dir = macro_files::create_temp!({
+        "generated.txt": "generated",
+        "vendor": macro_files::copy_dir("tests/fixtures/vendor"),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "generated.txt": "generated",
+        "vendor": {
+            "README.md": "# Vendor\n",
+            "lib": {
+                "lib.rs": "pub fn lib() {}\n",
+            },
+        },
+    });
+    macro_files::assert_tree!(dir.path(), {
+        "generated.txt": "generated",
+        "vendor": macro_files::copy_dir("tests/fixtures/vendor"),
+    });
+}
+
+#[test]
+fn rerun_if_changed_sources() {
+    let tree = macro_files::tree!({
+        "config.toml": from("tests/fixtures/config.toml"),
+        "data": {
+            "blob.bin": from("tests/fixtures/blob.bin"),
+            "copy.toml": from("tests/fixtures/config.toml"),
+        },
+        "vendor": macro_files::copy_dir("tests/fixtures/vendor"),
+        "generated.txt": "generated",
+    });
+
+    assert_eq!(
+        tree.sources(),
+        [
+            "tests/fixtures/config.toml",
+            "tests/fixtures/blob.bin",
+            "tests/fixtures/vendor",
+        ]
+        .map(std::path::PathBuf::from)
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let options = macro_files::Options::new().rerun_if_changed(true);
+    assert!(options.prints_rerun_if_changed());
+    tree.create_with(dir.path(), &options).unwrap();
+    assert!(tree.diff(dir.path()).unwrap().is_empty());
+}
+
+#[cfg(feature = "glob")]
+#[test]
+fn copy_from_glob() {
+    let dir = macro_files::create_temp!({
+        "all": macro_files::from_glob("tests/fixtures/**/*").unwrap(),
+        "rust": macro_files::from_glob("tests/fixtures/**/*.rs").unwrap(),
+        "none": macro_files::from_glob("tests/fixtures/*.missing").unwrap(),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "all": {
+            "Cargo.toml.tera": from("tests/fixtures/Cargo.toml.tera"),
+            "blob.bin": from("tests/fixtures/blob.bin"),
+            "config.toml": from("tests/fixtures/config.toml"),
+            "data.tar.gz": from("tests/fixtures/data.tar.gz"),
+            "data.zip": from("tests/fixtures/data.zip"),
+            "vendor": macro_files::copy_dir("tests/fixtures/vendor"),
+        },
+        "rust/vendor/lib/lib.rs": "pub fn lib() {}\n",
+        "none": {},
+    });
+
+    let err = macro_files::from_glob("tests/[fixtures").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn fixture_paths() {
+    let config = macro_files::fixture!("tests/fixtures/config.toml");
+    assert!(config.is_absolute());
+    assert_eq!(
+        config,
+        std::env::current_dir()
+            .unwrap()
+            .join("tests/fixtures/config.toml")
+    );
+
+    let dir = macro_files::create_temp!({
+        "config.toml": from(config),
+        "vendor": macro_files::copy_dir(macro_files::fixture!("tests/fixtures/vendor",)),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "config.toml": from("tests/fixtures/config.toml"),
+        "vendor": macro_files::copy_dir("tests/fixtures/vendor"),
+    });
+}
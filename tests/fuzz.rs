@@ -0,0 +1,57 @@
+#![cfg(all(feature = "arbitrary", feature = "tempfile"))]
+
+use macro_files::arbitrary::{Arbitrary, Unstructured};
+use macro_files::{Entry, Options, Tree};
+
+/// Deterministic pseudo-random bytes, standing in for fuzzer inputs.
+fn bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+fn names_are_valid(tree: &Tree) -> bool {
+    tree.entries().all(|(key, entry)| {
+        let name = key.to_str().unwrap();
+        let valid = !name.is_empty()
+            && !name.contains(&['/', '\\', '\0'][..])
+            && name.chars().any(|c| c != '.');
+        valid
+            && match entry {
+                Entry::Dir(tree) => names_are_valid(tree),
+                _ => true,
+            }
+    })
+}
+
+#[test]
+fn create_arbitrary_trees() {
+    let mut entries = 0;
+    for seed in 0..64 {
+        let data = bytes(seed, 4096);
+        let tree = Tree::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert!(names_are_valid(&tree), "{:?}", tree);
+        entries += tree.entries().count();
+
+        let dir = tempfile::tempdir().unwrap();
+        tree.create_with(dir.path(), &Options::new().sandbox(true))
+            .unwrap();
+        assert_eq!(
+            Tree::read_from(dir.path()).unwrap(),
+            tree.normalize().unwrap()
+        );
+    }
+    assert!(entries > 0);
+}
+
+#[test]
+fn empty_input_is_empty_tree() {
+    let tree = Tree::arbitrary(&mut Unstructured::new(&[])).unwrap();
+    assert!(tree.is_empty());
+}
@@ -0,0 +1,66 @@
+#![cfg(all(feature = "git", feature = "tempfile"))]
+
+use std::path::Path;
+use std::process::Command;
+
+use macro_files::Tree;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn commit_entries_in_repository() {
+    let temp_dir = macro_files::create_temp!({
+        "repo": git {
+            "README.md": "# Project",
+            "src": {
+                "main.rs": "fn main() {}",
+            },
+        },
+        "notes.txt": "",
+    })
+    .unwrap();
+
+    let repo = temp_dir.path().join("repo");
+    assert_eq!(
+        git(&repo, &["log", "--format=%an %s"]),
+        "macro_files Initial commit\n"
+    );
+    assert_eq!(git(&repo, &["ls-files"]), "README.md\nsrc/main.rs\n");
+    assert_eq!(git(&repo, &["status", "--porcelain"]), "");
+    assert_eq!(git(&repo, &["branch", "--show-current"]), "main\n");
+    assert!(!temp_dir.path().join(".git").exists());
+}
+
+#[test]
+fn commit_empty_repository() {
+    let mut tree = Tree::new();
+    tree.push("repo", Tree::new().git());
+    let temp_dir = tempfile::tempdir().unwrap();
+    tree.create_in(temp_dir.path()).unwrap();
+
+    let repo = temp_dir.path().join("repo");
+    assert_eq!(git(&repo, &["rev-list", "--count", "HEAD"]), "1\n");
+}
+
+#[test]
+fn named_repository() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let manifest = macro_files::create_manifest!({
+        temp_dir.path(): {
+            "repo" as repo: git {
+                "README.md": "# Project",
+            },
+        },
+    })
+    .unwrap();
+
+    assert!(manifest["repo"].join(".git").is_dir());
+}
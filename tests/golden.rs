@@ -0,0 +1,42 @@
+#![cfg(feature = "tempfile")]
+
+use std::panic::catch_unwind;
+
+#[test]
+fn golden_dirs() {
+    let actual = macro_files::create_temp!({
+        "out": { "main.rs": "fn main() {}", "empty": {} },
+    })
+    .unwrap();
+    let parent = macro_files::create_temp!({
+        "golden": { "out": { "main.rs": "fn main() {}\n" }, "stale.txt": "" },
+    })
+    .unwrap();
+    let golden = parent.path().join("golden");
+
+    let err =
+        catch_unwind(|| macro_files::assert_matches_dir!(actual.path(), &golden)).unwrap_err();
+    let message = err.downcast_ref::<String>().unwrap();
+    assert!(
+        message.contains("set UPDATE_GOLDEN=1 to update it"),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains("contents differ: out/main.rs"),
+        "{}",
+        message
+    );
+
+    std::env::set_var("UPDATE_GOLDEN", "1");
+    assert!(macro_files::updates_golden());
+    macro_files::assert_matches_dir!(actual.path(), &golden);
+    macro_files::assert_matches_dir!(actual.path(), parent.path().join("new"));
+    std::env::remove_var("UPDATE_GOLDEN");
+
+    macro_files::assert_matches_dir!(actual.path(), &golden);
+    macro_files::assert_matches_dir!(actual.path(), parent.path().join("new"));
+    macro_files::assert_tree!(&golden, {
+        "out": { "main.rs": "fn main() {}", "empty": {} },
+    });
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "tempfile")]
+
+use macro_files::from;
+
+#[test]
+fn guard_removes_created_entries() {
+    let dir = macro_files::create_temp!({
+        "docs": {
+            "README.md": "# Existing",
+        },
+    })
+    .unwrap();
+
+    let guard = macro_files::create_guarded!({
+        dir.path(): {
+            "docs": {
+                "README.md": "# Project",
+                "guide/intro.md": "# Intro",
+            },
+            "src/main.rs": "fn main() {}",
+        },
+    })
+    .unwrap();
+    assert_eq!(
+        guard.paths(),
+        [dir.path().join("docs/guide"), dir.path().join("src")]
+    );
+    drop(guard);
+
+    macro_files::assert_tree!(dir.path(), {
+        "docs/README.md": "# Project",
+    });
+}
+
+#[test]
+fn guard_removes_on_panic() {
+    let dir = tempfile::tempdir().unwrap();
+    let result = std::panic::catch_unwind(|| {
+        let _guard = macro_files::create_guarded!({
+            dir.path(): {
+                "fixture.txt": "data",
+            },
+        })
+        .unwrap();
+        panic!("test failed");
+    });
+
+    assert!(result.is_err());
+    assert!(!dir.path().join("fixture.txt").exists());
+}
+
+#[test]
+fn guard_removes_on_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let err = macro_files::create_guarded!({
+        dir.path(): {
+            "created.txt": "",
+            "copy.txt": from(dir.path().join("missing.txt")),
+        },
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(!dir.path().join("created.txt").exists());
+}
+
+#[test]
+fn guard_keep() {
+    let dir = tempfile::tempdir().unwrap();
+    let guard = macro_files::create_guarded!({
+        dir.path(): {
+            "kept.txt": "",
+        },
+    })
+    .unwrap();
+
+    let report = guard.keep();
+    assert_eq!(report.created().len(), 2);
+    assert!(dir.path().join("kept.txt").is_file());
+}
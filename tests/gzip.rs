@@ -0,0 +1,42 @@
+#![cfg(all(feature = "flate2", feature = "tempfile"))]
+
+use std::io::Read;
+use std::path::Path;
+
+use macro_files::flate2::read::GzDecoder;
+use macro_files::gz;
+
+fn decompress(path: &Path) -> Vec<u8> {
+    let mut contents = Vec::new();
+    GzDecoder::new(std::fs::File::open(path).unwrap())
+        .read_to_end(&mut contents)
+        .unwrap();
+    contents
+}
+
+#[test]
+fn gzip_compressed_files() {
+    let dir = macro_files::create_temp!({
+        "logs": {
+            "app.log.gz": gz("started\n"),
+            "empty.log.gz": gz(""),
+            "data.bin.gz": gz([0, 1, 2]),
+        },
+    })
+    .unwrap();
+
+    assert_eq!(
+        decompress(&dir.path().join("logs/app.log.gz")),
+        b"started\n"
+    );
+    assert_eq!(decompress(&dir.path().join("logs/empty.log.gz")), b"");
+    assert_eq!(decompress(&dir.path().join("logs/data.bin.gz")), [0, 1, 2]);
+}
+
+#[test]
+fn reproducible_compression() {
+    assert_eq!(gz("contents"), gz("contents"));
+    macro_files::assert_tree!(macro_files::create_temp!({ "a.gz": gz("a") }).unwrap().path(), {
+        "a.gz": gz("a"),
+    });
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "tempfile")]
+
+use std::path::Path;
+
+// The only test of this binary, as it changes the environment of the
+// process.
+#[test]
+fn enter_temp_home() {
+    std::env::set_var("XDG_CONFIG_HOME", "/previous/config");
+    std::env::remove_var("XDG_CACHE_HOME");
+    let home = macro_files::create_temp_tree!({
+        ".config/app/config.toml": "theme = \"dark\"",
+        ".local/share/app/history": "",
+    })
+    .unwrap();
+
+    {
+        let guard = home.enter_home();
+        assert!(guard
+            .previous()
+            .contains(&("XDG_CONFIG_HOME", Some("/previous/config".into()))));
+        assert_eq!(std::env::var_os("HOME").unwrap(), home.path());
+        let config = std::env::var_os("XDG_CONFIG_HOME").unwrap();
+        assert!(Path::new(&config).join("app/config.toml").is_file());
+        let data = std::env::var_os("XDG_DATA_HOME").unwrap();
+        assert!(Path::new(&data).join("app/history").is_file());
+        let cache = std::env::var_os("XDG_CACHE_HOME").unwrap();
+        assert_eq!(Path::new(&cache), home.path().join(".cache"));
+        #[cfg(windows)]
+        {
+            assert_eq!(std::env::var_os("USERPROFILE").unwrap(), home.path());
+            let appdata = std::env::var_os("APPDATA").unwrap();
+            assert_eq!(Path::new(&appdata), home.path().join("AppData\\Roaming"));
+        }
+    }
+
+    assert_eq!(
+        std::env::var_os("XDG_CONFIG_HOME").unwrap(),
+        "/previous/config"
+    );
+    assert_eq!(std::env::var_os("XDG_CACHE_HOME"), None);
+    assert_ne!(std::env::var_os("HOME").unwrap_or_default(), home.path());
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use macro_files::{file, File, Tree};
+
+#[test]
+fn run_hooks_once_entries_are_created() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let runs = Arc::new(Mutex::new(Vec::new()));
+    let record = |runs: &Arc<Mutex<Vec<(PathBuf, usize)>>>| {
+        let runs = runs.clone();
+        move |path: &std::path::Path| {
+            let count = std::fs::read_dir(path).map_or(0, |entries| entries.count());
+            runs.lock().unwrap().push((path.to_owned(), count));
+            Ok(())
+        }
+    };
+    let mut src = Tree::new().after_create(record(&runs));
+    src.push("main.rs", "fn main() {}");
+    src.push("lib.rs", "");
+    let mut project = Tree::new().after_create(record(&runs));
+    project.push("src", src);
+    project.push("Cargo.toml", "[package]");
+    let mut tree = Tree::new();
+    tree.push("project", project);
+    tree.create_in(temp_dir.path()).unwrap();
+
+    let project = temp_dir.path().join("project");
+    assert_eq!(
+        *runs.lock().unwrap(),
+        [(project.join("src"), 2), (project, 2)]
+    );
+}
+
+#[test]
+fn run_file_hooks_before_flags() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    macro_files::create!({
+        temp_dir.path(): {
+            "config.toml": file("debug = false").readonly().after_create(|path| {
+                std::fs::write(path, "debug = true")
+            }),
+        },
+    })
+    .unwrap();
+
+    let path = temp_dir.path().join("config.toml");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "debug = true");
+    let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+    assert!(permissions.readonly());
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    std::fs::set_permissions(&path, permissions).unwrap();
+}
+
+#[test]
+fn fail_with_hook_errors() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let err = macro_files::create!({
+        temp_dir.path(): {
+            "data.db": file("").after_create(|_| {
+                Err(Error::new(ErrorKind::Other, "database locked"))
+            }),
+            "README.md": "# Project",
+        },
+    })
+    .unwrap_err();
+
+    assert_eq!(err.to_string(), "database locked");
+    assert!(!temp_dir.path().join("README.md").exists());
+}
+
+#[test]
+fn compare_hooks_by_identity() {
+    let hooked = File::new("").after_create(|_| Ok(()));
+    assert!(hooked.has_hooks());
+    assert_eq!(hooked, hooked.clone());
+    assert_ne!(hooked, File::new("").after_create(|_| Ok(())));
+    assert!(!Tree::new().has_hooks());
+}
@@ -0,0 +1,20 @@
+#![cfg(all(feature = "include", feature = "tempfile"))]
+
+use macro_files::{copy_dir, include_tree, Entry};
+
+#[test]
+fn embed_directory() {
+    let vendor = include_tree!("tests/fixtures/vendor");
+
+    let keys: Vec<_> = vendor.entries().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["README.md", "lib"]);
+    assert!(matches!(vendor.entries().nth(1), Some((_, Entry::Dir(_)))));
+
+    let dir = macro_files::create_temp!({
+        "embedded": vendor,
+    })
+    .unwrap();
+    macro_files::assert_tree!(dir.path(), {
+        "embedded": copy_dir("tests/fixtures/vendor"),
+    });
+}
@@ -0,0 +1,54 @@
+#![cfg(all(feature = "io-uring", feature = "tempfile", target_os = "linux"))]
+
+use std::time::{Duration, SystemTime};
+
+use macro_files::{file, hardlink, EntryKind, Options, Tree, WriteOptions, WriteStrategy};
+
+#[test]
+fn io_uring_creation() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = SystemTime::UNIX_EPOCH + Duration::from_secs(86_400);
+    let mut tree = Tree::new();
+    for i in 0..600 {
+        tree.push(format!("dirs/{}/{}.txt", i % 7, i), i.to_string());
+    }
+    tree.push("dirs/0/link.txt", hardlink("dirs/0/0.txt"));
+    tree.push("old.txt", file("old").mtime(old));
+    tree.push("empty.txt", "");
+    tree.push("large.bin", vec![7; 100_000]);
+    tree.push("copy.toml", macro_files::from("Cargo.toml"));
+
+    let options = Options::new().io_uring(true).write(
+        WriteOptions::new()
+            .strategy(WriteStrategy::Chunked)
+            .buffer_size(4096)
+            .sync_data(true),
+    );
+    let report = tree.create_with(dir.path(), &options).unwrap();
+
+    let files = report
+        .created()
+        .iter()
+        .filter(|entry| entry.kind() == EntryKind::File)
+        .count();
+    assert_eq!(files, 605);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("dirs/4/599.txt")).unwrap(),
+        "599"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("dirs/0/link.txt")).unwrap(),
+        "0"
+    );
+    assert_eq!(
+        std::fs::read(dir.path().join("large.bin")).unwrap(),
+        [7; 100_000]
+    );
+    assert_eq!(std::fs::read(dir.path().join("empty.txt")).unwrap(), b"");
+    assert_eq!(
+        std::fs::read(dir.path().join("copy.toml")).unwrap(),
+        std::fs::read("Cargo.toml").unwrap()
+    );
+    let metadata = std::fs::metadata(dir.path().join("old.txt")).unwrap();
+    assert_eq!(metadata.modified().unwrap(), old);
+}
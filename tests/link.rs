@@ -0,0 +1,219 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use macro_files::{
+    hardlink, junction, symlink, Change, FsOps, Options, StdFs, SymlinkFallback, Tree, WriteOptions,
+};
+
+#[test]
+fn hard_links() {
+    let dir = macro_files::create_temp!({
+        "data.bin": [0, 1, 2],
+        "backup": {
+            "data.bin": hardlink("data.bin"),
+            "other.bin": hardlink("data.bin"),
+        },
+    })
+    .unwrap();
+
+    std::fs::write(dir.path().join("data.bin"), [3, 4]).unwrap();
+    assert_eq!(
+        std::fs::read(dir.path().join("backup/data.bin")).unwrap(),
+        [3, 4]
+    );
+
+    macro_files::assert_tree!(dir.path(), {
+        "data.bin": [3, 4],
+        "backup": {
+            "data.bin": hardlink("data.bin"),
+            "other.bin": hardlink("data.bin"),
+        },
+    });
+}
+
+#[test]
+fn not_linked() {
+    let dir = macro_files::create_temp!({
+        "data.bin": [0, 1, 2],
+        "copy.bin": [0, 1, 2],
+    })
+    .unwrap();
+
+    let mut tree = Tree::new();
+    tree.push("data.bin", [0, 1, 2]);
+    tree.push("copy.bin", hardlink("data.bin"));
+    assert_eq!(
+        tree.diff(dir.path()).unwrap().changes(),
+        &[Change::NotLinked {
+            path: PathBuf::from("copy.bin"),
+            original: PathBuf::from("data.bin"),
+        }]
+    );
+}
+
+#[test]
+fn deduplicate() {
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    macro_files::create_with!(Options::new().deduplicate(true), {
+        dir.path(): {
+            "data.bin": [0, 1, 2],
+            "backup": {
+                "data.bin": [0, 1, 2],
+                "other.bin": [3, 4],
+            },
+            "copy.bin": [0, 1, 2],
+            "readonly.bin": macro_files::file([0, 1, 2]).readonly(),
+        },
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "data.bin": [0, 1, 2],
+        "backup": {
+            "data.bin": hardlink("data.bin"),
+            "other.bin": [3, 4],
+        },
+        "copy.bin": hardlink("data.bin"),
+        "readonly.bin": [0, 1, 2],
+    });
+    let mut tree = Tree::new();
+    tree.push("data.bin", [0, 1, 2]);
+    tree.push("readonly.bin", hardlink("data.bin"));
+    assert!(!tree.diff(dir.path()).unwrap().is_empty());
+}
+
+#[test]
+fn missing_original_fails() {
+    let err = macro_files::create_temp!({
+        "copy.bin": hardlink("data.bin"),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let err = macro_files::create_temp!({
+        "copy.bin": hardlink("../data.bin"),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn junctions() {
+    let dir = macro_files::create_temp!({
+        "src/lib.rs": "",
+        "vendor": junction("src"),
+    })
+    .unwrap();
+
+    assert!(dir.path().join("vendor/lib.rs").is_file());
+    let metadata = std::fs::symlink_metadata(dir.path().join("vendor")).unwrap();
+    assert!(metadata.file_type().is_symlink());
+}
+
+/// Filesystem operations failing to create symlinks as on unprivileged
+/// Windows runners, creating junctions as symlinks.
+struct Unprivileged;
+
+impl FsOps for Unprivileged {
+    fn create_symlink(&self, _target: &Path, _path: &Path) -> Result<()> {
+        Err(Error::from(ErrorKind::PermissionDenied))
+    }
+
+    fn create_junction(&self, target: &Path, path: &Path) -> Result<()> {
+        StdFs.create_symlink(target, path)
+    }
+}
+
+#[test]
+fn symlink_fallback() {
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    let create = |fallback| {
+        let options = Options::new().write(WriteOptions::new().symlink_fallback(fallback));
+        macro_files::with_fs_ops(Unprivileged, || {
+            macro_files::create_with!(options, {
+                dir.path(): {
+                    "src/lib.rs": "",
+                    "README.md": "# Project",
+                    "vendor": symlink("src"),
+                    "docs/index.md": symlink("../README.md"),
+                },
+            })
+        })
+    };
+
+    let err = create(SymlinkFallback::Error).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+
+    create(SymlinkFallback::Junction).unwrap();
+    let metadata = std::fs::symlink_metadata(dir.path().join("vendor")).unwrap();
+    assert!(metadata.file_type().is_symlink());
+    let metadata = std::fs::symlink_metadata(dir.path().join("docs/index.md")).unwrap();
+    assert!(metadata.is_file());
+
+    std::fs::remove_file(dir.path().join("vendor")).unwrap();
+    create(SymlinkFallback::Copy).unwrap();
+    let metadata = std::fs::symlink_metadata(dir.path().join("vendor")).unwrap();
+    assert!(metadata.is_dir());
+    macro_files::assert_tree!(dir.path(), {
+        "src/lib.rs": "",
+        "README.md": "# Project",
+        "vendor/lib.rs": "",
+        "docs/index.md": "# Project",
+    });
+}
+
+#[test]
+fn dangling_symlink_fallback_fails() {
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    let options = Options::new().write(WriteOptions::new().symlink_fallback(SymlinkFallback::Copy));
+    let err = macro_files::with_fs_ops(Unprivileged, || {
+        macro_files::create_with!(options, {
+            dir.path().join("link"): symlink("missing"),
+        })
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+}
+
+#[test]
+#[cfg(unix)]
+fn dangling_symlink_policy() {
+    use std::sync::{Arc, Mutex};
+
+    use macro_files::DanglingSymlinks;
+
+    let mut tree = Tree::new();
+    tree.push("current", symlink("releases/v2"));
+    tree.push("latest", symlink("releases/v1"));
+    tree.push("releases/v1", "");
+
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    let err = tree
+        .create_with(
+            dir.path(),
+            &Options::new().dangling_symlinks(DanglingSymlinks::Error),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let dangling = Arc::new(Mutex::new(Vec::new()));
+    let options = Options::new().on_dangling_symlink({
+        let dangling = dangling.clone();
+        move |path, target| {
+            let entry = (path.to_owned(), target.to_owned());
+            dangling.lock().unwrap().push(entry);
+        }
+    });
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    tree.create_with(dir.path(), &options).unwrap();
+    assert_eq!(
+        *dangling.lock().unwrap(),
+        [(dir.path().join("current"), PathBuf::from("releases/v2"))]
+    );
+
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    tree.create_with(dir.path(), &Options::new()).unwrap();
+    assert!(dir.path().join("current").is_symlink());
+}
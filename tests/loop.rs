@@ -0,0 +1,53 @@
+#![cfg(feature = "tempfile")]
+
+use macro_files::size;
+
+#[test]
+fn loop_entries() {
+    let users = vec![("ada", 1815), ("alan", 1912)];
+
+    let dir = macro_files::create_temp!({
+        for i in 0..3 => format!("shard-{}.dat", i): size(i),
+        "users": {
+            for (name, year) in &users => {
+                *name: {
+                    "born": year,
+                    "notes.md": "",
+                },
+            },
+        },
+        for name in ["a", "b"] => name: name
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "shard-0.dat": "",
+        "shard-1.dat": [0],
+        "shard-2.dat": [0, 0],
+        "users": {
+            "ada": {
+                "born": "1815",
+                "notes.md": "",
+            },
+            "alan": {
+                "born": "1912",
+                "notes.md": "",
+            },
+        },
+        "a": "a",
+        "b": "b",
+    });
+}
+
+#[test]
+fn empty_loop() {
+    let dir = macro_files::create_temp!({
+        for _ in std::iter::empty::<()>() => "never": "",
+        "README.md": "",
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "",
+    });
+}
@@ -0,0 +1,58 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use macro_files::{copy_dir, file, hardlink, symlink, EntryKind, MemoryFs};
+
+#[test]
+fn create_in_memory() {
+    let fs = MemoryFs::new();
+    let mtime = UNIX_EPOCH + Duration::from_secs(60);
+    macro_files::create_with_fs!(fs.clone(), {
+        "memory-project": {
+            "README.md": file("# Project").mtime(mtime).readonly(),
+            "src/bin/main.rs": "fn main() {}",
+            "vendor": copy_dir("tests/fixtures/vendor"),
+            "latest": symlink("README.md"),
+            "copy.md": hardlink("memory-project/README.md"),
+        },
+    })
+    .unwrap();
+
+    assert!(!Path::new("memory-project").exists());
+    assert_eq!(fs.read("memory-project/README.md").unwrap(), b"# Project");
+    assert_eq!(fs.modified("memory-project/README.md"), Some(mtime));
+    assert!(fs.attributes("memory-project/README.md").unwrap().readonly);
+    assert_eq!(fs.kind("memory-project/src/bin"), Some(EntryKind::Dir));
+    assert_eq!(
+        fs.read_link("memory-project/latest").unwrap(),
+        Path::new("README.md")
+    );
+    assert_eq!(fs.read("memory-project/copy.md").unwrap(), b"# Project");
+    assert_eq!(
+        fs.kind("memory-project/vendor/lib/lib.rs"),
+        Some(EntryKind::File)
+    );
+    assert_eq!(fs.paths().len(), 11);
+    assert_eq!(fs.paths()[0], PathBuf::from("memory-project"));
+}
+
+#[test]
+fn memory_conflicts() {
+    let fs = MemoryFs::new();
+    macro_files::create_with_fs!(fs.clone(), {
+        "memory-conflicts": {
+            "config": "",
+        },
+    })
+    .unwrap();
+
+    let err = macro_files::create_with_fs!(fs.clone(), {
+        "memory-conflicts/config": {
+            "nested.toml": "",
+        },
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert_eq!(fs.kind("memory-conflicts/config"), Some(EntryKind::File));
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "mock")]
+
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use macro_files::mock::{self, Write};
+use macro_files::symlink;
+
+#[test]
+fn record_writes_in_order() {
+    let watcher = mock::watch_fs();
+    macro_files::write_file("notes.txt", "first").unwrap();
+    macro_files::create!({
+        "notes.txt": "second",
+        "docs": {
+            "latest": symlink("../notes.txt"),
+        },
+    })
+    .unwrap();
+
+    assert_eq!(
+        watcher.consume(),
+        [
+            Write::file("notes.txt", "first"),
+            Write::file("notes.txt", "second"),
+            Write::dir("docs"),
+            Write::Symlink(PathBuf::from("docs/latest"), PathBuf::from("../notes.txt")),
+        ]
+    );
+    assert!(!std::path::Path::new("docs").exists());
+}
+
+#[test]
+fn inject_failures() {
+    let watcher = mock::watch_fs();
+    mock::fail_fs_with("locked.txt", ErrorKind::PermissionDenied);
+
+    let err = macro_files::write_file("locked.txt", "").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    macro_files::write_file("open.txt", "").unwrap();
+    assert_eq!(watcher.consume(), [Write::file("open.txt", "")]);
+
+    // Failures only apply while watching.
+    mock::fail_fs("ignored.txt");
+    let watcher = mock::watch_fs();
+    macro_files::write_file("ignored.txt", "").unwrap();
+    assert_eq!(watcher.consume().len(), 1);
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "tempfile")]
+
+use std::path::Path;
+
+use macro_files::{file, CreateReport, Tree};
+
+fn origins(report: &CreateReport, base: &Path) -> Vec<(String, Option<String>)> {
+    report
+        .created()
+        .iter()
+        .map(|entry| {
+            let path = entry.path().strip_prefix(base).unwrap();
+            let path = path.to_string_lossy().replace('\\', "/");
+            (path, entry.origin().map(str::to_owned))
+        })
+        .collect()
+}
+
+#[test]
+fn nested_origins() {
+    let mut ci = Tree::new().origin("preset:ci");
+    ci.push("ci.yml", "on: push");
+    let mut rust = Tree::new().origin("preset:rust");
+    rust.push("Cargo.toml", "[package]");
+    rust.push(".github", ci);
+    rust.push("LICENSE", file("MIT").origin("license:mit"));
+    rust.push("target", macro_files::keep());
+    let mut spec = Tree::new().origin("spec");
+    spec.push("README.md", "# Project");
+    spec.push("app", rust);
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let report = spec.create_in(temp_dir.path()).unwrap();
+
+    let expected: Vec<(String, Option<String>)> = vec![
+        ("README.md".into(), Some("spec".into())),
+        ("app".into(), Some("preset:rust".into())),
+        ("app/Cargo.toml".into(), Some("preset:rust".into())),
+        ("app/.github".into(), Some("preset:ci".into())),
+        ("app/.github/ci.yml".into(), Some("preset:ci".into())),
+        ("app/LICENSE".into(), Some("license:mit".into())),
+        ("app/target".into(), Some("preset:rust".into())),
+    ];
+    assert_eq!(origins(&report, temp_dir.path()), expected);
+}
+
+#[test]
+fn no_origin() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut spec = Tree::new();
+    spec.push("README.md", "# Project");
+    let report = spec.create_in(temp_dir.path()).unwrap();
+
+    assert_eq!(report.created()[0].origin(), None);
+}
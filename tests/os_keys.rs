@@ -0,0 +1,43 @@
+#![cfg(feature = "tempfile")]
+
+use std::ffi::{OsStr, OsString};
+
+#[test]
+fn os_string_keys() {
+    let name = OsString::from("notes.txt");
+
+    let dir = macro_files::create_temp!({
+        OsStr::new("docs"): {
+            name: "notes",
+        },
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "docs/notes.txt": "notes",
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_keys() {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let dir = macro_files::create_temp!({
+        b"invalid-\xff.txt": "invalid",
+        "dir": {
+            b"caf\xe9": {
+                b"data.bin": [1, 2, 3],
+            },
+        },
+        OsString::from_vec(b"other-\xfe".to_vec()): "other",
+    })
+    .unwrap();
+
+    let path = dir.path().join(OsStr::from_bytes(b"invalid-\xff.txt"));
+    assert_eq!(std::fs::read(path).unwrap(), b"invalid");
+    let path = dir.path().join(OsStr::from_bytes(b"dir/caf\xe9/data.bin"));
+    assert_eq!(std::fs::read(path).unwrap(), [1, 2, 3]);
+    let path = dir.path().join(OsStr::from_bytes(b"other-\xfe"));
+    assert_eq!(std::fs::read(path).unwrap(), b"other");
+}
@@ -0,0 +1,45 @@
+#![cfg(all(feature = "unix", feature = "tempfile", unix))]
+
+use std::io::ErrorKind;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use macro_files::{file, Tree};
+
+fn owner(path: &Path) -> (u32, u32) {
+    let metadata = std::fs::symlink_metadata(path).unwrap();
+    (metadata.uid(), metadata.gid())
+}
+
+#[test]
+fn current_owner() {
+    let probe = tempfile::tempdir().unwrap();
+    let (uid, gid) = owner(probe.path());
+
+    let mut home = Tree::new().owner(uid, gid);
+    home.push(".profile", file("").owner(uid, gid));
+    home.push(".bashrc", macro_files::symlink(".profile").owner(uid, gid));
+    let dir = macro_files::create_temp!({
+        "home": home,
+    })
+    .unwrap();
+
+    assert_eq!(owner(&dir.path().join("home")), (uid, gid));
+    assert_eq!(owner(&dir.path().join("home/.profile")), (uid, gid));
+    assert_eq!(owner(&dir.path().join("home/.bashrc")), (uid, gid));
+}
+
+#[test]
+fn unprivileged() {
+    let probe = tempfile::tempdir().unwrap();
+    if owner(probe.path()).0 == 0 {
+        return;
+    }
+
+    let err = macro_files::create_temp!({
+        "root.txt": file("").owner(0, 0),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert!(err.to_string().contains("not privileged"));
+}
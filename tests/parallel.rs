@@ -0,0 +1,74 @@
+#![cfg(all(feature = "rayon", feature = "tempfile"))]
+
+use std::cell::RefCell;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use macro_files::{hardlink, EntryKind, FsOps, Options, Tree};
+
+#[test]
+fn parallel_creation() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = SystemTime::UNIX_EPOCH + Duration::from_secs(86_400);
+    let mut tree = Tree::new();
+    for i in 0..200 {
+        let mut nested = Tree::new();
+        nested.push(format!("{}.txt", i), i.to_string());
+        tree.push(format!("dirs/{}", i), nested.mtime(old));
+    }
+    tree.push("dirs/0/link.txt", hardlink("dirs/0/0.txt"));
+
+    let report = tree
+        .create_with(dir.path(), &Options::new().parallel(true))
+        .unwrap();
+
+    let files = report
+        .created()
+        .iter()
+        .filter(|entry| entry.kind() == EntryKind::File)
+        .count();
+    assert_eq!(files, 201);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("dirs/199/199.txt")).unwrap(),
+        "199"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("dirs/0/link.txt")).unwrap(),
+        "0"
+    );
+    let metadata = std::fs::metadata(dir.path().join("dirs/42")).unwrap();
+    assert_eq!(metadata.modified().unwrap(), old);
+}
+
+#[derive(Default)]
+struct Recorder(RefCell<Vec<PathBuf>>);
+
+impl FsOps for Recorder {
+    fn write_file(&self, path: &Path, _contents: &[u8]) -> Result<()> {
+        self.0.borrow_mut().push(path.to_owned());
+        Ok(())
+    }
+}
+
+#[test]
+fn parallel_creation_with_fs_ops() {
+    let recorder = Rc::new(Recorder::default());
+    let mut tree = Tree::new();
+    tree.push("a.txt", "a");
+    tree.push("b.txt", "b");
+
+    macro_files::with_fs_ops(recorder.clone(), || {
+        tree.create_with("project", &Options::new().parallel(true))
+    })
+    .unwrap();
+
+    assert_eq!(
+        *recorder.0.borrow(),
+        [
+            PathBuf::from("project/a.txt"),
+            PathBuf::from("project/b.txt")
+        ]
+    );
+}
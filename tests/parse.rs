@@ -0,0 +1,95 @@
+use std::io::ErrorKind;
+
+use macro_files::{Entry, SkipReason, Tree};
+
+#[test]
+fn parse_json() {
+    let tree = Tree::parse(
+        r##"{
+            "README.md": "# Project\nline \"two\" é😀",
+            "src/main.rs": "fn main() {}",
+            "empty": {},
+            "touch": true,
+            "skipped": false,
+            "none": null,
+            "version": 1.5e3
+        }"##,
+    )
+    .unwrap();
+
+    assert_eq!(
+        tree,
+        macro_files::tree!({
+            "README.md": "# Project\nline \"two\" \u{e9}\u{1f600}",
+            "src/main.rs": "fn main() {}",
+            "empty": {},
+            "touch": true,
+            "skipped": false,
+            "none": null,
+            "version": "1.5e3",
+        })
+    );
+    let skips: Vec<_> = tree
+        .entries()
+        .filter_map(|(_, entry)| match entry {
+            Entry::Skip(reason) => Some(*reason),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(skips, [SkipReason::False, SkipReason::Null]);
+}
+
+#[test]
+fn parse_json5() {
+    let tree =
+        Tree::parse("/* skeleton */ { docs: { 'index.md': 'it\\'s', }, bin: [0, 255,], // end\n }")
+            .unwrap();
+
+    assert_eq!(
+        tree,
+        macro_files::tree!({
+            "docs": { "index.md": "it's" },
+            "bin": [0u8, 255],
+        })
+    );
+    assert_eq!(Tree::parse(" {} ").unwrap(), Tree::new());
+    assert_eq!(
+        Tree::parse("{ stamps: ['a.done', \"b.done\",], none: [] }").unwrap(),
+        macro_files::tree!({
+            "stamps": { "a.done": "", "b.done": "" },
+            "none": [] as [u8; 0],
+        })
+    );
+}
+
+#[test]
+fn parse_errors() {
+    let error = |text: &str| {
+        let err = Tree::parse(text).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        err.to_string()
+    };
+
+    assert_eq!(
+        error("{\n  \"a\": yes\n}"),
+        "expected a value at line 2, column 8"
+    );
+    assert_eq!(error("{ \"a\" \"b\" }"), "expected `:` at line 1, column 7");
+    assert_eq!(
+        error("{ \"a\": [256] }"),
+        "expected a byte from 0 to 255 at line 1, column 9"
+    );
+    assert_eq!(
+        error("{ \"a\": \"b"),
+        "unterminated string at line 1, column 10"
+    );
+    assert_eq!(
+        error("{} {}"),
+        "expected the end of the spec at line 1, column 4"
+    );
+    assert_eq!(error("\"a\""), "expected `{` at line 1, column 1");
+    assert_eq!(
+        error("{ \"a\": [\"b\", 1] }"),
+        "expected a name at line 1, column 14"
+    );
+}
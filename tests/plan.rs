@@ -0,0 +1,85 @@
+#![cfg(feature = "tempfile")]
+
+use std::path::PathBuf;
+
+use macro_files::{from, hardlink, size, symlink, Operation, SkipReason};
+
+#[test]
+fn plan_without_creating() {
+    let temp_dir = macro_files::tempfile::tempdir().unwrap();
+    let root = temp_dir.path();
+    let plan = macro_files::plan!({
+        root: {
+            "README.md": "# Project",
+            "data.bin": size(1024),
+            "Cargo.lock": from("Cargo.toml"),
+            "docs/index.md": symlink("../README.md"),
+            "copy.md": hardlink("README.md"),
+            "vendor": macro_files::copy_dir("tests/fixtures/vendor"),
+            "target": null,
+        },
+    })
+    .unwrap();
+
+    assert_eq!(
+        plan.operations(),
+        [
+            Operation::CreateDir {
+                path: root.to_owned()
+            },
+            Operation::WriteFile {
+                path: root.join("README.md"),
+                size: Some(9)
+            },
+            Operation::WriteFile {
+                path: root.join("data.bin"),
+                size: Some(1024)
+            },
+            Operation::CopyFile {
+                from: PathBuf::from("Cargo.toml"),
+                to: root.join("Cargo.lock")
+            },
+            Operation::Symlink {
+                path: root.join("docs/index.md"),
+                target: PathBuf::from("../README.md")
+            },
+            Operation::HardLink {
+                path: root.join("copy.md"),
+                original: PathBuf::from("README.md")
+            },
+            Operation::CopyDir {
+                from: PathBuf::from("tests/fixtures/vendor"),
+                to: root.join("vendor")
+            },
+            Operation::Skip {
+                path: root.join("target"),
+                reason: SkipReason::Null
+            },
+        ]
+    );
+    assert_eq!(plan.bytes(), 1033);
+    assert_eq!(std::fs::read_dir(root).unwrap().count(), 0);
+}
+
+#[cfg(all(feature = "unix", unix))]
+#[test]
+fn plan_special_files() {
+    use macro_files::EntryKind;
+
+    let plan = macro_files::plan!({ "pipe": macro_files::fifo() }).unwrap();
+
+    assert_eq!(
+        plan.operations(),
+        [Operation::Special {
+            path: PathBuf::from("pipe"),
+            kind: EntryKind::Fifo
+        }]
+    );
+    assert_eq!(plan.to_string(), "fifo pipe\n");
+}
+
+#[test]
+fn plan_invalid_keys() {
+    let err = macro_files::plan!({ "docs": { String::from("../escape"): "" } }).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "tempfile")]
+
+use std::sync::Arc;
+
+use macro_files::TempTreePool;
+
+#[test]
+fn recycle_dirs() {
+    let fixture = macro_files::tree!({
+        "config.toml": "debug = false",
+        "data": {
+            "input.txt": "input",
+        },
+    });
+    let pool = Arc::new(TempTreePool::new(fixture, 2).unwrap());
+    assert_eq!(pool.idle(), 2);
+
+    let threads: Vec<_> = (0..8)
+        .map(|index| {
+            let pool = Arc::clone(&pool);
+            std::thread::spawn(move || {
+                let dir = pool.get().unwrap();
+                assert!(pool.tree().diff(dir.path()).unwrap().is_empty());
+                std::fs::write(dir.path().join("config.toml"), "debug = true").unwrap();
+                std::fs::remove_dir_all(dir.path().join("data")).unwrap();
+                std::fs::write(dir.path().join(format!("{}.log", index)), "").unwrap();
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert!(pool.idle() >= 2);
+    let dirs: Vec<_> = (0..pool.idle()).map(|_| pool.get().unwrap()).collect();
+    for dir in &dirs {
+        assert!(pool.tree().diff(dir.path()).unwrap().is_empty());
+    }
+    assert_eq!(pool.idle(), 0);
+    drop(dirs);
+    assert!(pool.idle() >= 2);
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "tempfile")]
+
+use std::path::Path;
+use std::process::Command;
+
+use macro_files::presets;
+
+fn cargo_metadata(manifest: &Path) -> String {
+    let output = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args([
+            "metadata",
+            "--no-deps",
+            "--offline",
+            "--format-version",
+            "1",
+        ])
+        .arg("--manifest-path")
+        .arg(manifest)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cargo_presets() {
+    let dir = macro_files::create_temp!({
+        ..presets::cargo_workspace(["app", "core"]),
+        "app": presets::cargo_bin("app"),
+        "core": presets::cargo_lib("core"),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "Cargo.toml": "[workspace]\nresolver = \"2\"\nmembers = [\"app\", \"core\"]\n",
+        "app": {
+            "Cargo.toml": macro_files::any(),
+            "src/main.rs": "fn main() {\n    println!(\"Hello, world!\");\n}\n",
+        },
+        "core": {
+            "Cargo.toml": macro_files::any(),
+            "src/lib.rs": "",
+        },
+    });
+    let metadata = cargo_metadata(&dir.path().join("Cargo.toml"));
+    assert!(metadata.contains("\"name\":\"app\""));
+    assert!(metadata.contains("\"name\":\"core\""));
+}
+
+#[test]
+fn npm_and_git_presets() {
+    let dir = macro_files::create_temp!({
+        "web": presets::npm_package("web"),
+        "repo": { ".git": presets::git_repo() },
+    })
+    .unwrap();
+
+    let package = std::fs::read_to_string(dir.path().join("web/package.json")).unwrap();
+    assert!(package.contains("\"name\": \"web\""));
+    assert!(dir.path().join("repo/.git/refs/heads").is_dir());
+
+    let git = Command::new("git")
+        .args(["symbolic-ref", "--short", "HEAD"])
+        .current_dir(dir.path().join("repo"))
+        .output();
+    // The skeleton is only checked against git when it is installed.
+    if let Ok(output) = git {
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "main");
+    }
+}
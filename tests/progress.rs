@@ -0,0 +1,63 @@
+#![cfg(feature = "tempfile")]
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use macro_files::{EntryKind, Options, Tree};
+
+#[test]
+fn report_each_created_entry() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let options = Options::new().progress({
+        let progress = progress.clone();
+        move |entry| {
+            let entry = (entry.path().to_owned(), entry.kind());
+            progress.lock().unwrap().push(entry);
+        }
+    });
+    assert!(options.reports_progress());
+    let mut tree = Tree::new();
+    tree.push("README.md", "# Project");
+    tree.push("src/main.rs", "fn main() {}");
+    let report = tree.create_with(temp_dir.path(), &options).unwrap();
+
+    let created: Vec<_> = report
+        .created()
+        .iter()
+        .map(|entry| (entry.path().to_owned(), entry.kind()))
+        .collect();
+    let dir = temp_dir.path();
+    assert_eq!(
+        created,
+        [
+            (dir.join("README.md"), EntryKind::File),
+            (dir.join("src/main.rs"), EntryKind::File),
+        ]
+    );
+    assert_eq!(*progress.lock().unwrap(), created);
+}
+
+#[test]
+fn report_progress_until_failure() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let progress = Arc::new(Mutex::new(Vec::<PathBuf>::new()));
+    let options = Options::new().progress({
+        let progress = progress.clone();
+        move |entry| progress.lock().unwrap().push(entry.path().to_owned())
+    });
+    macro_files::create_with!(options, {
+        temp_dir.path(): {
+            "README.md": "# Project",
+            "missing.txt": macro_files::from("missing.txt"),
+            "LICENSE": "MIT",
+        },
+    })
+    .unwrap_err();
+
+    let dir = temp_dir.path();
+    assert_eq!(
+        *progress.lock().unwrap(),
+        [dir.to_owned(), dir.join("README.md")]
+    );
+}
@@ -0,0 +1,46 @@
+#![cfg(all(feature = "proptest", feature = "tempfile"))]
+
+use macro_files::proptest::prelude::*;
+use macro_files::{Entry, Tree, TreeParams};
+
+fn depth(tree: &Tree) -> u32 {
+    tree.entries()
+        .map(|(_, entry)| match entry {
+            Entry::Dir(tree) => 1 + depth(tree),
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn create_arbitrary_trees(tree in any::<Tree>()) {
+        let dir = tempfile::tempdir().unwrap();
+        tree.create_in(dir.path()).unwrap();
+        prop_assert_eq!(Tree::read_from(dir.path()).unwrap(), tree.normalize().unwrap());
+    }
+
+    #[test]
+    fn respect_params(tree in any_with::<Tree>(
+        TreeParams::new().depth(1).entries(3).alphabet("ab./").name_len(2).file_size(4)
+    )) {
+        prop_assert!(depth(&tree) <= 1);
+        let mut stack = vec![&tree];
+        while let Some(tree) = stack.pop() {
+            prop_assert!(tree.entries().count() <= 3);
+            for (key, entry) in tree.entries() {
+                let name = key.to_str().unwrap();
+                prop_assert!(name.len() <= 2 && name.chars().all(|c| "ab.".contains(c)));
+                prop_assert!(name != "." && name != "..");
+                match entry {
+                    Entry::Dir(tree) => stack.push(tree),
+                    Entry::File(file) => prop_assert!(file.content().read().unwrap().len() <= 4),
+                    entry => prop_assert!(false, "unexpected entry {:?}", entry),
+                }
+            }
+        }
+    }
+}
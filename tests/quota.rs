@@ -0,0 +1,102 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+use std::time::{Duration, SystemTime};
+
+use macro_files::{Options, Quota, Tree};
+
+fn fixture(size: usize) -> Tree {
+    let mut tree = Tree::new();
+    tree.push("data.bin", vec![0; size]);
+    tree
+}
+
+#[test]
+fn evict_least_recently_used() {
+    let cache = tempfile::tempdir().unwrap();
+    for (name, days) in [("a", 2), ("b", 1), ("c", 3)] {
+        let path = cache.path().join(name);
+        fixture(10).create_in(&path).unwrap();
+        let used = SystemTime::UNIX_EPOCH + Duration::from_secs(days * 86_400);
+        macro_files::set_times(&path, None, Some(used)).unwrap();
+    }
+
+    let quota = Quota::new(cache.path(), 35);
+    assert_eq!(quota.usage().unwrap(), 30);
+    let options = Options::new().quota(quota.clone());
+    let report = fixture(10)
+        .create_with(cache.path().join("d"), &options)
+        .unwrap();
+
+    assert_eq!(report.evicted(), [cache.path().join("b")]);
+    assert_eq!(quota.usage().unwrap(), 30);
+
+    // Recreating a fixture keeps it and makes it the most recently used.
+    let report = fixture(10)
+        .create_with(cache.path().join("a"), &options)
+        .unwrap();
+    assert_eq!(report.evicted(), [cache.path().join("c")]);
+    let report = fixture(10)
+        .create_with(cache.path().join("e"), &options)
+        .unwrap();
+    assert!(report.evicted().is_empty());
+    let report = fixture(10)
+        .create_with(cache.path().join("f"), &options)
+        .unwrap();
+    assert_eq!(report.evicted(), [cache.path().join("d")]);
+}
+
+#[test]
+fn exceeding_quota() {
+    let cache = tempfile::tempdir().unwrap();
+    let options = Options::new().quota(Quota::new(cache.path(), 5));
+    let err = fixture(10)
+        .create_with(cache.path().join("a"), &options)
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(!cache.path().join("a").exists());
+}
+
+#[test]
+fn byte_budget() {
+    use macro_files::{Content, Generator, Reader};
+
+    let options = Options::new().max_bytes(20);
+    let dir = tempfile::tempdir().unwrap();
+    let mut tree = fixture(10);
+    tree.push(
+        "generated.bin",
+        Content::Generator(Generator::new(|| Ok(vec![1; 10]))),
+    );
+    tree.create_with(dir.path(), &options).unwrap();
+
+    let err = fixture(30)
+        .create_with(dir.path().join("known"), &options)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(!dir.path().join("known").exists());
+
+    let mut tree = fixture(10);
+    tree.push(
+        "generated.bin",
+        Content::Generator(Generator::new(|| Ok(vec![1; 20]))),
+    );
+    let err = tree
+        .create_with(dir.path().join("generated"), &options)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(dir.path().join("generated/data.bin").is_file());
+    assert!(!dir.path().join("generated/generated.bin").exists());
+
+    let mut tree = fixture(10);
+    tree.push(
+        "streamed.bin",
+        Content::Reader(Reader::new(std::io::repeat(1))),
+    );
+    let err = tree
+        .create_with(dir.path().join("streamed"), &options)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(!dir.path().join("streamed/streamed.bin").exists());
+}
@@ -0,0 +1,93 @@
+use std::io::ErrorKind;
+use std::path::Path;
+
+use macro_files::{file, Entry, InvalidKey, InvalidKeyReason, SkipReason, Tree};
+
+fn keys(tree: &Tree) -> Vec<&Path> {
+    tree.entries().map(|(key, _)| key).collect()
+}
+
+#[test]
+fn rebase_and_strip() {
+    let mut fixture = Tree::new().origin("fixture");
+    fixture.push("Cargo.toml", "[package]");
+    fixture.push("src/main.rs", "fn main() {}");
+
+    let rebased = fixture.clone().rebase("./crates/app/").unwrap();
+    assert_eq!(keys(&rebased), [Path::new("crates/app")]);
+
+    let stripped = rebased.strip_prefix("crates/app").unwrap();
+    assert_eq!(stripped.normalize().unwrap(), fixture.normalize().unwrap());
+    assert_eq!(stripped.declared_origin(), None);
+    for (_, entry) in stripped.entries() {
+        match entry {
+            Entry::File(file) => assert_eq!(file.declared_origin(), Some("fixture")),
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+    }
+}
+
+#[test]
+fn strip_merged_dirs() {
+    let mut app = Tree::new();
+    app.push("Cargo.toml", "[package]");
+    let mut tree = Tree::new();
+    tree.push("app", app);
+    tree.push("app/src/main.rs", file("fn main() {}"));
+    tree.push("target", Entry::Skip(SkipReason::False));
+
+    let stripped = tree.strip_prefix("app").unwrap();
+    assert_eq!(
+        keys(&stripped),
+        [Path::new("Cargo.toml"), Path::new("src/main.rs")]
+    );
+}
+
+#[test]
+fn invalid_prefix() {
+    let err = Tree::new().rebase("../outside").unwrap_err();
+    let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+    assert_eq!(key.reason(), InvalidKeyReason::ParentDir);
+
+    let err = Tree::new().strip_prefix("/app").unwrap_err();
+    let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+    assert_eq!(key.reason(), InvalidKeyReason::Absolute);
+}
+
+#[test]
+fn entry_outside_prefix() {
+    let mut tree = Tree::new();
+    tree.push("app/Cargo.toml", "[package]");
+    tree.push("README.md", "# Project");
+
+    let err = tree.strip_prefix("app").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("\"README.md\""));
+
+    let mut tree = Tree::new();
+    tree.push("app", "not a directory");
+    let err = tree.strip_prefix("app").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn strip_named_entries() {
+    let mut app = Tree::new();
+    app.push("Cargo.toml", "[package]");
+    app.name("Cargo.toml", "manifest");
+    let mut tree = Tree::new();
+    tree.push("app", app);
+    tree.push("app/src/main.rs", "fn main() {}");
+    tree.name("app/src/main.rs", "main");
+    tree.name("app", "app");
+
+    let stripped = tree.strip_prefix("app").unwrap();
+    let handles: Vec<_> = stripped.handles().collect();
+    assert_eq!(
+        handles,
+        [
+            (Path::new("src/main.rs"), "main"),
+            (Path::new("Cargo.toml"), "manifest")
+        ]
+    );
+}
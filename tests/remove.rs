@@ -0,0 +1,75 @@
+#![cfg(feature = "tempfile")]
+
+use macro_files::{copy_dir, keep, Tree};
+
+fn spec() -> Tree {
+    let mut docs = Tree::new();
+    docs.push("README.md", "# Documentation");
+    let mut tree = Tree::new();
+    tree.push("docs", docs);
+    tree.push("vendor", copy_dir("tests/fixtures/vendor"));
+    tree.push("cache", keep());
+    tree.push("nested/deep/file.txt", "");
+    tree.push("ignored.txt", macro_files::maybe(None::<&str>));
+    tree
+}
+
+#[test]
+fn remove_created_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    spec().create_in(dir.path()).unwrap();
+    std::fs::write(dir.path().join("ignored.txt"), "").unwrap();
+
+    spec().remove_in(dir.path()).unwrap();
+
+    let entries: Vec<_> = dir.path().read_dir().unwrap().collect();
+    assert_eq!(entries.len(), 1);
+    assert!(dir.path().join("ignored.txt").is_file());
+}
+
+#[test]
+fn remove_keeps_undeclared_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    spec().create_in(dir.path()).unwrap();
+    std::fs::write(dir.path().join("docs/notes.txt"), "").unwrap();
+    std::fs::write(dir.path().join("nested/notes.txt"), "").unwrap();
+
+    let removed = spec().remove_in(dir.path()).unwrap();
+
+    assert!(removed.contains(&dir.path().join("docs/README.md")));
+    assert!(removed.contains(&dir.path().join("nested/deep")));
+    macro_files::assert_tree!(dir.path(), {
+        "docs/notes.txt": "",
+        "nested/notes.txt": "",
+    });
+    assert!(spec().remove_in(dir.path()).unwrap().is_empty());
+}
+
+#[test]
+fn clean_before_creating() {
+    let dir = macro_files::create_temp!({
+        "stale.txt": "stale",
+        "out": { "nested/old.bin": [0; 4] },
+        "keep.txt": "old",
+    })
+    .unwrap();
+    let tree = macro_files::tree!({
+        "keep.txt": "new",
+        "out": {},
+    });
+
+    let report = tree
+        .create_with(dir.path(), &macro_files::Options::new().clean(true))
+        .unwrap();
+
+    assert_eq!(
+        report.removed(),
+        [
+            dir.path().join("keep.txt"),
+            dir.path().join("out"),
+            dir.path().join("stale.txt"),
+        ]
+    );
+    assert!(tree.diff(dir.path()).unwrap().is_empty());
+    assert!(report.skipped().is_empty());
+}
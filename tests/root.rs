@@ -0,0 +1,110 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+
+use macro_files::{EntryKind, InvalidKey, InvalidKeyReason, Options};
+
+#[test]
+fn default_root() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    assert_eq!(macro_files::default_root(), None);
+    macro_files::with_default_root(temp_dir.path(), || {
+        assert_eq!(macro_files::default_root().unwrap(), temp_dir.path());
+        macro_files::create!({
+            "README.md": "# Project",
+        })
+        .unwrap();
+        macro_files::create_with!(Options::new().require_root(true), {
+            "docs": {},
+        })
+        .unwrap();
+    });
+    assert_eq!(macro_files::default_root(), None);
+
+    macro_files::assert_tree!(temp_dir.path(), {
+        "README.md": "# Project",
+        "docs": {},
+    });
+}
+
+#[test]
+fn keys_stay_within_root() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let outside = temp_dir.path().join("outside.txt");
+    let root = temp_dir.path().join("root");
+    let err = macro_files::with_default_root(&root, || {
+        macro_files::create!({
+            &outside: "",
+        })
+    })
+    .unwrap_err();
+
+    let key = err.get_ref().unwrap().downcast_ref::<InvalidKey>().unwrap();
+    assert_eq!(key.reason(), InvalidKeyReason::Absolute);
+    assert!(!outside.exists());
+}
+
+#[test]
+fn required_root() {
+    let err = macro_files::create_with!(Options::new().require_root(true), {
+        "should_not_exist.txt": "",
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(!std::path::Path::new("should_not_exist.txt").exists());
+}
+
+#[test]
+fn manifest() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let manifest = macro_files::with_default_root(temp_dir.path(), || {
+        macro_files::create_manifest!({
+            "docs": {
+                "README.md": "# Docs",
+            },
+            "target": false,
+        })
+    })
+    .unwrap();
+
+    let entries: Vec<_> = manifest
+        .into_iter()
+        .map(|entry| (entry.path().to_owned(), entry.kind()))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            (temp_dir.path().join("docs"), EntryKind::Dir),
+            (temp_dir.path().join("docs/README.md"), EntryKind::File),
+        ]
+    );
+}
+
+#[test]
+fn absolute_root_checks() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path().join("srv/app");
+
+    let err = macro_files::create_at!(&root, { "app.toml": "" }).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    std::fs::create_dir_all(&root).unwrap();
+    let outside = Options::new().require_prefix(temp_dir.path().join("opt"));
+    let err = macro_files::create_at!(&root, options = outside, { "app.toml": "" }).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    let escaping = Options::new().require_prefix(&root);
+    let err = macro_files::create_at!(root.join("../.."), options = escaping, {
+        "app.toml": "",
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+
+    let options = Options::new()
+        .require_empty(true)
+        .require_prefix(temp_dir.path());
+    macro_files::create_at!(&root, options = options.clone(), { "app.toml": "" }).unwrap();
+    let err = macro_files::create_at!(&root, options = options, { "log": {} }).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    macro_files::assert_tree!(&root, { "app.toml": "" });
+}
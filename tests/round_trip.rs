@@ -0,0 +1,84 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+
+use macro_files::{
+    copy_dir, from, hardlink, symlink, Content, Entry, Generator, ReadOptions, SkipReason,
+    Symlinks, Tree,
+};
+
+fn spec() -> Tree {
+    let mut tree = Tree::new();
+    tree.push("README.md", "# Project");
+    tree.push("src/main.rs", Content::Text("fn main() {}\n".to_string()));
+    tree.push(
+        "src/lib.rs",
+        Content::Generator(Generator::new(|| Ok(b"".to_vec()))),
+    );
+    tree.push("target", Entry::Skip(SkipReason::False));
+    tree.push("fixtures/config.toml", from("tests/fixtures/config.toml"));
+    tree.push("fixtures/vendor", copy_dir("tests/fixtures/vendor"));
+    tree.push("fixtures/config.bak", hardlink("fixtures/config.toml"));
+    #[cfg(unix)]
+    tree.push("docs/index.md", macro_files::symlink("../README.md"));
+    tree
+}
+
+#[test]
+fn round_trip() {
+    let spec = spec();
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    spec.create_in(dir.path()).unwrap();
+
+    let tree = Tree::read_from(dir.path()).unwrap();
+    assert_eq!(spec.normalize().unwrap(), tree);
+    assert_eq!(tree.normalize().unwrap(), tree);
+
+    let copy = macro_files::tempfile::tempdir().unwrap();
+    tree.create_in(copy.path()).unwrap();
+    assert_eq!(Tree::read_from(copy.path()).unwrap(), tree);
+}
+
+#[test]
+fn read_symlinks() {
+    let dir = macro_files::create_temp!({
+        "README.md": "# Project",
+        "docs": {
+            "index.md": symlink("../README.md"),
+            "root": symlink(".."),
+        },
+    })
+    .unwrap();
+
+    let skipped = Tree::read_with(dir.path(), &ReadOptions::new().symlinks(Symlinks::Skip));
+    let mut expected = Tree::new();
+    expected.push("README.md", "# Project");
+    expected.push("docs", Tree::new());
+    assert_eq!(skipped.unwrap(), expected);
+
+    let err = Tree::read_with(dir.path(), &ReadOptions::new().symlinks(Symlinks::Follow));
+    assert_eq!(err.unwrap_err().kind(), ErrorKind::Other);
+}
+
+#[test]
+fn copy_filtered_tree() {
+    let fixture = macro_files::create_temp!({
+        ".cache/index": "",
+        "README.md": "# Project",
+        "src/main.rs": "fn main() {}",
+        "target/debug/app": "",
+    })
+    .unwrap();
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    let options = ReadOptions::new()
+        .hidden(false)
+        .filter(|path| path != std::path::Path::new("target"));
+    let report = macro_files::copy_tree_with(fixture.path(), dir.path(), &options).unwrap();
+
+    assert_eq!(report.created().len(), 3);
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Project",
+        "src/main.rs": "fn main() {}",
+    });
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+
+use macro_files::{symlink, Options, Tree};
+
+#[test]
+fn sandbox_symlink_escape() {
+    let outside = tempfile::tempdir().unwrap();
+    let dir = macro_files::create_temp!({
+        "link": symlink(outside.path()),
+        "nested": {
+            "up": symlink(".."),
+        },
+    })
+    .unwrap();
+    let options = Options::new().sandbox(true);
+
+    let mut tree = Tree::new();
+    let mut escape = Tree::new();
+    escape.push("escape.txt", "!");
+    tree.push("link", escape);
+    let err = tree.create_with(dir.path(), &options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert!(!outside.path().join("escape.txt").exists());
+
+    let mut tree = Tree::new();
+    tree.push("nested/up/inside.txt", "ok");
+    tree.create_with(dir.path(), &options).unwrap();
+    assert!(dir.path().join("inside.txt").is_file());
+}
+
+#[test]
+fn sandbox_allows_entries_within_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = Options::new().sandbox(true);
+
+    let mut tree = Tree::new();
+    tree.push("a/b.txt", "ok");
+    tree.push("c", Tree::new());
+    tree.create_with(dir.path().join("root"), &options).unwrap();
+    assert!(dir.path().join("root/c").is_dir());
+    assert!(dir.path().join("root/a/b.txt").is_file());
+}
+
+#[test]
+fn sandbox_disabled_by_default() {
+    let outside = tempfile::tempdir().unwrap();
+    let dir = macro_files::create_temp!({
+        "link": symlink(outside.path()),
+    })
+    .unwrap();
+    assert!(!Options::default().is_sandboxed());
+
+    let mut tree = Tree::new();
+    tree.push("link/escape.txt", "!");
+    tree.create_in(dir.path()).unwrap();
+    assert!(outside.path().join("escape.txt").is_file());
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "tempfile")]
+#![allow(dead_code, unused_imports)]
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Config {
+    name: &'static str,
+    workers: u8,
+    tags: Vec<&'static str>,
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn serialize_json() {
+    let config = Config {
+        name: "app",
+        workers: 4,
+        tags: vec!["web"],
+    };
+
+    let dir = macro_files::create_temp!({
+        "config.json": macro_files::json(&config),
+        "empty.json": macro_files::json(&macro_files::serde_json::json!({})),
+    })
+    .unwrap();
+
+    let contents = std::fs::read_to_string(dir.path().join("config.json")).unwrap();
+    let value: macro_files::serde_json::Value =
+        macro_files::serde_json::from_str(&contents).unwrap();
+    assert_eq!(
+        value,
+        macro_files::serde_json::json!({ "name": "app", "workers": 4, "tags": ["web"] })
+    );
+    macro_files::assert_tree!(dir.path(), {
+        "config.json": contents,
+        "empty.json": "{}\n",
+    });
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn serialization_error_on_creation() {
+    let mut map = BTreeMap::new();
+    map.insert(vec![1], "not a string key");
+    let file = macro_files::json(&map);
+
+    let err = macro_files::create_temp!({ "map.json": file }).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn serialize_yaml() {
+    let config = Config {
+        name: "app",
+        workers: 4,
+        tags: vec!["web"],
+    };
+
+    let dir = macro_files::create_temp!({
+        "config.yaml": macro_files::yaml(&config),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "config.yaml": "name: app\nworkers: 4\ntags:\n- web\n",
+    });
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn serialize_toml() {
+    let config = Config {
+        name: "app",
+        workers: 4,
+        tags: vec!["web"],
+    };
+
+    let dir = macro_files::create_temp!({
+        "config.toml": macro_files::toml(&config),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "config.toml": "name = \"app\"\nworkers = 4\ntags = [\"web\"]\n",
+    });
+    let err = macro_files::create_temp!({ "number.toml": macro_files::toml(&4) }).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn serialize_trees() {
+    use std::convert::TryFrom;
+
+    let tree = macro_files::tree!({
+        "README.md": "# Project",
+        "logo.png": [137u8, 80, 78, 71, 0xff],
+        "cache": macro_files::keep(),
+        "docs": {
+            "empty.md": true,
+        },
+        "skipped": false,
+        "none": null,
+    });
+
+    let value = serde_json::Value::try_from(&tree).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "README.md": "# Project",
+            "logo.png": [137, 80, 78, 71, 255],
+            "cache": {},
+            "docs": { "empty.md": "" },
+            "skipped": false,
+            "none": null,
+        })
+    );
+    let json = serde_json::to_string_pretty(&tree).unwrap();
+    assert!(json.starts_with("{\n  \"README.md\": \"# Project\",\n  \"logo.png\": ["));
+
+    let linked = macro_files::tree!({ "docs": { "link": macro_files::symlink("..") } });
+    let err = serde_json::to_string(&linked).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot serialize the symlink \"link\", its contents are not in memory"
+    );
+}
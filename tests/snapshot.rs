@@ -0,0 +1,52 @@
+#![cfg(all(feature = "snapshot", feature = "tempfile"))]
+
+use macro_files::{EntryKind, Snapshot, SnapshotEntry};
+
+#[test]
+fn snapshot_directory() {
+    let dir = macro_files::create_temp!({
+        "README.md": "# Project",
+        "src": {
+            "main.rs": "fn main() {}",
+        },
+        "data.bin": [0xff, 0xfe],
+        "empty": {},
+        "docs/index.md": macro_files::symlink("../README.md"),
+    })
+    .unwrap();
+
+    let snapshot = Snapshot::read(dir.path()).unwrap();
+    insta::assert_yaml_snapshot!(snapshot, @r###"
+    README.md: "# Project"
+    data.bin:
+      size: 2
+      sha256: b3d510ef04275ca8e698e5b3cbb0ece3949ef9252f0cdc839e9ee347409a2209
+    docs/:
+      index.md:
+        symlink: "../README.md"
+    empty/: {}
+    src/:
+      main.rs: "fn main() {}"
+    "###);
+}
+
+#[test]
+fn snapshot_tree_without_creating_it() {
+    let tree = {
+        let mut tree = macro_files::Tree::new();
+        tree.push("src/main.rs", "fn main() {}");
+        tree
+    };
+    let snapshot = tree.snapshot().unwrap();
+
+    let src = match snapshot.get("src") {
+        Some(SnapshotEntry::Dir(src)) => src,
+        entry => panic!("unexpected entry {:?}", entry),
+    };
+    let entries: Vec<_> = src.entries().collect();
+    assert_eq!(
+        entries,
+        [("main.rs", &SnapshotEntry::Text("fn main() {}".to_owned()))]
+    );
+    assert_eq!(snapshot.get("src").unwrap().kind(), EntryKind::Dir);
+}
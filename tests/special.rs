@@ -0,0 +1,69 @@
+#![cfg(all(feature = "unix", feature = "tempfile", unix))]
+
+use std::os::unix::fs::FileTypeExt;
+
+use macro_files::{fifo, unix_socket, Change, EntryKind, Tree};
+
+#[test]
+fn fifo_and_socket() {
+    let dir = macro_files::create_temp!({
+        "run": {
+            "events": fifo(),
+            "app.sock": unix_socket(),
+        },
+    })
+    .unwrap();
+
+    let file_type = |name: &str| {
+        std::fs::symlink_metadata(dir.path().join("run").join(name))
+            .unwrap()
+            .file_type()
+    };
+    assert!(file_type("events").is_fifo());
+    assert!(file_type("app.sock").is_socket());
+
+    macro_files::assert_tree!(dir.path(), {
+        "run": {
+            "events": fifo(),
+            "app.sock": unix_socket(),
+        },
+    });
+    let mut expected = Tree::new();
+    expected.push("run", Tree::read_from(dir.path().join("run")).unwrap());
+    assert!(expected.diff(dir.path()).unwrap().is_empty());
+}
+
+#[test]
+fn kind_mismatch() {
+    let dir = macro_files::create_temp!({
+        "events": "not a fifo",
+    })
+    .unwrap();
+
+    let mut expected = Tree::new();
+    expected.push("events", fifo());
+    let diff = expected.diff(dir.path()).unwrap();
+    assert_eq!(
+        diff.changes(),
+        [Change::KindMismatch {
+            path: "events".into(),
+            expected: EntryKind::Fifo,
+            actual: EntryKind::File,
+        }]
+    );
+}
+
+#[test]
+fn replace_existing() {
+    let dir = macro_files::create_temp!({
+        "events": "not a fifo",
+    })
+    .unwrap();
+    macro_files::create_in!(dir.path(), {
+        "events": fifo(),
+    })
+    .unwrap();
+
+    let metadata = std::fs::symlink_metadata(dir.path().join("events")).unwrap();
+    assert!(metadata.file_type().is_fifo());
+}
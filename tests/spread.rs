@@ -0,0 +1,70 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+
+use macro_files::{InvalidKey, InvalidKeyReason, Tree};
+
+fn base() -> Tree {
+    let mut base = Tree::new();
+    base.push("Cargo.toml", "[package]");
+    base.push("src/lib.rs", "");
+    base.name("Cargo.toml", "manifest");
+    base
+}
+
+#[test]
+fn spread_trees() {
+    let mut docs = Tree::new();
+    docs.push("index.md", "# Docs");
+
+    let dir = macro_files::create_temp!({
+        "README.md": "# Project",
+        ..base(),
+        "docs": {
+            "CHANGELOG.md": "",
+            ..docs
+        },
+        "src/main.rs": "fn main() {}",
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Project",
+        "Cargo.toml": "[package]",
+        "src": {
+            "lib.rs": "",
+            "main.rs": "fn main() {}",
+        },
+        "docs": {
+            "CHANGELOG.md": "",
+            "index.md": "# Docs",
+        },
+    });
+}
+
+#[test]
+fn spread_named_keys() {
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    let manifest = macro_files::create_manifest!({
+        dir.path(): {
+            ..base(),
+        },
+    })
+    .unwrap();
+
+    assert_eq!(manifest["manifest"], dir.path().join("Cargo.toml"));
+}
+
+#[test]
+fn spread_entries_declared_again() {
+    let err = macro_files::create_temp!({
+        ..base(),
+        "Cargo.toml": "[workspace]",
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = err.into_inner().unwrap().downcast::<InvalidKey>().unwrap();
+    assert_eq!(err.reason(), InvalidKeyReason::Duplicate);
+}
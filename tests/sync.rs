@@ -0,0 +1,64 @@
+#![cfg(feature = "tempfile")]
+
+use macro_files::{keep, SkipReason};
+
+#[test]
+fn sync_matches_spec() {
+    let dir = macro_files::create_temp!({
+        "README.md": "# Project",
+        "config": "not a directory",
+        "cache": {
+            "entry": "",
+        },
+        "docs": {
+            "old.md": "",
+            "guide.md": "# Old guide",
+        },
+    })
+    .unwrap();
+
+    let report = macro_files::sync!(dir.path(), {
+        "README.md": "# Project",
+        "config": {
+            "app.toml": "debug = false",
+        },
+        "cache": keep(),
+        "docs": {
+            "guide.md": "# Guide",
+        },
+    })
+    .unwrap();
+
+    let mut removed = report.removed().to_vec();
+    removed.sort();
+    assert_eq!(
+        removed,
+        [
+            dir.path().join("cache/entry"),
+            dir.path().join("config"),
+            dir.path().join("docs/guide.md"),
+            dir.path().join("docs/old.md"),
+        ]
+    );
+    let skipped: Vec<_> = report.skipped().iter().map(|entry| entry.path()).collect();
+    assert_eq!(skipped, [dir.path().join("README.md")]);
+    assert_eq!(report.skipped()[0].reason(), SkipReason::Exists);
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Project",
+        "config/app.toml": "debug = false",
+        "cache": keep(),
+        "docs/guide.md": "# Guide",
+    });
+}
+
+#[test]
+fn sync_creates_missing_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = macro_files::sync!(dir.path().join("generated"), {
+        "README.md": "# Generated",
+    })
+    .unwrap();
+
+    assert!(report.removed().is_empty());
+    assert!(dir.path().join("generated/README.md").is_file());
+}
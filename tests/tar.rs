@@ -0,0 +1,155 @@
+#![cfg(feature = "tar")]
+
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+use macro_files::tar::{Archive, EntryType};
+use macro_files::{copy_dir, file, hardlink, symlink};
+
+#[test]
+fn write_tar() {
+    let archive = macro_files::create_tar!(Vec::new(), {
+        "README.md": file("# Project").mtime(UNIX_EPOCH + Duration::from_secs(60)),
+        "src/bin": {
+            "main.rs": "fn main() {}",
+        },
+        "vendor": copy_dir("tests/fixtures/vendor"),
+        "latest": symlink("README.md"),
+        "copy.md": hardlink("README.md"),
+        "ignored.txt": false,
+    })
+    .unwrap();
+
+    let mut entries = Vec::new();
+    for entry in Archive::new(archive.as_slice()).entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let path = entry.path().unwrap().into_owned();
+        let header = entry.header().clone();
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        entries.push((path, header, contents));
+    }
+
+    let paths: Vec<_> = entries.iter().map(|(path, ..)| path.clone()).collect();
+    let expected = [
+        "README.md",
+        "latest",
+        "src",
+        "src/bin",
+        "src/bin/main.rs",
+        "vendor",
+        "vendor/README.md",
+        "vendor/lib",
+        "vendor/lib/lib.rs",
+        "copy.md",
+    ];
+    assert_eq!(
+        paths,
+        expected.iter().map(PathBuf::from).collect::<Vec<_>>()
+    );
+
+    let (_, header, contents) = &entries[0];
+    assert_eq!(contents, "# Project");
+    assert_eq!(header.mtime().unwrap(), 60);
+    assert_eq!(header.mode().unwrap(), 0o644);
+    assert_eq!(entries[2].1.entry_type(), EntryType::Directory);
+
+    let (_, header, _) = &entries[1];
+    assert_eq!(header.entry_type(), EntryType::Symlink);
+    assert_eq!(
+        header.link_name().unwrap().unwrap(),
+        PathBuf::from("README.md")
+    );
+    let (_, header, _) = &entries[9];
+    assert_eq!(header.entry_type(), EntryType::Link);
+}
+
+#[test]
+fn write_tar_rejects_absolute_keys() {
+    let mut tree = macro_files::Tree::new();
+    tree.push(std::env::temp_dir().join("file.txt"), "");
+
+    let err = tree.write_tar(Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+#[cfg(all(feature = "flate2", feature = "tempfile"))]
+fn extract_tar() {
+    let dir = macro_files::create_temp!({
+        "data": macro_files::extract("tests/fixtures/data.tar.gz"),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "data": {
+            "README.md": "# Data\n",
+            "bin/run.sh": "#!/bin/sh\necho run\n",
+            "records": {
+                "1.csv": "id,name\n1,first\n",
+                "2.csv": "id,name\n2,second\n",
+            },
+        },
+    });
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let run = std::fs::metadata(dir.path().join("data/bin/run.sh")).unwrap();
+        assert_eq!(run.permissions().mode() & 0o111, 0o111);
+    }
+}
+
+#[test]
+#[cfg(all(unix, feature = "tempfile"))]
+fn extract_tar_links() {
+    let archive = macro_files::create_tar!(Vec::new(), {
+        "README.md": "# Project",
+        "latest": symlink("README.md"),
+        "copy.md": hardlink("README.md"),
+    })
+    .unwrap();
+    let fixtures = macro_files::create_temp!({ "links.tar": archive }).unwrap();
+
+    let dir = macro_files::create_temp!({
+        "links": macro_files::extract(fixtures.path().join("links.tar")),
+        "missing": macro_files::extract(fixtures.path().join("missing.tar")),
+    });
+    assert_eq!(dir.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+
+    let dir = macro_files::create_temp!({
+        "links": macro_files::extract(fixtures.path().join("links.tar")),
+    })
+    .unwrap();
+    assert_eq!(
+        std::fs::read_link(dir.path().join("links/latest")).unwrap(),
+        PathBuf::from("README.md")
+    );
+    macro_files::assert_tree!(dir.path(), {
+        "links": {
+            "README.md": "# Project",
+            "latest": symlink("README.md"),
+            "copy.md": "# Project",
+        },
+    });
+}
+
+#[test]
+fn reproducible_tar() {
+    let archive = macro_files::create_tar!(Vec::new(), {
+        "src/main.rs": "fn main() {}",
+        "copy.md": hardlink("README.md"),
+        "README.md": "# Project",
+        "src": { "lib.rs": "" },
+    })
+    .unwrap();
+    let reordered = macro_files::create_tar!(Vec::new(), {
+        "README.md": "# Project",
+        "copy.md": hardlink("README.md"),
+        "src": { "lib.rs": "", "main.rs": "fn main() {}" },
+    })
+    .unwrap();
+
+    assert_eq!(archive, reordered);
+}
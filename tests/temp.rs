@@ -1,3 +1,5 @@
+#![cfg(feature = "tempfile")]
+
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
@@ -67,6 +69,85 @@ fn temp() {
     assert_eq!(get_entries(dir.path(), &PathBuf::default()), expected);
 }
 
+#[test]
+fn temp_namespaced() {
+    let base = macro_files::tempfile::tempdir().unwrap();
+    let first = macro_files::create_temp_namespaced!(base.path(), "temp::namespaced", {
+        "README.md": "# First",
+    })
+    .unwrap();
+    let second = macro_files::create_temp_namespaced!(base.path(), "temp::namespaced", {
+        "README.md": "# Second",
+    })
+    .unwrap();
+
+    assert_ne!(first.path(), second.path());
+    for dir in [&first, &second] {
+        assert_eq!(dir.path().parent(), Some(base.path()));
+        let name = dir.path().file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("temp__namespaced-"), "{}", name);
+    }
+    assert_eq!(
+        get_entries(second.path(), &PathBuf::default()),
+        HashSet::from([Entry::file("README.md", "# Second")])
+    );
+}
+
+#[test]
+fn temp_options() {
+    let parent = macro_files::tempfile::tempdir().unwrap();
+    let dir = macro_files::create_temp!(
+        prefix = "prefix-",
+        suffix = String::from("-suffix"),
+        rand_bytes = 4,
+        in = parent.path().join("nested"),
+        {
+            "README.md": "# Project",
+        }
+    );
+    // The parent directory must exist.
+    assert!(dir.is_err());
+
+    std::fs::create_dir(parent.path().join("nested")).unwrap();
+    let dir = macro_files::create_temp!(
+        prefix = "prefix-",
+        suffix = String::from("-suffix"),
+        rand_bytes = 4,
+        in = parent.path().join("nested"),
+        {
+            "README.md": "# Project",
+        }
+    )
+    .unwrap();
+
+    assert_eq!(
+        dir.path().parent(),
+        Some(parent.path().join("nested").as_path())
+    );
+    let name = dir.path().file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("prefix-"), "{}", name);
+    assert!(name.ends_with("-suffix"), "{}", name);
+    assert_eq!(name.len(), "prefix-".len() + 4 + "-suffix".len());
+    assert_eq!(
+        get_entries(dir.path(), &PathBuf::default()),
+        HashSet::from([Entry::file("README.md", "# Project")])
+    );
+}
+
+#[test]
+fn temp_in_memory() {
+    let dir = macro_files::create_temp!(prefix = "ram-", ram = true, {
+        "README.md": "# Project",
+    })
+    .unwrap();
+
+    assert_eq!(dir.path().parent(), Some(macro_files::ram_root().as_path()));
+    assert_eq!(
+        get_entries(dir.path(), &PathBuf::default()),
+        HashSet::from([Entry::file("README.md", "# Project")])
+    );
+}
+
 #[derive(Hash, PartialEq, Eq, Debug)]
 enum Entry {
     Dir(PathBuf),
@@ -102,3 +183,127 @@ fn get_entries(path: &Path, relative_path: &Path) -> HashSet<Entry> {
     }
     entries
 }
+
+#[test]
+fn temp_file() {
+    let file = macro_files::create_temp_file!(prefix = "config-", suffix = ".json", "{}").unwrap();
+    let name = file.path().file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("config-") && name.ends_with(".json"));
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "{}");
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = macro_files::create_temp_file!(in = dir.path(), 42).unwrap();
+    assert_eq!(file.path().parent(), Some(dir.path()));
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "42");
+    let path = file.path().to_owned();
+    drop(file);
+    assert!(!path.exists());
+
+    let err = macro_files::create_temp_file!(macro_files::Tree::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn temp_tree() {
+    let mut tree = macro_files::create_temp_tree!(prefix = "main-", {
+        "config.toml": "volume = \"mnt/volume\"",
+        "mnt": {
+            "volume" as volume: temp {
+                "data": {
+                    "file.bin": [0, 1],
+                },
+            },
+        },
+    })
+    .unwrap();
+
+    let volume = tree.path().join("mnt/volume");
+    assert_eq!(tree.nested().len(), 1);
+    assert_eq!(tree.nested()[0].0, volume);
+    assert!(std::fs::symlink_metadata(&volume)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert!(volume.join("data/file.bin").is_file());
+
+    let nested = tree.take_nested("mnt/volume").unwrap();
+    let nested_path = nested.path().to_owned();
+    drop(nested);
+    assert!(!nested_path.exists());
+    assert!(!volume.join("data/file.bin").exists());
+    assert!(tree.path().join("config.toml").is_file());
+
+    let err = macro_files::create_temp!({
+        "volume": temp {},
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn temp_persist() {
+    let parent = macro_files::tempfile::tempdir().unwrap();
+    let dir = macro_files::create_temp!(in = parent.path(), {
+        "docs": { "README.md": "# Documentation" },
+        "LICENSE": "MIT",
+    })
+    .unwrap();
+
+    let (path, manifest) = macro_files::persist(dir).unwrap();
+    assert!(path.join("docs/README.md").is_file());
+    let paths: Vec<_> = manifest.paths().collect();
+    assert_eq!(
+        paths,
+        [
+            &path.join("LICENSE"),
+            &path.join("docs"),
+            &path.join("docs/README.md"),
+        ]
+    );
+
+    let tree = macro_files::create_temp_tree!(in = parent.path(), {
+        "volume": temp { "data.bin": [1, 2, 3] },
+    })
+    .unwrap();
+    let (path, manifest) = tree.persist().unwrap();
+    let volume = std::fs::read_link(path.join("volume")).unwrap();
+    assert_eq!(manifest.len(), 1);
+    assert!(volume.join("data.bin").is_file());
+    std::fs::remove_dir_all(volume).unwrap();
+}
+
+#[test]
+fn temp_tree_changes() {
+    use macro_files::Change;
+
+    let tree = macro_files::create_temp_tree!({
+        "Cargo.toml": "[package]",
+        "src": { "main.rs": "fn main() {}" },
+    })
+    .unwrap();
+    assert!(tree.changes_since(tree.spec()).unwrap().is_empty());
+
+    std::fs::write(tree.child("Cargo.lock"), "# generated").unwrap();
+    let snapshot = tree.snapshot().unwrap();
+    let changes = tree.changes_since(tree.spec()).unwrap();
+    assert!(changes.unexpected().eq([Path::new("Cargo.lock")]));
+    assert!(tree.changes_since(&snapshot).unwrap().is_empty());
+
+    std::fs::write(tree.child("Cargo.lock"), "# updated").unwrap();
+    std::fs::remove_dir_all(tree.child("src")).unwrap();
+    let changes = tree.changes_since(&snapshot).unwrap();
+    assert_eq!(
+        changes.changes(),
+        &[
+            Change::Contents {
+                path: "Cargo.lock".into(),
+                expected: b"# generated".to_vec(),
+                actual: b"# updated".to_vec(),
+            },
+            Change::Missing {
+                path: "src".into(),
+                expected: macro_files::EntryKind::Dir,
+            },
+        ]
+    );
+}
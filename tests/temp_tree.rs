@@ -0,0 +1,87 @@
+#![cfg(feature = "attributes")]
+
+use std::path::PathBuf;
+
+use macro_files::tempfile::TempDir;
+use macro_files::{temp_tree, TempTree};
+
+#[temp_tree({
+    "README.md": "# Project",
+    "src": {
+        "main.rs": "fn main() {}",
+    },
+})]
+#[test]
+fn inject_temp_dir(dir: TempDir) {
+    macro_files::assert_tree!(dir.path(), {
+        "README.md": "# Project",
+        "src/main.rs": "fn main() {}",
+    });
+}
+
+#[temp_tree(prefix = "fixture-", {
+    "data.bin": [1, 2, 3],
+})]
+#[test]
+fn pass_builder_options(dir: TempDir) {
+    let name = dir.path().file_name().unwrap().to_string_lossy();
+    assert!(name.starts_with("fixture-"));
+}
+
+#[temp_tree({
+    "volume": temp {
+        "data.bin": [1, 2, 3],
+    },
+})]
+#[test]
+fn inject_temp_tree(mut tree: TempTree) {
+    let data: PathBuf = tree.path().join("volume/data.bin");
+    assert!(data.is_file());
+    drop(tree.take_nested("volume"));
+    assert!(!data.exists());
+}
+
+#[temp_tree({
+    "notes.txt": "",
+})]
+#[tokio::test]
+async fn inject_in_async_tests(dir: TempDir) {
+    let path = dir.path().join("notes.txt");
+    let exists = async { path.is_file() }.await;
+    assert!(exists);
+}
+
+#[test]
+fn temp_tree_helpers() {
+    let tree = macro_files::create_temp_tree!({
+        "docs": {
+            "README.md": "# Documentation",
+        },
+        "data.bin": [1, 2, 3],
+    })
+    .unwrap();
+
+    assert_eq!(
+        tree.child("docs/README.md"),
+        tree.path().join("docs/README.md")
+    );
+    assert_eq!(AsRef::<std::path::Path>::as_ref(&tree), tree.path());
+    assert_eq!(tree.read("data.bin").unwrap(), [1, 2, 3]);
+    assert_eq!(
+        tree.read_to_string("docs/README.md").unwrap(),
+        "# Documentation"
+    );
+    assert_eq!(tree.spec().entries().count(), 2);
+
+    std::fs::write(tree.child("notes.txt"), "notes").unwrap();
+    tree.assert_contains(tree.spec());
+    tree.assert_contains(&macro_files::tree!({ "notes.txt": "notes" }));
+}
+
+#[test]
+#[should_panic(expected = "does not contain the spec")]
+fn temp_tree_missing_entries() {
+    let tree = macro_files::create_temp_tree!({ "README.md": "# Project" }).unwrap();
+
+    tree.assert_contains(&macro_files::tree!({ "README.md": "# Other" }));
+}
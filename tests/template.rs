@@ -0,0 +1,39 @@
+#![cfg(all(feature = "tera", feature = "tempfile"))]
+
+use std::io::ErrorKind;
+
+use macro_files::template;
+use macro_files::tera::Context;
+
+fn context(name: &str) -> Context {
+    let mut context = Context::new();
+    context.insert("name", name);
+    context.insert("version", "0.1.0");
+    context
+}
+
+#[test]
+fn render_templates() {
+    let dir = macro_files::create_temp!({
+        "first/Cargo.toml": template("tests/fixtures/Cargo.toml.tera", &context("first")),
+        "second/Cargo.toml": template("tests/fixtures/Cargo.toml.tera", &context("second")),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "first/Cargo.toml": "[package]\nname = \"first\"\nversion = \"0.1.0\"\n",
+        "second/Cargo.toml": "[package]\nname = \"second\"\nversion = \"0.1.0\"\n",
+    });
+}
+
+#[test]
+fn missing_variable_fails() {
+    let mut context = Context::new();
+    context.insert("name", "fixture");
+
+    let err = macro_files::create_temp!({
+        "Cargo.toml": template("tests/fixtures/Cargo.toml.tera", &context),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,115 @@
+#![cfg(feature = "tempfile")]
+
+use macro_files::{crlf, dedent, lf, subst, utf16be, utf16le, with_bom, Content, LineEnding, Tree};
+
+#[test]
+fn dedent_nested_contents() {
+    let dir = macro_files::create_temp!({
+        "config": {
+            "app.toml": dedent("
+                [server]
+                port = 8080
+
+                [server.tls]
+                    enabled = false
+            "),
+        },
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "config/app.toml": "[server]\nport = 8080\n\n[server.tls]\n    enabled = false\n",
+    });
+}
+
+#[test]
+fn dedent_keeps_unindented_text() {
+    assert_eq!(dedent("a\n  b").contents(), Some(&b"a\n  b"[..]));
+    assert_eq!(dedent("  a\r\n   b").contents(), Some(&b"a\r\n b"[..]));
+    assert_eq!(dedent("\n\t\tone line").contents(), Some(&b"one line"[..]));
+    assert_eq!(dedent("").contents(), Some(&b""[..]));
+}
+
+#[test]
+fn line_endings_per_entry() {
+    let dir = macro_files::create_temp!({
+        "windows.txt": crlf("one\ntwo\r\nthree"),
+        "unix.txt": lf("one\r\ntwo\n"),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "windows.txt": "one\r\ntwo\r\nthree",
+        "unix.txt": "one\ntwo\n",
+    });
+}
+
+#[test]
+fn line_endings_per_spec() {
+    let mut nested = Tree::new();
+    nested.push("b.txt", "b\n");
+    let mut tree = Tree::new();
+    tree.push("a.txt", Content::Text("a\n".to_string()));
+    tree.push("nested", nested);
+    tree.push("binary.bin", [b'\n', 0]);
+    let tree = tree.line_endings(LineEnding::CrLf);
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    tree.create_in(dir.path()).unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "a.txt": "a\r\n",
+        "nested/b.txt": "b\r\n",
+        "binary.bin": [b'\n', 0],
+    });
+}
+
+#[test]
+fn encodings() {
+    let dir = macro_files::create_temp!({
+        "le.txt": utf16le("a€"),
+        "be.txt": utf16be("a€"),
+        "bom.txt": with_bom("a€"),
+        "crlf.txt": utf16le("a\r\n"),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "le.txt": [0xFF, 0xFE, 0x61, 0x00, 0xAC, 0x20],
+        "be.txt": [0xFE, 0xFF, 0x00, 0x61, 0x20, 0xAC],
+        "bom.txt": [0xEF, 0xBB, 0xBF, 0x61, 0xE2, 0x82, 0xAC],
+        "crlf.txt": [0xFF, 0xFE, 0x61, 0x00, 0x0D, 0x00, 0x0A, 0x00],
+    });
+}
+
+#[test]
+fn substitute_placeholders() {
+    let mut values = std::collections::BTreeMap::new();
+    values.insert("name".to_string(), "app".to_string());
+    values.insert("version".to_string(), "1.0.0".to_string());
+
+    let dir = macro_files::create_temp!({
+        "Cargo.toml": subst("[package]\nname = \"{{name}}\"\nversion = \"{{ version }}\"\n", &values),
+        "braces.txt": subst("{ {{name}} }", &values),
+        "plain.txt": subst("no placeholder", std::iter::empty::<(&str, &str)>()),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "Cargo.toml": "[package]\nname = \"app\"\nversion = \"1.0.0\"\n",
+        "braces.txt": "{ app }",
+        "plain.txt": "no placeholder",
+    });
+}
+
+#[test]
+fn missing_placeholder_values() {
+    let missing = subst("Hello {{name}}", [("other", "value")]);
+    let unclosed = subst("Hello {{name", [("name", "Ada")]);
+
+    let err = macro_files::create_temp!({ "missing.txt": missing }).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("{{name}}"));
+    let err = macro_files::create_temp!({ "unclosed.txt": unclosed }).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "tempfile")]
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use macro_files::{file, Tree};
+
+fn days(days: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(days * 86_400)
+}
+
+fn times(path: &Path) -> (SystemTime, SystemTime) {
+    let metadata = std::fs::symlink_metadata(path).unwrap();
+    (metadata.accessed().unwrap(), metadata.modified().unwrap())
+}
+
+#[test]
+fn file_and_dir_times() {
+    let mut logs = Tree::new().mtime(days(2)).atime(days(3));
+    logs.push("old.log", file("old").mtime(days(1)).atime(days(4)));
+    logs.push("new.log", "new");
+    let dir = macro_files::create_temp!({
+        "logs": logs,
+    })
+    .unwrap();
+
+    assert_eq!(times(&dir.path().join("logs")), (days(3), days(2)));
+    assert_eq!(times(&dir.path().join("logs/old.log")), (days(4), days(1)));
+    assert!(times(&dir.path().join("logs/new.log")).1 > days(2));
+}
+
+#[test]
+#[cfg(unix)]
+fn symlink_times() {
+    let dir = macro_files::create_temp!({
+        "data.txt": file("data").mtime(days(1)),
+        "link.txt": macro_files::symlink("data.txt").mtime(days(2)),
+    })
+    .unwrap();
+
+    assert_eq!(times(&dir.path().join("data.txt")).1, days(1));
+    assert_eq!(times(&dir.path().join("link.txt")).1, days(2));
+}
+
+#[test]
+fn touch_bumps_mtime() {
+    let dir = macro_files::create_temp!({
+        "main.rs": file("fn main() {}").mtime(days(1)),
+    })
+    .unwrap();
+
+    macro_files::tree!({
+        "main.rs": macro_files::touch(),
+        "lib.rs": macro_files::touch(),
+        "old.rs": macro_files::touch().mtime(days(2)),
+    })
+    .create_in(dir.path())
+    .unwrap();
+
+    assert!(times(&dir.path().join("main.rs")).1 > days(1));
+    assert_eq!(times(&dir.path().join("old.rs")).1, days(2));
+    macro_files::assert_tree!(dir.path(), {
+        "main.rs": "fn main() {}",
+        "lib.rs": "",
+        "old.rs": "",
+    });
+}
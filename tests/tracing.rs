@@ -0,0 +1,105 @@
+#![cfg(all(feature = "tracing", feature = "tempfile"))]
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Collects the fields of the events emitted, one string per event.
+#[derive(Clone, Default)]
+struct Collector {
+    events: Arc<Mutex<Vec<String>>>,
+    spans: Arc<Mutex<Vec<String>>>,
+}
+
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() != "elapsed" {
+            self.0 += &format!("{}={:?} ", field.name(), value);
+        }
+    }
+}
+
+impl Subscriber for Collector {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields(format!("{} ", span.metadata().name()));
+        span.record(&mut fields);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(fields.0.trim_end().to_string());
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields(String::new());
+        event.record(&mut fields);
+        let fields = fields.0.trim_end().to_string();
+        self.events.lock().unwrap().push(fields);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[test]
+fn trace_created_entries() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), || {
+        macro_files::with_default_root(temp_dir.path(), || {
+            macro_files::create!({
+                "project": {
+                    "README.md": "# Project",
+                },
+            })
+        })
+        .unwrap();
+    });
+
+    let project = temp_dir.path().join("project");
+    let readme = project.join("README.md");
+    assert_eq!(
+        *collector.spans.lock().unwrap(),
+        [format!("create root={}", temp_dir.path().display())]
+    );
+    assert_eq!(
+        *collector.events.lock().unwrap(),
+        [
+            format!(
+                "message=create directory {0} operation=create directory path={0}",
+                project.display()
+            ),
+            format!(
+                "message=write file {0} operation=write file path={0} size=9",
+                readme.display()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn trace_failed_operations() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("missing/notes.txt");
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), || {
+        macro_files::copy_file("missing.txt", &path).unwrap_err();
+    });
+
+    let events = collector.events.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].starts_with("message=failed to copy file"));
+    assert!(events[0].contains("error="));
+}
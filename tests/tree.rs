@@ -0,0 +1,292 @@
+#![cfg(feature = "tempfile")]
+
+use std::io::ErrorKind;
+use std::path::Path;
+
+use macro_files::{file, Entry, Tree};
+
+fn workspace() -> Tree {
+    macro_files::tree!({
+        "Cargo.toml": "[workspace]",
+        "crates" as crates: {
+            "app/src/main.rs": file("fn main() {}"),
+        },
+        "target": null,
+    })
+}
+
+#[test]
+fn build_without_creating() {
+    let tree = workspace();
+
+    let keys: Vec<_> = tree.entries().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["Cargo.toml", "crates", "target"]);
+    assert!(matches!(tree.entries().nth(1), Some((_, Entry::Dir(_)))));
+    let handles: Vec<_> = tree.handles().collect();
+    assert_eq!(handles, [(Path::new("crates"), "crates")]);
+}
+
+#[test]
+fn create_later() {
+    let tree = workspace();
+
+    let first = tree.create_temp().unwrap();
+    let second = macro_files::create_temp!({
+        "nested": workspace(),
+        ..workspace(),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(first.path(), {
+        "Cargo.toml": "[workspace]",
+        "crates/app/src/main.rs": "fn main() {}",
+    });
+    macro_files::assert_tree!(second.path(), {
+        "Cargo.toml": "[workspace]",
+        "crates/app/src/main.rs": "fn main() {}",
+        "nested": {
+            "Cargo.toml": "[workspace]",
+            "crates/app/src/main.rs": "fn main() {}",
+        },
+    });
+}
+
+#[test]
+fn same_tree_as_macros() {
+    let dir = macro_files::tempfile::tempdir().unwrap();
+    let tree = macro_files::tree!({
+        dir.path(): {
+            "README.md": "# Project",
+        },
+    });
+
+    tree.create_in("").unwrap();
+
+    assert!(dir.path().join("README.md").is_file());
+}
+
+#[test]
+fn comma_after_brace_optional() {
+    let docs = "docs";
+    let debug = true;
+    let without_commas = macro_files::tree!({
+        "src": {
+            "main.rs": "fn main() {}"
+        }
+        docs: {
+            "README.md": "# Documentation"
+        }
+        if debug => {
+            "debug.log": ""
+        }
+        for name in ["a", "b"] => {
+            name: {}
+        }
+        "README.md": "# Project",
+    });
+    let with_commas = macro_files::tree!({
+        "src": {
+            "main.rs": "fn main() {}",
+        },
+        docs: {
+            "README.md": "# Documentation",
+        },
+        if debug => {
+            "debug.log": "",
+        },
+        for name in ["a", "b"] => {
+            name: {},
+        },
+        "README.md": "# Project",
+    });
+
+    assert_eq!(without_commas, with_commas);
+}
+
+#[test]
+fn verbatim_keys() {
+    let readme = "NOTES.md";
+    let verbatim = macro_files::tree!({
+        @README.md: "# Project",
+        @readme: readme,
+        @src/main.rs: "fn main() {}",
+        @.github: { @dependabot.yml: "version: 2" }
+        @my-app.1.0.tar.gz as archive: true,
+        @Cargo.toml: null,
+    });
+    let expected = macro_files::tree!({
+        "README.md": "# Project",
+        "readme": "NOTES.md",
+        "src/main.rs": "fn main() {}",
+        ".github": { "dependabot.yml": "version: 2" }
+        "my-app.1.0.tar.gz" as archive: true,
+        "Cargo.toml": null,
+    });
+
+    assert_eq!(verbatim, expected);
+}
+
+#[test]
+fn keys_sharing_a_value() {
+    let name = String::from("d.txt");
+    let fanned_out = macro_files::tree!({
+        ["a.txt", "b.txt", name, ("e".to_owned() + ".txt"),]: "same",
+        ["empty", "other"]: true,
+        ["skipped", "ignored"]: null,
+        ["src", "tests"]: {
+            "mod.rs": "",
+        }
+        ["long", "path"].join("/"): "joined",
+    });
+    let expected = macro_files::tree!({
+        "a.txt": "same",
+        "b.txt": "same",
+        "d.txt": "same",
+        "e.txt": "same",
+        "empty": true,
+        "other": true,
+        "skipped": null,
+        "ignored": null,
+        "src": { "mod.rs": "" },
+        "tests": { "mod.rs": "" },
+        "long/path": "joined",
+    });
+
+    assert_eq!(fanned_out, expected);
+}
+
+#[test]
+fn create_deep_trees() {
+    let mut tree = Tree::new();
+    tree.push("leaf.txt", "leaf");
+    for depth in (0..32).rev() {
+        let mut parent = Tree::new();
+        parent.push(format!("{}.txt", depth), depth.to_string());
+        parent.push("nested/key.txt", "nested");
+        parent.push("dir", tree);
+        tree = parent;
+    }
+
+    let dir = tree.create_temp().unwrap();
+
+    let mut path = dir.path().to_owned();
+    for depth in 0..32 {
+        let contents = std::fs::read_to_string(path.join(format!("{}.txt", depth))).unwrap();
+        assert_eq!(contents, depth.to_string());
+        assert_eq!(
+            std::fs::read_to_string(path.join("nested/key.txt")).unwrap(),
+            "nested"
+        );
+        path.push("dir");
+    }
+    assert_eq!(
+        std::fs::read_to_string(path.join("leaf.txt")).unwrap(),
+        "leaf"
+    );
+}
+
+#[test]
+fn creation_stats() {
+    let tree = macro_files::tree!({
+        "README.md": "# Project",
+        "docs": {
+            "generated.txt": macro_files::Content::Generator(macro_files::Generator::new(|| {
+                Ok(b"generated".to_vec())
+            })),
+            "copy.md": macro_files::from("tests/fixtures/vendor/README.md"),
+        },
+        "empty": {},
+        "target": null,
+    });
+    let dir = macro_files::tempfile::tempdir().unwrap();
+
+    let report = tree.create_in(dir.path()).unwrap();
+
+    let copied = std::fs::metadata("tests/fixtures/vendor/README.md")
+        .unwrap()
+        .len();
+    let stats = report.stats();
+    assert_eq!((stats.dirs, stats.files), (2, 3));
+    assert_eq!(stats.bytes, 9 + 9 + copied);
+    assert!(stats.elapsed > std::time::Duration::from_secs(0));
+    let sizes: Vec<_> = report.created().iter().map(|entry| entry.size()).collect();
+    assert_eq!(sizes, [Some(9), None, Some(9), Some(copied), None]);
+}
+
+#[test]
+fn trailing_separator_dirs() {
+    let dir = macro_files::create_temp!({
+        "assets/": true,
+        "cache/": {},
+        "src/bin/": true,
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "assets": {},
+        "cache": {},
+        "src": { "bin": {} },
+    });
+
+    let mut tree = Tree::new();
+    tree.push("README.md/", "# Project");
+    let err = tree.create_temp().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn names_of_empty_files() {
+    let names = [String::from("x.done")];
+    let dir = macro_files::create_temp!({
+        "stamps": ["a.done", "b.done", "c.done"],
+        "other": names,
+        "none": [] as [&str; 0],
+        "logo.png": [137u8, 80, 78, 71],
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "stamps": { "a.done": true, "b.done": true, "c.done": true },
+        "other": { "x.done": true },
+        "none": {},
+        "logo.png": [137, 80, 78, 71],
+    });
+}
+
+#[test]
+fn large_specs() {
+    // More entries than the default recursion limit, mixing single token
+    // values with others.
+    let tree = macro_files::tree!({
+        "000": 0, "001": 1, "002": 2, "003": 3, "004": 4, "005": 5, "006": 6, "007": 7, "008": 8, "009": 9,
+        "010": 10, "011": 11, "012": 12, "013": 13, "014": 14, "015": 15, "016": 16, "017": 17, "018": 18, "019": 19,
+        "020": 20, "021": 21, "022": 22, "023": 23, "024": 24, "025": 25, "026": 26, "027": 27, "028": 28, "029": 29,
+        "030": 30, "031": 31, "032": 32, "033": 33, "034": 34, "035": 35, "036": 36, "037": 37, "038": 38, "039": 39,
+        "040": 40, "041": 41, "042": 42, "043": 43, "044": 44, "045": 45, "046": 46, "047": 47, "048": 48, "049": 49,
+        "dir4": { "a": "a" }, "upper4": "x".to_uppercase(),
+        "050": 50, "051": 51, "052": 52, "053": 53, "054": 54, "055": 55, "056": 56, "057": 57, "058": 58, "059": 59,
+        "060": 60, "061": 61, "062": 62, "063": 63, "064": 64, "065": 65, "066": 66, "067": 67, "068": 68, "069": 69,
+        "070": 70, "071": 71, "072": 72, "073": 73, "074": 74, "075": 75, "076": 76, "077": 77, "078": 78, "079": 79,
+        "080": 80, "081": 81, "082": 82, "083": 83, "084": 84, "085": 85, "086": 86, "087": 87, "088": 88, "089": 89,
+        "090": 90, "091": 91, "092": 92, "093": 93, "094": 94, "095": 95, "096": 96, "097": 97, "098": 98, "099": 99,
+        "dir9": { "a": "a" }, "upper9": "x".to_uppercase(),
+        "100": 100, "101": 101, "102": 102, "103": 103, "104": 104, "105": 105, "106": 106, "107": 107, "108": 108, "109": 109,
+        "110": 110, "111": 111, "112": 112, "113": 113, "114": 114, "115": 115, "116": 116, "117": 117, "118": 118, "119": 119,
+        "120": 120, "121": 121, "122": 122, "123": 123, "124": 124, "125": 125, "126": 126, "127": 127, "128": 128, "129": 129,
+        "130": 130, "131": 131, "132": 132, "133": 133, "134": 134, "135": 135, "136": 136, "137": 137, "138": 138, "139": 139,
+        "140": 140, "141": 141, "142": 142, "143": 143, "144": 144, "145": 145, "146": 146, "147": 147, "148": 148, "149": 149,
+        "dir14": { "a": "a" }, "upper14": "x".to_uppercase(),
+        "150": 150, "151": 151, "152": 152, "153": 153, "154": 154, "155": 155, "156": 156, "157": 157, "158": 158, "159": 159,
+        "160": 160, "161": 161, "162": 162, "163": 163, "164": 164, "165": 165, "166": 166, "167": 167, "168": 168, "169": 169,
+        "170": 170, "171": 171, "172": 172, "173": 173, "174": 174, "175": 175, "176": 176, "177": 177, "178": 178, "179": 179,
+        "180": 180, "181": 181, "182": 182, "183": 183, "184": 184, "185": 185, "186": 186, "187": 187, "188": 188, "189": 189,
+        "190": 190, "191": 191, "192": 192, "193": 193, "194": 194, "195": 195, "196": 196, "197": 197, "198": 198, "199": 199,
+        "dir19": { "a": "a" }, "upper19": "x".to_uppercase(),
+    });
+
+    assert_eq!(tree.entries().count(), 208);
+    assert_eq!(
+        tree.entries().last().map(|(key, _)| key),
+        Some(std::path::Path::new("upper19"))
+    );
+}
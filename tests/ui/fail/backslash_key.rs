@@ -0,0 +1,12 @@
+use macro_files::{create_temp, tree};
+
+fn main() {
+    let _ = create_temp!({
+        "docs\\index.md": "# Index",
+    });
+    let _ = tree!({
+        "src": {
+            "bin\\main.rs": "fn main() {}",
+        },
+    });
+}
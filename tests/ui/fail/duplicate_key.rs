@@ -0,0 +1,14 @@
+use macro_files::create_temp;
+
+fn main() {
+    let _ = create_temp!({
+        "README.md": "# Project",
+        "README.md": "# Duplicate",
+    });
+    let _ = create_temp!({
+        "docs": {
+            "index.md": format!("# {}", "Index"),
+            "index.md": true,
+        },
+    });
+}
@@ -0,0 +1,12 @@
+use macro_files::create_temp;
+
+fn main() {
+    let _ = create_temp!({
+        "/etc/passwd": "root",
+    });
+    let _ = create_temp!({
+        "docs": {
+            "../../escape": "!",
+        },
+    });
+}
@@ -0,0 +1,7 @@
+use macro_files::create;
+
+fn main() {
+    create!({
+        , "README.md": "# Title"
+    });
+}
@@ -0,0 +1,8 @@
+use macro_files::create;
+
+fn main() {
+    let name = "README.md";
+    create!({
+        name.to_uppercase() "# Title",
+    });
+}
@@ -0,0 +1,9 @@
+use macro_files::create;
+
+fn main() {
+    let title = "Title";
+    create!({
+        "README.md": format!("# {}", title)
+        ".gitignore": "/target"
+    });
+}
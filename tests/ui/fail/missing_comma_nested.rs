@@ -3,8 +3,8 @@ use macro_files::create;
 fn main() {
     create!({
         "docs": {
-            "README.md": "# Title"
-        }
-        "LICENSE": "MIT"
+            "index.md": "# Index"
+            "guide.md": "# Guide"
+        },
     });
 }
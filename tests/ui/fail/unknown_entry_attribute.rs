@@ -0,0 +1,7 @@
+use macro_files::{create_temp, entry};
+
+fn main() {
+    let _ = create_temp!({
+        "secret.key": entry! { contents: "key", permissions: 0o600 },
+    });
+}
@@ -0,0 +1,7 @@
+use macro_files::tree;
+
+fn main() {
+    let _ = tree!({
+        @README.md
+    });
+}
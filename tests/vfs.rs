@@ -0,0 +1,49 @@
+#![cfg(feature = "vfs")]
+
+use std::io::ErrorKind;
+
+use macro_files::vfs::{MemoryFS, VfsFileType, VfsPath};
+use macro_files::{copy_dir, symlink, VfsFs};
+
+#[test]
+fn create_in_vfs() {
+    let root = VfsPath::new(MemoryFS::new());
+    macro_files::create_with_fs!(VfsFs::new(root.clone()), {
+        "project": {
+            "README.md": "# Project",
+            "docs/index.md": "# Docs",
+            "vendor": copy_dir("tests/fixtures/vendor"),
+            "empty": {},
+        },
+    })
+    .unwrap();
+
+    let read = |path: &str| root.join(path).unwrap().read_to_string().unwrap();
+    assert_eq!(read("project/README.md"), "# Project");
+    assert_eq!(read("project/docs/index.md"), "# Docs");
+    assert!(!read("project/vendor/lib/lib.rs").is_empty());
+    let empty = root.join("project/empty").unwrap().metadata().unwrap();
+    assert_eq!(empty.file_type, VfsFileType::Directory);
+
+    let report = macro_files::with_fs_ops(VfsFs::new(root.clone()), || {
+        macro_files::merge!("project", {
+            "README.md": "# Other",
+            "LICENSE": "MIT",
+        })
+    })
+    .unwrap();
+    assert_eq!(report.skipped().len(), 1);
+    assert_eq!(read("project/README.md"), "# Project");
+    assert_eq!(read("project/LICENSE"), "MIT");
+}
+
+#[test]
+fn vfs_unsupported_entries() {
+    let root = VfsPath::new(MemoryFS::new());
+    let err = macro_files::create_with_fs!(VfsFs::new(root), {
+        "latest": symlink("README.md"),
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+}
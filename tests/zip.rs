@@ -0,0 +1,116 @@
+#![cfg(feature = "zip")]
+
+use std::io::{Cursor, ErrorKind, Read};
+use std::time::{Duration, UNIX_EPOCH};
+
+use macro_files::zip::ZipArchive;
+use macro_files::{copy_dir, file, hardlink};
+
+#[test]
+fn write_zip() {
+    // 2021-03-04 05:06:08 UTC.
+    let mtime = UNIX_EPOCH + Duration::from_secs(1_614_834_368);
+    let archive = macro_files::create_zip!({
+        "README.md": file("# Project").mtime(mtime),
+        "src/bin": {
+            "main.rs": file("fn main() {}").readonly(),
+        },
+        "vendor": copy_dir("tests/fixtures/vendor"),
+        "ignored.txt": false,
+    })
+    .unwrap();
+
+    let mut archive = ZipArchive::new(Cursor::new(archive)).unwrap();
+    let names: Vec<_> = (0..archive.len())
+        .map(|index| archive.by_index(index).unwrap().name().to_owned())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "README.md",
+            "src/",
+            "src/bin/",
+            "src/bin/main.rs",
+            "vendor/",
+            "vendor/README.md",
+            "vendor/lib/",
+            "vendor/lib/lib.rs",
+        ]
+    );
+
+    let mut readme = archive.by_name("README.md").unwrap();
+    let mut contents = String::new();
+    readme.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "# Project");
+    let modified = readme.last_modified();
+    assert_eq!(
+        (modified.year(), modified.month(), modified.day()),
+        (2021, 3, 4)
+    );
+    assert_eq!(
+        (modified.hour(), modified.minute(), modified.second()),
+        (5, 6, 8)
+    );
+    drop(readme);
+
+    let main = archive.by_name("src/bin/main.rs").unwrap();
+    assert_eq!(main.unix_mode().unwrap() & 0o777, 0o444);
+}
+
+#[test]
+fn write_zip_rejects_hard_links() {
+    let err = macro_files::create_zip!(Cursor::new(Vec::new()), {
+        "README.md": "# Project",
+        "copy.md": hardlink("README.md"),
+    })
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(
+        err.to_string(),
+        "hard link \"copy.md\" cannot be written into a zip archive"
+    );
+}
+
+#[test]
+#[cfg(feature = "tempfile")]
+fn extract_zip() {
+    let dir = macro_files::create_temp!({
+        "data": macro_files::extract("tests/fixtures/data.zip"),
+    })
+    .unwrap();
+
+    macro_files::assert_tree!(dir.path(), {
+        "data": {
+            "README.md": "# Data\n",
+            "bin/run.sh": "#!/bin/sh\necho run\n",
+            "records": {
+                "1.csv": "id,name\n1,first\n",
+                "2.csv": "id,name\n2,second\n",
+            },
+        },
+    });
+
+    let err = macro_files::create_temp!({
+        "data": macro_files::extract("tests/fixtures/data.rar"),
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn reproducible_zip() {
+    let archive = macro_files::create_zip!(Cursor::new(Vec::new()), {
+        "src/main.rs": "fn main() {}",
+        "README.md": "# Project",
+        "src": { "lib.rs": "" },
+    })
+    .unwrap();
+    let reordered = macro_files::create_zip!(Cursor::new(Vec::new()), {
+        "README.md": "# Project",
+        "src": { "lib.rs": "", "main.rs": "fn main() {}" },
+    })
+    .unwrap();
+
+    assert_eq!(archive.into_inner(), reordered.into_inner());
+}